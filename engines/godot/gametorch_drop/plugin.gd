@@ -0,0 +1,67 @@
+@tool
+extends EditorPlugin
+
+# Installed by `gametorch export --drop`. Watches the GameTorch drop manifest
+# and reimports every asset whose revision changed, so regenerated sprites
+# hot-reload into the open editor.
+
+const MANIFEST_PATH := "res://{{MANIFEST_PATH}}"
+const POLL_SECONDS := 1.0
+
+var _timer: Timer
+var _last_modified := 0
+var _seen = null
+
+
+func _enter_tree() -> void:
+	_timer = Timer.new()
+	_timer.wait_time = POLL_SECONDS
+	_timer.timeout.connect(_poll)
+	add_child(_timer)
+	_timer.start()
+
+
+func _exit_tree() -> void:
+	_timer.queue_free()
+
+
+func _poll() -> void:
+	if not FileAccess.file_exists(MANIFEST_PATH):
+		return
+	var modified := FileAccess.get_modified_time(MANIFEST_PATH)
+	if modified == _last_modified:
+		return
+	_last_modified = modified
+
+	var manifest = JSON.parse_string(FileAccess.get_file_as_string(MANIFEST_PATH))
+	if typeof(manifest) != TYPE_DICTIONARY:
+		return  # Retry on the next change.
+
+	# The first read only records revisions; Godot scans existing files itself.
+	var first_read: bool = _seen == null
+	if first_read:
+		_seen = {}
+
+	var textures := PackedStringArray()
+	var resources := PackedStringArray()
+	for asset in manifest.get("assets", []):
+		var revision := int(asset.get("revision", 0))
+		var name: String = asset.get("name", "")
+		if not first_read and _seen.get(name, -1) != revision:
+			for file in asset.get("files", []):
+				var path: String = "res://" + file
+				if path.ends_with(".png"):
+					textures.append(path)
+				else:
+					resources.append(path)
+		_seen[name] = revision
+
+	if textures.is_empty() and resources.is_empty():
+		return
+
+	var fs := get_editor_interface().get_resource_filesystem()
+	fs.scan()
+	fs.reimport_files(textures)
+	for path in resources:
+		ResourceLoader.load(path, "", ResourceLoader.CACHE_MODE_REPLACE)
+	print("GameTorch: reloaded %d file(s) (drop revision %d)" % [textures.size() + resources.size(), manifest.get("revision", 0)])
@@ -0,0 +1,102 @@
+// Installed by `gametorch export --drop`. Watches the GameTorch drop manifest
+// and force-reimports every asset whose revision changed, so regenerated
+// sprites hot-reload into the open editor without waiting for a focus refresh.
+using System;
+using System.Collections.Generic;
+using System.IO;
+using UnityEditor;
+using UnityEngine;
+
+namespace GameTorch.Editor
+{
+    [InitializeOnLoad]
+    public static class GametorchDropWatcher
+    {
+        const string ManifestPath = "{{MANIFEST_PATH}}";
+        const double PollSeconds = 1.0;
+
+        static double nextPoll;
+        static DateTime lastWrite;
+        static Dictionary<string, long> seen;
+
+        [Serializable]
+        class DropAsset
+        {
+            public string name;
+            public long revision;
+            public string[] files;
+        }
+
+        [Serializable]
+        class DropManifest
+        {
+            public long revision;
+            public DropAsset[] assets;
+        }
+
+        static GametorchDropWatcher()
+        {
+            EditorApplication.update += Poll;
+        }
+
+        static void Poll()
+        {
+            if (EditorApplication.timeSinceStartup < nextPoll)
+                return;
+            nextPoll = EditorApplication.timeSinceStartup + PollSeconds;
+
+            if (!File.Exists(ManifestPath))
+                return;
+            var write = File.GetLastWriteTimeUtc(ManifestPath);
+            if (write == lastWrite)
+                return;
+            lastWrite = write;
+
+            DropManifest manifest;
+            try
+            {
+                manifest = JsonUtility.FromJson<DropManifest>(File.ReadAllText(ManifestPath));
+            }
+            catch (Exception)
+            {
+                return; // Retry on the next poll.
+            }
+            if (manifest == null || manifest.assets == null)
+                return;
+
+            // The first read only records revisions; Unity imports whatever
+            // already exists on startup by itself.
+            var firstRead = seen == null;
+            seen ??= new Dictionary<string, long>();
+
+            var changed = new List<string>();
+            foreach (var asset in manifest.assets)
+            {
+                if (!firstRead && (!seen.TryGetValue(asset.name, out var rev) || rev != asset.revision))
+                {
+                    foreach (var file in asset.files)
+                    {
+                        if (!file.EndsWith(".meta", StringComparison.OrdinalIgnoreCase))
+                            changed.Add(file);
+                    }
+                }
+                seen[asset.name] = asset.revision;
+            }
+
+            if (changed.Count == 0)
+                return;
+
+            AssetDatabase.StartAssetEditing();
+            try
+            {
+                foreach (var path in changed)
+                    AssetDatabase.ImportAsset(path, ImportAssetOptions.ForceUpdate);
+            }
+            finally
+            {
+                AssetDatabase.StopAssetEditing();
+            }
+            Debug.Log($"GameTorch: reimported {changed.Count} file(s) (drop revision {manifest.revision})");
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! API keys: checking them and rotating between several.
+//!
+//! [`check`] asks `GET /api/account` who a key belongs to. A backend without
+//! that endpoint answers `404`; the key is then tried on the cheapest
+//! authenticated request there is (one animation of the listing) and
+//! reported valid or not, without account details.
+//!
+//! Studios sharing quota across keys can list them in the
+//! [configuration file](crate::config), in the order to try them:
+//!
+//! ```toml
+//! [[keys]]
+//! name = "art"
+//! key = "gt_..."
+//!
+//! [[keys]]
+//! name = "design"
+//! key = "gt_..."
+//! ```
+//!
+//! The [active](use_key) key (the first one unless another was chosen) is
+//! used for every request. A [`KeyRing`] installed in the
+//! [client](crate::client::ClientBuilder::keys) moves on to the next key when
+//! a request is answered `401` or `429`, and keeps using that key from then on.
+
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{Request, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// File naming the active key inside the data directory.
+pub const ACTIVE_KEY_FILE: &str = "active-key";
+
+/// A key listed in the configuration file.
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamedKey {
+    pub name: String,
+    pub key: String,
+}
+
+impl std::fmt::Debug for NamedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NamedKey")
+            .field("name", &self.name)
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Configured keys in failover order, one of them current.
+#[derive(Debug)]
+pub struct KeyRing {
+    keys: Vec<NamedKey>,
+    current: AtomicUsize,
+}
+
+impl KeyRing {
+    /// A ring starting at the key named `active`, or at the first key if
+    /// there is no such key.
+    pub fn new(keys: Vec<NamedKey>, active: Option<&str>) -> Result<KeyRing, Box<dyn std::error::Error + Send + Sync>> {
+        if keys.is_empty() {
+            return Err("no API keys configured".into());
+        }
+        for (i, key) in keys.iter().enumerate() {
+            if key.name.trim().is_empty() || key.key.trim().is_empty() {
+                return Err(format!("key {} needs both a name and a key", i + 1).into());
+            }
+            if keys[..i].iter().any(|other| other.name == key.name) {
+                return Err(format!("key name '{}' is listed twice", key.name).into());
+            }
+        }
+        let current = active.and_then(|name| keys.iter().position(|key| key.name == name)).unwrap_or(0);
+        Ok(KeyRing {
+            keys,
+            current: AtomicUsize::new(current),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The key requests are sent with.
+    pub fn current(&self) -> &NamedKey {
+        &self.keys[self.current.load(Ordering::Relaxed)]
+    }
+
+    /// Send `request` with the current key if it carries another key of
+    /// the ring (one handed out before a failover).
+    pub(crate) fn authorize(&self, request: &mut Request) {
+        if self.position(request).is_some()
+            && let Ok(value) = bearer(&self.current().key)
+        {
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+    }
+
+    /// Make the key after the one `request` carries current and put it on
+    /// `request`. Returns `false` if `request` carries none of the ring's keys.
+    pub(crate) fn fail_over(&self, request: &mut Request, status: StatusCode) -> bool {
+        let Some(rejected) = self.position(request) else {
+            return false;
+        };
+        let next = (rejected + 1) % self.keys.len();
+        let Ok(value) = bearer(&self.keys[next].key) else {
+            return false;
+        };
+        // Another request may have moved on already; only the first moves the ring.
+        if self
+            .current
+            .compare_exchange(rejected, next, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            eprintln!(
+                "API key '{}' got {}; switching to '{}'",
+                self.keys[rejected].name, status, self.keys[next].name
+            );
+        }
+        request.headers_mut().insert(AUTHORIZATION, value);
+        true
+    }
+
+    /// Index of the key `request` is authorized with.
+    fn position(&self, request: &Request) -> Option<usize> {
+        let auth = request.headers().get(AUTHORIZATION)?.to_str().ok()?;
+        let key = auth.strip_prefix("Bearer ")?;
+        self.keys.iter().position(|k| k.key == key)
+    }
+}
+
+fn bearer(key: &str) -> Result<HeaderValue, reqwest::header::InvalidHeaderValue> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", key))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Name of the key chosen with [`use_key`], if any.
+pub fn active_key() -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let path = active_key_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(name) if !name.trim().is_empty() => Ok(Some(name.trim().to_string())),
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("failed to read {}: {}", path.display(), e).into()),
+    }
+}
+
+/// Make the key named `name` among `keys` the active one, returning where
+/// the choice was written.
+pub fn use_key(name: &str, keys: &[NamedKey]) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    if !keys.iter().any(|key| key.name == name) {
+        let names: Vec<&str> = keys.iter().map(|key| key.name.as_str()).collect();
+        if names.is_empty() {
+            return Err("no API keys configured".into());
+        }
+        return Err(format!("no key named '{}' (configured: {})", name, names.join(", ")).into());
+    }
+    let path = active_key_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, format!("{}\n", name)).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Where the active key's name lives.
+pub fn active_key_path() -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(crate::paths::data_dir()?.join(ACTIVE_KEY_FILE))
+}
+
+/// What the API said about a key.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct KeyStatus {
+    pub valid: bool,
+    /// HTTP status of the check.
+    pub status: u16,
+    pub account_id: Option<String>,
+    pub email: Option<String>,
+    pub plan: Option<String>,
+    /// Remaining credits, if the API reports them.
+    pub credits: Option<f64>,
+    /// Why the key was rejected, as the API put it.
+    pub detail: Option<String>,
+}
+
+/// Check `api_key` against the API. A rejected key (`401`/`403`) is an
+/// `Ok` with `valid: false`; other failures are errors.
+pub async fn check(api_key: &str, base_url: &str) -> Result<KeyStatus, Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let auth = format!("Bearer {}", api_key);
+
+    let url = format!("{}/api/account", base_url);
+    let mut response = client.send(client.get(&url).header("Authorization", &auth)).await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        let url = format!("{}/api/animations", base_url);
+        let listing = client.get(&url).query(&[("page", "1"), ("per_page", "1")]);
+        response = client.send(listing.header("Authorization", &auth)).await?;
+    }
+
+    let status = response.status();
+    if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        return Ok(KeyStatus {
+            valid: false,
+            status: status.as_u16(),
+            detail: body.get("detail").and_then(Value::as_str).map(str::to_string),
+            ..Default::default()
+        });
+    }
+    let body: Value = response.error_for_status()?.json().await.unwrap_or(Value::Null);
+    Ok(KeyStatus {
+        valid: true,
+        status: status.as_u16(),
+        account_id: field(&body, &["account_id", "user_id", "id"]).map(|id| match id {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }),
+        email: field(&body, &["email"]).and_then(Value::as_str).map(str::to_string),
+        plan: field(&body, &["plan", "tier", "subscription"]).and_then(|plan| match plan {
+            Value::String(s) => Some(s.clone()),
+            other => other.get("name").and_then(Value::as_str).map(str::to_string),
+        }),
+        credits: field(&body, &["credits", "credit_balance", "balance"]).and_then(Value::as_f64),
+        detail: None,
+    })
+}
+
+/// The first of `names` set on the account object, which may be the body
+/// itself or nested under `account` or `user`.
+fn field<'a>(body: &'a Value, names: &[&str]) -> Option<&'a Value> {
+    if !body.is_object() {
+        return None;
+    }
+    [body, &body["account"], &body["user"]]
+        .into_iter()
+        .flat_map(|object| names.iter().filter_map(move |name| object.get(*name)))
+        .find(|value| !value.is_null())
+}
@@ -0,0 +1,116 @@
+//! A blocking API for applications without an async runtime.
+//!
+//! [`Client`] mirrors the functions in [`crate::animations`], running each on
+//! an internal single-threaded runtime, so build scripts and synchronous
+//! programs can call GameTorch without setting up tokio:
+//!
+//! ```no_run
+//! let client = gametorch::blocking::Client::new("my-api-key");
+//! let created = client.generate("walking to the left", 5, Some("mule.png"))?;
+//! # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+//! ```
+//!
+//! Requests go through the same [shared HTTP client](crate::client), with its
+//! retries and rate limit. Calling these methods from inside an async runtime
+//! panics; use the async functions there.
+
+use crate::animations::{self, GenerateRequest, ListOptions, Metadata};
+use serde_json::Value;
+use std::future::Future;
+use std::sync::OnceLock;
+
+/// Production API base URL.
+pub const DEFAULT_BASE_URL: &str = crate::client::DEFAULT_BASE_URL;
+
+/// The runtime all blocking calls run on. One per process, so pooled
+/// connections of the shared HTTP client stay usable between calls.
+static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("failed to start the blocking runtime")
+        })
+        .block_on(future)
+}
+
+/// Blocking GameTorch API client for one API key.
+#[derive(Clone, Debug)]
+pub struct Client {
+    api_key: String,
+    base_url: String,
+}
+
+impl Client {
+    pub fn new(api_key: impl Into<String>) -> Client {
+        Client {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+        }
+    }
+
+    /// Talk to another server (e.g. `http://localhost:8000`).
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Client {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// See [`animations::get`].
+    pub fn get(&self, animation_id: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        block_on(animations::get(&self.api_key, &self.base_url, animation_id))
+    }
+
+    /// See [`animations::list`].
+    pub fn list(&self, options: &ListOptions) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        block_on(animations::list(&self.api_key, &self.base_url, options))
+    }
+
+    /// Submit a generation job without waiting for it; see [`animations::generate_from`].
+    pub fn generate(
+        &self,
+        prompt: &str,
+        duration_seconds: u32,
+        input_image_path: Option<&str>,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let mut request = GenerateRequest::new(prompt).duration(duration_seconds);
+        request.input_image = input_image_path.map(str::to_string);
+        self.generate_from(&request)
+    }
+
+    /// Submit `request` without waiting for it; see [`animations::generate_from`].
+    pub fn generate_from(&self, request: &GenerateRequest) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        block_on(animations::generate_from(&self.api_key, &self.base_url, request, false, None, true))
+    }
+
+    /// Wait for an animation to complete; see [`animations::wait`].
+    pub fn wait(&self, animation_id: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        block_on(animations::wait(&self.api_key, &self.base_url, animation_id, true))
+    }
+
+    /// See [`animations::download_zip`].
+    pub fn download_zip(&self, result_id: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        block_on(animations::download_zip(&self.api_key, &self.base_url, result_id, true))
+    }
+
+    /// See [`animations::update_metadata`].
+    pub fn update_metadata(
+        &self,
+        animation_id: &str,
+        metadata: &Metadata,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        block_on(animations::update_metadata(&self.api_key, &self.base_url, animation_id, metadata))
+    }
+
+    /// See [`animations::delete`].
+    pub fn delete(&self, animation_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        block_on(animations::delete(&self.api_key, &self.base_url, animation_id))
+    }
+
+    /// See [`animations::regenerate`].
+    pub fn regenerate(&self, animation_id: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        block_on(animations::regenerate(&self.api_key, &self.base_url, animation_id, None))
+    }
+}
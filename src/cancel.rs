@@ -0,0 +1,46 @@
+//! Cancelling long-running calls.
+//!
+//! [`generate_with_progress`](crate::animations::generate_with_progress),
+//! [`wait_with_progress`](crate::animations::wait_with_progress) and
+//! [`download_zip_with_progress`](crate::animations::download_zip_with_progress)
+//! take a [`CancellationToken`]. Cancelling it stops polling or downloading
+//! promptly and fails the call with [`Cancelled`], which carries the
+//! animation ID when one was already assigned so the job can be picked up
+//! again with `wait`. A submitted job keeps rendering on the server either
+//! way.
+
+use std::fmt;
+use std::future::Future;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// The error returned by a call whose token was cancelled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cancelled {
+    /// The animation being waited for, if the job had been submitted.
+    pub animation_id: Option<String>,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.animation_id {
+            Some(id) => write!(f, "cancelled while waiting for animation {}", id),
+            None => f.write_str("cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Run `fut`, failing with [`Cancelled`] as soon as `cancel` fires.
+pub async fn until<T>(
+    cancel: &CancellationToken,
+    animation_id: Option<&str>,
+    fut: impl Future<Output = Result<T, Box<dyn std::error::Error + Send + Sync>>>,
+) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    tokio::select! {
+        biased;
+        _ = cancel.cancelled() => Err(Cancelled { animation_id: animation_id.map(str::to_string) }.into()),
+        result = fut => result,
+    }
+}
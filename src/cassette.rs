@@ -0,0 +1,227 @@
+//! Recording and replaying API traffic (`--record FILE` / `--replay FILE`).
+//!
+//! A cassette is a JSON file of the responses the API gave, in order. While
+//! recording, the [client](crate::client) sends every request as usual and
+//! saves each response (status, headers and body) to the cassette. While
+//! replaying, nothing reaches the network: each request is answered with
+//! the next recorded response for the same method and path (and query), so
+//! scripts built on the CLI run deterministically in CI without spending
+//! credits.
+//!
+//! When a request has been made more often than it was recorded (a poll
+//! that happens to loop once more, say), its last recorded response is
+//! served again. A request that was never recorded gets a `404` and a
+//! warning on stderr. Request headers are not recorded, so API keys never end
+//! up in a cassette; binary bodies such as result ZIPs are stored base64
+//! encoded.
+
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Request, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const VERSION: u32 = 1;
+
+/// Response headers never written to a cassette.
+const SKIPPED_HEADERS: &[&str] = &["set-cookie", "date", "transfer-encoding"];
+
+/// Whether a cassette is being written or played back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Record,
+    Replay,
+}
+
+/// One request and the response it got.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Interaction {
+    method: String,
+    /// Path and query, without scheme and host.
+    path: String,
+    status: u16,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CassetteFile {
+    version: u32,
+    interactions: Vec<Interaction>,
+}
+
+struct State {
+    interactions: Vec<Interaction>,
+    /// Which interactions have been replayed.
+    used: Vec<bool>,
+}
+
+/// An open cassette.
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    state: Mutex<State>,
+}
+
+impl std::fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Cassette {
+    /// Start recording into `path`, replacing whatever it held.
+    pub fn record(path: impl Into<PathBuf>) -> Result<Cassette, Box<dyn std::error::Error + Send + Sync>> {
+        let cassette = Cassette {
+            path: path.into(),
+            mode: Mode::Record,
+            state: Mutex::new(State {
+                interactions: Vec::new(),
+                used: Vec::new(),
+            }),
+        };
+        if let Some(parent) = cassette.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        cassette.save(&[])?;
+        Ok(cassette)
+    }
+
+    /// Load the cassette at `path` for replaying.
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Cassette, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.into();
+        let bytes = std::fs::read(&path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let file: CassetteFile =
+            serde_json::from_slice(&bytes).map_err(|e| format!("invalid cassette {}: {}", path.display(), e))?;
+        if file.version > VERSION {
+            return Err(format!("{} was written by a newer gametorch (version {})", path.display(), file.version).into());
+        }
+        let used = vec![false; file.interactions.len()];
+        Ok(Cassette {
+            path,
+            mode: Mode::Replay,
+            state: Mutex::new(State {
+                interactions: file.interactions,
+                used,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Answer `request` from the cassette, or send it with `http` and
+    /// record the response. A recorded response is read in full and handed
+    /// back rebuilt, so the caller sees the same status, headers and body.
+    pub(crate) async fn execute(&self, http: &reqwest::Client, request: Request) -> reqwest::Result<Response> {
+        let method = request.method().to_string();
+        let path = path_of(request.url());
+        if self.mode == Mode::Replay {
+            return Ok(self.replayed(&method, &path));
+        }
+
+        let response = http.execute(request).await?;
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        let (text, base64) = match std::str::from_utf8(&body) {
+            Ok(text) => (Some(text.to_string()), None),
+            Err(_) => (None, Some(general_purpose::STANDARD.encode(&body))),
+        };
+        let interaction = Interaction {
+            method,
+            path,
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect(),
+            body: text.filter(|t| !t.is_empty()),
+            body_base64: base64,
+        };
+        let interactions = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.interactions.push(interaction);
+            state.interactions.clone()
+        };
+        if let Err(e) = self.save(&interactions) {
+            eprintln!("Failed to write cassette {}: {}", self.path.display(), e);
+        }
+
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        Ok(Response::from(rebuilt))
+    }
+
+    /// The next recorded response to `method` `path`.
+    fn replayed(&self, method: &str, path: &str) -> Response {
+        let interaction = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let State { interactions, used } = &mut *state;
+            let matching: Vec<usize> = (0..interactions.len())
+                .filter(|&i| interactions[i].method == method && interactions[i].path == path)
+                .collect();
+            matching.iter().find(|&&i| !used[i]).or(matching.last()).map(|&i| {
+                used[i] = true;
+                interactions[i].clone()
+            })
+        };
+        let Some(interaction) = interaction else {
+            eprintln!("No recorded response for {} {} in cassette {}", method, path, self.path.display());
+            let mut missing = http::Response::new(String::from(r#"{"detail": "not in cassette"}"#));
+            *missing.status_mut() = StatusCode::NOT_FOUND;
+            return Response::from(missing);
+        };
+
+        let body = match (&interaction.body_base64, interaction.body) {
+            (Some(encoded), _) => general_purpose::STANDARD.decode(encoded).unwrap_or_default(),
+            (None, text) => text.unwrap_or_default().into_bytes(),
+        };
+        let mut headers = HeaderMap::new();
+        for (name, value) in &interaction.headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                headers.append(name, value);
+            }
+        }
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = StatusCode::from_u16(interaction.status).unwrap_or(StatusCode::OK);
+        *rebuilt.headers_mut() = headers;
+        Response::from(rebuilt)
+    }
+
+    /// Write the cassette (via a temporary file and rename).
+    fn save(&self, interactions: &[Interaction]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let file = CassetteFile {
+            version: VERSION,
+            interactions: interactions.to_vec(),
+        };
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&file)? + "\n")?;
+        std::fs::rename(&tmp, &self.path).map_err(|e| format!("failed to write {}: {}", self.path.display(), e).into())
+    }
+}
+
+fn path_of(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
@@ -0,0 +1,189 @@
+//! Local organization of animations: tags, aliases and workspaces.
+//!
+//! GameTorch itself only knows animation IDs and prompts. The catalog keeps
+//! what a team layers on top in `catalog.json` in the
+//! [data directory](crate::paths::data_dir). Changes are made with a
+//! [`Batch`], which validates every change before the file is replaced in one
+//! atomic rename, so a bulk edit applies completely or not at all.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File name of the catalog inside the data directory.
+pub const CATALOG_FILE: &str = "catalog.json";
+
+/// What the catalog records about one animation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// A unique, human-friendly name for the animation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct CatalogFile {
+    version: u32,
+    /// Keyed by animation ID.
+    animations: BTreeMap<String, Entry>,
+}
+
+/// The catalog at a given path.
+#[derive(Clone, Debug)]
+pub struct Catalog {
+    path: PathBuf,
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Catalog {
+    /// Load the catalog at `path`; a missing file is an empty catalog.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Catalog, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.into();
+        let entries = match std::fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice::<CatalogFile>(&bytes)
+                    .map_err(|e| format!("invalid catalog {}: {}", path.display(), e))?
+                    .animations
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("failed to read {}: {}", path.display(), e).into()),
+        };
+        Ok(Catalog { path, entries })
+    }
+
+    /// The catalog in the default data directory.
+    pub fn load_default() -> Result<Catalog, Box<dyn std::error::Error + Send + Sync>> {
+        Catalog::load(crate::paths::data_dir()?.join(CATALOG_FILE))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The entry for `animation_id` (empty if it has none).
+    pub fn entry(&self, animation_id: &str) -> Entry {
+        self.entries.get(animation_id).cloned().unwrap_or_default()
+    }
+
+    /// `item` with its catalog `tags`, `alias` and `workspace` merged in, so
+    /// filters can match on them. Catalog tags are added to any tags the API
+    /// already reports. Items without an `id` are returned as-is.
+    pub fn annotate(&self, item: &Value) -> Value {
+        let mut item = item.clone();
+        let Some(id) = item.get("id").map(id_string) else {
+            return item;
+        };
+        let entry = self.entry(&id);
+        if let Value::Object(map) = &mut item {
+            let mut tags: Vec<String> = map
+                .get("tags")
+                .and_then(|t| t.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|t| t.as_str().map(str::to_string))
+                .collect();
+            tags.extend(entry.tags);
+            tags.sort();
+            tags.dedup();
+            map.insert("tags".to_string(), serde_json::json!(tags));
+            map.insert("alias".to_string(), serde_json::json!(entry.alias));
+            map.insert("workspace".to_string(), serde_json::json!(entry.workspace));
+        }
+        item
+    }
+
+    /// Start a set of changes to apply together.
+    pub fn batch(&self) -> Batch<'_> {
+        Batch {
+            catalog: self,
+            entries: self.entries.clone(),
+            changes: Vec::new(),
+        }
+    }
+}
+
+/// A change to one animation's entry.
+#[derive(Clone, Debug, Serialize)]
+pub struct Change {
+    pub animation_id: String,
+    pub before: Entry,
+    pub after: Entry,
+}
+
+/// Pending catalog changes; nothing is written until [`Batch::commit`].
+pub struct Batch<'a> {
+    catalog: &'a Catalog,
+    entries: BTreeMap<String, Entry>,
+    changes: Vec<Change>,
+}
+
+impl Batch<'_> {
+    /// Replace the entry for `animation_id` with the result of `edit`.
+    pub fn update(&mut self, animation_id: &str, edit: impl FnOnce(&mut Entry)) {
+        let before = self.entries.get(animation_id).cloned().unwrap_or_default();
+        let mut after = before.clone();
+        edit(&mut after);
+        after.tags.sort();
+        after.tags.dedup();
+        if after == before {
+            return;
+        }
+        self.entries.insert(animation_id.to_string(), after.clone());
+        self.changes.push(Change {
+            animation_id: animation_id.to_string(),
+            before,
+            after,
+        });
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Check the batch would leave the catalog consistent.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut aliases: BTreeMap<&str, &str> = BTreeMap::new();
+        for (id, entry) in &self.entries {
+            if let Some(alias) = &entry.alias
+                && let Some(other) = aliases.insert(alias.as_str(), id.as_str())
+            {
+                return Err(format!("alias '{}' would be used by both {} and {}", alias, other, id).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate and write every change at once, returning them.
+    pub fn commit(self) -> Result<Vec<Change>, Box<dyn std::error::Error + Send + Sync>> {
+        self.validate()?;
+        if self.changes.is_empty() {
+            return Ok(self.changes);
+        }
+        let path = &self.catalog.path;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = CatalogFile {
+            version: 1,
+            animations: self.entries.into_iter().filter(|(_, e)| *e != Entry::default()).collect(),
+        };
+        // Write via a temporary file and rename, so readers see old or new, never half.
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&file)?)?;
+        std::fs::rename(&tmp, path).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+        Ok(self.changes)
+    }
+}
+
+/// An animation ID as a string, whether the API sent a number or a string.
+pub fn id_string(id: &Value) -> String {
+    match id {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
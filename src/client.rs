@@ -0,0 +1,568 @@
+//! The HTTP client behind every API call.
+//!
+//! All requests go through one shared [`Client`], configured once with a
+//! [`ClientBuilder`] and [installed](Client::install) before the first call
+//! (the CLI does this from its global flags). Until then a default client is
+//! used.
+//!
+//! Requests are retried according to a [`RetryPolicy`]: timeouts, connection
+//! failures, `429 Too Many Requests` and `5xx` responses are retried with
+//! exponential backoff and jitter, and a `Retry-After` header overrides the
+//! computed delay. Requests that may not be safe to repeat (`POST`, `PATCH`)
+//! are only retried when the server cannot have acted on them: connection
+//! failures and `429`.
+//!
+//! An optional [rate limit](ClientBuilder::rate_limit) spaces requests out
+//! with a token bucket, so batch commands touching hundreds of animations do
+//! not hammer the API. Every attempt, retries included, takes a token.
+//!
+//! The API lives at [`DEFAULT_BASE_URL`]; [`base_url`] checks and normalizes
+//! another one (a staging server, a tunnel or a self-hosted backend).
+//!
+//! Timeouts, a proxy and extra root certificates can be set on the builder,
+//! usually from the [configuration file](crate::config). A
+//! [transcript](crate::transcript) of every attempt can be recorded for bug
+//! reports, and `GET` responses can be [cached](crate::http_cache) on disk
+//! and revalidated with ETags. All traffic can be recorded to, or replayed
+//! from, a [cassette](crate::cassette).
+//!
+//! With several API keys [configured](crate::auth), a request answered `401`
+//! or `429` is sent again straight away with the next key, before any
+//! backoff.
+//!
+//! Without the `native` feature the client builds for
+//! `wasm32-unknown-unknown` on reqwest's browser backend, for web and Tauri
+//! tools. The browser then owns timeouts, proxies and certificates, and the
+//! cache, transcripts and cassettes, which live on disk, are left out.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, Request, RequestBuilder, Response, StatusCode};
+use crate::auth::KeyRing;
+#[cfg(feature = "native")]
+use crate::cassette::Cassette;
+use crate::clock::{self, Instant};
+#[cfg(feature = "native")]
+use crate::http_cache::HttpCache;
+#[cfg(feature = "native")]
+use crate::transcript::Transcript;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+static SHARED: OnceLock<Client> = OnceLock::new();
+
+/// Production API base URL.
+pub const DEFAULT_BASE_URL: &str = "https://gametorch.app";
+
+/// Base URL of a development server (`--local`).
+pub const LOCAL_BASE_URL: &str = "http://localhost:8000";
+
+/// Environment variable overriding the base URL.
+pub const BASE_URL_ENV: &str = "GAMETORCH_BASE_URL";
+
+/// Check and normalize an API base URL.
+///
+/// A URL without a scheme gets `https://` (`http://` for `localhost` and
+/// loopback addresses). Only `http` and `https` are accepted, without
+/// credentials, query or fragment; a path prefix (for a backend behind a
+/// reverse proxy) is kept, without trailing slashes.
+pub fn base_url(url: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err("base URL is empty".into());
+    }
+    let with_scheme = if url.contains("://") {
+        url.to_string()
+    } else {
+        let host = url.split(['/', ':']).next().unwrap_or_default();
+        let local = host == "localhost" || host.starts_with("127.");
+        format!("{}://{}", if local { "http" } else { "https" }, url)
+    };
+    let parsed = reqwest::Url::parse(&with_scheme).map_err(|e| format!("invalid base URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("base URL '{}' must use http or https", url).into());
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("base URL '{}' has no host", url).into());
+    }
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err(format!("base URL '{}' must not contain credentials", url).into());
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err(format!("base URL '{}' must not have a query or fragment", url).into());
+    }
+    let origin = parsed.origin().ascii_serialization();
+    Ok(format!("{}{}", origin, parsed.path().trim_end_matches('/')))
+}
+
+/// When and how often to retry a failed request.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first; `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled (times `multiplier`) for each further one.
+    pub initial_backoff: Duration,
+    pub multiplier: f64,
+    /// Upper bound for any single delay, including `Retry-After`.
+    pub max_backoff: Duration,
+    /// Randomize each delay between half and all of its value, so many
+    /// clients backing off together do not retry in lockstep.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            multiplier: 2.0,
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before retry number `retry` (1-based).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = self.multiplier.max(1.0).powi(retry.saturating_sub(1) as i32);
+        let delay = self.initial_backoff.mul_f64(exp).min(self.max_backoff);
+        if self.jitter {
+            delay.mul_f64(0.5 + 0.5 * random_fraction())
+        } else {
+            delay
+        }
+    }
+
+    /// Whether a response with `status` should be retried.
+    fn retries_status(status: StatusCode, idempotent: bool) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || (idempotent && status.is_server_error())
+    }
+
+    /// Whether a transport error should be retried.
+    pub(crate) fn retries_error(err: &reqwest::Error, idempotent: bool) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            err.is_connect() || (idempotent && err.is_timeout())
+        }
+        // A failed fetch does not say whether it reached the server.
+        #[cfg(target_arch = "wasm32")]
+        {
+            idempotent && (err.is_timeout() || err.is_request())
+        }
+    }
+}
+
+/// Token bucket allowing `rate` requests per second on average, with bursts
+/// of up to one second's worth.
+#[derive(Debug)]
+struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    /// Available tokens and when they were last topped up.
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(rate: f64) -> RateLimiter {
+        let burst = rate.max(1.0);
+        RateLimiter {
+            rate,
+            burst,
+            state: Mutex::new((burst, Instant::now())),
+        }
+    }
+
+    /// Wait until a request may be sent.
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                let (tokens, last) = &mut *state;
+                let now = Instant::now();
+                *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.rate).min(self.burst);
+                *last = now;
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - *tokens) / self.rate)
+            };
+            clock::sleep(wait).await;
+        }
+    }
+}
+
+/// Configures a [`Client`].
+#[derive(Clone, Debug, Default)]
+pub struct ClientBuilder {
+    retry: RetryPolicy,
+    rate_limit: Option<f64>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+    ca_cert: Option<PathBuf>,
+    #[cfg(feature = "native")]
+    transcript: Option<PathBuf>,
+    #[cfg(feature = "native")]
+    cache: Option<PathBuf>,
+    #[cfg(feature = "native")]
+    cassette: Option<(PathBuf, crate::cassette::Mode)>,
+    keys: Option<Arc<KeyRing>>,
+}
+
+impl ClientBuilder {
+    pub fn new() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> ClientBuilder {
+        self.retry = retry;
+        self
+    }
+
+    /// Send at most `requests_per_second` requests per second (on average).
+    pub fn rate_limit(mut self, requests_per_second: f64) -> ClientBuilder {
+        self.rate_limit = Some(requests_per_second);
+        self
+    }
+
+    /// Give up connecting after `timeout`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Give up on a request (including reading its body) after `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send every request through the proxy at `url` (`http://`, `https://`,
+    /// `socks5://` or `socks5h://`) instead of the one from the environment.
+    pub fn proxy(mut self, url: impl Into<String>) -> ClientBuilder {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Also trust the root certificates in the PEM file at `path`.
+    pub fn ca_cert(mut self, path: impl Into<PathBuf>) -> ClientBuilder {
+        self.ca_cert = Some(path.into());
+        self
+    }
+
+    /// Append a sanitized transcript of every request and response to `path`.
+    #[cfg(feature = "native")]
+    pub fn transcript(mut self, path: impl Into<PathBuf>) -> ClientBuilder {
+        self.transcript = Some(path.into());
+        self
+    }
+
+    /// Cache `GET` responses in the directory at `path`, revalidating them
+    /// with `If-None-Match`.
+    #[cfg(feature = "native")]
+    pub fn cache(mut self, path: impl Into<PathBuf>) -> ClientBuilder {
+        self.cache = Some(path.into());
+        self
+    }
+
+    /// Record every response into the cassette at `path`.
+    #[cfg(feature = "native")]
+    pub fn record(mut self, path: impl Into<PathBuf>) -> ClientBuilder {
+        self.cassette = Some((path.into(), crate::cassette::Mode::Record));
+        self
+    }
+
+    /// Answer every request from the cassette at `path` instead of the
+    /// network.
+    #[cfg(feature = "native")]
+    pub fn replay(mut self, path: impl Into<PathBuf>) -> ClientBuilder {
+        self.cassette = Some((path.into(), crate::cassette::Mode::Replay));
+        self
+    }
+
+    /// Fail over between the keys of `keys` when one is rejected or out of
+    /// quota. Requests authorized with other keys are left alone.
+    pub fn keys(mut self, keys: KeyRing) -> ClientBuilder {
+        self.keys = Some(Arc::new(keys));
+        self
+    }
+
+    pub fn build(self) -> Result<Client, Box<dyn std::error::Error + Send + Sync>> {
+        let limiter = match self.rate_limit {
+            Some(rate) if rate.is_finite() && rate > 0.0 => Some(Arc::new(RateLimiter::new(rate))),
+            Some(rate) => return Err(format!("rate limit must be a positive number, got {}", rate).into()),
+            None => None,
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let http = {
+            let mut http = reqwest::Client::builder();
+            if let Some(timeout) = self.connect_timeout {
+                http = http.connect_timeout(timeout);
+            }
+            if let Some(timeout) = self.timeout {
+                http = http.timeout(timeout);
+            }
+            if let Some(url) = &self.proxy {
+                let proxy = reqwest::Proxy::all(url.as_str()).map_err(|e| format!("invalid proxy '{}': {}", url, e))?;
+                http = http.proxy(proxy);
+            }
+            if let Some(path) = &self.ca_cert {
+                let pem = std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+                for cert in reqwest::Certificate::from_pem_bundle(&pem)
+                    .map_err(|e| format!("invalid certificate file {}: {}", path.display(), e))?
+                {
+                    http = http.add_root_certificate(cert);
+                }
+            }
+            http.build()?
+        };
+        #[cfg(target_arch = "wasm32")]
+        let http = {
+            let configured = self.connect_timeout.is_some()
+                || self.timeout.is_some()
+                || self.proxy.is_some()
+                || self.ca_cert.is_some();
+            if configured {
+                return Err("timeouts, proxies and certificates are up to the browser".into());
+            }
+            reqwest::Client::builder().build()?
+        };
+        Ok(Client {
+            http,
+            retry: self.retry,
+            limiter,
+            #[cfg(feature = "native")]
+            transcript: self.transcript.map(Transcript::open).transpose()?.map(Arc::new),
+            #[cfg(feature = "native")]
+            cache: self.cache.map(HttpCache::open).transpose()?.map(Arc::new),
+            #[cfg(feature = "native")]
+            cassette: match self.cassette {
+                Some((path, crate::cassette::Mode::Record)) => Some(Arc::new(Cassette::record(path)?)),
+                Some((path, crate::cassette::Mode::Replay)) => Some(Arc::new(Cassette::replay(path)?)),
+                None => None,
+            },
+            keys: self.keys,
+        })
+    }
+}
+
+/// HTTP client with retries and rate limiting; see the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    retry: RetryPolicy,
+    limiter: Option<Arc<RateLimiter>>,
+    #[cfg(feature = "native")]
+    transcript: Option<Arc<Transcript>>,
+    #[cfg(feature = "native")]
+    cache: Option<Arc<HttpCache>>,
+    #[cfg(feature = "native")]
+    cassette: Option<Arc<Cassette>>,
+    keys: Option<Arc<KeyRing>>,
+}
+
+impl Client {
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Make this the client used by every API call. Fails if calls have
+    /// already been made with another client.
+    pub fn install(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        SHARED
+            .set(self)
+            .map_err(|_| "the HTTP client is already in use and can no longer be configured".into())
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.http.get(url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.http.post(url)
+    }
+
+    pub fn patch(&self, url: &str) -> RequestBuilder {
+        self.http.patch(url)
+    }
+
+    pub fn delete(&self, url: &str) -> RequestBuilder {
+        self.http.delete(url)
+    }
+
+    /// Send a request, retrying per the policy.
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        self.execute(request.build()?).await
+    }
+
+    /// Send a request once, without retrying: for polls, which try again on
+    /// their own schedule.
+    pub async fn send_once(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let mut request = request.build()?;
+        if let Some(keys) = &self.keys {
+            keys.authorize(&mut request);
+        }
+        self.attempt(request, 1).await
+    }
+
+    /// The configured key requests are currently sent with, if any.
+    pub fn current_key(&self) -> Option<&str> {
+        self.keys.as_ref().map(|keys| keys.current().name.as_str())
+    }
+
+    /// Execute a built request, retrying per the policy.
+    pub async fn execute(&self, mut request: Request) -> reqwest::Result<Response> {
+        let idempotent = !matches!(*request.method(), Method::POST | Method::PATCH);
+        if let Some(keys) = &self.keys {
+            keys.authorize(&mut request);
+        }
+        let mut retry = 1;
+        let mut failovers = 0;
+        loop {
+            let retries_left = retry < self.retry.max_attempts;
+            // The last attempt (or a request whose body cannot be replayed)
+            // sends the original and returns whatever happens.
+            let attempt = if retries_left || self.can_fail_over(failovers) {
+                request.try_clone()
+            } else {
+                None
+            };
+            let Some(attempt) = attempt else {
+                return self.attempt(request, retry).await;
+            };
+            let delay = match self.attempt(attempt, retry).await {
+                Ok(response) if self.fail_over(&mut request, response.status(), &mut failovers) => continue,
+                Ok(response) if retries_left && RetryPolicy::retries_status(response.status(), idempotent) => {
+                    retry_after(response.headers())
+                        .map(|d| d.min(self.retry.max_backoff))
+                        .unwrap_or_else(|| self.retry.backoff(retry))
+                }
+                Ok(response) => return Ok(response),
+                Err(err) if retries_left && RetryPolicy::retries_error(&err, idempotent) => self.retry.backoff(retry),
+                Err(err) => return Err(err),
+            };
+            clock::sleep(delay).await;
+            retry += 1;
+        }
+    }
+
+    /// Send one attempt, after waiting for the rate limit, through the cache
+    /// and recording it in the transcript. Replayed attempts skip the rate
+    /// limit.
+    #[cfg(feature = "native")]
+    async fn attempt(&self, mut request: Request, attempt: u32) -> reqwest::Result<Response> {
+        let replaying = self
+            .cassette
+            .as_ref()
+            .is_some_and(|cassette| cassette.mode() == crate::cassette::Mode::Replay);
+        if let Some(limiter) = &self.limiter
+            && !replaying
+        {
+            limiter.acquire().await;
+        }
+        let cached = self.cache.as_ref().and_then(|cache| Some((cache, cache.prepare(&mut request)?)));
+        let result = match &self.transcript {
+            Some(transcript) => {
+                let sent = transcript.sent(&request, attempt);
+                transcript.received(sent, self.execute_once(request).await).await
+            }
+            None => self.execute_once(request).await,
+        };
+        match cached {
+            Some((cache, key)) => cache.received(&key, result).await,
+            None => result,
+        }
+    }
+
+    /// Send one attempt after waiting for the rate limit.
+    #[cfg(not(feature = "native"))]
+    async fn attempt(&self, request: Request, _attempt: u32) -> reqwest::Result<Response> {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire().await;
+        }
+        self.http.execute(request).await
+    }
+
+    /// Whether another key is left to try after `failovers` failovers.
+    fn can_fail_over(&self, failovers: usize) -> bool {
+        self.keys.as_ref().is_some_and(|keys| failovers + 1 < keys.len())
+    }
+
+    /// Move `request` to the next key if `status` says its key was rejected
+    /// or is out of quota, and another key is left to try.
+    fn fail_over(&self, request: &mut Request, status: StatusCode, failovers: &mut usize) -> bool {
+        let failover = matches!(status, StatusCode::UNAUTHORIZED | StatusCode::TOO_MANY_REQUESTS)
+            && self.can_fail_over(*failovers)
+            && self.keys.as_ref().is_some_and(|keys| keys.fail_over(request, status));
+        if failover {
+            *failovers += 1;
+        }
+        failover
+    }
+
+    /// Send a request once, or answer it from the cassette.
+    #[cfg(feature = "native")]
+    async fn execute_once(&self, request: Request) -> reqwest::Result<Response> {
+        match &self.cassette {
+            Some(cassette) => cassette.execute(&self.http, request).await,
+            None => self.http.execute(request).await,
+        }
+    }
+}
+
+/// The client installed with [`Client::install`], or a default one.
+pub fn shared() -> &'static Client {
+    SHARED.get_or_init(|| ClientBuilder::new().build().expect("failed to initialize the HTTP client"))
+}
+
+/// A `Retry-After` header given in seconds.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Read `response`'s body piece by piece as it arrives, passing each piece to
+/// `chunk`, which stops the read by failing. The browser hands the body over
+/// in one piece.
+pub(crate) async fn read_chunks(
+    response: Response,
+    mut chunk: impl FnMut(&[u8]) -> Result<(), Box<dyn std::error::Error + Send + Sync>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let mut response = response;
+        while let Some(bytes) = response.chunk().await? {
+            chunk(bytes.as_ref())?;
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    chunk(response.bytes().await?.as_ref())?;
+    Ok(())
+}
+
+/// A cheap pseudo-random number in `[0, 1)`; only used to spread retries.
+fn random_fraction() -> f64 {
+    let nanos = clock::unix_time().subsec_nanos();
+    // xorshift over the clock's low bits.
+    let mut x = u64::from(nanos) ^ 0x9e37_79b9_7f4a_7c15;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,61 @@
+//! Images on the system clipboard.
+//!
+//! `generate --input-image clipboard` uploads whatever image is on the
+//! clipboard and `--copy-result` puts the first frame of the result back, so
+//! iterating between an image editor and GameTorch needs no files. Requires
+//! the `clipboard` feature.
+//!
+//! On Linux the clipboard belongs to the process that set it; a copied frame
+//! stays available after the CLI exits only if a clipboard manager is running.
+
+use image::RgbaImage;
+
+/// `--input-image` value that reads the clipboard instead of a file.
+pub const CLIPBOARD_INPUT: &str = "clipboard";
+
+/// The image on the clipboard, encoded as PNG.
+pub fn read_image() -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    #[cfg(feature = "clipboard")]
+    {
+        let data = arboard::Clipboard::new()?
+            .get_image()
+            .map_err(|e| format!("no image on the clipboard: {}", e))?;
+        let image = RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned())
+            .ok_or("clipboard image has an unexpected size")?;
+        let mut png = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut png, image::ImageFormat::Png)?;
+        Ok(png.into_inner())
+    }
+    #[cfg(not(feature = "clipboard"))]
+    Err(unsupported())
+}
+
+/// Put `image` on the clipboard.
+pub fn copy_image(image: &RgbaImage) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    #[cfg(feature = "clipboard")]
+    {
+        arboard::Clipboard::new()?.set_image(arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+        })?;
+        Ok(())
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        let _ = image;
+        Err(unsupported())
+    }
+}
+
+/// Put the first frame of a result ZIP or frame directory on the clipboard.
+pub fn copy_first_frame(path: impl AsRef<std::path::Path>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let sequence = crate::frames::Sequence::load(path)?;
+    let frame = sequence.frames.first().ok_or("the result has no frames")?;
+    copy_image(&frame.image)
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn unsupported() -> Box<dyn std::error::Error + Send + Sync> {
+    "this build has no clipboard support (rebuild with --features clipboard)".into()
+}
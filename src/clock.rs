@@ -0,0 +1,86 @@
+//! Sleeping and reading the clock, natively and in the browser.
+//!
+//! `wasm32-unknown-unknown` has no timers, and `std`'s clocks panic there.
+//! Retries, rate limiting and polling go through this module instead, which
+//! uses tokio and `std` natively and JavaScript's `setTimeout` and
+//! `Date.now()` in the browser.
+
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+pub use browser::Instant;
+
+/// Wait for `duration`.
+pub async fn sleep(duration: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+    #[cfg(target_arch = "wasm32")]
+    browser::sleep(duration).await;
+}
+
+/// Time since the Unix epoch.
+pub fn unix_time() -> Duration {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+    #[cfg(target_arch = "wasm32")]
+    Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
+/// `YYYY-MM-DD` for a Unix timestamp (Howard Hinnant's civil-from-days).
+pub(crate) fn utc_date(unix: u64) -> String {
+    let z = (unix / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use std::time::Duration;
+
+    /// A moment measured with `Date.now()`, standing in for
+    /// [`std::time::Instant`]. Not monotonic: a clock change shows up in
+    /// [`elapsed`](Instant::elapsed), clamped at zero.
+    #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+    pub struct Instant(f64);
+
+    impl Instant {
+        pub fn now() -> Instant {
+            Instant(js_sys::Date::now())
+        }
+
+        pub fn duration_since(&self, earlier: Instant) -> Duration {
+            Duration::from_secs_f64((self.0 - earlier.0).max(0.0) / 1000.0)
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Instant::now().duration_since(*self)
+        }
+    }
+
+    /// Resolve a promise with `setTimeout`, which windows, workers and
+    /// Tauri webviews all have.
+    pub async fn sleep(duration: Duration) {
+        let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+        let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+            let global = js_sys::global();
+            if let Ok(set_timeout) = js_sys::Reflect::get(&global, &"setTimeout".into()) {
+                let _ = js_sys::Function::from(set_timeout).call2(&global, &resolve, &millis.into());
+            }
+        });
+        let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+    }
+}
@@ -0,0 +1,42 @@
+//! Values offered by dynamic shell completion.
+//!
+//! `gametorch completions <shell>` installs completion that calls back into
+//! the CLI, so arguments can be completed from local data without touching
+//! the network: animation IDs from the [job history](crate::history) (with
+//! their prompts as descriptions) and model names seen there or set in the
+//! [workspace](crate::workspace). Errors just mean no suggestions.
+
+use crate::history::History;
+use crate::workspace::Workspace;
+
+/// The model `generate` uses when none is given.
+pub const DEFAULT_MODEL_NAME: &str = "alpha/v2.1";
+
+/// Animation IDs from the local history with their prompts, most recent
+/// first.
+pub fn animation_ids() -> Vec<(String, Option<String>)> {
+    let Ok(jobs) = History::open_default().and_then(|history| history.jobs()) else {
+        return Vec::new();
+    };
+    jobs.into_iter()
+        .rev()
+        .map(|job| (job.animation_id.to_string(), job.prompt))
+        .collect()
+}
+
+/// Model names from the workspace and the local history, plus the default.
+pub fn model_names() -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(Some(workspace)) = Workspace::current()
+        && let (None, Some(name)) = workspace.config.model()
+    {
+        names.push(name);
+    }
+    if let Ok(jobs) = History::open_default().and_then(|history| history.jobs()) {
+        names.extend(jobs.into_iter().rev().filter_map(|job| job.model).filter(|m| m.parse::<u32>().is_err()));
+    }
+    names.push(DEFAULT_MODEL_NAME.to_string());
+    let mut seen = std::collections::HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+    names
+}
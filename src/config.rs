@@ -0,0 +1,124 @@
+//! User configuration: `config.toml` in the [data directory](crate::paths::data_dir).
+//!
+//! It holds network settings for the [HTTP client](crate::client), which
+//! corporate networks often need, the opt-ins for local
+//! [usage stats](crate::usage) and the [asset store](crate::store), and
+//! optionally the team's [API keys](crate::auth):
+//!
+//! ```toml
+//! [http]
+//! connect_timeout = 10          # seconds
+//! timeout = 300                 # seconds, whole request including the body
+//! proxy = "socks5h://proxy.example.com:1080"
+//! ca_cert = "/etc/ssl/certs/corp-root.pem"
+//!
+//! [stats]
+//! enabled = true                # record command timings for `gametorch stats`
+//!
+//! [store]
+//! enabled = true                # keep downloads once in the store and link to them
+//!
+//! [[keys]]
+//! name = "art"
+//! key = "gt_..."
+//! ```
+//!
+//! `proxy` takes `http://`, `https://`, `socks5://` and `socks5h://` URLs and
+//! applies to all requests; without it the usual `HTTPS_PROXY`/`HTTP_PROXY`
+//! environment variables are honoured. `ca_cert` is a PEM file of extra root
+//! certificates to trust.
+
+use crate::auth::NamedKey;
+use crate::client::ClientBuilder;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File name of the configuration inside the data directory.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Network settings for API requests.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpSection {
+    /// Seconds to wait for a connection.
+    pub connect_timeout: Option<f64>,
+    /// Seconds to wait for a whole request, including its response body.
+    pub timeout: Option<f64>,
+    pub proxy: Option<String>,
+    /// PEM file with additional trusted root certificates.
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl HttpSection {
+    /// Apply these settings to `builder`.
+    pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(secs) = self.connect_timeout {
+            builder = builder.connect_timeout(seconds("connect_timeout", secs)?);
+        }
+        if let Some(secs) = self.timeout {
+            builder = builder.timeout(seconds("timeout", secs)?);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_cert {
+            builder = builder.ca_cert(path);
+        }
+        Ok(builder)
+    }
+}
+
+/// Local usage stats.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatsSection {
+    /// Record every run in the usage log; off unless opted in.
+    pub enabled: bool,
+}
+
+/// The content-addressed [store](crate::store) for downloads.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreSection {
+    /// Keep downloaded ZIPs once in the store and link to them; off unless
+    /// opted in.
+    pub enabled: bool,
+}
+
+/// The configuration file.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub http: HttpSection,
+    pub stats: StatsSection,
+    pub store: StoreSection,
+    /// API keys in failover order.
+    pub keys: Vec<NamedKey>,
+}
+
+impl Config {
+    /// Read a configuration file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Config, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// The configuration in the data directory; defaults if there is none.
+    pub fn load_default() -> Result<Config, Box<dyn std::error::Error + Send + Sync>> {
+        let path = crate::paths::data_dir()?.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        Config::load(path)
+    }
+}
+
+fn seconds(key: &str, secs: f64) -> Result<Duration, Box<dyn std::error::Error + Send + Sync>> {
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|d| !d.is_zero())
+        .ok_or_else(|| format!("http.{} must be a positive number of seconds, got {}", key, secs).into())
+}
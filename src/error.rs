@@ -0,0 +1,166 @@
+//! Error kinds and the CLI's exit codes.
+//!
+//! Every failure falls into an [`ErrorKind`] with a stable exit code, so
+//! scripts can branch on the type of failure:
+//!
+//! | Code | Kind                | Meaning                                        |
+//! |------|---------------------|------------------------------------------------|
+//! | 1    | `error`             | anything else                                  |
+//! | 2    | `validation`        | bad arguments or input, rejected locally or by the API (4xx) |
+//! | 3    | `auth`              | missing or rejected API key (401, 403)         |
+//! | 4    | `network`           | the API could not be reached                   |
+//! | 5    | `server`            | the API failed (5xx)                           |
+//! | 6    | `timeout`           | a request or wait timed out                    |
+//! | 7    | `failed_generation` | the animation failed to render                 |
+//!
+//! The library marks errors it classifies itself with [`Error`];
+//! [`ErrorKind::of`] also recognizes transport errors anywhere in a source
+//! chain.
+
+use std::fmt;
+
+/// What went wrong, broadly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    Other,
+    Validation,
+    Auth,
+    Network,
+    Server,
+    Timeout,
+    FailedGeneration,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Other => "error",
+            ErrorKind::Validation => "validation",
+            ErrorKind::Auth => "auth",
+            ErrorKind::Network => "network",
+            ErrorKind::Server => "server",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::FailedGeneration => "failed_generation",
+        }
+    }
+
+    /// The process exit code for this kind.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Validation => 2,
+            ErrorKind::Auth => 3,
+            ErrorKind::Network => 4,
+            ErrorKind::Server => 5,
+            ErrorKind::Timeout => 6,
+            ErrorKind::FailedGeneration => 7,
+        }
+    }
+
+    /// The kind of an HTTP error status.
+    pub fn from_status(status: reqwest::StatusCode) -> ErrorKind {
+        match status.as_u16() {
+            401 | 403 => ErrorKind::Auth,
+            408 | 504 => ErrorKind::Timeout,
+            400..=499 => ErrorKind::Validation,
+            500..=599 => ErrorKind::Server,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// The kind of `err`: the first classified error in its source chain.
+    pub fn of(err: &(dyn std::error::Error + 'static)) -> ErrorKind {
+        let mut current = Some(err);
+        while let Some(err) = current {
+            if let Some(err) = err.downcast_ref::<Error>() {
+                return err.kind;
+            }
+            if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+                return match err.status() {
+                    Some(status) => ErrorKind::from_status(status),
+                    None if err.is_timeout() => ErrorKind::Timeout,
+                    None if is_connect(err) || err.is_request() || err.is_body() => ErrorKind::Network,
+                    None if err.is_decode() => ErrorKind::Server,
+                    None => ErrorKind::Other,
+                };
+            }
+            if err.is::<crate::poll::PollTimeout>() {
+                return ErrorKind::Timeout;
+            }
+            if let Some(err) = err.downcast_ref::<std::io::Error>()
+                && err.kind() == std::io::ErrorKind::TimedOut
+            {
+                return ErrorKind::Timeout;
+            }
+            current = err.source();
+        }
+        ErrorKind::Other
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ErrorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            ErrorKind::Other,
+            ErrorKind::Validation,
+            ErrorKind::Auth,
+            ErrorKind::Network,
+            ErrorKind::Server,
+            ErrorKind::Timeout,
+            ErrorKind::FailedGeneration,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == s)
+        .ok_or_else(|| format!("unknown error kind '{}'", s))
+    }
+}
+
+/// An error the library has classified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Error {
+        Error {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Whether `err` is a failure to connect; the browser does not say, and
+/// reports it as a request error.
+fn is_connect(err: &reqwest::Error) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        err.is_connect()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = err;
+        false
+    }
+}
@@ -0,0 +1,121 @@
+//! Bevy export: a plain sprite sheet plus the grid parameters needed for
+//! `TextureAtlasLayout::from_grid`.
+//!
+//! Bevy has no metadata file format for atlases, so alongside the sheet we
+//! emit a small Rust snippet that builds the layout in code.
+
+use super::ExportOptions;
+use crate::frames::Sequence;
+use crate::sheet::{self, SheetLayout};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Everything needed to construct a Bevy `TextureAtlasLayout` for a sheet.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BevyAtlas {
+    /// Name used for the generated identifiers.
+    pub name: String,
+    /// Path of the sheet as passed to `AssetServer::load`.
+    pub asset_path: String,
+    pub tile_size: (u32, u32),
+    pub columns: u32,
+    pub rows: u32,
+    pub padding: u32,
+    pub frame_count: u32,
+    /// Average playback rate of the sequence.
+    pub fps: f32,
+}
+
+impl BevyAtlas {
+    /// Derive atlas parameters from a packed layout.
+    pub fn new(sequence: &Sequence, layout: &SheetLayout, name: &str, asset_path: &str) -> BevyAtlas {
+        let total_ms = sequence.total_duration_ms();
+        let fps = if total_ms == 0 {
+            0.0
+        } else {
+            sequence.len() as f32 * 1000.0 / total_ms as f32
+        };
+        BevyAtlas {
+            name: name.to_string(),
+            asset_path: asset_path.to_string(),
+            tile_size: (layout.frame_width, layout.frame_height),
+            columns: layout.columns,
+            rows: layout.rows,
+            padding: layout.padding,
+            frame_count: layout.frame_count,
+            fps,
+        }
+    }
+
+    /// Rust source constructing the atlas with Bevy's API.
+    pub fn snippet(&self) -> String {
+        let ident = rust_ident(&self.name);
+        let upper = ident.to_ascii_uppercase();
+        let padding = if self.padding == 0 {
+            "None".to_string()
+        } else {
+            format!("Some(UVec2::splat({}))", self.padding)
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(out, "// Generated by gametorch for `{}`.", self.name);
+        let _ = writeln!(out, "use bevy::prelude::*;\n");
+        let _ = writeln!(out, "pub const {}_FRAMES: usize = {};", upper, self.frame_count);
+        let _ = writeln!(out, "pub const {}_FPS: f32 = {:.3};\n", upper, self.fps);
+        let _ = writeln!(out, "pub fn {}_atlas(", ident);
+        let _ = writeln!(out, "    asset_server: &AssetServer,");
+        let _ = writeln!(out, "    layouts: &mut Assets<TextureAtlasLayout>,");
+        let _ = writeln!(out, ") -> (Handle<Image>, TextureAtlas) {{");
+        let _ = writeln!(out, "    let texture = asset_server.load(\"{}\");", self.asset_path);
+        let _ = writeln!(
+            out,
+            "    let layout = TextureAtlasLayout::from_grid(UVec2::new({}, {}), {}, {}, {}, None);",
+            self.tile_size.0, self.tile_size.1, self.columns, self.rows, padding
+        );
+        let _ = writeln!(out, "    let layout = layouts.add(layout);");
+        let _ = writeln!(out, "    (texture, TextureAtlas {{ layout, index: 0 }})");
+        let _ = writeln!(out, "}}");
+        out
+    }
+}
+
+/// Pack `sequence` into `<out_dir>/<name>.png` and return the atlas parameters.
+///
+/// The returned [`BevyAtlas`] refers to the sheet by file name; adjust
+/// `asset_path` if the sheet lives in a subdirectory of `assets/`.
+pub fn sprite_sheet(
+    sequence: &Sequence,
+    out_dir: &Path,
+    options: &ExportOptions,
+) -> Result<(PathBuf, BevyAtlas), Box<dyn std::error::Error + Send + Sync>> {
+    std::fs::create_dir_all(out_dir)?;
+    let (sheet, layout) = sheet::pack(sequence, &options.pack);
+    let file_name = format!("{}.png", options.name);
+    let path = out_dir.join(&file_name);
+    super::write_png(&sheet, &path, options.provenance.as_ref())?;
+    Ok((path, BevyAtlas::new(sequence, &layout, &options.name, &file_name)))
+}
+
+pub(crate) fn write(
+    sequence: &Sequence,
+    layout: &SheetLayout,
+    out_dir: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let atlas = BevyAtlas::new(sequence, layout, name, &format!("{}.png", name));
+    let path = out_dir.join(format!("{}.rs", rust_ident(name)));
+    std::fs::write(&path, atlas.snippet())?;
+    Ok(vec![path])
+}
+
+/// Turn an arbitrary asset name into a snake_case Rust identifier.
+fn rust_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
@@ -0,0 +1,294 @@
+//! "Engine drop" exports: write straight into a game project.
+//!
+//! Each [`EnginePreset`] fixes where assets land inside the project and how
+//! files are named. Every drop bumps a revision in a JSON manifest next to the
+//! assets; the bundled editor scripts (installed on first drop) watch that
+//! manifest and reimport whatever changed, so regenerated assets hot-reload
+//! into a running editor.
+
+use super::{ExportOptions, Target};
+use crate::frames::Sequence;
+use crate::sheet::PackOptions;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the manifest written inside the preset's root directory.
+pub const MANIFEST_FILE: &str = "gametorch-drop.json";
+
+const UNITY_WATCHER: &str = include_str!("../../engines/unity/GametorchDropWatcher.cs");
+const GODOT_PLUGIN_CFG: &str = include_str!("../../engines/godot/gametorch_drop/plugin.cfg");
+const GODOT_PLUGIN_GD: &str = include_str!("../../engines/godot/gametorch_drop/plugin.gd");
+
+/// Game engines with a built-in drop preset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    Unity,
+    Godot,
+}
+
+impl Engine {
+    pub const ALL: &'static [Engine] = &[Engine::Unity, Engine::Godot];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Engine::Unity => "unity",
+            Engine::Godot => "godot",
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Engine::ALL
+            .iter()
+            .copied()
+            .find(|e| e.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = Engine::ALL.iter().map(|e| e.as_str()).collect();
+                format!("unknown engine '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Directory and naming contract for one engine.
+///
+/// `dir` and `file` are templates; `{name}` expands to the asset name,
+/// `{slug}` to its [`slug`](crate::slug::slug) and `{engine}` to the engine
+/// name.
+#[derive(Clone, Debug)]
+pub struct EnginePreset {
+    pub engine: Engine,
+    pub target: Target,
+    /// Directory (relative to the project root) holding all drops and the manifest.
+    pub root: String,
+    /// Per-asset directory under `root`.
+    pub dir: String,
+    /// Base name of the files written for each asset.
+    pub file: String,
+}
+
+impl EnginePreset {
+    /// The built-in preset for `engine`.
+    pub fn for_engine(engine: Engine) -> EnginePreset {
+        match engine {
+            Engine::Unity => EnginePreset {
+                engine,
+                target: Target::Unity,
+                root: "Assets/GameTorch".to_string(),
+                dir: "{name}".to_string(),
+                file: "{name}".to_string(),
+            },
+            Engine::Godot => EnginePreset {
+                engine,
+                target: Target::Godot,
+                root: "gametorch".to_string(),
+                dir: "{name}".to_string(),
+                file: "{name}".to_string(),
+            },
+        }
+    }
+
+    fn expand(&self, template: &str, name: &str) -> String {
+        template
+            .replace("{name}", name)
+            .replace("{slug}", &crate::slug::slug(name))
+            .replace("{engine}", self.engine.as_str())
+    }
+}
+
+/// The manifest watched by the engine-side import scripts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DropManifest {
+    pub version: u32,
+    pub engine: String,
+    /// Incremented on every drop into this project.
+    pub revision: u64,
+    pub assets: Vec<DropAsset>,
+}
+
+/// One asset tracked by the drop manifest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DropAsset {
+    pub name: String,
+    /// Manifest revision at which this asset last changed.
+    pub revision: u64,
+    pub target: String,
+    /// Written files, relative to the project root with `/` separators.
+    pub files: Vec<String>,
+    pub frames: usize,
+    /// Unix timestamp (seconds) of the last drop.
+    pub updated_at: u64,
+}
+
+/// Outcome of a drop.
+#[derive(Debug)]
+pub struct Dropped {
+    pub asset: DropAsset,
+    pub manifest: PathBuf,
+    /// Editor scripts installed by this drop (empty once they exist).
+    pub installed_scripts: Vec<PathBuf>,
+}
+
+/// Export `sequence` as asset `name` into the project at `project`.
+pub fn drop_into(
+    project: &Path,
+    preset: &EnginePreset,
+    name: &str,
+    sequence: &Sequence,
+    pack: &PackOptions,
+) -> Result<Dropped, Box<dyn std::error::Error + Send + Sync>> {
+    if !project.is_dir() {
+        return Err(format!("project directory {} does not exist", project.display()).into());
+    }
+
+    let root = project.join(checked_relative(&preset.root)?);
+    let asset_dir = root.join(checked_relative(&preset.expand(&preset.dir, name))?);
+    let manifest_path = root.join(MANIFEST_FILE);
+
+    let mut manifest = read_manifest(&manifest_path)?;
+    if !manifest.engine.is_empty() && manifest.engine != preset.engine.as_str() {
+        return Err(format!(
+            "{} belongs to a {} project, not {}",
+            manifest_path.display(),
+            manifest.engine,
+            preset.engine
+        )
+        .into());
+    }
+    manifest.version = 1;
+    manifest.engine = preset.engine.to_string();
+    manifest.revision += 1;
+
+    let options = ExportOptions {
+        name: preset.expand(&preset.file, name),
+        pack: pack.clone(),
+        ..Default::default()
+    };
+    let exported = super::export(sequence, preset.target, &asset_dir, &options)?;
+
+    let files = exported
+        .files
+        .iter()
+        .map(|f| project_relative(project, f))
+        .collect::<Result<Vec<_>, _>>()?;
+    let asset = DropAsset {
+        name: name.to_string(),
+        revision: manifest.revision,
+        target: preset.target.to_string(),
+        files,
+        frames: sequence.len(),
+        updated_at: unix_now(),
+    };
+    match manifest.assets.iter_mut().find(|a| a.name == name) {
+        Some(existing) => *existing = asset.clone(),
+        None => manifest.assets.push(asset.clone()),
+    }
+
+    // The manifest goes last so watchers never see a revision whose files
+    // are still being written.
+    write_manifest(&manifest_path, &manifest)?;
+    let manifest_rel = project_relative(project, &manifest_path)?;
+    let installed_scripts = install_scripts(project, preset.engine, &manifest_rel)?;
+
+    Ok(Dropped {
+        asset,
+        manifest: manifest_path,
+        installed_scripts,
+    })
+}
+
+fn read_manifest(path: &Path) -> Result<DropManifest, Box<dyn std::error::Error + Send + Sync>> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| format!("invalid drop manifest {}: {}", path.display(), e).into()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DropManifest::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write via a temporary file and rename, so readers see old or new, never half.
+fn write_manifest(
+    path: &Path,
+    manifest: &DropManifest,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(manifest)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Install the editor-side watcher unless the project already has one.
+fn install_scripts(
+    project: &Path,
+    engine: Engine,
+    manifest_rel: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let scripts: Vec<(PathBuf, &str)> = match engine {
+        Engine::Unity => vec![(
+            project.join("Assets/GameTorch/Editor/GametorchDropWatcher.cs"),
+            UNITY_WATCHER,
+        )],
+        Engine::Godot => vec![
+            (project.join("addons/gametorch_drop/plugin.cfg"), GODOT_PLUGIN_CFG),
+            (project.join("addons/gametorch_drop/plugin.gd"), GODOT_PLUGIN_GD),
+        ],
+    };
+
+    let mut installed = Vec::new();
+    for (path, contents) in scripts {
+        if path.exists() {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents.replace("{{MANIFEST_PATH}}", manifest_rel))?;
+        installed.push(path);
+    }
+    Ok(installed)
+}
+
+/// Reject absolute paths and `..` so templates cannot escape the project.
+fn checked_relative(path: &str) -> Result<&Path, Box<dyn std::error::Error + Send + Sync>> {
+    let p = Path::new(path);
+    if p.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        Ok(p)
+    } else {
+        Err(format!("drop path '{}' must stay inside the project", path).into())
+    }
+}
+
+fn project_relative(
+    project: &Path,
+    path: &Path,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let rel = path
+        .strip_prefix(project)
+        .map_err(|_| format!("{} is outside the project", path.display()))?;
+    let parts: Vec<_> = rel
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect();
+    Ok(parts.join("/"))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,104 @@
+//! GameMaker export: a single horizontal strip named `<name>_strip<N>.png`.
+//!
+//! GameMaker's sprite import detects the `_stripN` suffix and slices the
+//! image into N equal frames automatically. The optional `.yy` file is a
+//! minimal GMSprite stub for projects that add resources by hand.
+
+use crate::frames::Sequence;
+use crate::sheet::SheetLayout;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// File name of the strip for `frame_count` frames.
+pub(crate) fn strip_name(name: &str, frame_count: usize) -> String {
+    format!("{}_strip{}.png", name, frame_count)
+}
+
+pub(crate) fn write(
+    sequence: &Sequence,
+    layout: &SheetLayout,
+    out_dir: &Path,
+    name: &str,
+    yy_stub: bool,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    if !yy_stub {
+        return Ok(Vec::new());
+    }
+    let path = out_dir.join(format!("{}.yy", name));
+    std::fs::write(&path, serde_json::to_string_pretty(&sprite_yy(sequence, layout, name))?)?;
+    Ok(vec![path])
+}
+
+fn sprite_yy(sequence: &Sequence, layout: &SheetLayout, name: &str) -> serde_json::Value {
+    let (w, h) = (layout.frame_width, layout.frame_height);
+    let total_ms = sequence.total_duration_ms();
+    let fps = if total_ms == 0 {
+        0.0
+    } else {
+        sequence.len() as f64 * 1000.0 / total_ms as f64
+    };
+    let layer_id = uuid(&format!("{}/layer", name));
+    let yy_path = format!("sprites/{0}/{0}.yy", name);
+
+    let frames: Vec<_> = (0..sequence.len())
+        .map(|i| {
+            json!({
+                "resourceType": "GMSpriteFrame",
+                "resourceVersion": "1.1",
+                "name": uuid(&format!("{}/frame/{}", name, i)),
+            })
+        })
+        .collect();
+
+    json!({
+        "resourceType": "GMSprite",
+        "resourceVersion": "1.0",
+        "name": name,
+        "type": 0,
+        "width": w,
+        "height": h,
+        "bboxMode": 0,
+        "bbox_left": 0,
+        "bbox_right": w.saturating_sub(1),
+        "bbox_top": 0,
+        "bbox_bottom": h.saturating_sub(1),
+        "collisionKind": 1,
+        "origin": 4,
+        "preMultiplyAlpha": false,
+        "edgeFiltering": false,
+        "frames": frames,
+        "layers": [{
+            "resourceType": "GMImageLayer",
+            "resourceVersion": "1.0",
+            "name": layer_id,
+            "displayName": "default",
+            "visible": true,
+            "isLocked": false,
+            "blendMode": 0,
+            "opacity": 100.0,
+        }],
+        "sequence": {
+            "resourceType": "GMSequence",
+            "resourceVersion": "1.4",
+            "name": name,
+            "playback": 1,
+            "playbackSpeed": fps,
+            "playbackSpeedType": 0,
+            "length": sequence.len() as f64,
+            "xorigin": w / 2,
+            "yorigin": h / 2,
+            "spriteId": { "name": name, "path": yy_path },
+        },
+        "textureGroupId": { "name": "Default", "path": "texturegroups/Default" },
+        "parent": { "name": "Sprites", "path": "folders/Sprites.yy" },
+        "tags": [],
+    })
+}
+
+/// Deterministic RFC 4122-shaped identifier for `seed`.
+fn uuid(seed: &str) -> String {
+    let d = Sha256::digest(seed.as_bytes());
+    let hex: String = d[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
@@ -0,0 +1,131 @@
+//! Godot 4 export: a `SpriteFrames` resource (`.tres`) slicing the sheet with
+//! `AtlasTexture` regions, ready to assign to an `AnimatedSprite2D`.
+//!
+//! Sequences with markers also get `<name>_events.tres`, an `Animation` for an
+//! `AnimationPlayer` whose root node is the sprite: a value track steps
+//! `frame`, and a method track calls `_on_animation_marker(name)` on each
+//! marker.
+
+use crate::frames::Sequence;
+use crate::sheet::SheetLayout;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+pub(crate) fn write(
+    sequence: &Sequence,
+    layout: &SheetLayout,
+    out_dir: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let path = out_dir.join(format!("{}.tres", name));
+    std::fs::write(&path, sprite_frames(sequence, layout, name))?;
+    let mut files = vec![path];
+    if !sequence.markers.is_empty() {
+        let path = out_dir.join(format!("{}_events.tres", name));
+        std::fs::write(&path, event_animation(sequence, name))?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+fn sprite_frames(sequence: &Sequence, layout: &SheetLayout, name: &str) -> String {
+    // Godot stores a single playback speed plus a relative duration per frame,
+    // so the shortest frame becomes the unit.
+    let base_ms = sequence
+        .frames
+        .iter()
+        .map(|f| f.duration_ms)
+        .min()
+        .unwrap_or(1)
+        .max(1);
+    let speed = 1000.0 / base_ms as f64;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "[gd_resource type=\"SpriteFrames\" load_steps={} format=3]\n",
+        sequence.len() + 2
+    );
+    // Relative paths are resolved against the directory of this resource.
+    let _ = writeln!(out, "[ext_resource type=\"Texture2D\" path=\"{}.png\" id=\"1_sheet\"]\n", name);
+
+    for (i, rect) in layout.rects().iter().enumerate() {
+        let _ = writeln!(out, "[sub_resource type=\"AtlasTexture\" id=\"AtlasTexture_{}\"]", i);
+        let _ = writeln!(out, "atlas = ExtResource(\"1_sheet\")");
+        let _ = writeln!(
+            out,
+            "region = Rect2({}, {}, {}, {})\n",
+            rect.x, rect.y, rect.width, rect.height
+        );
+    }
+
+    let frames: Vec<String> = sequence
+        .frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            format!(
+                "{{\n\"duration\": {:.3},\n\"texture\": SubResource(\"AtlasTexture_{}\")\n}}",
+                frame.duration_ms as f64 / base_ms as f64,
+                i
+            )
+        })
+        .collect();
+
+    let _ = writeln!(out, "[resource]");
+    let _ = writeln!(out, "animations = [{{");
+    let _ = writeln!(out, "\"frames\": [{}],", frames.join(", "));
+    let _ = writeln!(out, "\"loop\": true,");
+    let _ = writeln!(out, "\"name\": &\"{}\",", name);
+    let _ = writeln!(out, "\"speed\": {:.3}", speed);
+    let _ = writeln!(out, "}}]");
+    out
+}
+
+fn event_animation(sequence: &Sequence, name: &str) -> String {
+    let seconds = |ms: u64| format!("{:.3}", ms as f64 / 1000.0);
+    let float_array = |values: &[String]| format!("PackedFloat32Array({})", values.join(", "));
+
+    let frame_times: Vec<String> = (0..sequence.len()).map(|i| seconds(sequence.frame_start_ms(i))).collect();
+    let frame_values: Vec<String> = (0..sequence.len()).map(|i| i.to_string()).collect();
+    let marker_times: Vec<String> = sequence
+        .markers
+        .iter()
+        .map(|m| seconds(sequence.frame_start_ms(m.frame)))
+        .collect();
+    let calls: Vec<String> = sequence
+        .markers
+        .iter()
+        .map(|m| format!("{{\n\"args\": [{:?}],\n\"method\": &\"_on_animation_marker\"\n}}", m.name))
+        .collect();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "[gd_resource type=\"Animation\" format=3]\n");
+    let _ = writeln!(out, "[resource]");
+    let _ = writeln!(out, "resource_name = \"{}\"", name);
+    let _ = writeln!(out, "length = {}", seconds(sequence.total_duration_ms()));
+    let _ = writeln!(out, "loop_mode = 1");
+    track_header(&mut out, 0, "value", ".:frame");
+    let _ = writeln!(out, "tracks/0/keys = {{");
+    let _ = writeln!(out, "\"times\": {},", float_array(&frame_times));
+    let _ = writeln!(out, "\"transitions\": {},", float_array(&vec!["1".to_string(); frame_times.len()]));
+    let _ = writeln!(out, "\"update\": 1,");
+    let _ = writeln!(out, "\"values\": [{}]", frame_values.join(", "));
+    let _ = writeln!(out, "}}");
+    track_header(&mut out, 1, "method", ".");
+    let _ = writeln!(out, "tracks/1/keys = {{");
+    let _ = writeln!(out, "\"times\": {},", float_array(&marker_times));
+    let _ = writeln!(out, "\"transitions\": {},", float_array(&vec!["1".to_string(); marker_times.len()]));
+    let _ = writeln!(out, "\"values\": [{}]", calls.join(", "));
+    let _ = writeln!(out, "}}");
+    out
+}
+
+fn track_header(out: &mut String, index: usize, kind: &str, path: &str) {
+    let _ = writeln!(out, "tracks/{}/type = \"{}\"", index, kind);
+    let _ = writeln!(out, "tracks/{}/imported = false", index);
+    let _ = writeln!(out, "tracks/{}/enabled = true", index);
+    let _ = writeln!(out, "tracks/{}/path = NodePath(\"{}\")", index, path);
+    let _ = writeln!(out, "tracks/{}/interp = 1", index);
+    let _ = writeln!(out, "tracks/{}/loop_wrap = true", index);
+}
@@ -0,0 +1,92 @@
+//! LÖVE (Love2D) export: a Lua module describing every quad and its timing.
+//!
+//! ```lua
+//! local walk = require("walk").load()
+//! love.graphics.draw(walk.texture, walk.quads[1], x, y)
+//! ```
+
+use crate::frames::Sequence;
+use crate::sheet::SheetLayout;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+pub(crate) fn write(
+    sequence: &Sequence,
+    layout: &SheetLayout,
+    out_dir: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let path = out_dir.join(format!("{}.lua", name));
+    std::fs::write(&path, lua_module(sequence, layout, name))?;
+    Ok(vec![path])
+}
+
+fn lua_module(sequence: &Sequence, layout: &SheetLayout, name: &str) -> String {
+    let total_ms = sequence.total_duration_ms();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "-- Generated by gametorch for `{}`.", name);
+    let _ = writeln!(out, "local M = {{");
+    let _ = writeln!(out, "  image = {:?},", format!("{}.png", name));
+    let _ = writeln!(out, "  width = {},", layout.width);
+    let _ = writeln!(out, "  height = {},", layout.height);
+    let _ = writeln!(out, "  frame_width = {},", layout.frame_width);
+    let _ = writeln!(out, "  frame_height = {},", layout.frame_height);
+    let _ = writeln!(out, "  duration = {:.3},", total_ms as f64 / 1000.0);
+    let _ = writeln!(out, "  frames = {{");
+    for (rect, frame) in layout.rects().iter().zip(&sequence.frames) {
+        let _ = writeln!(
+            out,
+            "    {{ x = {}, y = {}, w = {}, h = {}, duration = {:.3} }},",
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            frame.duration_ms as f64 / 1000.0
+        );
+    }
+    let _ = writeln!(out, "  }},");
+    // Frame numbers are 1-based to match `frames` and `quads`.
+    let _ = writeln!(out, "  markers = {{");
+    for marker in &sequence.markers {
+        let _ = writeln!(
+            out,
+            "    {{ name = {:?}, frame = {}, time = {:.3} }},",
+            marker.name,
+            marker.frame + 1,
+            sequence.frame_start_ms(marker.frame) as f64 / 1000.0
+        );
+    }
+    let _ = writeln!(out, "  }},");
+    let _ = writeln!(out, "}}\n");
+    out.push_str(LOAD_FUNCTION);
+    out
+}
+
+const LOAD_FUNCTION: &str = r#"-- Create the texture and one Quad per frame. `dir` is the directory holding
+-- the sheet, relative to the game root (defaults to the root itself).
+function M.load(dir)
+  local path = dir and (dir .. "/" .. M.image) or M.image
+  M.texture = love.graphics.newImage(path)
+  M.texture:setFilter("nearest", "nearest")
+  M.quads = {}
+  for i, f in ipairs(M.frames) do
+    M.quads[i] = love.graphics.newQuad(f.x, f.y, f.w, f.h, M.width, M.height)
+  end
+  return M
+end
+
+-- Index of the frame shown `t` seconds into a looping playback.
+function M.frame_at(t)
+  t = t % M.duration
+  for i, f in ipairs(M.frames) do
+    if t < f.duration then
+      return i
+    end
+    t = t - f.duration
+  end
+  return #M.frames
+end
+
+return M
+"#;
@@ -0,0 +1,167 @@
+//! Engine-specific exports of animation results.
+//!
+//! Every target packs the frames into a single sprite sheet (see
+//! [`crate::sheet`]) and writes whatever metadata the engine needs to slice it
+//! back into individual sprites on import.
+
+pub mod bevy;
+pub mod engine_drop;
+mod gamemaker;
+mod godot;
+mod love;
+mod texturepacker;
+mod unity;
+
+use crate::frames::Sequence;
+use crate::provenance::{self, Provenance};
+use crate::sheet::{self, PackOptions, SheetLayout};
+use image::RgbaImage;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Supported export targets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Sheet plus a Unity `.png.meta` with the sprites pre-sliced.
+    Unity,
+    /// Sheet plus a TexturePacker-compatible JSON (array format).
+    TexturePacker,
+    /// Sheet plus a Godot 4 `SpriteFrames` resource.
+    Godot,
+    /// Sheet plus a Rust snippet building a Bevy `TextureAtlasLayout`.
+    Bevy,
+    /// Sheet plus a LÖVE Lua module of quads and frame timings.
+    Love,
+    /// Horizontal `<name>_stripN.png` strip, optionally with a `.yy` stub.
+    GameMaker,
+}
+
+impl Target {
+    pub const ALL: &'static [Target] = &[
+        Target::Unity,
+        Target::TexturePacker,
+        Target::Godot,
+        Target::Bevy,
+        Target::Love,
+        Target::GameMaker,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Target::Unity => "unity",
+            Target::TexturePacker => "texturepacker",
+            Target::Godot => "godot",
+            Target::Bevy => "bevy",
+            Target::Love => "love",
+            Target::GameMaker => "gamemaker",
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Target::ALL
+            .iter()
+            .copied()
+            .find(|t| t.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = Target::ALL.iter().map(|t| t.as_str()).collect();
+                format!("unknown export target '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Options shared by all export targets.
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    /// Base name used for every written file (`<name>.png`, ...).
+    pub name: String,
+    pub pack: PackOptions,
+    /// Also write a GameMaker `.yy` sprite stub (gamemaker target only).
+    pub gamemaker_yy: bool,
+    /// Generation metadata to embed in the sheet (see [`crate::provenance`]).
+    pub provenance: Option<Provenance>,
+}
+
+/// Files written by an export, plus the sheet geometry.
+#[derive(Debug)]
+pub struct Exported {
+    pub files: Vec<PathBuf>,
+    pub layout: SheetLayout,
+}
+
+/// Pack `sequence` and write it to `out_dir` in the format of `target`.
+pub fn export(
+    sequence: &Sequence,
+    target: Target,
+    out_dir: &Path,
+    options: &ExportOptions,
+) -> Result<Exported, Box<dyn std::error::Error + Send + Sync>> {
+    if sequence.is_empty() {
+        return Err("nothing to export: sequence has no frames".into());
+    }
+    std::fs::create_dir_all(out_dir)?;
+
+    // GameMaker slices strips by dividing the width evenly, so it always
+    // gets a single unpadded row.
+    let (pack, sheet_name) = match target {
+        Target::GameMaker => (
+            PackOptions {
+                columns: Some(sequence.len() as u32),
+                padding: 0,
+            },
+            gamemaker::strip_name(&options.name, sequence.len()),
+        ),
+        _ => (options.pack.clone(), format!("{}.png", options.name)),
+    };
+    let (sheet, layout) = sheet::pack(sequence, &pack);
+    let sheet_path = out_dir.join(sheet_name);
+    write_png(&sheet, &sheet_path, options.provenance.as_ref())?;
+
+    let mut files = vec![sheet_path];
+    match target {
+        Target::Unity => files.extend(unity::write(sequence, &layout, out_dir, &options.name)?),
+        Target::TexturePacker => {
+            files.extend(texturepacker::write(sequence, &layout, out_dir, &options.name)?)
+        }
+        Target::Godot => files.extend(godot::write(sequence, &layout, out_dir, &options.name)?),
+        Target::Bevy => files.extend(bevy::write(sequence, &layout, out_dir, &options.name)?),
+        Target::Love => files.extend(love::write(sequence, &layout, out_dir, &options.name)?),
+        Target::GameMaker => files.extend(gamemaker::write(
+            sequence,
+            &layout,
+            out_dir,
+            &options.name,
+            options.gamemaker_yy,
+        )?),
+    }
+
+    Ok(Exported { files, layout })
+}
+
+/// Name of the sprite at `index` as it appears in engine metadata.
+pub(crate) fn frame_name(name: &str, index: usize) -> String {
+    format!("{}_{}", name, index)
+}
+
+fn write_png(
+    image: &RgbaImage,
+    path: &Path,
+    provenance: Option<&Provenance>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+    if let Some(provenance) = provenance.filter(|p| !p.is_empty()) {
+        png = provenance::embed_png(&png, provenance)?;
+    }
+    std::fs::write(path, png).map_err(|e| format!("failed to write {}: {}", path.display(), e).into())
+}
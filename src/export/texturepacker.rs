@@ -0,0 +1,64 @@
+//! TexturePacker "JSON (Array)" export, understood by Unity importers,
+//! Phaser, PixiJS and most other 2D frameworks.
+
+use super::frame_name;
+use crate::frames::Sequence;
+use crate::sheet::SheetLayout;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+pub(crate) fn write(
+    sequence: &Sequence,
+    layout: &SheetLayout,
+    out_dir: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let frames: Vec<_> = layout
+        .rects()
+        .iter()
+        .zip(&sequence.frames)
+        .enumerate()
+        .map(|(i, (rect, frame))| {
+            json!({
+                "filename": frame_name(name, i),
+                "frame": { "x": rect.x, "y": rect.y, "w": rect.width, "h": rect.height },
+                "rotated": false,
+                "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": rect.width, "h": rect.height },
+                "sourceSize": { "w": rect.width, "h": rect.height },
+                "duration": frame.duration_ms,
+            })
+        })
+        .collect();
+
+    let mut doc = json!({
+        "frames": frames,
+        "meta": {
+            "app": "https://gametorch.app",
+            "version": env!("CARGO_PKG_VERSION"),
+            "image": format!("{}.png", name),
+            "format": "RGBA8888",
+            "size": { "w": layout.width, "h": layout.height },
+            "scale": "1",
+        },
+    });
+    if !sequence.markers.is_empty() {
+        let markers: Vec<_> = sequence
+            .markers
+            .iter()
+            .map(|m| {
+                json!({
+                    "name": m.name,
+                    "frame": m.frame,
+                    "filename": frame_name(name, m.frame),
+                    "time": sequence.frame_start_ms(m.frame),
+                })
+            })
+            .collect();
+        doc["meta"]["markers"] = json!(markers);
+    }
+
+    let path = out_dir.join(format!("{}.json", name));
+    std::fs::write(&path, serde_json::to_string_pretty(&doc)?)?;
+    Ok(vec![path])
+}
@@ -0,0 +1,186 @@
+//! Unity export: a `.png.meta` importing the sheet as a multi-sprite texture.
+//!
+//! The GUID and sprite IDs are derived from the export name, so re-exporting
+//! the same asset keeps existing scene and prefab references intact.
+//!
+//! When the sequence has markers, a `<name>.anim` clip is written as well. It
+//! swaps the `SpriteRenderer` sprite on every frame and raises an animation
+//! event calling `OnAnimationMarker(string)` with the marker name.
+
+use super::frame_name;
+use crate::frames::Sequence;
+use crate::sheet::SheetLayout;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Base of Unity's legacy sprite file IDs (class 213); each sprite adds 2.
+pub(crate) const SPRITE_FILE_ID_BASE: i64 = 21300000;
+
+const IMPORTER_SETTINGS: &str = "  externalObjects: {}
+  serializedVersion: 12
+  mipmaps:
+    enableMipMap: 0
+  isReadable: 0
+  textureType: 8
+  textureShape: 1
+  spriteMode: 2
+  spritePixelsToUnits: 100
+  alphaUsage: 1
+  alphaIsTransparency: 1
+  textureSettings:
+    serializedVersion: 2
+    filterMode: 0
+    aniso: 1
+    mipBias: 0
+    wrapU: 1
+    wrapV: 1
+    wrapW: 1
+  textureFormat: 1
+  textureCompression: 0
+  spriteSheet:
+    serializedVersion: 2
+    sprites:
+";
+
+/// Binding of the clip's only curve: `SpriteRenderer.m_Sprite` on the root.
+const CLIP_BINDINGS: &str = "  m_ClipBindingConstant:
+    genericBindings:
+    - serializedVersion: 2
+      path: 0
+      attribute: 0
+      script: {fileID: 0}
+      typeID: 212
+      customType: 23
+      isPPtrCurve: 1
+";
+
+const IMPORTER_FOOTER: &str = "  spritePackingTag:
+  userData:
+  assetBundleName:
+  assetBundleVariant:
+";
+
+pub(crate) fn write(
+    sequence: &Sequence,
+    layout: &SheetLayout,
+    out_dir: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let path = out_dir.join(format!("{}.png.meta", name));
+    std::fs::write(&path, texture_meta(sequence, layout, name))?;
+    let mut files = vec![path];
+    if !sequence.markers.is_empty() {
+        let path = out_dir.join(format!("{}.anim", name));
+        std::fs::write(&path, animation_clip(sequence, name))?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+/// Deterministic 32-hex-digit identifier for `seed`.
+pub(crate) fn guid(seed: &str) -> String {
+    let digest = Sha256::digest(seed.as_bytes());
+    digest[..16].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn sprite_file_id(index: usize) -> i64 {
+    SPRITE_FILE_ID_BASE + 2 * index as i64
+}
+
+fn texture_meta(sequence: &Sequence, layout: &SheetLayout, name: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "fileFormatVersion: 2");
+    let _ = writeln!(out, "guid: {}", guid(name));
+    let _ = writeln!(out, "TextureImporter:");
+    let _ = writeln!(out, "  internalIDToNameTable:");
+    for i in 0..sequence.len() {
+        let _ = writeln!(out, "  - first:");
+        let _ = writeln!(out, "      213: {}", sprite_file_id(i));
+        let _ = writeln!(out, "    second: {}", frame_name(name, i));
+    }
+    out.push_str(IMPORTER_SETTINGS);
+    for (i, rect) in layout.rects().iter().enumerate() {
+        let sprite = frame_name(name, i);
+        // Unity measures sprite rects from the bottom-left corner.
+        let y = layout.height - rect.y - rect.height;
+        let _ = writeln!(out, "    - serializedVersion: 2");
+        let _ = writeln!(out, "      name: {}", sprite);
+        let _ = writeln!(out, "      rect:");
+        let _ = writeln!(out, "        serializedVersion: 2");
+        let _ = writeln!(out, "        x: {}", rect.x);
+        let _ = writeln!(out, "        y: {}", y);
+        let _ = writeln!(out, "        width: {}", rect.width);
+        let _ = writeln!(out, "        height: {}", rect.height);
+        let _ = writeln!(out, "      alignment: 0");
+        let _ = writeln!(out, "      pivot: {{x: 0.5, y: 0.5}}");
+        let _ = writeln!(out, "      border: {{x: 0, y: 0, z: 0, w: 0}}");
+        let _ = writeln!(out, "      outline: []");
+        let _ = writeln!(out, "      physicsShape: []");
+        let _ = writeln!(out, "      tessellationDetail: 0");
+        let _ = writeln!(out, "      bones: []");
+        let _ = writeln!(out, "      spriteID: {}", guid(&sprite));
+        let _ = writeln!(out, "      internalID: {}", sprite_file_id(i));
+        let _ = writeln!(out, "      vertices: []");
+        let _ = writeln!(out, "      indices:");
+        let _ = writeln!(out, "      edges: []");
+        let _ = writeln!(out, "      weights: []");
+    }
+    out.push_str(IMPORTER_FOOTER);
+    out
+}
+
+fn animation_clip(sequence: &Sequence, name: &str) -> String {
+    let texture = guid(name);
+    let sprite_ref = |i: usize| format!("{{fileID: {}, guid: {}, type: 3}}", sprite_file_id(i), texture);
+    let seconds = |ms: u64| ms as f64 / 1000.0;
+    let total = seconds(sequence.total_duration_ms());
+
+    let mut out = String::new();
+    let _ = writeln!(out, "%YAML 1.1");
+    let _ = writeln!(out, "%TAG !u! tag:unity3d.com,2011:");
+    let _ = writeln!(out, "--- !u!74 &7400000");
+    let _ = writeln!(out, "AnimationClip:");
+    let _ = writeln!(out, "  m_ObjectHideFlags: 0");
+    let _ = writeln!(out, "  m_Name: {}", name);
+    let _ = writeln!(out, "  serializedVersion: 7");
+    let _ = writeln!(out, "  m_Legacy: 0");
+    let _ = writeln!(out, "  m_Compressed: 0");
+    let _ = writeln!(out, "  m_UseHighQualityCurve: 1");
+    let _ = writeln!(out, "  m_PPtrCurves:");
+    let _ = writeln!(out, "  - curve:");
+    for i in 0..sequence.len() {
+        let _ = writeln!(out, "    - time: {:.3}", seconds(sequence.frame_start_ms(i)));
+        let _ = writeln!(out, "      value: {}", sprite_ref(i));
+    }
+    // Hold the last sprite until the clip loops.
+    let _ = writeln!(out, "    - time: {:.3}", total);
+    let _ = writeln!(out, "      value: {}", sprite_ref(sequence.len() - 1));
+    let _ = writeln!(out, "    attribute: m_Sprite");
+    let _ = writeln!(out, "    path:");
+    let _ = writeln!(out, "    classID: 212");
+    let _ = writeln!(out, "    script: {{fileID: 0}}");
+    let _ = writeln!(out, "  m_SampleRate: 1000");
+    let _ = writeln!(out, "  m_WrapMode: 0");
+    out.push_str(CLIP_BINDINGS);
+    let _ = writeln!(out, "    pptrCurveMapping:");
+    for i in 0..sequence.len() {
+        let _ = writeln!(out, "    - {}", sprite_ref(i));
+    }
+    let _ = writeln!(out, "  m_AnimationClipSettings:");
+    let _ = writeln!(out, "    serializedVersion: 2");
+    let _ = writeln!(out, "    m_StartTime: 0");
+    let _ = writeln!(out, "    m_StopTime: {:.3}", total);
+    let _ = writeln!(out, "    m_LoopTime: 1");
+    let _ = writeln!(out, "  m_Events:");
+    for marker in &sequence.markers {
+        let _ = writeln!(out, "  - time: {:.3}", seconds(sequence.frame_start_ms(marker.frame)));
+        let _ = writeln!(out, "    functionName: OnAnimationMarker");
+        let _ = writeln!(out, "    data: {}", marker.name);
+        let _ = writeln!(out, "    objectReferenceParameter: {{fileID: 0}}");
+        let _ = writeln!(out, "    floatParameter: 0");
+        let _ = writeln!(out, "    intParameter: {}", marker.frame);
+        let _ = writeln!(out, "    messageOptions: 0");
+    }
+    out
+}
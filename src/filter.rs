@@ -0,0 +1,207 @@
+//! A small filter language for animation listings.
+//!
+//! A filter is a space-separated list of terms that must all match:
+//!
+//! ```text
+//! status!=failed prompt~knight id>1200 tags=hero
+//! ```
+//!
+//! | Operator | Meaning                               |
+//! |----------|---------------------------------------|
+//! | `=`      | equal (case-insensitive)              |
+//! | `!=`     | not equal                             |
+//! | `~`      | contains (case-insensitive)           |
+//! | `>` `<`  | numeric comparison                    |
+//!
+//! Fields are looked up in the item's JSON (`a.b` for nested objects). Array
+//! fields such as `tags` match when any element does, and numeric `status`
+//! codes also match their names (`generating`, `complete`, `failed`). Values
+//! may be quoted to include spaces: `prompt~"walking left"`.
+
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    NotEq,
+    Contains,
+    Gt,
+    Lt,
+}
+
+impl Op {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::NotEq => "!=",
+            Op::Contains => "~",
+            Op::Gt => ">",
+            Op::Lt => "<",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Term {
+    field: String,
+    op: Op,
+    value: String,
+}
+
+/// A parsed filter; the empty filter matches everything.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filter {
+    terms: Vec<Term>,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Whether every term matches `item`.
+    pub fn matches(&self, item: &Value) -> bool {
+        self.terms.iter().all(|term| term.matches(item))
+    }
+}
+
+impl Term {
+    fn matches(&self, item: &Value) -> bool {
+        let found = self
+            .field
+            .split('.')
+            .try_fold(item, |v, key| v.get(key))
+            .unwrap_or(&Value::Null);
+        let candidates: Vec<&Value> = match found {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        // `!=` must hold for every element, i.e. no element is equal.
+        if self.op == Op::NotEq {
+            candidates.iter().all(|v| self.matches_value(v))
+        } else {
+            candidates.iter().any(|v| self.matches_value(v))
+        }
+    }
+
+    fn matches_value(&self, value: &Value) -> bool {
+        let texts = texts(&self.field, value);
+        let wanted = self.value.to_lowercase();
+        match self.op {
+            Op::Eq => texts.iter().any(|t| t.to_lowercase() == wanted),
+            Op::NotEq => !texts.iter().any(|t| t.to_lowercase() == wanted),
+            Op::Contains => texts.iter().any(|t| t.to_lowercase().contains(&wanted)),
+            Op::Gt | Op::Lt => match (as_number(value), self.value.parse::<f64>()) {
+                (Some(v), Ok(w)) if self.op == Op::Gt => v > w,
+                (Some(v), Ok(w)) => v < w,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Textual forms a value can match: its string form, plus the status name
+/// for numeric status codes.
+fn texts(field: &str, value: &Value) -> Vec<String> {
+    let mut texts = match value {
+        Value::Null => Vec::new(),
+        Value::String(s) => vec![s.clone()],
+        other => vec![other.to_string()],
+    };
+    if field.rsplit('.').next() == Some("status") {
+        let name = match value.as_i64() {
+            Some(1) => Some("generating"),
+            Some(2) => Some("complete"),
+            Some(3) => Some("failed"),
+            _ => None,
+        };
+        texts.extend(name.map(str::to_string));
+    }
+    texts
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut terms = Vec::new();
+        for token in tokenize(s)? {
+            // The earliest operator splits the term (so `a!=b` is `!=`, not
+            // `=`), leaving values free to contain operator characters.
+            let (index, op) = ["!=", "=", "~", ">", "<"]
+                .iter()
+                .filter_map(|op| token.find(op).map(|i| (i, *op)))
+                .min_by_key(|&(i, _)| i)
+                .ok_or_else(|| format!("filter term '{}' has no operator (=, !=, ~, >, <)", token))?;
+            let field = token[..index].trim();
+            let value = token[index + op.len()..].trim();
+            if field.is_empty() {
+                return Err(format!("filter term '{}' has no field", token));
+            }
+            let op = match op {
+                "!=" => Op::NotEq,
+                "=" => Op::Eq,
+                "~" => Op::Contains,
+                ">" => Op::Gt,
+                _ => Op::Lt,
+            };
+            terms.push(Term {
+                field: field.to_string(),
+                op,
+                value: value.to_string(),
+            });
+        }
+        Ok(Filter { terms })
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            if term.value.contains(' ') {
+                write!(f, "{}{}\"{}\"", term.field, term.op.as_str(), term.value)?;
+            } else {
+                write!(f, "{}{}{}", term.field, term.op.as_str(), term.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split on whitespace outside double quotes, dropping the quotes.
+fn tokenize(s: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("unterminated quote in filter '{}'", s));
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,400 @@
+//! Frame sequences extracted from animation results.
+//!
+//! A result ZIP downloaded from GameTorch contains one PNG per frame. This
+//! module loads those frames (from the ZIP itself or from a directory of
+//! extracted PNGs) so they can be processed, packed into sheets and exported.
+//!
+//! Sequences saved by this crate also carry a `sequence.json` sidecar listing
+//! the frame files in order with their durations and any [`Marker`]s, so
+//! timing and events survive a round trip through disk between processing
+//! steps.
+//!
+//! Frames are decoded and encoded in parallel on the rayon thread pool; see
+//! [`process::set_jobs`](crate::process::set_jobs).
+
+use crate::markers::Marker;
+use image::RgbaImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+/// Frame rate assumed when nothing better is known about a result.
+pub const DEFAULT_FPS: u32 = 24;
+
+/// Name of the sidecar written next to saved frames.
+pub const SEQUENCE_FILE: &str = "sequence.json";
+
+/// A single decoded frame and how long it is shown for.
+#[derive(Clone)]
+pub struct Frame {
+    pub image: RgbaImage,
+    pub duration_ms: u32,
+}
+
+/// An ordered list of frames.
+#[derive(Clone, Default)]
+pub struct Sequence {
+    pub frames: Vec<Frame>,
+    /// Named events, sorted by frame index.
+    pub markers: Vec<Marker>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Sidecar {
+    frames: Vec<SidecarFrame>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    markers: Vec<Marker>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SidecarFrame {
+    file: String,
+    duration_ms: u32,
+}
+
+impl Sequence {
+    /// Load a sequence from a result ZIP or a directory of PNG frames.
+    ///
+    /// Without a sidecar, frames are ordered by file name (which matches the
+    /// numbering used in GameTorch result archives) and play at
+    /// [`DEFAULT_FPS`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Sequence, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Self::load_dir(path)
+        } else {
+            let bytes = std::fs::read(path)
+                .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+            Self::from_zip_bytes(&bytes)
+        }
+    }
+
+    /// Decode every PNG inside an in-memory ZIP archive.
+    pub fn from_zip_bytes(bytes: &[u8]) -> Result<Sequence, Box<dyn std::error::Error + Send + Sync>> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+
+        let mut pngs: Vec<(String, Vec<u8>)> = Vec::new();
+        let mut sidecar = None;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if !file.is_file() || file.name().starts_with("__MACOSX") {
+                continue;
+            }
+            let name = file.name().to_string();
+            let is_sidecar = base_name(&name) == SEQUENCE_FILE;
+            if !is_sidecar && !is_png_name(&name) {
+                continue;
+            }
+            let mut buf = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut buf)?;
+            if is_sidecar {
+                sidecar = Some(parse_sidecar(&buf)?);
+            } else {
+                pngs.push((name, buf));
+            }
+        }
+
+        // Reading the archive is sequential; decoding is not.
+        let entries = pngs
+            .into_par_iter()
+            .map(|(name, buf)| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+                let image = decode_png(&buf).map_err(|e| format!("{}: {}", name, e))?;
+                Ok((name, image))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::assemble(entries, sidecar)
+    }
+
+    /// The PNG bytes of the first frame of an in-memory ZIP archive, as
+    /// stored (the other frames are not decoded).
+    pub fn first_frame_png(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+        let names: Vec<String> = archive
+            .file_names()
+            .filter(|name| !name.starts_with("__MACOSX") && !name.ends_with('/'))
+            .map(str::to_string)
+            .collect();
+        let sidecar = match names.iter().find(|name| base_name(name) == SEQUENCE_FILE) {
+            Some(name) => {
+                let mut buf = Vec::new();
+                archive.by_name(name)?.read_to_end(&mut buf)?;
+                Some(parse_sidecar(&buf)?)
+            }
+            None => None,
+        };
+        let first = match sidecar.as_ref().and_then(|s| s.frames.first()) {
+            Some(listed) => names.iter().find(|name| base_name(name) == listed.file),
+            None => names.iter().filter(|name| is_png_name(name)).min(),
+        }
+        .ok_or("no PNG frames found")?;
+        let mut buf = Vec::new();
+        archive.by_name(first)?.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Load every PNG in a directory (non-recursive).
+    pub fn load_dir(dir: &Path) -> Result<Sequence, Box<dyn std::error::Error + Send + Sync>> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if path.is_file() && is_png_name(name) {
+                paths.push((name.to_string(), path));
+            }
+        }
+        let entries = paths
+            .into_par_iter()
+            .map(|(name, path)| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+                let image = image::open(&path)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?
+                    .into_rgba8();
+                Ok((name, image))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let sidecar = match std::fs::read(dir.join(SEQUENCE_FILE)) {
+            Ok(bytes) => Some(parse_sidecar(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        Self::assemble(entries, sidecar)
+    }
+
+    /// Build a sequence from already-decoded images played at a constant rate.
+    pub fn from_images(
+        images: Vec<RgbaImage>,
+        fps: u32,
+    ) -> Result<Sequence, Box<dyn std::error::Error + Send + Sync>> {
+        if images.is_empty() {
+            return Err("no PNG frames found".into());
+        }
+        if fps == 0 {
+            return Err("fps must be greater than zero".into());
+        }
+        let duration_ms = 1000 / fps;
+        let frames = images
+            .into_iter()
+            .map(|image| Frame { image, duration_ms })
+            .collect();
+        Ok(Sequence {
+            frames,
+            markers: Vec::new(),
+        })
+    }
+
+    /// Save the frames as numbered PNGs plus a sidecar.
+    ///
+    /// Paths ending in `.zip` produce an archive; anything else is treated as
+    /// a directory (created if needed).
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let is_zip = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+        if is_zip {
+            crate::store::save(path, &self.to_zip_bytes()?)
+        } else {
+            self.save_dir(path)
+        }
+    }
+
+    /// Write numbered PNGs and the sidecar into `dir`.
+    pub fn save_dir(&self, dir: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        std::fs::create_dir_all(dir)?;
+        self.frames
+            .par_iter()
+            .enumerate()
+            .try_for_each(|(i, frame)| {
+                let path = dir.join(frame_file_name(i));
+                frame
+                    .image
+                    .save_with_format(&path, image::ImageFormat::Png)
+                    .map_err(|e| format!("failed to write {}: {}", path.display(), e))
+            })?;
+        std::fs::write(dir.join(SEQUENCE_FILE), self.sidecar_json()?)?;
+        Ok(())
+    }
+
+    /// Encode the sequence as a ZIP of numbered PNGs plus the sidecar.
+    pub fn to_zip_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        // PNGs are already compressed; storing them keeps saving fast.
+        let stored = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        let pngs = self
+            .frames
+            .par_iter()
+            .map(|frame| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+                let mut png = Cursor::new(Vec::new());
+                frame.image.write_to(&mut png, image::ImageFormat::Png)?;
+                Ok(png.into_inner())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (i, png) in pngs.iter().enumerate() {
+            writer.start_file(frame_file_name(i), stored)?;
+            writer.write_all(png)?;
+        }
+        writer.start_file(SEQUENCE_FILE, zip::write::SimpleFileOptions::default())?;
+        writer.write_all(self.sidecar_json()?.as_bytes())?;
+        Ok(writer.finish()?.into_inner())
+    }
+
+    /// Encode the sequence as a looping animated GIF.
+    ///
+    /// GIF has one bit of alpha, so partly transparent pixels come out either
+    /// opaque or fully transparent.
+    pub fn to_gif_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        use image::codecs::gif::{GifEncoder, Repeat};
+        use image::Delay;
+
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = GifEncoder::new_with_speed(&mut bytes, 10);
+            encoder.set_repeat(Repeat::Infinite)?;
+            encoder.encode_frames(self.frames.iter().map(|frame| {
+                image::Frame::from_parts(
+                    frame.image.clone(),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(frame.duration_ms, 1),
+                )
+            }))?;
+        }
+        Ok(bytes)
+    }
+
+    /// Set every frame to the same duration derived from `fps`.
+    pub fn set_fps(&mut self, fps: u32) {
+        let duration_ms = 1000 / fps.max(1);
+        for frame in &mut self.frames {
+            frame.duration_ms = duration_ms;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Replace the markers, checking every one refers to an existing frame.
+    pub fn set_markers(
+        &mut self,
+        mut markers: Vec<Marker>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(m) = markers.iter().find(|m| m.frame >= self.len()) {
+            return Err(format!(
+                "marker '{}' refers to frame {} but the sequence has {} frames",
+                m.name,
+                m.frame,
+                self.len()
+            )
+            .into());
+        }
+        markers.sort_by(|a, b| a.frame.cmp(&b.frame).then_with(|| a.name.cmp(&b.name)));
+        self.markers = markers;
+        Ok(())
+    }
+
+    /// Time at which frame `index` starts playing.
+    pub fn frame_start_ms(&self, index: usize) -> u64 {
+        self.frames[..index.min(self.len())]
+            .iter()
+            .map(|f| f.duration_ms as u64)
+            .sum()
+    }
+
+    /// Size of the largest frame; frames are usually uniform.
+    pub fn frame_size(&self) -> (u32, u32) {
+        self.frames.iter().fold((0, 0), |(w, h), f| {
+            (w.max(f.image.width()), h.max(f.image.height()))
+        })
+    }
+
+    /// Total playback time.
+    pub fn total_duration_ms(&self) -> u64 {
+        self.frames.iter().map(|f| f.duration_ms as u64).sum()
+    }
+
+    /// Order decoded entries using the sidecar if there is one.
+    fn assemble(
+        mut entries: Vec<(String, RgbaImage)>,
+        sidecar: Option<Sidecar>,
+    ) -> Result<Sequence, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(sidecar) = sidecar else {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            return Self::from_images(entries.into_iter().map(|(_, img)| img).collect(), DEFAULT_FPS);
+        };
+
+        let frames = sidecar
+            .frames
+            .iter()
+            .map(|listed| {
+                entries
+                    .iter()
+                    .find(|(name, _)| base_name(name) == listed.file)
+                    .map(|(_, image)| Frame {
+                        image: image.clone(),
+                        duration_ms: listed.duration_ms,
+                    })
+                    .ok_or_else(|| format!("{} lists missing frame {}", SEQUENCE_FILE, listed.file).into())
+            })
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error + Send + Sync>>>()?;
+        if frames.is_empty() {
+            return Err("no PNG frames found".into());
+        }
+        let mut sequence = Sequence {
+            frames,
+            markers: Vec::new(),
+        };
+        sequence
+            .set_markers(sidecar.markers)
+            .map_err(|e| format!("invalid {}: {}", SEQUENCE_FILE, e))?;
+        Ok(sequence)
+    }
+
+    fn sidecar_json(&self) -> Result<String, serde_json::Error> {
+        let sidecar = Sidecar {
+            frames: self
+                .frames
+                .iter()
+                .enumerate()
+                .map(|(i, f)| SidecarFrame {
+                    file: frame_file_name(i),
+                    duration_ms: f.duration_ms,
+                })
+                .collect(),
+            markers: self.markers.clone(),
+        };
+        serde_json::to_string_pretty(&sidecar)
+    }
+}
+
+/// File name used for frame `index` when saving.
+pub fn frame_file_name(index: usize) -> String {
+    format!("frame_{:04}.png", index)
+}
+
+fn parse_sidecar(bytes: &[u8]) -> Result<Sidecar, Box<dyn std::error::Error + Send + Sync>> {
+    serde_json::from_slice(bytes).map_err(|e| format!("invalid {}: {}", SEQUENCE_FILE, e).into())
+}
+
+fn base_name(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+fn is_png_name(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".png") && !name.starts_with("__MACOSX")
+}
+
+fn decode_png(bytes: &[u8]) -> Result<RgbaImage, image::ImageError> {
+    Ok(image::load_from_memory_with_format(bytes, image::ImageFormat::Png)?.into_rgba8())
+}
@@ -0,0 +1,175 @@
+//! Local history of generation jobs.
+//!
+//! The CLI appends one JSON line to `history.jsonl` in the
+//! [data directory](crate::paths::data_dir) whenever it submits or finishes a
+//! job. A job's later lines supersede earlier ones, so the file is only ever
+//! appended to and concurrent CLI runs cannot corrupt each other's records.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the history log inside the data directory.
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+/// One generation job as last recorded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Job {
+    pub animation_id: i64,
+    /// `generate`, `regenerate`, `variation`, `upscale` or `inpaint`.
+    pub kind: String,
+    /// `submitted`, `complete` or `failed`.
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<u32>,
+    /// Input image as given on the command line (file, URL, `-` or `clipboard`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_prompt: Option<String>,
+    /// Seed of the render: the one returned by the backend, or else the one
+    /// requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// The animation a `regenerate` job was made from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_animation_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zip_path: Option<String>,
+    /// Hex SHA-256 of the ZIP when it was saved, for `gametorch verify`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zip_sha256: Option<String>,
+    /// Unix timestamp (seconds) of submission.
+    pub submitted_at: u64,
+    /// Unix timestamp (seconds) the job completed or failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<u64>,
+}
+
+impl Job {
+    /// A freshly submitted job.
+    pub fn submitted(animation_id: i64, kind: &str) -> Job {
+        Job {
+            animation_id,
+            kind: kind.to_string(),
+            status: "submitted".to_string(),
+            prompt: None,
+            slug: None,
+            model: None,
+            duration_seconds: None,
+            input_image: None,
+            negative_prompt: None,
+            seed: None,
+            source_animation_id: None,
+            result_id: None,
+            zip_path: None,
+            zip_sha256: None,
+            submitted_at: unix_now(),
+            completed_at: None,
+        }
+    }
+
+    /// Mark the job complete now, recording the hash of the ZIP if it was
+    /// saved.
+    pub fn complete(mut self, result_id: Option<i64>, zip_path: Option<String>) -> Job {
+        self.status = "complete".to_string();
+        self.result_id = result_id;
+        self.zip_sha256 = zip_path
+            .as_deref()
+            .and_then(|path| crate::manifest::hash_file(Path::new(path)).ok());
+        self.zip_path = zip_path;
+        self.completed_at = Some(unix_now());
+        self
+    }
+
+    /// Seconds from submission to completion, for finished jobs.
+    pub fn render_seconds(&self) -> Option<u64> {
+        self.completed_at.map(|done| done.saturating_sub(self.submitted_at))
+    }
+}
+
+/// The history log at a given path.
+#[derive(Clone, Debug)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: impl Into<PathBuf>) -> History {
+        History { path: path.into() }
+    }
+
+    /// The history log in the default data directory.
+    pub fn open_default() -> Result<History, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(History::new(crate::paths::data_dir()?.join(HISTORY_FILE)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `job`, superseding any earlier record of the same animation.
+    pub fn record(&self, job: &Job) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(job)?;
+        line.push('\n');
+        // A single write keeps lines from concurrent appenders intact.
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| format!("failed to write {}: {}", self.path.display(), e).into())
+    }
+
+    /// The latest record of every job, oldest submission first.
+    ///
+    /// A missing file is an empty history; unreadable lines (e.g. a write cut
+    /// short) are skipped.
+    pub fn jobs(&self) -> Result<Vec<Job>, Box<dyn std::error::Error + Send + Sync>> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("failed to read {}: {}", self.path.display(), e).into()),
+        };
+        let mut latest: HashMap<i64, Job> = HashMap::new();
+        for job in text.lines().filter_map(|line| serde_json::from_str::<Job>(line).ok()) {
+            latest.insert(job.animation_id, job);
+        }
+        let mut jobs: Vec<Job> = latest.into_values().collect();
+        jobs.sort_by_key(|j| (j.submitted_at, j.animation_id));
+        Ok(jobs)
+    }
+
+    /// Jobs that recorded a prompt, most recently submitted first; the
+    /// numbering `history` shows and `generate --again N` uses (1-based).
+    pub fn prompts(&self) -> Result<Vec<Job>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut jobs: Vec<Job> = self.jobs()?.into_iter().filter(|j| j.prompt.is_some()).collect();
+        jobs.reverse();
+        Ok(jobs)
+    }
+
+    /// The latest record of one job.
+    pub fn job(&self, animation_id: i64) -> Result<Option<Job>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.jobs()?.into_iter().find(|j| j.animation_id == animation_id))
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
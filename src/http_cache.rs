@@ -0,0 +1,214 @@
+//! On-disk cache of API `GET` responses, validated with ETags.
+//!
+//! When enabled, the [client](crate::client) keeps every successful JSON
+//! `GET` response that carries an `ETag` in `http-cache/` under the
+//! [data directory](crate::paths::data_dir), one file per URL and API key,
+//! and sends later requests for the same URL with `If-None-Match`. A
+//! `304 Not Modified` is answered from the cache as the `200` it stands for,
+//! so polling an unchanged listing or animation costs the API a header
+//! exchange instead of a body. Cached bodies are always revalidated first,
+//! never served stale.
+//!
+//! The `Authorization` header only feeds the file name's hash; it is never
+//! written to disk. Downloads (result ZIPs and other non-JSON bodies) are
+//! not cached.
+//!
+//! Nothing expires on its own; [`HttpCache::prune`] (`gametorch gc`) drops
+//! old responses or trims the cache to a size.
+
+use reqwest::header::{HeaderValue, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use reqwest::{Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Name of the cache directory inside the data directory.
+pub const CACHE_DIR: &str = "http-cache";
+
+/// One cached response.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    etag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    body: String,
+}
+
+/// What [`HttpCache::prune`] deleted (or would) and kept.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Pruned {
+    pub removed: usize,
+    pub freed_bytes: u64,
+    pub kept: usize,
+    pub kept_bytes: u64,
+}
+
+/// A cache directory.
+#[derive(Clone, Debug)]
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+impl HttpCache {
+    /// Use (and create if needed) the cache at `dir`.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<HttpCache, Box<dyn std::error::Error + Send + Sync>> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        Ok(HttpCache { dir })
+    }
+
+    /// The cache in the default data directory.
+    pub fn open_default() -> Result<HttpCache, Box<dyn std::error::Error + Send + Sync>> {
+        HttpCache::open(crate::paths::data_dir()?.join(CACHE_DIR))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Delete every cached response, returning how many there were.
+    pub fn clear(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let mut removed = 0;
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Delete responses cached before `cutoff`, then the oldest of the rest
+    /// until they fit in `max_bytes`. With `dry_run`, only report what would
+    /// be deleted.
+    pub fn prune(
+        &self,
+        cutoff: Option<SystemTime>,
+        max_bytes: Option<u64>,
+        dry_run: bool,
+    ) -> Result<Pruned, Box<dyn std::error::Error + Send + Sync>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                let metadata = std::fs::metadata(&path)?;
+                files.push((metadata.modified()?, metadata.len(), path));
+            }
+        }
+        // Newest first, so what is kept comes first.
+        files.sort_by_key(|f| std::cmp::Reverse(f.0));
+
+        let mut pruned = Pruned::default();
+        for (modified, len, path) in files {
+            let expired = cutoff.is_some_and(|cutoff| modified < cutoff);
+            let over = max_bytes.is_some_and(|max| pruned.kept_bytes + len > max);
+            if !expired && !over {
+                pruned.kept += 1;
+                pruned.kept_bytes += len;
+                continue;
+            }
+            if !dry_run {
+                std::fs::remove_file(&path).map_err(|e| format!("failed to remove {}: {}", path.display(), e))?;
+            }
+            pruned.removed += 1;
+            pruned.freed_bytes += len;
+        }
+        Ok(pruned)
+    }
+
+    /// Make `request` conditional if its response is cached. Returns the
+    /// cache key for [`received`](Self::received), or `None` for requests
+    /// that are not cached at all.
+    pub(crate) fn prepare(&self, request: &mut Request) -> Option<String> {
+        if *request.method() != Method::GET {
+            return None;
+        }
+        let credentials = request
+            .headers()
+            .get(reqwest::header::AUTHORIZATION)
+            .map(|v| v.as_bytes())
+            .unwrap_or_default();
+        let key = crate::manifest::hash_bytes(&[request.url().as_str().as_bytes(), b"\n", credentials].concat());
+        if let Some(entry) = self.load(&key)
+            && let Ok(etag) = HeaderValue::from_str(&entry.etag)
+        {
+            request.headers_mut().insert(IF_NONE_MATCH, etag);
+        }
+        Some(key)
+    }
+
+    /// Answer a `304` from the cache, and store cacheable `200`s. A response
+    /// that is read to be stored is handed back rebuilt, so the caller sees
+    /// the same status, headers and body.
+    pub(crate) async fn received(&self, key: &str, result: reqwest::Result<Response>) -> reqwest::Result<Response> {
+        let response = result?;
+        if response.status() == StatusCode::NOT_MODIFIED
+            && let Some(entry) = self.load(key)
+        {
+            let mut rebuilt = http::Response::new(entry.body);
+            *rebuilt.version_mut() = response.version();
+            *rebuilt.headers_mut() = response.headers().clone();
+            if let Some(content_type) = entry.content_type.and_then(|c| HeaderValue::from_str(&c).ok()) {
+                rebuilt.headers_mut().insert(CONTENT_TYPE, content_type);
+            }
+            return Ok(Response::from(rebuilt));
+        }
+
+        let etag = response.headers().get(ETAG).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let is_json = content_type.as_deref().is_some_and(|c| c.contains("json"));
+        let (Some(etag), true, StatusCode::OK) = (etag, is_json, response.status()) else {
+            return Ok(response);
+        };
+
+        let url = response.url().to_string();
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        if let Ok(text) = std::str::from_utf8(&body) {
+            self.store(
+                key,
+                &Entry {
+                    url,
+                    etag,
+                    content_type,
+                    body: text.to_string(),
+                },
+            );
+        }
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        Ok(Response::from(rebuilt))
+    }
+
+    fn file(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    fn load(&self, key: &str) -> Option<Entry> {
+        serde_json::from_slice(&std::fs::read(self.file(key)).ok()?).ok()
+    }
+
+    /// Write an entry (via a temporary file and rename). A cache that cannot
+    /// be written just stays cold.
+    fn store(&self, key: &str, entry: &Entry) {
+        let path = self.file(key);
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        let written = serde_json::to_vec(entry)
+            .map_err(std::io::Error::from)
+            .and_then(|bytes| std::fs::write(&tmp, bytes))
+            .and_then(|()| std::fs::rename(&tmp, &path));
+        if written.is_err() {
+            let _ = std::fs::remove_file(&tmp);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Still sprite generation.
+//!
+//! Mirrors [`animations`](crate::animations) for single images: submit a
+//! prompt (optionally with an input image), poll until the image is
+//! rendered, download it as PNG. The endpoints follow the animation ones:
+//!
+//! - `POST /api/image` with `prompt`, `input_image_base64` and
+//!   `image_model_name`, answering `{ "image_id": ... }`;
+//! - `GET /api/image_results/<image_id>`, with the same result statuses as
+//!   animations (1 generating, 2 complete, 3 failed and refunded);
+//! - `GET /api/image_result_png/<result_id>` for the PNG.
+
+use crate::animations::{first_result_id, first_status, status_name, STDIO_PATH};
+use crate::error::{Error, ErrorKind};
+use crate::poll::{poll_until, Poll, PollOptions};
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::Value;
+
+/// Generate a still image from a prompt.
+///
+/// Returns the creation response, or with `block` a JSON object of shape
+/// `{ "image_id": ..., "result_id": ..., "png_path": ... }` once the PNG
+/// has been saved to `output_file` (default `image_<id>_<result id>.png`;
+/// [`STDIO_PATH`] writes it to stdout and implies `silent`). Input images
+/// are read and checked as for [`animations::generate`](crate::animations::generate).
+#[allow(clippy::too_many_arguments)]
+pub async fn generate(
+    api_key: &str,
+    base_url: &str,
+    prompt: &str,
+    block: bool,
+    output_file: Option<&str>,
+    input_image_path: Option<&str>,
+    preprocess: bool,
+    model_name: Option<&str>,
+    silent: bool,
+) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let silent = silent || (block && output_file == Some(STDIO_PATH));
+
+    let mut body = serde_json::Map::new();
+    body.insert("prompt".to_string(), Value::String(prompt.to_string()));
+    if let Some(path) = input_image_path {
+        let bytes = crate::input::read(path, silent).await?;
+        let prepared = crate::input::prepare(bytes, preprocess, None).map_err(|e| Error::new(ErrorKind::Validation, e.to_string()))?;
+        for warning in &prepared.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        let bytes = prepared.bytes;
+        body.insert("input_image_base64".to_string(), Value::String(general_purpose::STANDARD.encode(bytes)));
+    }
+    if let Some(name) = model_name {
+        body.insert("image_model_name".to_string(), Value::String(name.to_string()));
+    }
+
+    if !silent {
+        println!("Starting image generation request...");
+    }
+    let url = format!("{}/api/image", base_url);
+    let created: Value = client
+        .send(
+            client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", api_key))
+                .json(&Value::Object(body)),
+        )
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let image_id = created
+        .get("image_id")
+        .and_then(|v| v.as_i64())
+        .ok_or("image_id missing from response")?;
+    if !silent {
+        println!("Image created successfully (ID: {}).", image_id);
+    }
+    if !block {
+        return Ok(created);
+    }
+
+    let results = wait(api_key, base_url, &image_id.to_string(), silent).await?;
+    let result_id = first_result_id(&results).ok_or("result id missing")?;
+    let png = download(api_key, base_url, &result_id.to_string()).await?;
+
+    let path = output_file
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("image_{}_{}.png", image_id, result_id));
+    if path == STDIO_PATH {
+        use std::io::Write;
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&png)?;
+        stdout.flush()?;
+    } else {
+        #[cfg(feature = "native")]
+        tokio::fs::write(&path, &png).await?;
+        #[cfg(not(feature = "native"))]
+        std::fs::write(&path, &png)?;
+    }
+    if !silent {
+        println!("PNG saved to {}", path);
+    }
+
+    Ok(serde_json::json!({
+        "image_id": image_id,
+        "slug": crate::slug::slug(prompt),
+        "result_id": result_id,
+        "png_path": path,
+    }))
+}
+
+/// Fetch the results of an image.
+///
+/// Hits `GET /api/image_results/<image_id>`.
+pub async fn get(api_key: &str, base_url: &str, image_id: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let url = format!("{}/api/image_results/{}", base_url, image_id);
+
+    let json: Value = client
+        .send(client.get(&url).header("Authorization", format!("Bearer {}", api_key)))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(json)
+}
+
+/// Poll an image's results every 5 seconds until it is rendered; fails if
+/// it lands in status 3 (failed and refunded).
+pub async fn wait(
+    api_key: &str,
+    base_url: &str,
+    image_id: &str,
+    silent: bool,
+) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    if !silent {
+        println!("Polling for results every 5 seconds...");
+    }
+    poll_until(&PollOptions::default(), |_| async move {
+        let results = get(api_key, base_url, image_id).await?;
+        match first_status(&results) {
+            Some(2) => Ok(Poll::Ready(results)),
+            Some(3) => Err(Error::new(ErrorKind::FailedGeneration, "image failed and refunded (status=3)").into()),
+            status => Ok(Poll::Pending(status.and_then(status_name).unwrap_or("pending").to_string())),
+        }
+    })
+    .await
+}
+
+/// Download a rendered image as PNG bytes.
+///
+/// Hits `GET /api/image_result_png/<result_id>`.
+pub async fn download(api_key: &str, base_url: &str, result_id: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let url = format!("{}/api/image_result_png/{}", base_url, result_id);
+
+    let resp = client
+        .send(client.get(&url).header("Authorization", format!("Bearer {}", api_key)))
+        .await?;
+    let status = resp.status();
+    if !status.is_success() {
+        let message = format!("failed to download image: HTTP {}", status);
+        return Err(Error::new(ErrorKind::from_status(status), message).into());
+    }
+    let bytes = resp.bytes().await?;
+    if !bytes.starts_with(b"\x89PNG") {
+        return Err(Error::new(ErrorKind::Server, "downloaded image is not a PNG").into());
+    }
+    Ok(bytes.to_vec())
+}
@@ -0,0 +1,244 @@
+//! A local SQLite index of animations, results and downloaded files.
+//!
+//! With the `index` feature, every command that fetches animations from the
+//! API also writes them to `index.sqlite3` in the
+//! [data directory](crate::paths::data_dir), and every download is recorded
+//! against its animation. The index can then answer listings offline
+//! (`--offline`) and tell which local files came from which prompt.
+//!
+//! The index is a cache: each row keeps the JSON the API returned, and
+//! anything missing or stale is simply fetched again by the next online
+//! command. Animations deleted on the server stay in the index, marked with
+//! `deleted_at`, once `gametorch sync` has noticed.
+
+use crate::animations::ListOptions;
+use crate::catalog::id_string;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// File name of the index inside the data directory.
+pub const INDEX_FILE: &str = "index.sqlite3";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS animations (
+    id INTEGER PRIMARY KEY,
+    prompt TEXT,
+    name TEXT,
+    status INTEGER,
+    json TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    -- Set by `sync` when the server no longer has the animation.
+    deleted_at INTEGER
+);
+CREATE INDEX IF NOT EXISTS animations_prompt ON animations (prompt);
+CREATE TABLE IF NOT EXISTS results (
+    id INTEGER PRIMARY KEY,
+    animation_id INTEGER NOT NULL,
+    status INTEGER,
+    json TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_animation ON results (animation_id);
+CREATE TABLE IF NOT EXISTS files (
+    path TEXT PRIMARY KEY,
+    animation_id INTEGER NOT NULL,
+    result_id INTEGER,
+    recorded_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS files_animation ON files (animation_id);
+";
+
+/// An open index database.
+pub struct Index {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl Index {
+    /// Open (or create) the index at `path`.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Index, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(&path).map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Index { path, conn })
+    }
+
+    /// The index in the default data directory.
+    pub fn open_default() -> Result<Index, Box<dyn std::error::Error + Send + Sync>> {
+        Index::open(crate::paths::data_dir()?.join(INDEX_FILE))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Store animations from a listing (an array) or a single animation
+    /// object. Fields missing from `value` keep their stored values.
+    pub fn upsert_animations(&mut self, value: &Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let items: Vec<&Value> = match value {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        let now = crate::history::unix_now() as i64;
+        let tx = self.conn.transaction()?;
+        for item in items {
+            let Some(id) = item.get("id").and_then(id_i64) else {
+                continue;
+            };
+            let stored: Option<String> = tx
+                .query_row("SELECT json FROM animations WHERE id = ?1", [id], |row| row.get(0))
+                .optional()?;
+            let mut merged = stored
+                .and_then(|json| serde_json::from_str::<Value>(&json).ok())
+                .filter(Value::is_object)
+                .unwrap_or_else(|| Value::Object(Default::default()));
+            if let (Some(merged), Some(item)) = (merged.as_object_mut(), item.as_object()) {
+                merged.extend(item.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO animations (id, prompt, name, status, json, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    id,
+                    merged.get("prompt").and_then(Value::as_str),
+                    merged.get("name").and_then(Value::as_str),
+                    merged.get("status").and_then(Value::as_i64),
+                    merged.to_string(),
+                    now,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store an `animation_results` response for `animation_id`.
+    pub fn upsert_results(
+        &mut self,
+        animation_id: i64,
+        results: &Value,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let items: Vec<&Value> = match results {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        let now = crate::history::unix_now() as i64;
+        let tx = self.conn.transaction()?;
+        for item in items {
+            let Some(id) = item.get("id").and_then(id_i64) else {
+                continue;
+            };
+            tx.execute(
+                "INSERT OR REPLACE INTO results (id, animation_id, status, json, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![id, animation_id, item.get("status").and_then(Value::as_i64), item.to_string(), now],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record that `path` holds a download of `animation_id`.
+    pub fn record_file(
+        &self,
+        path: impl AsRef<Path>,
+        animation_id: i64,
+        result_id: Option<i64>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let path = std::path::absolute(path.as_ref())?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO files (path, animation_id, result_id, recorded_at) VALUES (?1, ?2, ?3, ?4)",
+            params![path.to_string_lossy(), animation_id, result_id, crate::history::unix_now() as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Forget an animation, its results and its files.
+    pub fn remove_animation(&self, animation_id: i64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.conn.execute_batch("BEGIN")?;
+        for table in ["animations WHERE id", "results WHERE animation_id", "files WHERE animation_id"] {
+            self.conn.execute(&format!("DELETE FROM {} = ?1", table), [animation_id])?;
+        }
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+
+    /// IDs of indexed animations not marked deleted.
+    pub fn live_ids(&self) -> Result<Vec<i64>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut statement = self.conn.prepare("SELECT id FROM animations WHERE deleted_at IS NULL ORDER BY id")?;
+        let ids = statement.query_map([], |row| row.get(0))?.collect::<Result<Vec<i64>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Mark an animation as deleted on the server, keeping its row and files.
+    pub fn mark_deleted(&self, animation_id: i64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.conn.execute(
+            "UPDATE animations SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![animation_id, crate::history::unix_now() as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Indexed animations, each with a `files` list of its downloads that
+    /// still exist (and `deleted_at` if the server no longer has it),
+    /// filtered and paged like an API listing.
+    pub fn list(&self, options: &ListOptions) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        // The prompt search runs in SQL; everything else as for a listing.
+        let pattern = options.search.as_ref().map(|s| format!("%{}%", s));
+        let mut statement = self.conn.prepare(
+            "SELECT id, json, deleted_at FROM animations
+             WHERE ?1 IS NULL OR prompt LIKE ?1 OR name LIKE ?1 ORDER BY id",
+        )?;
+        let rows = statement.query_map([pattern], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<i64>>(2)?))
+        })?;
+        let mut items = Vec::new();
+        for row in rows {
+            let (id, json, deleted_at) = row?;
+            let mut item: Value = serde_json::from_str(&json)?;
+            item["files"] = Value::from(self.files(id)?);
+            if let Some(deleted_at) = deleted_at {
+                item["deleted_at"] = Value::from(deleted_at);
+            }
+            items.push(item);
+        }
+        let options = ListOptions {
+            search: None,
+            ..options.clone()
+        };
+        Ok(options.apply(Value::Array(items)))
+    }
+
+    /// Stored results of `animation_id`, as an `animation_results` response
+    /// would list them; `None` if none were ever fetched.
+    pub fn results(&self, animation_id: i64) -> Result<Option<Value>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT json FROM results WHERE animation_id = ?1 ORDER BY id")?;
+        let results = statement
+            .query_map([animation_id], |row| row.get::<_, String>(0))?
+            .map(|json| Ok(serde_json::from_str::<Value>(&json?)?))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error + Send + Sync>>>()?;
+        Ok((!results.is_empty()).then_some(Value::Array(results)))
+    }
+
+    /// Paths of existing downloads of `animation_id`.
+    pub fn files(&self, animation_id: i64) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT path FROM files WHERE animation_id = ?1 ORDER BY recorded_at")?;
+        let paths = statement
+            .query_map([animation_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(paths.into_iter().filter(|p| Path::new(p).exists()).collect())
+    }
+}
+
+/// An animation or result ID, given as a number or a numeric string.
+fn id_i64(id: &Value) -> Option<i64> {
+    id_string(id).parse().ok()
+}
@@ -0,0 +1,396 @@
+//! Checking input images before they are uploaded.
+//!
+//! The backend rejects images it cannot use with a bare `4xx`. [`prepare`]
+//! decodes the image locally first, failing on images that cannot be
+//! decoded and warning about formats the backend may not take; the backend
+//! has the final say on everything else. With `preprocess` it fixes what it
+//! can: the image is padded to a square (transparent), scaled down to fit
+//! and re-encoded as PNG, which also drops EXIF and other metadata.
+//!
+//! Images hosted elsewhere can be [fetched](fetch) by URL first, and any
+//! image can be [compressed](compress) losslessly to upload faster.
+//! Inpainting masks are checked against their input image and converted by
+//! [`prepare_mask`].
+//!
+//! Commands that submit many jobs [prefetch](prefetch) their inputs first:
+//! files and URLs are read concurrently, once per distinct path, and later
+//! [`read`]s are served from memory.
+
+use image::codecs::png::{CompressionType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, ImageFormat, ImageReader, Luma, Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+/// Largest image downloaded from a URL, in bytes.
+pub const MAX_DOWNLOAD_BYTES: usize = 32 * 1024 * 1024;
+
+/// Most bytes [`prefetch`] keeps in memory; inputs past it are read when
+/// needed instead.
+pub const PREFETCH_MAX_BYTES: usize = 256 * 1024 * 1024;
+
+/// How many inputs [`prefetch`] reads at once.
+#[cfg(feature = "native")]
+const PREFETCH_CONCURRENCY: usize = 8;
+
+/// Inputs read ahead by [`prefetch`], by path as given.
+static PREFETCHED: Mutex<Option<HashMap<String, Arc<Vec<u8>>>>> = Mutex::new(None);
+
+/// Formats the web UI takes; others are uploaded as they are, but may be
+/// rejected.
+const COMMON_FORMATS: &[ImageFormat] = &[ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP];
+
+/// An input image ready to upload.
+#[derive(Clone, Debug)]
+pub struct Prepared {
+    pub bytes: Vec<u8>,
+    /// Reasons the backend might still reject it.
+    pub warnings: Vec<String>,
+}
+
+/// Validate an input image and, with `preprocess`, convert it into a
+/// square PNG no larger than `max_side` (the model's limit, when known).
+/// Fails only on images that cannot be decoded.
+pub fn prepare(
+    bytes: Vec<u8>,
+    preprocess: bool,
+    max_side: Option<u32>,
+) -> Result<Prepared, Box<dyn std::error::Error + Send + Sync>> {
+    let format = image::guess_format(&bytes).map_err(|_| "input image is not in a recognised image format")?;
+    let image = image::load_from_memory_with_format(&bytes, format)
+        .map_err(|e| format!("input image could not be decoded ({}): {}", format_name(format), e))?;
+    if preprocess {
+        return Ok(Prepared {
+            bytes: encode_png(&conform(image.into_rgba8(), max_side))?,
+            warnings: Vec::new(),
+        });
+    }
+
+    let mut warnings = Vec::new();
+    if !COMMON_FORMATS.contains(&format) {
+        warnings.push(format!(
+            "input image is {}, which the backend may not accept (pass --preprocess to convert it to PNG)",
+            format_name(format)
+        ));
+    }
+    Ok(Prepared { bytes, warnings })
+}
+
+/// Validate an inpainting mask for the input image `image` (as read, before
+/// [`prepare`]) and convert it into the form the API takes: a greyscale PNG,
+/// white where the image is regenerated and black where it is kept. Light,
+/// opaque pixels of `mask` count as masked; dark or transparent ones do not.
+///
+/// The mask must be the size of the input image. With `preprocess` it is
+/// padded and scaled the same way as the image.
+pub fn prepare_mask(
+    mask: Vec<u8>,
+    image: &[u8],
+    preprocess: bool,
+    max_side: Option<u32>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let (width, height) = dimensions(image)?;
+    let mask = image::load_from_memory(&mask)
+        .map_err(|e| format!("mask could not be decoded: {}", e))?
+        .into_rgba8();
+    if mask.dimensions() != (width, height) {
+        return Err(format!(
+            "mask is {}x{} but the input image is {}x{}; they must be the same size",
+            mask.width(),
+            mask.height(),
+            width,
+            height
+        )
+        .into());
+    }
+
+    let mut mask = RgbaImage::from_fn(width, height, |x, y| {
+        if is_masked(mask.get_pixel(x, y)) {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    });
+    if preprocess {
+        mask = conform(mask, max_side);
+    }
+    let mask = GrayImage::from_fn(mask.width(), mask.height(), |x, y| {
+        Luma([if is_masked(mask.get_pixel(x, y)) { 255 } else { 0 }])
+    });
+    if mask.pixels().all(|p| p.0[0] == 0) {
+        return Err("mask is empty: paint the region to regenerate white".into());
+    }
+
+    let mut png = Cursor::new(Vec::new());
+    mask.write_to(&mut png, ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+/// Width and height of an encoded image, read from its header.
+pub fn dimensions(image: &[u8]) -> Result<(u32, u32), Box<dyn std::error::Error + Send + Sync>> {
+    Ok(ImageReader::new(Cursor::new(image))
+        .with_guessed_format()?
+        .into_dimensions()
+        .map_err(|e| format!("input image could not be decoded: {}", e))?)
+}
+
+/// Whether a mask pixel is light and opaque enough to count as masked.
+fn is_masked(pixel: &Rgba<u8>) -> bool {
+    let [r, g, b, a] = pixel.0.map(u32::from);
+    a >= 128 && (299 * r + 587 * g + 114 * b) / 1000 >= 128
+}
+
+/// An input image after [`compress`].
+#[derive(Clone, Debug)]
+pub struct Compressed {
+    pub bytes: Vec<u8>,
+    /// Size before compression, in bytes.
+    pub original_len: usize,
+    /// The format chosen, or `None` if the original was kept.
+    pub format: Option<ImageFormat>,
+}
+
+impl fmt::Display for Compressed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            Some(format) => write!(
+                f,
+                "Compressed input image from {} to {} ({:.0}% smaller, {})",
+                kilobytes(self.original_len),
+                kilobytes(self.bytes.len()),
+                100.0 * (1.0 - self.bytes.len() as f64 / self.original_len as f64),
+                format_name(format)
+            ),
+            None => write!(
+                f,
+                "Input image kept as is ({}); re-encoding it would not make it smaller",
+                kilobytes(self.original_len)
+            ),
+        }
+    }
+}
+
+/// Re-encode an image losslessly, as a maximally compressed PNG or as a
+/// lossless WebP, whichever is smaller. The original is kept when neither
+/// beats it (usually the case for JPEG) or when it cannot be decoded, which
+/// [`prepare`] then reports.
+pub fn compress(bytes: Vec<u8>) -> Result<Compressed, Box<dyn std::error::Error + Send + Sync>> {
+    let original_len = bytes.len();
+    let smallest = match image::load_from_memory(&bytes) {
+        Ok(image) => Some(smallest_encoding(&image)?),
+        Err(_) => None,
+    };
+    Ok(match smallest {
+        Some((encoded, format)) if encoded.len() < original_len => Compressed {
+            bytes: encoded,
+            original_len,
+            format: Some(format),
+        },
+        _ => Compressed {
+            bytes,
+            original_len,
+            format: None,
+        },
+    })
+}
+
+/// The smaller of `image` as a maximally compressed PNG and as a lossless
+/// WebP.
+fn smallest_encoding(image: &DynamicImage) -> Result<(Vec<u8>, ImageFormat), Box<dyn std::error::Error + Send + Sync>> {
+    let mut png = Vec::new();
+    let encoder = PngEncoder::new_with_quality(&mut png, CompressionType::Best, image::codecs::png::FilterType::Adaptive);
+    image.write_with_encoder(encoder)?;
+
+    // Lossless WebP holds 8 bits per channel; deeper images stay PNG.
+    let color = image.color();
+    if color.bits_per_pixel() / u16::from(color.channel_count()) > 8 {
+        return Ok((png, ImageFormat::Png));
+    }
+    let mut webp = Vec::new();
+    DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(WebPEncoder::new_lossless(&mut webp))?;
+    Ok(if webp.len() < png.len() {
+        (webp, ImageFormat::WebP)
+    } else {
+        (png, ImageFormat::Png)
+    })
+}
+
+/// Read an input image given on the command line: a file, an `http(s)://`
+/// URL ([fetched](fetch)), [`STDIO_PATH`](crate::animations::STDIO_PATH)
+/// for stdin or [`CLIPBOARD_INPUT`](crate::clipboard::CLIPBOARD_INPUT).
+pub async fn read(path: &str, silent: bool) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(bytes) = prefetched(path) {
+        return Ok(bytes.to_vec());
+    }
+    if path == crate::animations::STDIO_PATH {
+        use std::io::Read;
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        if bytes.is_empty() {
+            return Err("no input image on stdin".into());
+        }
+        Ok(bytes)
+    } else if path == crate::clipboard::CLIPBOARD_INPUT {
+        crate::clipboard::read_image()
+    } else if is_url(path) {
+        if !silent {
+            println!("Downloading input image from {}...", path);
+        }
+        fetch(path).await
+    } else {
+        #[cfg(feature = "native")]
+        {
+            Ok(tokio::fs::read(path).await?)
+        }
+        #[cfg(not(feature = "native"))]
+        {
+            Ok(std::fs::read(path)?)
+        }
+    }
+}
+
+/// Read the files and URLs among `paths` concurrently, each distinct path
+/// once, so later [`read`]s of them come from memory.
+///
+/// Holds at most `max_bytes` in total. Inputs past that, inputs that fail to
+/// read (the error is reported by the later `read`), stdin and the clipboard
+/// are left to be read when needed. Returns how many inputs were prefetched.
+#[cfg(feature = "native")]
+pub async fn prefetch(paths: impl IntoIterator<Item = String>, max_bytes: usize) -> usize {
+    let mut held: usize = with_prefetched(|cache| cache.values().map(|bytes| bytes.len()).sum());
+    let mut pending: Vec<String> = Vec::new();
+    for path in paths {
+        if path != crate::animations::STDIO_PATH
+            && path != crate::clipboard::CLIPBOARD_INPUT
+            && prefetched(&path).is_none()
+            && !pending.contains(&path)
+        {
+            pending.push(path);
+        }
+    }
+
+    let permits = Arc::new(tokio::sync::Semaphore::new(PREFETCH_CONCURRENCY));
+    let mut tasks = tokio::task::JoinSet::new();
+    for path in pending {
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let bytes = if is_url(&path) {
+                fetch(&path).await.ok()
+            } else {
+                tokio::fs::read(&path).await.ok()
+            };
+            (path, bytes)
+        });
+    }
+
+    let mut count = 0;
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((path, Some(bytes))) = joined else { continue };
+        if held + bytes.len() > max_bytes {
+            continue;
+        }
+        held += bytes.len();
+        count += 1;
+        with_prefetched(|cache| cache.insert(path, Arc::new(bytes)));
+    }
+    count
+}
+
+fn prefetched(path: &str) -> Option<Arc<Vec<u8>>> {
+    with_prefetched(|cache| cache.get(path).cloned())
+}
+
+fn with_prefetched<T>(f: impl FnOnce(&mut HashMap<String, Arc<Vec<u8>>>) -> T) -> T {
+    let mut cache = PREFETCHED.lock().unwrap_or_else(|e| e.into_inner());
+    f(cache.get_or_insert_with(HashMap::new))
+}
+
+/// Whether `path` is an `http://` or `https://` URL rather than a file.
+pub fn is_url(path: &str) -> bool {
+    let lower = path.get(..8).unwrap_or(path).to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Download an image from `url`.
+///
+/// Fails on error statuses, on responses that declare a non-image content
+/// type (such as an HTML page in front of the image) and on bodies larger
+/// than [`MAX_DOWNLOAD_BYTES`], which are abandoned as soon as the limit is
+/// passed.
+pub async fn fetch(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let response = client
+        .send(client.get(url))
+        .await
+        .map_err(|e| format!("failed to download {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("failed to download {}: HTTP {}", url, response.status()).into());
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or(v).trim().to_ascii_lowercase());
+    if let Some(content_type) = content_type
+        && !content_type.starts_with("image/")
+        && content_type != "application/octet-stream"
+    {
+        return Err(format!("{} is not an image (content type {})", url, content_type).into());
+    }
+    let too_large = || format!("{} is larger than {}", url, megabytes(MAX_DOWNLOAD_BYTES));
+    if response.content_length().is_some_and(|len| len > MAX_DOWNLOAD_BYTES as u64) {
+        return Err(too_large().into());
+    }
+    let mut bytes = Vec::new();
+    crate::client::read_chunks(response, |chunk| {
+        if bytes.len() + chunk.len() > MAX_DOWNLOAD_BYTES {
+            return Err(too_large().into());
+        }
+        bytes.extend_from_slice(chunk);
+        Ok(())
+    })
+    .await?;
+    Ok(bytes)
+}
+
+/// Pad `image` to a centred square and scale it down (Lanczos) if it is
+/// larger than `max_side`. Small images, pixel art especially, are kept at
+/// their size.
+fn conform(image: RgbaImage, max_side: Option<u32>) -> RgbaImage {
+    let side = image.width().max(image.height());
+    let square = if image.width() == image.height() {
+        image
+    } else {
+        let mut square = RgbaImage::new(side, side);
+        let x = (side - image.width()) / 2;
+        let y = (side - image.height()) / 2;
+        imageops::replace(&mut square, &image, x as i64, y as i64);
+        square
+    };
+    match max_side {
+        Some(max) if side > max => imageops::resize(&square, max, max, FilterType::Lanczos3),
+        _ => square,
+    }
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+fn format_name(format: ImageFormat) -> String {
+    format!("{:?}", format).to_uppercase()
+}
+
+fn megabytes(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn kilobytes(bytes: usize) -> String {
+    format!("{:.1} KB", bytes as f64 / 1024.0)
+}
@@ -0,0 +1,126 @@
+//! Quick facts about a result, for `gametorch inspect`.
+//!
+//! [`inspect`] takes a result ZIP, a directory of frames, an animated GIF or
+//! a single image such as a packed sheet, and reports what a QA pass looks
+//! at first: frame count and size, timing, colours, transparency and size on
+//! disk, plus any generation [provenance](crate::provenance) an exported
+//! image carries.
+
+use crate::frames::{Frame, Sequence};
+use crate::provenance::{self, Provenance};
+use image::AnimationDecoder;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::path::Path;
+
+/// What [`inspect`] found.
+#[derive(Clone, Debug, Serialize)]
+pub struct Inspection {
+    pub path: String,
+    /// `zip`, `frames` (a directory), `gif` or `image`.
+    pub kind: &'static str,
+    /// Size on disk; for a directory, of the files directly in it.
+    pub bytes: u64,
+    pub frames: usize,
+    /// Size of the first frame.
+    pub width: u32,
+    pub height: u32,
+    /// Whether every frame has that size.
+    pub uniform_size: bool,
+    /// Frame rate, when every frame is shown equally long.
+    pub fps: Option<f64>,
+    pub duration_ms: u64,
+    /// Distinct RGBA colours among visible pixels, across all frames.
+    pub colors: usize,
+    /// Share of pixels that are fully transparent.
+    pub transparent: f64,
+    /// Share of pixels that are partly transparent.
+    pub semi_transparent: f64,
+    pub provenance: Option<Provenance>,
+}
+
+/// Inspect the result, frames or image at `path`.
+pub fn inspect(path: &Path) -> Result<Inspection, Box<dyn std::error::Error + Send + Sync>> {
+    let (kind, bytes, frames, provenance) = if path.is_dir() {
+        let bytes = std::fs::read_dir(path)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum();
+        ("frames", bytes, Sequence::load_dir(path)?.frames, None)
+    } else {
+        let data = std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let bytes = data.len() as u64;
+        if data.starts_with(b"PK") {
+            ("zip", bytes, Sequence::from_zip_bytes(&data)?.frames, None)
+        } else if data.starts_with(b"GIF8") {
+            let decoder = image::codecs::gif::GifDecoder::new(Cursor::new(&data))?;
+            let frames = decoder
+                .into_frames()
+                .map(|frame| {
+                    let frame = frame?;
+                    let (numer, denom) = frame.delay().numer_denom_ms();
+                    Ok(Frame {
+                        duration_ms: numer / denom.max(1),
+                        image: frame.into_buffer(),
+                    })
+                })
+                .collect::<Result<Vec<Frame>, image::ImageError>>()?;
+            ("gif", bytes, frames, provenance::read(&data))
+        } else {
+            let image = image::load_from_memory(&data)
+                .map_err(|e| format!("{} is not a result ZIP, frame directory or image: {}", path.display(), e))?;
+            let frame = Frame {
+                image: image.to_rgba8(),
+                duration_ms: 0,
+            };
+            ("image", bytes, vec![frame], provenance::read(&data))
+        }
+    };
+    let Some(first) = frames.first() else {
+        return Err(format!("{} has no frames", path.display()).into());
+    };
+    let (width, height) = first.image.dimensions();
+
+    let mut colors: HashSet<[u8; 4]> = HashSet::new();
+    let (mut pixels, mut transparent, mut semi_transparent) = (0u64, 0u64, 0u64);
+    for frame in &frames {
+        for pixel in frame.image.pixels() {
+            pixels += 1;
+            match pixel.0[3] {
+                0 => transparent += 1,
+                255 => {
+                    colors.insert(pixel.0);
+                }
+                _ => {
+                    semi_transparent += 1;
+                    colors.insert(pixel.0);
+                }
+            }
+        }
+    }
+    let share = |count: u64| if pixels == 0 { 0.0 } else { count as f64 / pixels as f64 };
+
+    let durations: HashSet<u32> = frames.iter().map(|frame| frame.duration_ms).collect();
+    let fps = match durations.into_iter().collect::<Vec<_>>()[..] {
+        [duration] if duration > 0 && kind != "image" => Some(1000.0 / f64::from(duration)),
+        _ => None,
+    };
+    Ok(Inspection {
+        path: path.display().to_string(),
+        kind,
+        bytes,
+        frames: frames.len(),
+        width,
+        height,
+        uniform_size: frames.iter().all(|frame| frame.image.dimensions() == (width, height)),
+        fps,
+        duration_ms: frames.iter().map(|frame| u64::from(frame.duration_ms)).sum(),
+        colors: colors.len(),
+        transparent: share(transparent),
+        semi_transparent: share(semi_transparent),
+        provenance,
+    })
+}
@@ -3,9 +3,77 @@
 //! This crate will expose reusable APIs that power the `gametorch` CLI.
 //! Functionality will be filled in as development progresses.
 
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cancel;
+#[cfg(feature = "native")]
+pub mod cassette;
+pub mod catalog;
+pub mod client;
+pub mod clipboard;
+pub mod clock;
+pub mod completions;
+pub mod config;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod frames;
+pub mod history;
+#[cfg(feature = "native")]
+pub mod http_cache;
+pub mod images;
+#[cfg(feature = "index")]
+pub mod index;
+pub mod input;
+pub mod inspect;
+pub mod lint;
+#[cfg(feature = "native")]
+pub mod listen;
+pub mod manifest;
+pub mod markers;
+pub mod metrics;
+#[cfg(feature = "test-util")]
+pub mod mock;
+pub mod models;
+pub mod output;
+pub mod paths;
+pub mod pending;
+pub mod pipeline;
+pub mod poll;
+pub mod preview;
+pub mod pricing;
+pub mod process;
+pub mod profile;
+pub mod progress;
+pub mod provenance;
+pub mod render_queue;
+pub mod resume;
+#[cfg(feature = "native")]
+pub mod serve;
+pub mod sheet;
+pub mod slug;
+pub mod storage;
+pub mod store;
+pub mod timeline;
+#[cfg(feature = "native")]
+pub mod transcript;
+#[cfg(feature = "tui")]
+pub mod ui;
+pub mod usage;
+pub mod verify;
+pub mod watch;
+#[cfg(feature = "native")]
+pub mod watch_dir;
+pub mod webhooks;
+pub mod workspace;
+
 pub mod animations {
     use serde_json::Value;
-    use reqwest;
+    use crate::cancel::{self, CancellationToken};
+    use crate::error::{Error, ErrorKind};
+    use crate::poll::{poll_until, Poll, PollOptions, PollTimeout};
+    use crate::progress::{ProgressEvent, ProgressSink};
     use base64::{engine::general_purpose, Engine as _};
 
     /// Fetch animation results for a given animation.
@@ -17,13 +85,11 @@ pub mod animations {
         base_url: &str,
         animation_id: &str,
     ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-        let client = reqwest::Client::new();
+        let client = crate::client::shared();
 
         let url = format!("{}/api/animation_results/{}", base_url, animation_id);
         let json: Value = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .send()
+            .send(client.get(&url).header("Authorization", format!("Bearer {}", api_key)))
             .await?
             .error_for_status()?
             .json()
@@ -32,30 +98,496 @@ pub mod animations {
         Ok(json)
     }
 
-    /// List all animations belonging to the current user.
+    /// Sort order for [`list`].
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum ListSort {
+        #[default]
+        Newest,
+        Oldest,
+    }
+
+    impl ListSort {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                ListSort::Newest => "newest",
+                ListSort::Oldest => "oldest",
+            }
+        }
+    }
+
+    impl std::str::FromStr for ListSort {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_ascii_lowercase().as_str() {
+                "newest" => Ok(ListSort::Newest),
+                "oldest" => Ok(ListSort::Oldest),
+                other => Err(format!("unknown sort '{}' (expected newest or oldest)", other)),
+            }
+        }
+    }
+
+    /// Pagination, filtering and sorting for [`list`].
+    ///
+    /// The options are sent as query parameters. Servers that ignore them are
+    /// covered by applying the same filters to the response, so the result is
+    /// the same either way.
+    #[derive(Clone, Debug, Default)]
+    pub struct ListOptions {
+        /// 1-based page number (requires `per_page`).
+        pub page: Option<u32>,
+        pub per_page: Option<u32>,
+        /// Status code: 1 generating, 2 complete, 3 failed. Items without a
+        /// status are dropped.
+        pub status: Option<i64>,
+        /// Only animations created after this Unix timestamp (seconds).
+        pub created_after: Option<u64>,
+        /// Case-insensitive substring of the prompt.
+        pub search: Option<String>,
+        /// Only animations carrying this tag (case-insensitive).
+        pub tag: Option<String>,
+        pub sort: Option<ListSort>,
+    }
+
+    impl ListOptions {
+        fn query(&self) -> Vec<(&'static str, String)> {
+            let mut query = Vec::new();
+            if let Some(per_page) = self.per_page {
+                query.push(("page", self.page.unwrap_or(1).to_string()));
+                query.push(("per_page", per_page.to_string()));
+            }
+            if let Some(status) = self.status {
+                query.push(("status", status.to_string()));
+            }
+            if let Some(after) = self.created_after {
+                query.push(("created_after", after.to_string()));
+            }
+            if let Some(search) = &self.search {
+                query.push(("search", search.clone()));
+            }
+            if let Some(tag) = &self.tag {
+                query.push(("tag", tag.clone()));
+            }
+            if let Some(sort) = self.sort {
+                query.push(("sort", sort.as_str().to_string()));
+            }
+            query
+        }
+
+        /// Apply the options to a listing client-side.
+        pub fn apply(&self, list: Value) -> Value {
+            let Value::Array(mut items) = list else {
+                return list;
+            };
+            // A server that paginated returns at most a page; anything more
+            // means it ignored the parameters.
+            let unpaged = self.per_page.is_some_and(|per_page| items.len() > per_page as usize);
+            if let Some(status) = self.status {
+                // Strict: bulk commands act on what this keeps, so an item
+                // whose status the listing left out is never assumed to match.
+                let status = Status::from_code(status);
+                items.retain(|item| Status::of(item) == Some(status));
+            }
+            if let Some(after) = self.created_after {
+                items.retain(|item| created_at(item).is_none_or(|t| t > after));
+            }
+            if let Some(search) = &self.search {
+                let search = search.to_lowercase();
+                items.retain(|item| {
+                    item.get("prompt")
+                        .and_then(|p| p.as_str())
+                        .is_some_and(|p| p.to_lowercase().contains(&search))
+                });
+            }
+            if let Some(tag) = &self.tag {
+                items.retain(|item| {
+                    item.get("tags")
+                        .and_then(|tags| tags.as_array())
+                        .is_some_and(|tags| tags.iter().any(|t| t.as_str().is_some_and(|t| t.eq_ignore_ascii_case(tag))))
+                });
+            }
+            if let Some(sort) = self.sort {
+                // Creation time where known, the (increasing) ID otherwise.
+                items.sort_by_key(|item| (created_at(item), item.get("id").and_then(|id| id.as_i64())));
+                if sort == ListSort::Newest {
+                    items.reverse();
+                }
+            }
+            if let Some(per_page) = self.per_page.map(|n| n as usize)
+                && unpaged
+            {
+                let start = (self.page.unwrap_or(1).max(1) as usize - 1) * per_page;
+                items = items.into_iter().skip(start).take(per_page).collect();
+            }
+            Value::Array(items)
+        }
+    }
+
+    /// The status of an animation or result.
+    ///
+    /// The API reports statuses as numbers. A `Status` deserializes from the
+    /// number or a name (`generating`, `complete`, `failed`) and serializes
+    /// back to the number, which is what `--porcelain` output keeps;
+    /// [`humanize_statuses`] swaps in the [labels](Status::label) instead.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum Status {
+        Generating,
+        Complete,
+        /// Failed; the credits were refunded.
+        Failed,
+        /// A code this release does not know.
+        Other(i64),
+    }
+
+    impl Status {
+        pub fn from_code(code: i64) -> Status {
+            match code {
+                1 => Status::Generating,
+                2 => Status::Complete,
+                3 => Status::Failed,
+                other => Status::Other(other),
+            }
+        }
+
+        pub fn code(&self) -> i64 {
+            match self {
+                Status::Generating => 1,
+                Status::Complete => 2,
+                Status::Failed => 3,
+                Status::Other(code) => *code,
+            }
+        }
+
+        /// Short name, as accepted by `--status`; `None` for unknown codes.
+        pub fn name(&self) -> Option<&'static str> {
+            match self {
+                Status::Generating => Some("generating"),
+                Status::Complete => Some("complete"),
+                Status::Failed => Some("failed"),
+                Status::Other(_) => None,
+            }
+        }
+
+        /// Human-readable label for output; `None` for unknown codes.
+        pub fn label(&self) -> Option<&'static str> {
+            match self {
+                Status::Failed => Some("failed and refunded"),
+                other => other.name(),
+            }
+        }
+
+        /// The `status` of a JSON item, given as a number or a name.
+        pub fn of(item: &Value) -> Option<Status> {
+            match item.get("status")? {
+                Value::Number(n) => n.as_i64().map(Status::from_code),
+                Value::String(s) => s.parse().ok(),
+                _ => None,
+            }
+        }
+    }
+
+    impl std::fmt::Display for Status {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self.name() {
+                Some(name) => f.write_str(name),
+                None => write!(f, "{}", self.code()),
+            }
+        }
+    }
+
+    impl std::str::FromStr for Status {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_ascii_lowercase().as_str() {
+                "generating" => Ok(Status::Generating),
+                "complete" | "completed" => Ok(Status::Complete),
+                "failed" | "failed and refunded" => Ok(Status::Failed),
+                other => other
+                    .parse()
+                    .map(Status::from_code)
+                    .map_err(|_| format!("unknown status '{}' (expected generating, complete or failed)", s)),
+            }
+        }
+    }
+
+    impl serde::Serialize for Status {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_i64(self.code())
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for Status {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            match <Value as serde::Deserialize>::deserialize(deserializer)? {
+                Value::Number(n) => n
+                    .as_i64()
+                    .map(Status::from_code)
+                    .ok_or_else(|| serde::de::Error::custom(format!("invalid status {}", n))),
+                Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+                other => Err(serde::de::Error::custom(format!("invalid status {}", other))),
+            }
+        }
+    }
+
+    /// Replace every numeric `status` in `value`, at any depth, with its
+    /// [label](Status::label). Unknown codes are left as numbers.
+    pub fn humanize_statuses(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                if let Some(status) = map.get_mut("status")
+                    && let Some(label) = status.as_i64().and_then(|code| Status::from_code(code).label())
+                {
+                    *status = Value::String(label.to_string());
+                }
+                for v in map.values_mut() {
+                    humanize_statuses(v);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(humanize_statuses),
+            _ => {}
+        }
+    }
+
+    /// Status code for a status name (`generating`, `complete`, `failed`) or number.
+    pub fn parse_status(s: &str) -> Result<i64, String> {
+        s.parse::<Status>().map(|status| status.code())
+    }
+
+    /// Name of a status code, if it is a known one.
+    pub fn status_name(status: i64) -> Option<&'static str> {
+        Status::from_code(status).name()
+    }
+
+    /// Parse a point in time: a Unix timestamp, a `YYYY-MM-DD` date (UTC), or
+    /// an age such as `90m`, `12h` or `7d` before `now`.
+    pub fn parse_since(s: &str, now: u64) -> Result<u64, String> {
+        if let Ok(unix) = s.parse::<u64>() {
+            return Ok(unix);
+        }
+        if let Some(unix) = parse_date(s) {
+            return Ok(unix);
+        }
+        let last = s.chars().last().unwrap_or(' ');
+        let unit = match last {
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            'w' => 604_800,
+            _ => 0,
+        };
+        match s[..s.len() - last.len_utf8().min(s.len())].parse::<u64>() {
+            Ok(n) if unit > 0 => Ok(now.saturating_sub(n.saturating_mul(unit))),
+            _ => Err(format!("invalid time '{}' (expected YYYY-MM-DD, a Unix timestamp or an age like 7d)", s)),
+        }
+    }
+
+    /// Creation time of a listed animation, from a Unix timestamp or an
+    /// ISO 8601 `created_at`.
+    fn created_at(item: &Value) -> Option<u64> {
+        match item.get("created_at")? {
+            Value::Number(n) => n.as_u64(),
+            Value::String(s) => parse_date(s),
+            _ => None,
+        }
+    }
+
+    /// Unix seconds of an ISO 8601 date or date-time (treated as UTC).
+    pub(crate) fn parse_date(s: &str) -> Option<u64> {
+        let date = s.get(..10)?;
+        let mut parts = date.split('-');
+        let year: i64 = parts.next()?.parse().ok()?;
+        let month: i64 = parts.next()?.parse().ok()?;
+        let day: i64 = parts.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        let mut seconds = 0;
+        if let Some(time) = s.get(11..19) {
+            let mut hms = time.split(':').map(|p| p.parse::<i64>().ok());
+            seconds = hms.next()?? * 3_600 + hms.next()?? * 60 + hms.next()??;
+        }
+        // Howard Hinnant's days-from-civil.
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let mp = (month + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + day - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+        u64::try_from(days * 86_400 + seconds).ok()
+    }
+
+    /// List animations belonging to the current user.
+    ///
+    /// Hits `GET /api/animations` with `options` as query parameters.
     pub async fn list(
         api_key: &str,
         base_url: &str,
+        options: &ListOptions,
     ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-        let client = reqwest::Client::new();
+        let client = crate::client::shared();
 
         let url = format!("{}/api/animations", base_url);
         let animations: Value = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .send()
+            .send(
+                client
+                    .get(&url)
+                    .query(&options.query())
+                    .header("Authorization", format!("Bearer {}", api_key)),
+            )
             .await?
             .error_for_status()?
             .json()
             .await?;
 
-        Ok(animations)
+        Ok(options.apply(animations))
+    }
+
+    /// Path meaning standard input (for the input image) or standard output
+    /// (for the result ZIP).
+    pub const STDIO_PATH: &str = "-";
+
+    /// Everything a generation sends to `POST /api/animation`, built up
+    /// from [`GenerateRequest::new`]:
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// use gametorch::animations::{self, GenerateRequest, Model};
+    ///
+    /// let request = GenerateRequest::new("walking to the left")
+    ///     .duration(5)
+    ///     .input_image("mule.png")
+    ///     .model(Model::Id(6));
+    /// animations::generate_from("api-key", "https://gametorch.app", &request, true, None, false).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// An `input_image` may be a file, an `http(s)://` URL, which is
+    /// downloaded first (see [`crate::input::fetch`]), [`STDIO_PATH`] for
+    /// stdin or [`CLIPBOARD_INPUT`](crate::clipboard::CLIPBOARD_INPUT) for the
+    /// clipboard. It is checked locally before upload (and, with
+    /// `preprocess`, converted); see [`crate::input::prepare`]. With
+    /// `compress` it is then re-encoded losslessly if that makes it smaller;
+    /// see [`crate::input::compress`].
+    ///
+    /// A `seed` makes the render reproducible: the same prompt, settings and
+    /// seed give the same animation. Without one the backend picks a seed.
+    /// A `negative_prompt` describes what to keep out of the animation, such
+    /// as extra limbs or a background.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct GenerateRequest {
+        pub prompt: String,
+        /// One the model renders (see [`crate::models`]); 5 or 10 for the
+        /// default model.
+        pub duration_seconds: u32,
+        pub input_image: Option<String>,
+        pub preprocess: bool,
+        pub compress: bool,
+        /// At most one of `model_id` and `model_name`; with neither, model 6.
+        pub model_id: Option<u32>,
+        pub model_name: Option<String>,
+        /// Have the backend POST to this URL when the animation completes.
+        pub webhook_url: Option<String>,
+        pub seed: Option<u64>,
+        pub negative_prompt: Option<String>,
+        /// Send the request even if the duration or input image size is not
+        /// one the model is known to take, leaving the backend to judge.
+        pub skip_model_checks: bool,
+        /// When blocking, regenerate an animation that fails (status 3, so
+        /// refunded) up to this many times before giving up.
+        pub retry_on_failure: u32,
+    }
+
+    impl GenerateRequest {
+        /// A 5-second request for `prompt` with the default model and no
+        /// input image.
+        pub fn new(prompt: impl Into<String>) -> GenerateRequest {
+            GenerateRequest {
+                prompt: prompt.into(),
+                duration_seconds: 5,
+                input_image: None,
+                preprocess: false,
+                compress: false,
+                model_id: None,
+                model_name: None,
+                webhook_url: None,
+                seed: None,
+                negative_prompt: None,
+                skip_model_checks: false,
+                retry_on_failure: 0,
+            }
+        }
+
+        pub fn duration(mut self, seconds: u32) -> GenerateRequest {
+            self.duration_seconds = seconds;
+            self
+        }
+
+        pub fn input_image(mut self, path: impl Into<String>) -> GenerateRequest {
+            self.input_image = Some(path.into());
+            self
+        }
+
+        pub fn preprocess(mut self, preprocess: bool) -> GenerateRequest {
+            self.preprocess = preprocess;
+            self
+        }
+
+        pub fn compress(mut self, compress: bool) -> GenerateRequest {
+            self.compress = compress;
+            self
+        }
+
+        /// Use `model`, replacing any model set before.
+        pub fn model(mut self, model: Model) -> GenerateRequest {
+            (self.model_id, self.model_name) = match model {
+                Model::Id(id) => (Some(id), None),
+                Model::Name(name) => (None, Some(name)),
+            };
+            self
+        }
+
+        pub fn webhook_url(mut self, url: impl Into<String>) -> GenerateRequest {
+            self.webhook_url = Some(url.into());
+            self
+        }
+
+        pub fn seed(mut self, seed: u64) -> GenerateRequest {
+            self.seed = Some(seed);
+            self
+        }
+
+        pub fn negative_prompt(mut self, negative_prompt: impl Into<String>) -> GenerateRequest {
+            self.negative_prompt = Some(negative_prompt.into());
+            self
+        }
+
+        pub fn skip_model_checks(mut self, skip: bool) -> GenerateRequest {
+            self.skip_model_checks = skip;
+            self
+        }
+
+        pub fn retry_on_failure(mut self, retries: u32) -> GenerateRequest {
+            self.retry_on_failure = retries;
+            self
+        }
+    }
+
+    /// An animation model, by ID or by name (such as `alpha/v2.1`).
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Model {
+        Id(u32),
+        Name(String),
     }
 
     /// Generate a new animation from a prompt.
     ///
-    /// Returns a JSON object of shape:
-    /// `{ "animation_id": ..., "result_id": ..., "zip_path": ... }`.
+    /// The parameters are those of [`GenerateRequest`]; see
+    /// [`generate_from`].
+    #[deprecated(note = "build a `GenerateRequest` and call `generate_from`")]
+    #[allow(clippy::too_many_arguments)]
     pub async fn generate(
         api_key: &str,
         base_url: &str,
@@ -64,44 +596,391 @@ pub mod animations {
         block: bool,
         output_file: Option<&str>,
         input_image_path: Option<&str>,
+        preprocess: bool,
+        compress: bool,
         model_id: Option<u32>,
         model_name: Option<&str>,
+        webhook_url: Option<&str>,
+        seed: Option<u64>,
+        silent: bool,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let request = GenerateRequest {
+            prompt: prompt.to_string(),
+            duration_seconds,
+            input_image: input_image_path.map(str::to_string),
+            preprocess,
+            compress,
+            model_id,
+            model_name: model_name.map(str::to_string),
+            webhook_url: webhook_url.map(str::to_string),
+            seed,
+            negative_prompt: None,
+            skip_model_checks: false,
+            retry_on_failure: 0,
+        };
+        generate_from(api_key, base_url, &request, block, output_file, silent).await
+    }
+
+    /// Submit `request`, and with `block` wait for the render and download
+    /// its ZIP.
+    ///
+    /// Returns the creation response, or with `block` a JSON object of shape
+    /// `{ "animation_id": ..., "result_id": ..., "zip_path": ..., "seed": ... }`
+    /// once the ZIP has been saved to `output_file` (default
+    /// `animation_<id>_<result id>.zip`). The seed is the one used, when the
+    /// backend reports it (`null` if not). An `output_file` of
+    /// [`STDIO_PATH`] writes the ZIP to stdout and implies `silent`, so the
+    /// archive is all that stdout carries.
+    pub async fn generate_from(
+        api_key: &str,
+        base_url: &str,
+        request: &GenerateRequest,
+        block: bool,
+        output_file: Option<&str>,
         silent: bool,
     ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-        // Validate duration
-        if duration_seconds != 5 && duration_seconds != 10 {
-            return Err("duration must be either 5 or 10 seconds".into());
+        generate_with_progress(api_key, base_url, request, block, output_file, silent, &(), &CancellationToken::new()).await
+    }
+
+    /// [`generate_from`], reporting each stage to `progress`; see
+    /// [`crate::progress`]. Cancelling `cancel` stops waiting for the result
+    /// (the job keeps rendering) and fails with [`cancel::Cancelled`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn generate_with_progress(
+        api_key: &str,
+        base_url: &str,
+        request: &GenerateRequest,
+        block: bool,
+        output_file: Option<&str>,
+        silent: bool,
+        progress: &dyn ProgressSink,
+        cancel: &CancellationToken,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let silent = silent || (block && output_file == Some(STDIO_PATH));
+
+        if !silent {
+            println!("Starting animation generation request...");
         }
 
-        let client = reqwest::Client::new();
+        let prepared = build_request(base_url, request, None, silent).await?;
+
+        let post_url = format!("{}/api/animation", base_url);
+        let post_resp = submit(api_key, &post_url, &prepared).await?;
+
+        let mut animation_id = post_resp
+            .get("animation_id")
+            .and_then(|v| v.as_i64())
+            .ok_or("animation_id missing from response")?;
+
+        if !silent {
+            println!("Animation created successfully (ID: {}).", animation_id);
+        }
+        progress.event(ProgressEvent::Submitted { animation_id });
+
+        // If not blocking, return immediately
+        if !block {
+            progress.event(ProgressEvent::Done {
+                animation_id,
+                result_id: None,
+                zip_path: None,
+            });
+            return Ok(post_resp);
+        }
+
+        let mut failed_ids = Vec::new();
+        let animation_results = loop {
+            match wait_with_progress(api_key, base_url, &animation_id.to_string(), silent, progress, cancel).await {
+                Err(err)
+                    if failed_ids.len() < request.retry_on_failure as usize
+                        && ErrorKind::of(err.as_ref()) == ErrorKind::FailedGeneration =>
+                {
+                    let failed = animation_id;
+                    failed_ids.push(failed);
+                    animation_id = regenerate_failed(api_key, base_url, failed).await?;
+                    if !silent {
+                        println!(
+                            "Animation {} failed (credits refunded); regenerated as {} (retry {} of {}).",
+                            failed,
+                            animation_id,
+                            failed_ids.len(),
+                            request.retry_on_failure
+                        );
+                    }
+                    progress.event(ProgressEvent::Submitted { animation_id });
+                }
+                result => break result?,
+            }
+        };
+
+        // Determine result ID to download
+        let result_id = first_result_id(&animation_results).ok_or("result id missing")?;
+
+        // Download ZIP
+        if !silent {
+            println!("Render complete, downloading ZIP...");
+        }
+        progress.event(ProgressEvent::Rendering);
+        let bytes = cancel::until(
+            cancel,
+            Some(&animation_id.to_string()),
+            download_zip_with_progress(api_key, base_url, &result_id.to_string(), silent, progress, cancel),
+        )
+        .await?;
+
+        // Determine output file path
+        let path = output_file
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("animation_{}_{}.zip", animation_id, result_id));
+
+        if path == STDIO_PATH {
+            use std::io::Write;
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&bytes)?;
+            stdout.flush()?;
+        } else {
+            crate::store::save(std::path::Path::new(&path), &bytes)?;
+        }
+
+        if !silent {
+            println!("ZIP saved to {}", path);
+        }
+
+        progress.event(ProgressEvent::Done {
+            animation_id,
+            result_id: Some(result_id),
+            zip_path: Some(path.clone()),
+        });
+
+        let seed = first_result(&animation_results)
+            .and_then(|result| result.get("seed"))
+            .or_else(|| post_resp.get("seed"))
+            .and_then(Value::as_u64)
+            .or(request.seed);
+        let mut out_json = serde_json::json!({
+            "animation_id": animation_id,
+            "slug": crate::slug::slug(&request.prompt),
+            "result_id": result_id,
+            "zip_path": path,
+            "seed": seed,
+        });
+        if !failed_ids.is_empty() {
+            out_json["failed_animation_ids"] = serde_json::json!(failed_ids);
+        }
+
+        Ok(out_json)
+    }
+
+    /// What [`generate_from`] would send, without sending it.
+    ///
+    /// Runs every local check `generate_from` does (duration and image size
+    /// for the model, webhook URL, reading and validating the input image) and returns the request
+    /// as `{ "method", "url", "content_type", "body", "estimated_credits",
+    /// "pricing" }`, with the image replaced by a summary. The estimate
+    /// comes from [`pricing`](crate::pricing) and is `null` for a model
+    /// without a known price.
+    pub async fn dry_run(base_url: &str, request: &GenerateRequest) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let prepared = build_request(base_url, request, None, true).await?;
+        let mut body = Value::Object(prepared.fields.clone());
+        if let Some(image) = &prepared.image {
+            body["input_image"] = Value::String(format!("<{} byte image>", image.len()));
+        } else {
+            body["input_image_base64"] = Value::String(String::new());
+        }
+        let prices = crate::pricing::table(base_url).await;
+        let job = crate::pricing::Job::new(request.model_id, request.model_name.clone(), request.duration_seconds);
+        Ok(serde_json::json!({
+            "method": "POST",
+            "url": format!("{}/api/animation", base_url),
+            "content_type": if prepared.image.is_some() { "multipart/form-data" } else { "application/json" },
+            "body": body,
+            "estimated_credits": prices.price(&job).ok().map(|price| price.credits),
+            "pricing": prices.source.as_str(),
+        }))
+    }
+
+    /// A [`GenerateRequest`] ready to send: its fields, the prepared input
+    /// image and, for inpainting, its mask, sent as multipart form data or,
+    /// as a fallback, base64 in JSON.
+    struct PreparedRequest {
+        fields: serde_json::Map<String, Value>,
+        image: Option<Vec<u8>>,
+        mask: Option<Vec<u8>>,
+    }
+
+    impl PreparedRequest {
+        /// The JSON body, with the image base64-encoded (empty without one)
+        /// and the mask as `mask_image_base64`.
+        fn json(&self) -> Value {
+            let mut body = self.fields.clone();
+            let encoded = self.image.as_ref().map(|image| general_purpose::STANDARD.encode(image));
+            body.insert("input_image_base64".to_string(), Value::String(encoded.unwrap_or_default()));
+            if let Some(mask) = &self.mask {
+                body.insert("mask_image_base64".to_string(), Value::String(general_purpose::STANDARD.encode(mask)));
+            }
+            Value::Object(body)
+        }
+
+        /// The fields as text parts plus the image as a file part named
+        /// `input_image` and the mask as one named `mask_image`.
+        fn multipart(&self) -> Result<reqwest::multipart::Form, Box<dyn std::error::Error + Send + Sync>> {
+            let mut form = reqwest::multipart::Form::new();
+            for (name, value) in &self.fields {
+                let text = match value {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                form = form.text(name.clone(), text);
+            }
+            if let Some(image) = &self.image {
+                let format = image::guess_format(image)?;
+                let part = reqwest::multipart::Part::bytes(image.clone())
+                    .file_name(format!("input.{}", format.extensions_str().first().copied().unwrap_or("png")))
+                    .mime_str(format.to_mime_type())?;
+                form = form.part("input_image", part);
+            }
+            if let Some(mask) = &self.mask {
+                let part = reqwest::multipart::Part::bytes(mask.clone())
+                    .file_name("mask.png")
+                    .mime_str("image/png")?;
+                form = form.part("mask_image", part);
+            }
+            Ok(form)
+        }
+    }
+
+    /// Set once the backend has answered a multipart upload with `415
+    /// Unsupported Media Type`, so later submissions go straight to JSON.
+    static MULTIPART_UNSUPPORTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+    /// Submit a generation request.
+    ///
+    /// With an input image, the image is uploaded as multipart form data,
+    /// which saves the third that base64 adds to the upload and the memory
+    /// for the encoded copy. A backend that rejects the form (`415`, `400`
+    /// or `422`) gets the base64 JSON body instead.
+    async fn submit(
+        api_key: &str,
+        url: &str,
+        request: &PreparedRequest,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        use std::sync::atomic::Ordering;
+        let client = crate::client::shared();
+        let auth = format!("Bearer {}", api_key);
+
+        if request.image.is_some() && !MULTIPART_UNSUPPORTED.load(Ordering::Relaxed) {
+            let resp = client
+                .send(client.post(url).header("Authorization", &auth).multipart(request.multipart()?))
+                .await?;
+            match resp.status() {
+                reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE => MULTIPART_UNSUPPORTED.store(true, Ordering::Relaxed),
+                reqwest::StatusCode::BAD_REQUEST | reqwest::StatusCode::UNPROCESSABLE_ENTITY => {}
+                _ => return Ok(resp.error_for_status()?.json().await?),
+            }
+        }
+
+        let json: Value = client
+            .send(client.post(url).header("Authorization", auth).json(&request.json()))
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(json)
+    }
+
+    /// Validate a [`GenerateRequest`] and prepare it for sending, reading
+    /// and preparing the input image and, if given, its inpainting mask.
+    /// The duration and image size are checked against the model's
+    /// [capabilities](crate::models) unless `skip_model_checks` is set.
+    async fn build_request(
+        base_url: &str,
+        request: &GenerateRequest,
+        mask_path: Option<&str>,
+        silent: bool,
+    ) -> Result<PreparedRequest, Box<dyn std::error::Error + Send + Sync>> {
+        let GenerateRequest {
+            prompt,
+            duration_seconds,
+            input_image,
+            preprocess,
+            compress,
+            model_id,
+            model_name,
+            webhook_url,
+            seed,
+            negative_prompt,
+            skip_model_checks,
+            retry_on_failure: _,
+        } = request;
+        let (duration_seconds, preprocess, compress) = (*duration_seconds, *preprocess, *compress);
+        let input_image_path = input_image.as_deref();
 
         // Validate mutually exclusive parameters (should already be handled by CLI)
         if model_id.is_some() && model_name.is_some() {
-            return Err("Specify either model_id or model_name, not both".into());
+            return Err(Error::new(ErrorKind::Validation, "Specify either model_id or model_name, not both").into());
         }
 
-        if !silent {
-            println!("Starting animation generation request...");
+        // Validate duration against what the model renders
+        let capabilities = if *skip_model_checks {
+            None
+        } else {
+            crate::models::table(base_url).await.find(*model_id, model_name.as_deref()).cloned()
+        };
+        match &capabilities {
+            Some(capabilities) => capabilities.check_duration(duration_seconds)?,
+            None if duration_seconds == 0 => {
+                return Err(Error::new(ErrorKind::Validation, "duration must be greater than zero").into());
+            }
+            None => {}
+        }
+        if let Some(url) = webhook_url {
+            crate::webhooks::validate_url(url).map_err(|e| Error::new(ErrorKind::Validation, e.to_string()))?;
+        }
+
+        if mask_path.is_some() && input_image_path.is_none() {
+            return Err(Error::new(ErrorKind::Validation, "a mask needs an input image to apply to").into());
         }
 
-        // Prepare input_image_base64 if provided
-        let input_image_base64 = if let Some(path) = input_image_path {
-            let bytes = tokio::fs::read(path).await?;
-            general_purpose::STANDARD.encode(bytes)
+        // Prepare the input image (and its mask) if provided
+        let mut mask = None;
+        // Unknown without the model checks, and then left to the backend.
+        let max_side = capabilities.as_ref().and_then(|capabilities| capabilities.max_resolution);
+        let image = if let Some(path) = input_image_path {
+            let bytes = crate::input::read(path, silent).await?;
+            if let Some(mask_path) = mask_path {
+                let mask_bytes = crate::input::read(mask_path, silent).await?;
+                let prepared = crate::input::prepare_mask(mask_bytes, &bytes, preprocess, max_side)
+                    .map_err(|e| Error::new(ErrorKind::Validation, e.to_string()))?;
+                mask = Some(prepared);
+            }
+            let prepared = crate::input::prepare(bytes, preprocess, max_side)
+                .map_err(|e| Error::new(ErrorKind::Validation, e.to_string()))?;
+            for warning in &prepared.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            let bytes = prepared.bytes;
+            if let Some(capabilities) = &capabilities {
+                let (width, height) = crate::input::dimensions(&bytes)?;
+                capabilities.check_resolution(width, height)?;
+            }
+            if compress {
+                let compressed = crate::input::compress(bytes)?;
+                if !silent {
+                    println!("{}", compressed);
+                }
+                Some(compressed.bytes)
+            } else {
+                Some(bytes)
+            }
         } else {
-            String::new()
+            None
         };
 
         // Build request body dynamically
         let mut body_map = serde_json::Map::new();
         body_map.insert("prompt".to_string(), serde_json::Value::String(prompt.to_string()));
         body_map.insert("duration_seconds".to_string(), serde_json::Value::Number(duration_seconds.into()));
-        body_map.insert(
-            "input_image_base64".to_string(),
-            serde_json::Value::String(input_image_base64),
-        );
 
-        match (model_id, model_name) {
+        match (*model_id, model_name) {
             (Some(id), None) => {
                 body_map.insert(
                     "animation_model_id".to_string(),
@@ -124,155 +1003,419 @@ pub mod animations {
             _ => unreachable!(),
         }
 
-        let body = serde_json::Value::Object(body_map);
-
-        let post_url = format!("{}/api/animation", base_url);
-        let post_resp: Value = client
-            .post(&post_url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .json(&body)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        if let Some(url) = webhook_url {
+            body_map.insert("webhook_url".to_string(), serde_json::Value::String(url.to_string()));
+        }
+        if let Some(seed) = *seed {
+            body_map.insert("seed".to_string(), serde_json::Value::Number(seed.into()));
+        }
+        if let Some(negative) = negative_prompt.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+            body_map.insert("negative_prompt".to_string(), serde_json::Value::String(negative.to_string()));
+        }
 
-        let animation_id = post_resp
-            .get("animation_id")
-            .and_then(|v| v.as_i64())
-            .ok_or("animation_id missing from response")?;
+        Ok(PreparedRequest {
+            fields: body_map,
+            image,
+            mask,
+        })
+    }
 
+    /// Regenerate only part of `request`'s input image: the region painted
+    /// white in the mask at `mask_path` is redrawn following the prompt, the
+    /// rest is kept. The mask must be the size of the input image; see
+    /// [`crate::input::prepare_mask`].
+    ///
+    /// Sent like [`generate_from`], with the mask alongside the image, and
+    /// returns the JSON response (shape: `{ "animation_id": <new_id> }`).
+    pub async fn inpaint(
+        api_key: &str,
+        base_url: &str,
+        request: &GenerateRequest,
+        mask_path: &str,
+        silent: bool,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
         if !silent {
-            println!("Animation created successfully (ID: {}).", animation_id);
+            println!("Starting inpainting request...");
         }
+        let prepared = build_request(base_url, request, Some(mask_path), silent).await?;
+        submit(api_key, &format!("{}/api/animation", base_url), &prepared).await
+    }
 
-        // If not blocking, return immediately
-        if !block {
-            return Ok(post_resp);
-        }
+    /// Poll an animation's results every 5 seconds until it completes.
+    ///
+    /// Returns the results JSON once the first result reaches status 2
+    /// (complete), or an error if it lands in status 3 (failed and refunded).
+    pub async fn wait(
+        api_key: &str,
+        base_url: &str,
+        animation_id: &str,
+        silent: bool,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        wait_with_progress(api_key, base_url, animation_id, silent, &(), &CancellationToken::new()).await
+    }
+
+    /// [`wait`], sending [`ProgressEvent::Polling`] after every poll and
+    /// giving up with [`cancel::Cancelled`] when `cancel` fires.
+    pub async fn wait_with_progress(
+        api_key: &str,
+        base_url: &str,
+        animation_id: &str,
+        silent: bool,
+        progress: &dyn ProgressSink,
+        cancel: &CancellationToken,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        cancel::until(
+            cancel,
+            Some(animation_id),
+            poll_results(api_key, base_url, animation_id, silent, progress),
+        )
+        .await
+    }
+
+    async fn poll_results(
+        api_key: &str,
+        base_url: &str,
+        animation_id: &str,
+        silent: bool,
+        progress: &dyn ProgressSink,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let client = crate::client::shared();
 
         if !silent {
             println!("Polling for results every 5 seconds...");
         }
 
-        // Poll for results every 5 seconds until complete
+        let results_url = format!("{}/api/animation_results/{}", base_url, animation_id);
+        let mut polls = 0u32;
+        poll_until(&PollOptions::default(), |elapsed| {
+            polls += 1;
+            // Every sixth poll, i.e. every 30 seconds.
+            let report = !silent && polls > 1 && polls % 6 == 1;
+            let results_url = &results_url;
+            async move {
+                if report {
+                    println!("Still polling ({} total seconds elapsed)", elapsed.as_secs());
+                }
+                let resp: Value = client
+                    .send(
+                        client
+                            .get(results_url)
+                            .header("Authorization", format!("Bearer {}", api_key)),
+                    )
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                match first_status(&resp) {
+                    // completed successfully
+                    Some(2) => Ok(Poll::Ready(resp)),
+                    // failed and refunded
+                    Some(3) => Err(Error::new(ErrorKind::FailedGeneration, "animation failed and refunded (status=3)").into()),
+                    // 1 = generating; continue polling
+                    status => {
+                        progress.event(ProgressEvent::Polling { elapsed });
+                        Ok(Poll::Pending(status.and_then(status_name).unwrap_or("pending").to_string()))
+                    }
+                }
+            }
+        })
+        .await
+    }
+
+    /// Poll several animations concurrently until each completes or fails.
+    ///
+    /// Every 5 seconds the results of all unfinished animations are fetched
+    /// at once through the shared client, and unless `silent` a combined
+    /// progress line is kept on stderr. Returns one outcome per ID, in order:
+    /// the results JSON as [`wait`] would return it, or why it failed.
+    #[cfg(feature = "native")]
+    pub async fn wait_all(
+        api_key: &str,
+        base_url: &str,
+        animation_ids: &[String],
+        silent: bool,
+    ) -> Vec<Result<Value, Box<dyn std::error::Error + Send + Sync>>> {
+        use std::io::IsTerminal;
         use tokio::time::{sleep, Duration};
 
-        let results_url = format!("{}/api/animation_results/{}", base_url, animation_id);
-        let animation_results: Value;
+        let mut outcomes: Vec<Option<Result<Value, Box<dyn std::error::Error + Send + Sync>>>> =
+            animation_ids.iter().map(|_| None).collect();
+        let interactive = std::io::stderr().is_terminal();
         let mut elapsed: u32 = 0;
         loop {
-            let resp: Value = client
-                .get(&results_url)
-                .header("Authorization", format!("Bearer {}", api_key))
-                .send()
-                .await?
-                .error_for_status()?
-                .json()
-                .await?;
-
-            // Assume response is array; take first item
-            let status_complete = if resp.is_array() {
-                resp.get(0)
-                    .and_then(|item| item.get("status"))
-                    .and_then(|s| s.as_i64())
-            } else {
-                resp.get("status")
-                    .and_then(|s| s.as_i64())
-            };
-
-            if let Some(status) = status_complete {
-                match status {
-                    2 => {
-                        // completed successfully
-                        animation_results = resp;
-                        break;
+            let mut polls = tokio::task::JoinSet::new();
+            for (i, id) in animation_ids.iter().enumerate().filter(|(i, _)| outcomes[*i].is_none()) {
+                let url = format!("{}/api/animation_results/{}", base_url, id);
+                let auth = format!("Bearer {}", api_key);
+                polls.spawn(async move {
+                    let client = crate::client::shared();
+                    let resp = async {
+                        client
+                            .send(client.get(&url).header("Authorization", auth))
+                            .await?
+                            .error_for_status()?
+                            .json::<Value>()
+                            .await
                     }
-                    3 => {
-                        // failed and refunded
-                        return Err("animation failed and refunded (status=3)".into());
+                    .await;
+                    (i, resp)
+                });
+            }
+            while let Some(joined) = polls.join_next().await {
+                let (i, resp) = match joined {
+                    Ok(polled) => polled,
+                    Err(err) => panic!("polling task failed: {}", err),
+                };
+                outcomes[i] = match resp {
+                    Ok(resp) => match first_status(&resp) {
+                        Some(2) => Some(Ok(resp)),
+                        Some(3) => Some(Err(Error::new(ErrorKind::FailedGeneration, "animation failed and refunded (status=3)").into())),
+                        _ => None,
+                    },
+                    Err(err) => Some(Err(err.into())),
+                };
+            }
+
+            let done = outcomes.iter().filter(|o| matches!(o, Some(Ok(_)))).count();
+            let failed = outcomes.iter().filter(|o| matches!(o, Some(Err(_)))).count();
+            let pending = outcomes.len() - done - failed;
+            if !silent && (interactive || pending == 0 || elapsed.is_multiple_of(30)) {
+                let line = format!(
+                    "{}/{} complete, {} failed, {} rendering ({}s elapsed)",
+                    done,
+                    outcomes.len(),
+                    failed,
+                    pending,
+                    elapsed
+                );
+                if interactive {
+                    eprint!("\r\x1b[2K{}", line);
+                    if pending == 0 {
+                        eprintln!();
                     }
-                    _ => {} // 1 = generating; continue polling
+                } else {
+                    eprintln!("{}", line);
                 }
             }
-
+            if pending == 0 {
+                return outcomes.into_iter().map(|o| o.expect("every animation finished")).collect();
+            }
             sleep(Duration::from_secs(5)).await;
             elapsed += 5;
-            if !silent && elapsed % 30 == 0 {
-                println!("Still polling ({} total seconds elapsed)", elapsed);
-            }
         }
+    }
 
-        // Determine result ID to download
-        let result_id = if animation_results.is_array() {
-            animation_results
-                .get(0)
-                .and_then(|item| item.get("id"))
-                .and_then(|v| v.as_i64())
-        } else {
-            animation_results.get("id").and_then(|v| v.as_i64())
-        }
-        .ok_or("result id missing")?;
+    /// Download the ZIP for a completed animation result.
+    ///
+    /// Until the archive has been built the backend answers `202 Accepted`
+    /// or `404`, so this retries every 5 seconds (or as `Retry-After` says)
+    /// for up to two minutes, as it does when rate limited. Server errors and
+    /// dropped connections are retried too, but only a few times; errors
+    /// carry the start of the response body. A download that
+    /// does not match the checksum the backend sent fails; see
+    /// [`crate::verify`].
+    pub async fn download_zip(
+        api_key: &str,
+        base_url: &str,
+        result_id: &str,
+        silent: bool,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        download_zip_with_progress(api_key, base_url, result_id, silent, &(), &CancellationToken::new()).await
+    }
 
-        // Download ZIP
-        if !silent {
-            println!("Render complete, downloading ZIP...");
-        }
+    /// [`download_zip`], sending [`ProgressEvent::Downloading`] as the
+    /// archive arrives and giving up with [`cancel::Cancelled`] when `cancel`
+    /// fires.
+    pub async fn download_zip_with_progress(
+        api_key: &str,
+        base_url: &str,
+        result_id: &str,
+        silent: bool,
+        progress: &dyn ProgressSink,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        cancel::until(cancel, None, fetch_zip(api_key, base_url, result_id, silent, progress)).await
+    }
+
+    /// The ZIP download fails on this many server errors (or dropped
+    /// connections), which are tolerated until then in case the backend
+    /// is still building it.
+    const ZIP_SERVER_ERROR_ATTEMPTS: u32 = 3;
+
+    async fn fetch_zip(
+        api_key: &str,
+        base_url: &str,
+        result_id: &str,
+        silent: bool,
+        progress: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::time::Duration;
+
+        let client = crate::client::shared();
         let zip_url = format!("{}/api/animation_result_zip/{}", base_url, result_id);
 
-        let mut waited_sec = 0u32;
-        let bytes;
-        loop {
-            let resp_result = client
-                .get(&zip_url)
-                .header("Authorization", format!("Bearer {}", api_key))
-                .send()
-                .await;
-
-            match resp_result {
-                Ok(resp) => {
-                    if resp.status().is_success() {
-                        bytes = resp.bytes().await?;
-                        break;
-                    } else if resp.status().as_u16() == 500 {
-                        // zip not ready yet
-                        if waited_sec == 0 && !silent {
-                            println!("Animation rendered successfully, waiting on .zip file...");
-                        }
-                    } else {
-                        return Err(format!("failed to download zip: HTTP {}", resp.status()).into());
+        let options = PollOptions::every(Duration::from_secs(5)).timeout(Duration::from_secs(120));
+        let server_errors = AtomicU32::new(0);
+        let result = poll_until(&options, |elapsed| {
+            let zip_url = &zip_url;
+            let server_errors = &server_errors;
+            // Counts one more server error; false once they are used up.
+            let tolerate = move || server_errors.fetch_add(1, Ordering::Relaxed) + 1 < ZIP_SERVER_ERROR_ATTEMPTS;
+            async move {
+                // Each poll is a single attempt, so the client's retries do
+                // not multiply the server errors counted here.
+                let resp = match client
+                    .send_once(
+                        client
+                            .get(zip_url)
+                            .header("Authorization", format!("Bearer {}", api_key)),
+                    )
+                    .await
+                {
+                    Ok(resp) => resp,
+                    Err(err) if crate::client::RetryPolicy::retries_error(&err, true) && tolerate() => {
+                        return Ok(Poll::Pending(err.to_string()));
+                    }
+                    Err(err) => {
+                        return Err(Error::new(ErrorKind::of(&err), format!("failed to download zip: {}", err)).into());
                     }
+                };
+
+                let status = resp.status();
+                if status.is_success() && status != reqwest::StatusCode::ACCEPTED {
+                    let checksum = resp
+                        .headers()
+                        .get(crate::verify::CHECKSUM_HEADER)
+                        .and_then(|v| v.to_str().ok())
+                        .map(str::to_string);
+                    let total = resp.content_length();
+                    let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+                    crate::client::read_chunks(resp, |chunk| {
+                        bytes.extend_from_slice(chunk);
+                        progress.event(ProgressEvent::Downloading {
+                            bytes: bytes.len() as u64,
+                            total,
+                        });
+                        Ok(())
+                    })
+                    .await?;
+                    if let Some(expected) = checksum {
+                        crate::verify::check(&bytes, &expected)
+                            .map_err(|e| Error::new(ErrorKind::Server, format!("corrupt zip download: {}", e)))?;
+                    }
+                    return Ok(Poll::Ready(bytes));
+                }
+
+                let retry_after = crate::client::retry_after(resp.headers());
+                let body = response_excerpt(resp).await;
+                let seen = format!("HTTP {}{}", status, body.as_deref().map(|b| format!(": {}", b)).unwrap_or_default());
+                let not_ready = match status {
+                    // 202: still being built; 404: not built yet; 429: asked
+                    // to slow down.
+                    reqwest::StatusCode::ACCEPTED
+                    | reqwest::StatusCode::NOT_FOUND
+                    | reqwest::StatusCode::TOO_MANY_REQUESTS => true,
+                    // Tolerate a few server errors while the ZIP is built, but
+                    // not a server that keeps failing.
+                    s if s.is_server_error() => tolerate(),
+                    _ => false,
+                };
+                if !not_ready {
+                    let message = format!("failed to download zip: {}", seen);
+                    return Err(Error::new(ErrorKind::from_status(status), message).into());
                 }
-                Err(err) => {
-                    return Err(format!("failed to download zip: {}", err).into());
+                if elapsed.is_zero() && !silent {
+                    println!("Animation rendered successfully, waiting on .zip file...");
                 }
+                Ok(match retry_after {
+                    Some(after) => Poll::RetryAfter(seen, after),
+                    None => Poll::Pending(seen),
+                })
             }
+        })
+        .await;
+        result.map_err(|err| match err.downcast::<PollTimeout>() {
+            Ok(timeout) => Error::new(ErrorKind::Timeout, format!("timed out waiting for .zip file: {}", timeout)).into(),
+            Err(err) => err,
+        })
+    }
 
-            if waited_sec >= 120 {
-                return Err("timed out waiting for .zip file".into());
-            }
-            sleep(Duration::from_secs(5)).await;
-            waited_sec += 5;
+    /// The start of an error response's body, for error messages.
+    async fn response_excerpt(resp: reqwest::Response) -> Option<String> {
+        const MAX_CHARS: usize = 200;
+        let text = resp.text().await.ok()?;
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
         }
+        Some(match text.char_indices().nth(MAX_CHARS) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text.to_string(),
+        })
+    }
 
-        // Determine output file path
-        let path = output_file
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| format!("animation_{}_{}.zip", animation_id, result_id));
+    /// The first frame of a result as PNG bytes, for quick previews.
+    ///
+    /// Hits `GET /api/animation_result_thumbnail/<result_id>`; when the
+    /// backend has no thumbnail for the result (`404`) the ZIP is downloaded
+    /// instead and its first frame extracted.
+    pub async fn thumbnail(
+        api_key: &str,
+        base_url: &str,
+        result_id: &str,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let client = crate::client::shared();
+        let url = format!("{}/api/animation_result_thumbnail/{}", base_url, result_id);
 
-        tokio::fs::write(&path, &bytes).await?;
+        let resp = client
+            .send(client.get(&url).header("Authorization", format!("Bearer {}", api_key)))
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            let zip = download_zip(api_key, base_url, result_id, true).await?;
+            return crate::frames::Sequence::first_frame_png(&zip);
+        }
+        let bytes = resp.error_for_status()?.bytes().await?;
+        if !bytes.starts_with(b"\x89PNG") {
+            return Err("thumbnail is not a PNG".into());
+        }
+        Ok(bytes.to_vec())
+    }
 
-        if !silent {
-            println!("ZIP saved to {}", path);
+    /// Status of the first result in an `animation_results` response.
+    ///
+    /// The backend returns either an array of results or a single object.
+    pub fn first_status(results: &Value) -> Option<i64> {
+        first_result(results)
+            .and_then(|item| item.get("status"))
+            .and_then(|s| s.as_i64())
+    }
+
+    /// ID of the first result in an `animation_results` response.
+    pub fn first_result_id(results: &Value) -> Option<i64> {
+        first_result(results)
+            .and_then(|item| item.get("id"))
+            .and_then(|v| v.as_i64())
+    }
+
+    /// The first result in an `animation_results` response.
+    pub fn first_result(results: &Value) -> Option<&Value> {
+        if results.is_array() {
+            results.get(0)
+        } else {
+            Some(results)
         }
+    }
 
-        let out_json = serde_json::json!({
-            "animation_id": animation_id,
-            "result_id": result_id,
-            "zip_path": path,
-        });
+    /// The web UI page of an animation.
+    pub fn web_url(base_url: &str, animation_id: &str) -> String {
+        format!("{}/sprite-animator/{}", base_url, animation_id)
+    }
 
-        Ok(out_json)
+    /// The web UI's crop-and-trim page for a result.
+    pub fn crop_url(base_url: &str, result_id: &str) -> String {
+        format!("{}/sprite-animator/crop-and-trim/{}", base_url, result_id)
     }
 
     #[allow(unused_variables)]
@@ -280,7 +1423,73 @@ pub mod animations {
         unimplemented!("crop animation");
     }
 
-    /// Regenerate an animation using the same parameters as an existing one.
+    /// Editable metadata of an animation; `None` fields are left unchanged.
+    #[derive(Clone, Debug, Default, serde::Serialize)]
+    pub struct Metadata {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub name: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub description: Option<String>,
+        /// Replaces all tags when set.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub tags: Option<Vec<String>>,
+    }
+
+    impl Metadata {
+        pub fn is_empty(&self) -> bool {
+            self.name.is_none() && self.description.is_none() && self.tags.is_none()
+        }
+    }
+
+    /// Set the name, description and/or tags of an animation.
+    ///
+    /// Hits `PATCH /api/animation/<animation_id>` and returns the updated
+    /// animation as JSON.
+    pub async fn update_metadata(
+        api_key: &str,
+        base_url: &str,
+        animation_id: &str,
+        metadata: &Metadata,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let client = crate::client::shared();
+        let url = format!("{}/api/animation/{}", base_url, animation_id);
+
+        let json: Value = client
+            .send(
+                client
+                    .patch(&url)
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .json(metadata),
+            )
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(json)
+    }
+
+    /// Delete an animation and its results.
+    ///
+    /// Hits `DELETE /api/animation/<animation_id>`.
+    pub async fn delete(
+        api_key: &str,
+        base_url: &str,
+        animation_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let client = crate::client::shared();
+        let url = format!("{}/api/animation/{}", base_url, animation_id);
+
+        client
+            .send(client.delete(&url).header("Authorization", format!("Bearer {}", api_key)))
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Regenerate an animation using the same parameters as an existing one,
+    /// with `seed` in place of its seed if given (see [`generate`]).
     ///
     /// Hits `POST /api/animation/regenerate/<animation_id>` and returns the JSON
     /// response (shape: `{ "animation_id": <new_id> }`).
@@ -288,14 +1497,17 @@ pub mod animations {
         api_key: &str,
         base_url: &str,
         animation_id: &str,
+        seed: Option<u64>,
     ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-        let client = reqwest::Client::new();
+        let client = crate::client::shared();
         let url = format!("{}/api/animation/regenerate/{}", base_url, animation_id);
 
+        let mut request = client.post(&url).header("Authorization", format!("Bearer {}", api_key));
+        if let Some(seed) = seed {
+            request = request.json(&serde_json::json!({ "seed": seed }));
+        }
         let json: Value = client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .send()
+            .send(request)
             .await?
             .error_for_status()? // surface non-2xx responses
             .json()
@@ -303,4 +1515,231 @@ pub mod animations {
 
         Ok(json)
     }
+
+    /// Regenerate an animation that failed, returning the new animation's ID.
+    async fn regenerate_failed(
+        api_key: &str,
+        base_url: &str,
+        animation_id: i64,
+    ) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+        regenerate(api_key, base_url, &animation_id.to_string(), None)
+            .await?
+            .get("animation_id")
+            .and_then(Value::as_i64)
+            .ok_or_else(|| "animation_id missing from regenerate response".into())
+    }
+
+    /// The outcome of an animation waited for by [`wait_all_regenerating`].
+    #[derive(Debug)]
+    pub struct Retried {
+        /// The animation the outcome is for: the one given, or its last
+        /// regeneration.
+        pub animation_id: String,
+        /// Animations that failed on the way, in order.
+        pub failed_ids: Vec<String>,
+        pub outcome: Result<Value, Box<dyn std::error::Error + Send + Sync>>,
+    }
+
+    /// [`wait_all`], regenerating each animation that fails (status 3) up to
+    /// `retries` times. Returns one outcome per given ID, in order.
+    #[cfg(feature = "native")]
+    pub async fn wait_all_regenerating(
+        api_key: &str,
+        base_url: &str,
+        animation_ids: &[String],
+        retries: u32,
+        silent: bool,
+    ) -> Vec<Retried> {
+        let mut ids = animation_ids.to_vec();
+        let mut failed: Vec<Vec<String>> = vec![Vec::new(); ids.len()];
+        let mut outcomes = wait_all(api_key, base_url, &ids, silent).await;
+        for _ in 0..retries {
+            let mut again = Vec::new();
+            for (i, outcome) in outcomes.iter_mut().enumerate() {
+                let Err(err) = outcome else { continue };
+                if ErrorKind::of(err.as_ref()) != ErrorKind::FailedGeneration {
+                    continue;
+                }
+                let Ok(id) = ids[i].parse::<i64>() else { continue };
+                match regenerate_failed(api_key, base_url, id).await {
+                    Ok(new_id) => {
+                        if !silent {
+                            eprintln!("Animation {} failed (credits refunded); regenerated as {}.", id, new_id);
+                        }
+                        failed[i].push(std::mem::replace(&mut ids[i], new_id.to_string()));
+                        again.push(i);
+                    }
+                    Err(err) => *outcome = Err(err),
+                }
+            }
+            if again.is_empty() {
+                break;
+            }
+            let again_ids: Vec<String> = again.iter().map(|&i| ids[i].clone()).collect();
+            let waited = wait_all(api_key, base_url, &again_ids, silent).await;
+            for (i, outcome) in again.into_iter().zip(waited) {
+                outcomes[i] = outcome;
+            }
+        }
+        ids.into_iter()
+            .zip(failed)
+            .zip(outcomes)
+            .map(|((animation_id, failed_ids), outcome)| Retried {
+                animation_id,
+                failed_ids,
+                outcome,
+            })
+            .collect()
+    }
+
+    /// Regenerations [`regenerate_all_failed`] submits at once by default.
+    pub const DEFAULT_REGENERATE_CONCURRENCY: usize = 4;
+
+    /// A failed animation resubmitted by [`regenerate_all_failed`].
+    #[derive(Clone, Debug, serde::Serialize)]
+    pub struct Resubmitted {
+        pub animation_id: i64,
+        pub prompt: Option<String>,
+        /// The regeneration, if it was accepted.
+        pub new_animation_id: Option<i64>,
+        /// Why it was not.
+        pub error: Option<String>,
+    }
+
+    /// Regenerate every failed animation (status 3) created after
+    /// `created_after` (Unix seconds; all of them if `None`), `concurrency`
+    /// at a time. Returns one entry per failed animation, in listing order;
+    /// a rejected resubmission is reported in its entry rather than failing
+    /// the rest.
+    #[cfg(feature = "native")]
+    pub async fn regenerate_all_failed(
+        api_key: &str,
+        base_url: &str,
+        created_after: Option<u64>,
+        concurrency: usize,
+    ) -> Result<Vec<Resubmitted>, Box<dyn std::error::Error + Send + Sync>> {
+        let options = ListOptions {
+            status: Some(Status::Failed.code()),
+            created_after,
+            ..Default::default()
+        };
+        let failed: Vec<(i64, Option<String>)> = match list(api_key, base_url, &options).await? {
+            Value::Array(items) => items
+                .iter()
+                .filter_map(|item| {
+                    // Resubmitting spends credits: only what the listing
+                    // itself says has failed.
+                    if Status::of(item) != Some(Status::Failed) {
+                        return None;
+                    }
+                    let id = item.get("id").or_else(|| item.get("animation_id"))?.as_i64()?;
+                    Some((id, item.get("prompt").and_then(Value::as_str).map(str::to_string)))
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let permits = std::sync::Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
+        let mut submissions = tokio::task::JoinSet::new();
+        for (i, &(id, _)) in failed.iter().enumerate() {
+            let (api_key, base_url, permits) = (api_key.to_string(), base_url.to_string(), permits.clone());
+            submissions.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                (i, regenerate_failed(&api_key, &base_url, id).await.map_err(|e| e.to_string()))
+            });
+        }
+        let mut outcomes: Vec<Result<i64, String>> = vec![Err("not submitted".to_string()); failed.len()];
+        while let Some(joined) = submissions.join_next().await {
+            let (i, outcome) = joined.map_err(|e| format!("regenerate task failed: {}", e))?;
+            outcomes[i] = outcome;
+        }
+
+        Ok(failed
+            .into_iter()
+            .zip(outcomes)
+            .map(|((animation_id, prompt), outcome)| Resubmitted {
+                animation_id,
+                prompt,
+                new_animation_id: outcome.as_ref().ok().copied(),
+                error: outcome.err(),
+            })
+            .collect())
+    }
+
+    /// Upscaling factors the backend offers.
+    pub const UPSCALE_FACTORS: &[u32] = &[2, 4];
+
+    /// Upscale a result on the backend, which renders it again at `factor`
+    /// times the size as a new animation.
+    ///
+    /// Hits `POST /api/animation_result/<result_id>/upscale` and returns the
+    /// JSON response (shape: `{ "animation_id": <new_id> }`), or `None` if
+    /// the backend does not offer upscaling (`404`, `501`), so callers can
+    /// scale locally with [`crate::process::scale`] instead.
+    pub async fn upscale(
+        api_key: &str,
+        base_url: &str,
+        result_id: &str,
+        factor: u32,
+    ) -> Result<Option<Value>, Box<dyn std::error::Error + Send + Sync>> {
+        if !UPSCALE_FACTORS.contains(&factor) {
+            return Err(Error::new(ErrorKind::Validation, "upscale factor must be 2 or 4").into());
+        }
+        let client = crate::client::shared();
+        let url = format!("{}/api/animation_result/{}/upscale", base_url, result_id);
+
+        let resp = client
+            .send(
+                client
+                    .post(&url)
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .json(&serde_json::json!({ "factor": factor })),
+            )
+            .await?;
+        if matches!(resp.status(), reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::NOT_IMPLEMENTED) {
+            return Ok(None);
+        }
+        Ok(Some(resp.error_for_status()?.json().await?))
+    }
+
+    /// Most variations one request may ask for.
+    pub const MAX_VARIATIONS: u32 = 8;
+
+    /// Submit `count` variation jobs based on an existing result; `strength`
+    /// (above 0, at most 1) is how far each may stray from it.
+    ///
+    /// Hits `POST /api/animation_result/<result_id>/variations` and returns
+    /// the JSON response (shape: `{ "animation_ids": [...] }`); the new
+    /// animations are followed like any other, e.g. with [`wait_all`].
+    pub async fn variations(
+        api_key: &str,
+        base_url: &str,
+        result_id: &str,
+        count: u32,
+        strength: f32,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        if count == 0 || count > MAX_VARIATIONS {
+            let message = format!("variation count must be between 1 and {}", MAX_VARIATIONS);
+            return Err(Error::new(ErrorKind::Validation, message).into());
+        }
+        if !(strength > 0.0 && strength <= 1.0) {
+            return Err(Error::new(ErrorKind::Validation, "variation strength must be above 0 and at most 1").into());
+        }
+        let client = crate::client::shared();
+        let url = format!("{}/api/animation_result/{}/variations", base_url, result_id);
+
+        let json: Value = client
+            .send(
+                client
+                    .post(&url)
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .json(&serde_json::json!({ "count": count, "strength": strength })),
+            )
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(json)
+    }
 } 
\ No newline at end of file
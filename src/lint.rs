@@ -0,0 +1,305 @@
+//! Checking results against a project's art constraints, for `gametorch lint`.
+//!
+//! Rules come from the `[lint]` table of the [workspace](crate::workspace)
+//! and can be overridden per run:
+//!
+//! ```toml
+//! [lint]
+//! grid = 4                    # logical pixel size; every cell one colour
+//! max_fringe = 0.05           # share of outline pixels that may be semi-transparent
+//! background = "transparent"  # or "#rrggbb": what the frame border must be
+//! max_colors = 32             # distinct visible colours across all frames
+//! uniform_size = true         # every frame the same size (the default)
+//! ```
+//!
+//! Rules left out are not checked, except `uniform_size`. Colours within
+//! [`PIXEL_TOLERANCE`] per channel count as the same, so compression noise
+//! alone breaks no rule.
+
+use crate::frames::Sequence;
+use crate::metrics::PIXEL_TOLERANCE;
+use crate::process::{self, PixelGrid, Rgb};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// The rules a result must keep to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintRules {
+    /// Logical pixel size: every `grid`×`grid` cell must be one colour.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grid: Option<u32>,
+    /// Largest share of a frame's outline pixels that may be
+    /// semi-transparent, in `[0, 1]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fringe: Option<f64>,
+    /// `transparent`, or the `#rrggbb` colour the frame border must be.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    /// Most distinct visible colours across all frames.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_colors: Option<usize>,
+    /// Every frame must have the same size.
+    pub uniform_size: bool,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        LintRules {
+            grid: None,
+            max_fringe: None,
+            background: None,
+            max_colors: None,
+            uniform_size: true,
+        }
+    }
+}
+
+impl LintRules {
+    /// Check every value is usable.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.grid == Some(0) {
+            return Err("lint.grid must be greater than zero".into());
+        }
+        if let Some(share) = self.max_fringe
+            && !(0.0..=1.0).contains(&share)
+        {
+            return Err(format!("lint.max_fringe must be between 0 and 1, got {}", share).into());
+        }
+        self.background()?;
+        Ok(())
+    }
+
+    pub fn background(&self) -> Result<Option<Background>, Box<dyn std::error::Error + Send + Sync>> {
+        match self.background.as_deref() {
+            None => Ok(None),
+            Some(text) if text.eq_ignore_ascii_case("transparent") => Ok(Some(Background::Transparent)),
+            Some(text) => text
+                .parse()
+                .map(|color| Some(Background::Color(color)))
+                .map_err(|e| format!("lint.background: {} (or transparent)", e).into()),
+        }
+    }
+}
+
+/// What the frame border must be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Transparent,
+    /// Transparent, or this colour.
+    Color(Rgb),
+}
+
+/// A rule a result breaks.
+#[derive(Clone, Debug, Serialize)]
+pub struct Violation {
+    /// `uniform_size`, `grid`, `fringe`, `background` or `max_colors`.
+    pub rule: &'static str,
+    pub message: String,
+    /// Indices of the frames breaking it; empty for rules over the whole
+    /// sequence.
+    pub frames: Vec<usize>,
+}
+
+/// Check `sequence` against `rules`.
+pub fn lint(sequence: &Sequence, rules: &LintRules) -> Result<Vec<Violation>, Box<dyn std::error::Error + Send + Sync>> {
+    rules.validate()?;
+    let mut violations = Vec::new();
+    let Some(first) = sequence.frames.first() else {
+        return Ok(violations);
+    };
+
+    if rules.uniform_size {
+        let size = first.image.dimensions();
+        let frames = frames_where(sequence, |image| image.dimensions() != size);
+        if !frames.is_empty() {
+            violations.push(Violation {
+                rule: "uniform_size",
+                message: format!("{} of {} frames are not {}x{}", frames.len(), sequence.len(), size.0, size.1),
+                frames,
+            });
+        }
+    }
+
+    if let Some(size) = rules.grid {
+        let grid = process::detect_offsets(sequence, size);
+        let pixels: Vec<u64> = sequence.frames.iter().map(|frame| off_grid_pixels(&frame.image, &grid)).collect();
+        let frames: Vec<usize> = (0..pixels.len()).filter(|&i| pixels[i] > 0).collect();
+        if !frames.is_empty() {
+            violations.push(Violation {
+                rule: "grid",
+                message: format!(
+                    "{} pixels in {} frames differ from the rest of their {}x{} cell",
+                    pixels.iter().sum::<u64>(),
+                    frames.len(),
+                    size,
+                    size
+                ),
+                frames,
+            });
+        }
+    }
+
+    if let Some(max) = rules.max_fringe {
+        let shares: Vec<f64> = sequence.frames.iter().map(|frame| fringe(&frame.image)).collect();
+        let frames: Vec<usize> = (0..shares.len()).filter(|&i| shares[i] > max).collect();
+        if !frames.is_empty() {
+            let worst = shares.iter().copied().fold(0.0, f64::max);
+            violations.push(Violation {
+                rule: "fringe",
+                message: format!(
+                    "{} frames have more than {:.1}% semi-transparent outline pixels (worst {:.1}%)",
+                    frames.len(),
+                    max * 100.0,
+                    worst * 100.0
+                ),
+                frames,
+            });
+        }
+    }
+
+    if let Some(background) = rules.background()? {
+        let mut stray: HashMap<[u8; 3], u64> = HashMap::new();
+        let frames = frames_where(sequence, |image| {
+            let before: u64 = stray.values().sum();
+            for p in border(image) {
+                let allowed = match background {
+                    Background::Transparent => p[3] == 0,
+                    Background::Color(color) => p[3] == 0 || close(&p[..3], &color.0),
+                };
+                if !allowed {
+                    *stray.entry([p[0], p[1], p[2]]).or_default() += 1;
+                }
+            }
+            stray.values().sum::<u64>() > before
+        });
+        if let Some((&color, _)) = stray.iter().max_by_key(|&(color, count)| (*count, std::cmp::Reverse(*color))) {
+            let expected = match background {
+                Background::Transparent => "transparent".to_string(),
+                Background::Color(color) => format!("transparent or {}", color),
+            };
+            violations.push(Violation {
+                rule: "background",
+                message: format!(
+                    "{} border pixels in {} frames are not {} (mostly {})",
+                    stray.values().sum::<u64>(),
+                    frames.len(),
+                    expected,
+                    Rgb(color)
+                ),
+                frames,
+            });
+        }
+    }
+
+    if let Some(max) = rules.max_colors {
+        let colors: BTreeSet<[u8; 3]> = sequence
+            .frames
+            .iter()
+            .flat_map(|frame| frame.image.pixels())
+            .filter(|p| p.0[3] > 0)
+            .map(|p| [p.0[0], p.0[1], p.0[2]])
+            .collect();
+        if distinct(&colors, max).len() > max {
+            violations.push(Violation {
+                rule: "max_colors",
+                message: format!("more than the {} distinct colours allowed", max),
+                frames: Vec::new(),
+            });
+        }
+    }
+    Ok(violations)
+}
+
+/// Indices of the frames `test` holds for.
+fn frames_where(sequence: &Sequence, mut test: impl FnMut(&RgbaImage) -> bool) -> Vec<usize> {
+    (0..sequence.len()).filter(|&i| test(&sequence.frames[i].image)).collect()
+}
+
+/// Pixels that differ from the most common colour of their grid cell.
+/// Fully transparent pixels are alike whatever their RGB.
+fn off_grid_pixels(image: &RgbaImage, grid: &PixelGrid) -> u64 {
+    let (w, h) = image.dimensions();
+    let visible = |x: u32, y: u32| {
+        let p = image.get_pixel(x, y).0;
+        if p[3] == 0 { [0; 4] } else { p }
+    };
+    let mut count = 0;
+    for &(y0, y1) in &cells(h, grid.cell_height, grid.offset_y) {
+        for &(x0, x1) in &cells(w, grid.cell_width, grid.offset_x) {
+            let pixels: Vec<[u8; 4]> = (y0..y1).flat_map(|y| (x0..x1).map(move |x| visible(x, y))).collect();
+            let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+            for p in &pixels {
+                *counts.entry(*p).or_default() += 1;
+            }
+            let Some((majority, _)) = counts.into_iter().max_by_key(|&(p, n)| (n, std::cmp::Reverse(p))) else {
+                continue;
+            };
+            count += pixels.iter().filter(|p| !close(&p[..], &majority)).count() as u64;
+        }
+    }
+    count
+}
+
+/// Start and end of each grid cell along an axis of `len` pixels; a partial
+/// cell comes first when the grid is offset.
+fn cells(len: u32, cell: u32, offset: u32) -> Vec<(u32, u32)> {
+    let first = offset % cell;
+    let mut starts = if first > 0 { vec![0] } else { Vec::new() };
+    starts.extend((first..len).step_by(cell as usize));
+    let ends = starts.iter().skip(1).copied().chain([len]);
+    starts.iter().copied().zip(ends).collect()
+}
+
+/// Share of the outline pixels (visible, next to a transparent pixel) that
+/// are semi-transparent.
+fn fringe(image: &RgbaImage) -> f64 {
+    let (w, h) = image.dimensions();
+    let transparent = |x: i64, y: i64| {
+        x >= 0 && y >= 0 && x < w as i64 && y < h as i64 && image.get_pixel(x as u32, y as u32).0[3] == 0
+    };
+    let (mut outline, mut fringe) = (0u64, 0u64);
+    for (x, y, p) in image.enumerate_pixels() {
+        let alpha = p.0[3];
+        let (x, y) = (x as i64, y as i64);
+        if alpha == 0 || ![(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter().any(|&(x, y)| transparent(x, y)) {
+            continue;
+        }
+        outline += 1;
+        if alpha < 255 {
+            fringe += 1;
+        }
+    }
+    if outline == 0 { 0.0 } else { fringe as f64 / outline as f64 }
+}
+
+/// One representative of each group of `colors` within [`PIXEL_TOLERANCE`]
+/// of each other, taking them in order. Stops once there are more than
+/// `max`, as only that is checked.
+fn distinct(colors: &BTreeSet<[u8; 3]>, max: usize) -> Vec<[u8; 3]> {
+    let mut kept: Vec<[u8; 3]> = Vec::new();
+    for color in colors {
+        if !kept.iter().any(|k| close(color, k)) {
+            kept.push(*color);
+            if kept.len() > max {
+                break;
+            }
+        }
+    }
+    kept
+}
+
+/// The pixels along the edges of `image`.
+fn border(image: &RgbaImage) -> impl Iterator<Item = [u8; 4]> + '_ {
+    let (w, h) = image.dimensions();
+    image
+        .enumerate_pixels()
+        .filter(move |&(x, y, _)| x == 0 || y == 0 || x + 1 == w || y + 1 == h)
+        .map(|(_, _, p)| p.0)
+}
+
+/// Whether every channel of `a` is within [`PIXEL_TOLERANCE`] of `b`.
+fn close(a: &[u8], b: &[u8]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x.abs_diff(*y) <= PIXEL_TOLERANCE)
+}
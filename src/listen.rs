@@ -0,0 +1,190 @@
+//! Receiving completion webhooks.
+//!
+//! `gametorch listen` is the other end of `generate --webhook-url`: a small
+//! HTTP server that accepts the backend's completion callbacks, checks
+//! their signature and hands each completed animation to the caller (the
+//! CLI downloads it and runs a pipeline on it).
+//!
+//! The backend signs every callback with the shared secret: the
+//! [`SIGNATURE_HEADER`] carries `sha256=` and the hex HMAC-SHA256 of the raw
+//! body. Requests with a missing or wrong signature are rejected with `401`
+//! and never reach the caller. Accepted callbacks are answered with `202`
+//! straight away; the work happens afterwards.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+/// Default address for `gametorch listen`; loopback only, so expose it
+/// through a tunnel or reverse proxy (or bind another address).
+pub const DEFAULT_BIND: &str = "127.0.0.1:8788";
+
+/// Environment variable holding the shared secret.
+pub const SECRET_ENV: &str = "GAMETORCH_WEBHOOK_SECRET";
+
+/// Header carrying the body's signature.
+pub const SIGNATURE_HEADER: &str = "x-gametorch-signature";
+
+/// Requests with a larger head than this are rejected.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Callbacks with a larger body than this are rejected.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// A completion callback.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Completion {
+    pub animation_id: i64,
+    #[serde(default)]
+    pub result_id: Option<i64>,
+    /// Numeric status as in `animation_results` (2 complete, 3 failed).
+    #[serde(default)]
+    pub status: Option<i64>,
+}
+
+/// The [`SIGNATURE_HEADER`] value for `body`.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// Whether `signature` is the [`SIGNATURE_HEADER`] value for `body`
+/// (compared in constant time).
+pub fn verify(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let Some(hex) = signature.trim().strip_prefix("sha256=") else {
+        return false;
+    };
+    if hex.len() != 64 || !hex.is_ascii() {
+        return false;
+    }
+    let Ok(expected) = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+    else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Accept callbacks on `bind` until the process exits, sending each
+/// verified one to `completions`.
+pub async fn listen(
+    bind: &str,
+    secret: Vec<u8>,
+    completions: mpsc::UnboundedSender<Completion>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if secret.is_empty() {
+        return Err("the webhook secret must not be empty".into());
+    }
+    let listener = TcpListener::bind(bind)
+        .await
+        .map_err(|e| format!("failed to listen on {}: {}", bind, e))?;
+    let secret = std::sync::Arc::new(secret);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let secret = secret.clone();
+        let completions = completions.clone();
+        tokio::spawn(async move {
+            // A client hanging up mid-request is not worth reporting.
+            let _ = handle(stream, &secret, &completions).await;
+        });
+    }
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    secret: &[u8],
+    completions: &mpsc::UnboundedSender<Completion>,
+) -> std::io::Result<()> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_HEAD_BYTES {
+            return respond(&mut stream, 431, "request header too large").await;
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+    let (method, path) = (request_line.next().unwrap_or(""), request_line.next().unwrap_or(""));
+    let header = |name: &str| {
+        head.lines()
+            .skip(1)
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().to_string())
+    };
+
+    if path == "/health" {
+        return respond(&mut stream, 200, "ok").await;
+    }
+    if method != "POST" {
+        return respond(&mut stream, 405, "only POST is supported").await;
+    }
+    let Some(length) = header("content-length").and_then(|l| l.parse::<usize>().ok()) else {
+        return respond(&mut stream, 411, "Content-Length required").await;
+    };
+    if length > MAX_BODY_BYTES {
+        return respond(&mut stream, 413, "body too large").await;
+    }
+    let mut body = buf.split_off(head_end);
+    while body.len() < length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(length);
+
+    if !header(SIGNATURE_HEADER).is_some_and(|signature| verify(secret, &body, &signature)) {
+        return respond(&mut stream, 401, "invalid signature").await;
+    }
+    match serde_json::from_slice::<Completion>(&body) {
+        Ok(completion) => {
+            let _ = completions.send(completion);
+            respond(&mut stream, 202, "accepted").await
+        }
+        Err(err) => respond(&mut stream, 400, &format!("invalid callback: {}", err)).await,
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: u16, message: &str) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        _ => "Request Header Fields Too Large",
+    };
+    let body = serde_json::json!({ "message": message }).to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -1,7 +1,53 @@
-use clap::{Parser, Subcommand};
-use gametorch::animations;
-use serde_json;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
+use gametorch::animations::{self, GenerateRequest, Model};
+use gametorch::auth::{self, KeyRing};
+use gametorch::cancel::{CancellationToken, Cancelled};
+use gametorch::catalog::{self, Catalog};
+use gametorch::client::{Client, ClientBuilder, RetryPolicy};
+use gametorch::config::Config;
+use gametorch::error::ErrorKind;
+use gametorch::export::engine_drop::{self, Engine, EnginePreset};
+use gametorch::export::{self, ExportOptions};
+use gametorch::filter::Filter;
+use gametorch::frames::Sequence;
+use gametorch::history::{History, Job};
+use gametorch::http_cache::HttpCache;
+use gametorch::images;
+use gametorch::listen::{self, Completion};
+#[cfg(feature = "index")]
+use gametorch::index::Index;
+use gametorch::inspect;
+use gametorch::lint;
+use gametorch::metrics;
+use gametorch::output::{self, Output, OutputFormat};
+use gametorch::pending::{PendingJob, Queue};
+use gametorch::manifest::Manifest;
+use gametorch::markers;
+use gametorch::pipeline::Pipeline;
+use gametorch::preview;
+use gametorch::pricing;
+use gametorch::process;
+use gametorch::profile::{self, Profile};
+use gametorch::provenance::Provenance;
+use gametorch::render_queue;
+use gametorch::resume::{self, Interrupted, ResumeFile};
+use gametorch::serve;
+use gametorch::sheet::PackOptions;
+use gametorch::storage;
+use gametorch::store::{self, Store};
+use gametorch::timeline;
+use gametorch::usage::{self, UsageLog};
+use gametorch::verify;
+use gametorch::watch::{self, WatchState};
+use gametorch::watch_dir;
+use gametorch::webhooks;
+use gametorch::workspace::{self, Workspace, WorkspaceConfig};
 use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// GameTorch command-line interface.
 ///
@@ -14,9 +60,42 @@ pub struct Cli {
     /// Use local server (http://localhost:8000) instead of production.
     #[arg(short = 'l', long = "local", global = true)]
     local: bool,
+    /// Use the API at URL (staging, a tunnel, a self-hosted backend); defaults to $GAMETORCH_BASE_URL
+    #[arg(long = "base-url", value_name = "URL", global = true, conflicts_with = "local")]
+    base_url: Option<String>,
     /// Output raw computer-friendly JSON (no human status replacement)
     #[arg(short = 'p', long = "porcelain", global = true)]
     porcelain: bool,
+    /// Output format (json, yaml, csv, ndjson, table); listings default to a table on a terminal
+    #[arg(long = "format", value_name = "FORMAT", global = true, conflicts_with = "porcelain")]
+    format: Option<OutputFormat>,
+    /// JMESPath expression selecting what to print, e.g. "[].id"
+    #[arg(long = "query", value_name = "EXPR", global = true)]
+    query: Option<String>,
+    /// Retries for API requests that time out or fail with 429/5xx (0 disables)
+    #[arg(long = "retries", value_name = "N", default_value_t = 3, global = true)]
+    retries: u32,
+    /// Send at most this many API requests per second (useful for batch commands)
+    #[arg(long = "rate-limit", value_name = "RPS", global = true)]
+    rate_limit: Option<f64>,
+    /// Serve `animations get` from the local index and queue `generate` for `gametorch flush`
+    #[arg(long = "offline", global = true)]
+    offline: bool,
+    /// Append a transcript of API requests and responses (credentials redacted) to FILE
+    #[arg(long = "debug-http", value_name = "FILE", global = true)]
+    debug_http: Option<PathBuf>,
+    /// Always fetch API responses in full instead of revalidating cached ones
+    #[arg(long = "no-cache", global = true)]
+    no_cache: bool,
+    /// Record every API response into a cassette FILE for --replay
+    #[arg(long = "record", value_name = "FILE", global = true, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+    /// Answer API requests from a cassette FILE made with --record, without touching the network
+    #[arg(long = "replay", value_name = "FILE", global = true)]
+    replay: Option<PathBuf>,
+    /// Threads for decoding, processing and encoding frames (defaults to one per CPU core)
+    #[arg(short = 'j', long = "jobs", value_name = "N", global = true)]
+    jobs: Option<usize>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -28,6 +107,460 @@ pub enum Commands {
         #[command(subcommand)]
         action: AnimationCommands,
     },
+    /// Still sprite generation
+    Images {
+        #[command(subcommand)]
+        action: ImageCommands,
+    },
+    /// Post-process result frames (writes a ZIP or directory of PNG frames)
+    Process {
+        /// Where per-pixel work runs (auto, cpu, gpu; gpu needs a build with --features gpu)
+        #[arg(long = "backend", value_name = "BACKEND", default_value = "auto", global = true)]
+        backend: process::Backend,
+        #[command(subcommand)]
+        action: ProcessCommands,
+    },
+    /// Export a result as an engine-ready sprite sheet
+    Export {
+        /// Result ZIP or directory of PNG frames
+        input: String,
+        /// Export target (unity, texturepacker, godot, bevy, love, gamemaker); defaults to the profile's targets or the workspace's target
+        #[arg(short = 't', long = "target", value_name = "TARGET", conflicts_with = "drop")]
+        target: Option<export::Target>,
+        /// Directory to write the sheet and its metadata into (defaults to the workspace's output directory, or .)
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR", conflicts_with = "drop")]
+        output_dir: Option<String>,
+        /// Engine drop mode: write into this game project using the engine's naming contract
+        #[arg(long = "drop", value_name = "PROJECT_DIR")]
+        drop: Option<String>,
+        /// Engine preset for --drop (unity, godot); defaults to the profile's engine
+        #[arg(long = "engine", value_name = "ENGINE", requires = "drop")]
+        engine: Option<Engine>,
+        /// Override the preset's root directory inside the project
+        #[arg(long = "drop-root", value_name = "DIR", requires = "drop")]
+        drop_root: Option<String>,
+        /// Override the preset's per-asset directory template (supports {name}, {slug}, {engine})
+        #[arg(long = "drop-dir", value_name = "TEMPLATE", requires = "drop")]
+        drop_dir: Option<String>,
+        /// Override the preset's file name template (supports {name}, {slug}, {engine})
+        #[arg(long = "drop-file", value_name = "TEMPLATE", requires = "drop")]
+        drop_file: Option<String>,
+        /// Base name for written files (defaults to the input file name)
+        #[arg(short = 'n', long = "name")]
+        name: Option<String>,
+        /// Number of sheet columns (defaults to a roughly square grid)
+        #[arg(long = "columns")]
+        columns: Option<u32>,
+        /// Transparent padding between frames, in pixels (default 0)
+        #[arg(long = "padding")]
+        padding: Option<u32>,
+        /// Playback rate to use instead of the input's frame durations
+        #[arg(long = "fps")]
+        fps: Option<u32>,
+        /// Edit list (YAML/JSON `frames: [0, 1, 2, 2, 3]`) to reorder, hold or skip frames
+        #[arg(long = "edit-list", value_name = "FILE")]
+        edit_list: Option<String>,
+        /// Markers file (`hit: frame 7`) replacing any markers stored with the input
+        #[arg(long = "markers", value_name = "FILE")]
+        markers: Option<String>,
+        /// Repair glaring single-frame artifacts before packing
+        #[arg(long = "fix-frames")]
+        fix_frames: bool,
+        /// How --fix-frames repairs a broken frame (blend, drop)
+        #[arg(long = "fix-mode", value_name = "MODE", default_value = "blend", requires = "fix_frames")]
+        fix_mode: process::FixMode,
+        /// Also write a GameMaker .yy sprite stub (gamemaker target only)
+        #[arg(long = "yy")]
+        yy: bool,
+        /// Print the Bevy atlas snippet instead of the JSON summary (bevy target only)
+        #[arg(long = "snippet", conflicts_with = "drop")]
+        snippet: bool,
+        /// Export profile to use instead of the active one
+        #[arg(long = "profile", value_name = "FILE", conflicts_with = "no_profile")]
+        profile: Option<String>,
+        /// Ignore the active export profile
+        #[arg(long = "no-profile")]
+        no_profile: bool,
+    },
+    /// Poll for newly completed animations, download them and run a pipeline on each
+    Watch {
+        /// Pipeline YAML file run on every new result
+        #[arg(long = "pipeline", value_name = "FILE")]
+        pipeline: String,
+        /// Directory to download result ZIPs into
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR", default_value = ".")]
+        output_dir: String,
+        /// Seconds between polls
+        #[arg(long = "interval", value_name = "SECS", default_value_t = watch::DEFAULT_INTERVAL_SECS)]
+        interval: u64,
+        /// Only process animations matching this filter (same syntax as `animations bulk`)
+        #[arg(long = "filter", value_name = "FILTER", default_value = "")]
+        filter: Filter,
+        /// On the first run, also process results that completed before watching started
+        #[arg(long = "all")]
+        all: bool,
+        /// Poll once and exit
+        #[arg(long = "once")]
+        once: bool,
+    },
+    /// Watch a folder and submit a generation for every image dropped into it
+    WatchDir {
+        /// Directory to watch
+        dir: String,
+        /// Use the file name as the prompt for images without a sidecar .txt
+        #[arg(long = "prompt-from-filename")]
+        prompt_from_filename: bool,
+        /// Prompt for images without a sidecar .txt
+        #[arg(long = "prompt", conflicts_with = "prompt_from_filename")]
+        prompt: Option<String>,
+        /// Duration in seconds (one the model renders; defaults to the workspace's, or 5)
+        #[arg(short = 'd', long = "duration", value_name = "SECONDS")]
+        duration: Option<u32>,
+        /// Pad each image to a square, scale it into range and re-encode it as PNG
+        #[arg(long = "preprocess")]
+        preprocess: bool,
+        /// Optional animation model ID (defaults to the workspace's, or 6)
+        #[arg(long = "model-id", value_name = "ID", conflicts_with = "model_name")]
+        model_id: Option<u32>,
+        /// Optional animation model name (defaults to the workspace's, or 'alpha/v2.1')
+        #[arg(long = "model-name", value_name = "NAME", conflicts_with = "model_id", add = ArgValueCandidates::new(complete_model_names))]
+        model_name: Option<String>,
+        /// Also submit the images already in the directory
+        #[arg(long = "existing")]
+        existing: bool,
+    },
+    /// Receive completion webhooks and run a pipeline on every completed result
+    Listen {
+        /// Pipeline YAML file run on every completed result
+        #[arg(long = "pipeline", value_name = "FILE")]
+        pipeline: String,
+        /// Directory to download result ZIPs into
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR", default_value = ".")]
+        output_dir: String,
+        /// Address to listen on
+        #[arg(long = "bind", value_name = "ADDR", default_value = listen::DEFAULT_BIND)]
+        bind: String,
+        /// Shared secret the callbacks are signed with (defaults to $GAMETORCH_WEBHOOK_SECRET)
+        #[arg(long = "secret", value_name = "SECRET")]
+        secret: Option<String>,
+    },
+    /// Share export settings (targets, packing, engine, pipeline) as a profile file
+    ExportProfile {
+        #[command(subcommand)]
+        action: ProfileCommands,
+    },
+    /// List recent prompts from the local job history, numbered for `generate --again N`
+    History {
+        /// Number of prompts to show
+        #[arg(long = "limit", value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+    /// Browse animations in an interactive terminal UI (needs a build with --features tui)
+    Ui,
+    /// Serve a read-only JSON API for dashboards
+    Serve {
+        /// Serve the local job history (/api/jobs, /api/stats)
+        #[arg(long = "history-api", required = true)]
+        history_api: bool,
+        /// Address to listen on
+        #[arg(long = "bind", value_name = "ADDR", default_value = serve::DEFAULT_BIND)]
+        bind: String,
+    },
+    /// Set up a workspace: write gametorch.toml with project defaults for every command
+    Init {
+        /// Project root
+        #[arg(default_value = ".")]
+        dir: String,
+        /// Project name
+        #[arg(long = "name")]
+        name: Option<String>,
+        /// Default animation model (name or numeric ID)
+        #[arg(long = "model", value_name = "MODEL")]
+        model: Option<String>,
+        /// Default duration in seconds (5 or 10 for the default model)
+        #[arg(long = "duration", value_name = "SECONDS")]
+        duration: Option<u32>,
+        /// Palette for `process palettize` (relative to the project root)
+        #[arg(long = "palette", value_name = "FILE")]
+        palette: Option<PathBuf>,
+        /// Where generated and exported assets go (relative to the project root)
+        #[arg(long = "output-dir", value_name = "DIR", default_value = workspace::DEFAULT_OUTPUT_DIR)]
+        output_dir: PathBuf,
+        /// Export target for `export`
+        #[arg(short = 't', long = "target", value_name = "TARGET")]
+        target: Option<export::Target>,
+        /// Replace an existing gametorch.toml
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// Inspect and restore the workspace's asset manifest (assets.json)
+    Assets {
+        #[command(subcommand)]
+        action: AssetCommands,
+    },
+    /// Submit the generation requests queued with `generate --offline`
+    Flush,
+    /// Estimate the credit cost of generations before submitting them
+    Estimate {
+        /// Animation model ID (defaults to the workspace's model, or 6)
+        #[arg(long = "model-id", value_name = "ID", conflicts_with = "model_name")]
+        model_id: Option<u32>,
+        /// Animation model name
+        #[arg(long = "model-name", value_name = "NAME", conflicts_with = "model_id", add = ArgValueCandidates::new(complete_model_names))]
+        model_name: Option<String>,
+        /// Duration in seconds (one the model renders; defaults to the workspace's, or 5)
+        #[arg(short = 'd', long = "duration", value_name = "SECONDS")]
+        duration: Option<u32>,
+        /// Number of generations
+        #[arg(short = 'n', long = "count", value_name = "N", default_value_t = 1)]
+        count: u32,
+        /// Price a batch file instead: a JSON array of jobs or one per line ({"model_name", "duration_seconds", "count"})
+        #[arg(long = "batch", value_name = "FILE", conflicts_with_all = ["model_id", "model_name", "duration", "pending"])]
+        batch: Option<PathBuf>,
+        /// Price the requests queued with `generate --offline`
+        #[arg(long = "pending", conflicts_with_all = ["model_id", "model_name", "duration"])]
+        pending: bool,
+    },
+    /// Continue a `generate --block` run interrupted with Ctrl+C: wait for the render and save the ZIP
+    Resume {
+        /// Animation to resume (defaults to the most recently interrupted)
+        #[arg(add = ArgValueCandidates::new(complete_animation_ids))]
+        animation_id: Option<i64>,
+        /// Suppress progress output
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
+    /// Back up the library: download completed results missing from a directory and report drift
+    Sync {
+        /// Directory holding one `<animation id>.zip` per result
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR", default_value = ".")]
+        output_dir: String,
+        /// Report what would change without downloading anything
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+    /// Re-check downloaded results against their recorded SHA-256
+    Verify {
+        /// A downloaded file, or an animation or result ID
+        target: String,
+    },
+    /// Check the API key
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommands,
+    },
+    /// Manage completion webhooks registered with `generate --webhook-url`
+    Webhooks {
+        #[command(subcommand)]
+        action: WebhookCommands,
+    },
+    /// Print a shell completion script (bash, zsh, fish, powershell, elvish)
+    Completions {
+        /// Shell to complete for
+        #[arg(value_parser = ["bash", "zsh", "fish", "powershell", "elvish"])]
+        shell: String,
+    },
+    /// List animations still generating, with elapsed time and estimated completion
+    Queue {
+        /// Keep refreshing the list until interrupted
+        #[arg(long = "watch")]
+        watch: bool,
+        /// Seconds between refreshes with --watch
+        #[arg(long = "interval", value_name = "SECS", default_value_t = render_queue::DEFAULT_REFRESH_SECS, requires = "watch")]
+        interval: u64,
+    },
+    /// Prune the HTTP cache and remove downloads from the asset store that no file refers to anymore
+    Gc {
+        /// Only report what would be removed
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+        /// Only drop cached responses and unreferenced downloads older than this (an age like 30d, YYYY-MM-DD or a Unix timestamp)
+        #[arg(long = "older-than", value_name = "WHEN")]
+        older_than: Option<String>,
+        /// Trim the HTTP cache and the asset store each to this size, oldest first (e.g. 200M); downloads a file refers to are kept
+        #[arg(long = "max-size", value_name = "SIZE", value_parser = storage::parse_size)]
+        max_size: Option<u64>,
+    },
+    /// Report frame count, size, FPS, colors, transparency and size of a result ZIP, frame directory or sheet
+    Inspect {
+        /// A result ZIP, a directory of frames, a GIF or an image such as a packed sheet
+        file: PathBuf,
+    },
+    /// Check results against the workspace's [lint] rules; exits 1 if any rule is broken
+    Lint {
+        /// Result ZIPs or directories of PNG frames
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Logical pixel size: every cell of the grid must be one color
+        #[arg(long = "grid", value_name = "PIXELS")]
+        grid: Option<u32>,
+        /// Largest share (0-1) of a frame's outline pixels that may be semi-transparent
+        #[arg(long = "max-fringe", value_name = "SHARE")]
+        max_fringe: Option<f64>,
+        /// What the frame border must be: transparent or a #rrggbb color
+        #[arg(long = "background", value_name = "COLOR")]
+        background: Option<String>,
+        /// Most distinct visible colors allowed across all frames
+        #[arg(long = "max-colors", value_name = "N")]
+        max_colors: Option<usize>,
+        /// Allow frames of different sizes
+        #[arg(long = "allow-mixed-sizes")]
+        allow_mixed_sizes: bool,
+    },
+    /// Summarize generation success, render times and credits per week, and command timings
+    Stats {
+        /// Number of weeks to show
+        #[arg(long = "weeks", value_name = "N", default_value_t = 8)]
+        weeks: usize,
+    },
+    /// Report remote storage used by results and local disk usage of downloads and exports
+    Storage {
+        /// Local directories to scan (defaults to the current directory)
+        #[arg(default_value = ".")]
+        paths: Vec<String>,
+        /// Only scan local directories; skip the API
+        #[arg(long = "local-only")]
+        local_only: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuthCommands {
+    /// Report whether the API key is valid and whose it is; exits non-zero if it is rejected
+    Check,
+    /// Make one of the keys listed in config.toml the active one
+    Use {
+        /// Name of the key
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WebhookCommands {
+    /// List registered webhooks
+    List,
+    /// Delete registered webhooks
+    Delete {
+        /// Webhook IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AssetCommands {
+    /// List the assets in the manifest
+    List,
+    /// Check every recorded file exists and matches its hash
+    Verify,
+    /// Download recorded result ZIPs that are missing
+    Fetch,
+    /// Compare assets with freshly downloaded (or regenerated) results and fail if they drift
+    Check {
+        /// Assets to check (defaults to every asset with a result ZIP)
+        names: Vec<String>,
+        /// Regenerate each asset with its recorded parameters instead of downloading its result again
+        #[arg(long = "regenerate")]
+        regenerate: bool,
+        /// Lowest acceptable mean SSIM against the committed frames
+        #[arg(long = "min-ssim", value_name = "SSIM", default_value_t = 0.99)]
+        min_ssim: f64,
+        /// Highest acceptable mean fraction of changed pixels (0 to 1)
+        #[arg(long = "max-changed", value_name = "FRACTION", default_value_t = 0.01)]
+        max_changed: f64,
+        /// Write a comparison sheet (committed on top) for every asset that fails into this directory
+        #[arg(long = "diff-dir", value_name = "DIR")]
+        diff_dir: Option<String>,
+    },
+    /// Regenerate an asset and replace it under the same name and file
+    Update {
+        /// Asset name
+        name: String,
+        /// New prompt (defaults to the asset's current prompt)
+        #[arg(long = "prompt")]
+        prompt: Option<String>,
+        /// Input image: a file, an http(s) URL or "clipboard"
+        #[arg(short = 'i', long = "input-image", value_name = "FILE")]
+        input_image: Option<String>,
+        /// Pad the input image to a square, scale it into range and re-encode it as PNG
+        #[arg(long = "preprocess", requires = "input_image")]
+        preprocess: bool,
+        /// Duration in seconds (one the model renders; defaults to the workspace's, or 5)
+        #[arg(short = 'd', long = "duration", value_name = "SECONDS")]
+        duration: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// Write a profile file from the given settings
+    Save {
+        /// Profile file to write (TOML)
+        file: String,
+        /// Profile name
+        #[arg(long = "name")]
+        name: Option<String>,
+        /// What the profile is for
+        #[arg(long = "description")]
+        description: Option<String>,
+        /// Export target; repeat for several
+        #[arg(short = 't', long = "target", value_name = "TARGET")]
+        targets: Vec<export::Target>,
+        /// Engine preset for `export --drop`
+        #[arg(long = "engine", value_name = "ENGINE")]
+        engine: Option<Engine>,
+        /// Number of sheet columns
+        #[arg(long = "columns")]
+        columns: Option<u32>,
+        /// Transparent padding between frames, in pixels
+        #[arg(long = "padding", default_value_t = 0)]
+        padding: u32,
+        /// Playback rate
+        #[arg(long = "fps")]
+        fps: Option<u32>,
+        /// Pipeline file whose processing steps run before every export
+        #[arg(long = "pipeline", value_name = "FILE")]
+        pipeline: Option<String>,
+    },
+    /// Make a profile file the active profile for `export`
+    Load {
+        /// Profile file (TOML)
+        file: String,
+    },
+    /// Print the active profile
+    Show,
+}
+
+#[derive(Subcommand)]
+pub enum ImageCommands {
+    /// Get the results of an image
+    Get {
+        /// The image ID
+        id: String,
+    },
+    /// Generate a new still image
+    Generate {
+        /// The prompt used for generation
+        prompt: String,
+        /// Block until rendering finishes and download the PNG
+        #[arg(short = 'b', long = "block")]
+        block: bool,
+        /// Output file for the PNG when using --block ("-" for stdout)
+        #[arg(short = 'o', long = "output-file")]
+        output_file: Option<String>,
+        /// Optional input image: a file, an http(s) URL, "-" for stdin or "clipboard"
+        #[arg(short = 'i', long = "input-image", value_name = "FILE")]
+        input_image: Option<String>,
+        /// Pad the input image to a square, scale it down if too large and re-encode it as PNG
+        #[arg(long = "preprocess", requires = "input_image")]
+        preprocess: bool,
+        /// Optional image model name
+        #[arg(long = "model-name", value_name = "NAME")]
+        model_name: Option<String>,
+        /// Suppress informational logs
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -35,175 +568,3811 @@ pub enum AnimationCommands {
     /// Retrieve an existing animation
     Get {
         /// The identifier of the animation to fetch (omit to list all animations)
+        #[arg(add = ArgValueCandidates::new(complete_animation_ids))]
         id: Option<String>,
+        /// Only list animations with this status (generating, complete, failed)
+        #[arg(long = "status", value_name = "STATUS", value_parser = animations::parse_status, conflicts_with = "id")]
+        status: Option<i64>,
+        /// List at most this many animations (one page)
+        #[arg(long = "limit", value_name = "N", value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "id")]
+        limit: Option<u32>,
+        /// Page to list with --limit (1-based)
+        #[arg(long = "page", value_name = "N", requires = "limit", conflicts_with = "id")]
+        page: Option<u32>,
+        /// Only list animations created after this (YYYY-MM-DD, Unix timestamp, or an age like 7d)
+        #[arg(long = "since", value_name = "WHEN", conflicts_with = "id")]
+        since: Option<String>,
+        /// Only list animations whose prompt contains this text
+        #[arg(long = "search", value_name = "TEXT", conflicts_with = "id")]
+        search: Option<String>,
+        /// Only list animations with this tag
+        #[arg(long = "tag", value_name = "TAG", conflicts_with = "id")]
+        tag: Option<String>,
+        /// Listing order (newest, oldest)
+        #[arg(long = "sort", value_name = "ORDER", conflicts_with = "id")]
+        sort: Option<animations::ListSort>,
     },
     /// Generate a new animation
     Generate {
         /// The prompt or parameters used for generation
-        prompt: String,
+        #[arg(required_unless_present = "again")]
+        prompt: Option<String>,
+        /// Re-run the Nth most recent prompt from `gametorch history` (default 1); other flags override its settings
+        #[arg(long = "again", value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+        again: Option<usize>,
         /// Block until rendering finishes and download ZIP.
         #[arg(short = 'b', long = "block")]
         block: bool,
-        /// Output file for the resulting ZIP when using --block.
+        /// Output file for the resulting ZIP when using --block ("-" for stdout)
         #[arg(short = 'o', long = "output-file")]
         output_file: Option<String>,
-        /// Optional input image file path to include in generation
+        /// Write the result into DIR/<animation_id>/<result_id>/: the ZIP, its frames and a metadata.json (with --block)
+        #[arg(long = "output-dir", value_name = "DIR", requires = "block", conflicts_with_all = ["output_file", "count"])]
+        output_dir: Option<PathBuf>,
+        /// Also export the result into the --output-dir layout for this target (repeatable)
+        #[arg(long = "export", value_name = "TARGET", requires = "output_dir")]
+        export: Vec<export::Target>,
+        /// Optional input image to include in generation: a file, an http(s) URL, "-" for stdin or "clipboard"
         #[arg(short = 'i', long = "input-image", value_name = "FILE")]
         input_image: Option<String>,
+        /// Pad the input image to a square, scale it down if too large and re-encode it as PNG
+        #[arg(long = "preprocess", requires = "input_image")]
+        preprocess: bool,
+        /// Re-encode the input image losslessly (optimized PNG or WebP) if that makes the upload smaller
+        #[arg(long = "compress-input", requires = "input_image")]
+        compress_input: bool,
+        /// Record the result in the workspace's asset manifest under this name (with --block)
+        #[arg(long = "as", value_name = "NAME", requires = "block")]
+        asset: Option<String>,
+        /// Copy the first frame of the result to the clipboard (with --block)
+        #[arg(long = "copy-result", requires = "block")]
+        copy_result: bool,
         /// Optional animation model ID (defaults to 6)
         #[arg(long = "model-id", value_name = "ID", conflicts_with = "model_name")]
         model_id: Option<u32>,
         /// Optional animation model name (defaults to 'alpha/v2.1')
-        #[arg(long = "model-name", value_name = "NAME", conflicts_with = "model_id")]
+        #[arg(long = "model-name", value_name = "NAME", conflicts_with = "model_id", add = ArgValueCandidates::new(complete_model_names))]
         model_name: Option<String>,
+        /// Have the backend POST to this URL when the animation completes
+        #[arg(long = "webhook-url", value_name = "URL")]
+        webhook_url: Option<String>,
         /// Suppress informational logs
         #[arg(short = 's', long = "silent")]
         silent: bool,
-        /// Duration in seconds (allowed values: 5 or 10, defaults to 5)
-        #[arg(short = 'd', long = "duration", value_name = "SECONDS", default_value_t = 5)]
-        duration: u32,
+        /// Duration in seconds (allowed values depend on the model: 5 or 10 for alpha/v2.1; defaults to 5)
+        #[arg(short = 'd', long = "duration", value_name = "SECONDS")]
+        duration: Option<u32>,
+        /// Skip the local duration and image size checks for the model and let the backend decide (for models newer than this release knows)
+        #[arg(long = "skip-model-checks")]
+        skip_model_checks: bool,
+        /// Validate everything and print the request that would be sent, with its estimated cost, without sending it
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+        /// What to keep out of the animation, e.g. "extra limbs, background"
+        #[arg(long = "negative-prompt", value_name = "TEXT")]
+        negative_prompt: Option<String>,
+        /// Seed for a reproducible render (with --again, defaults to the recorded one)
+        #[arg(long = "seed", value_name = "SEED")]
+        seed: Option<u64>,
+        /// Submit N generations of the prompt, wait for all of them and download each ZIP into one run directory (-o names it) with an index.json comparing them
+        #[arg(short = 'n', long = "count", value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=MAX_CANDIDATES), conflicts_with_all = ["asset", "copy_result"])]
+        count: u32,
+        /// Regenerate a render that fails (and is refunded) up to N times before reporting failure (with --block or --count)
+        #[arg(long = "retry-on-failure", value_name = "N", default_value_t = 0)]
+        retry_on_failure: u32,
+    },
+    /// Open an animation, or the crop-and-trim page of a result, in the default browser
+    Open {
+        /// The animation to open (an animation_id; with --crop, an animation_result_id)
+        #[arg(add = ArgValueCandidates::new(complete_animation_ids))]
+        id: String,
+        /// Open the crop-and-trim page of this result instead
+        #[arg(long = "crop")]
+        crop: bool,
+        /// Print the URL instead of opening it
+        #[arg(long = "print")]
+        print: bool,
     },
     /// Display instructions for cropping an animation result
     Crop {
         /// (Optional) Animation result ID. If omitted, prints general instructions.
         animation_result_id: Option<String>,
     },
+    /// Show the first frame of a result in the terminal, or play it
+    Preview {
+        /// Result ZIP, directory of PNG frames, or animation result ID
+        result: String,
+        /// Play every frame instead of showing the first
+        #[arg(long = "play")]
+        play: bool,
+        /// Times to play the animation with --play (0 loops until interrupted)
+        #[arg(long = "loops", value_name = "N", default_value_t = 1, requires = "play")]
+        loops: u32,
+        /// Width in terminal columns
+        #[arg(long = "width", value_name = "COLUMNS", default_value_t = preview::DEFAULT_COLUMNS)]
+        width: u32,
+        /// Graphics protocol (auto, kitty, iterm, sixel, blocks)
+        #[arg(long = "protocol", value_name = "PROTOCOL", default_value = "auto")]
+        protocol: preview::Protocol,
+    },
+    /// Show when an animation was submitted, started rendering, completed and had its ZIP ready
+    History {
+        /// The animation_id
+        #[arg(add = ArgValueCandidates::new(complete_animation_ids))]
+        animation_id: i64,
+    },
+    /// Download every completed result of an animation into a directory, with a manifest.json listing them
+    DownloadAll {
+        /// The animation_id
+        #[arg(add = ArgValueCandidates::new(complete_animation_ids))]
+        animation_id: i64,
+        /// Directory for the ZIPs (defaults to animation_<id>)
+        #[arg(long = "dir", value_name = "DIR")]
+        dir: Option<PathBuf>,
+        /// Suppress the progress lines
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
+    /// Compute quality metrics for downloaded results and flag outlier frames
+    Metrics {
+        /// Result ZIPs or directories of PNG frames
+        #[arg(required = true)]
+        inputs: Vec<String>,
+    },
+    /// Tag, alias or move every animation matching a filter in one atomic update
+    Bulk {
+        /// Terms that must all match, e.g. "status=complete prompt~knight" (empty matches all)
+        #[arg(long = "filter", value_name = "FILTER", default_value = "")]
+        filter: Filter,
+        /// Tag to add (repeatable)
+        #[arg(long = "add-tag", value_name = "TAG")]
+        add_tags: Vec<String>,
+        /// Tag to remove (repeatable)
+        #[arg(long = "remove-tag", value_name = "TAG")]
+        remove_tags: Vec<String>,
+        /// Alias template, e.g. "knight-{id}" (supports {id}, {slug})
+        #[arg(long = "alias", value_name = "TEMPLATE")]
+        alias: Option<String>,
+        /// Route matches to a workspace ("workspace:NAME")
+        #[arg(long = "move-to", value_name = "workspace:NAME")]
+        move_to: Option<String>,
+        /// Show the changes without writing them
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+    /// Wait for several animations at once, optionally downloading each result
+    Wait {
+        /// Animations to wait for
+        #[arg(required = true, add = ArgValueCandidates::new(complete_animation_ids))]
+        ids: Vec<String>,
+        /// Download each completed result into this directory as <id>.zip
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR")]
+        output_dir: Option<String>,
+        /// Suppress the progress line
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
+    /// Regenerate only the masked region of an input image
+    Inpaint {
+        /// The image to fix: a file, an http(s) URL, "-" for stdin or "clipboard"
+        #[arg(short = 'i', long = "input-image", value_name = "FILE")]
+        input_image: String,
+        /// Mask the size of the input image: white where it is regenerated, black where it is kept
+        #[arg(long = "mask", value_name = "FILE")]
+        mask: String,
+        /// What to draw in the masked region
+        #[arg(long = "prompt", value_name = "TEXT")]
+        prompt: String,
+        /// Duration in seconds (allowed values depend on the model: 5 or 10 for alpha/v2.1)
+        #[arg(short = 'd', long = "duration", value_name = "SECONDS", default_value_t = 5)]
+        duration: u32,
+        /// Skip the local duration and image size checks for the model and let the backend decide
+        #[arg(long = "skip-model-checks")]
+        skip_model_checks: bool,
+        /// Pad the input image and mask to a square, scale them down if too large and re-encode them as PNG
+        #[arg(long = "preprocess")]
+        preprocess: bool,
+        /// Optional animation model ID (defaults to 6)
+        #[arg(long = "model-id", value_name = "ID", conflicts_with = "model_name")]
+        model_id: Option<u32>,
+        /// Optional animation model name (defaults to 'alpha/v2.1')
+        #[arg(long = "model-name", value_name = "NAME", conflicts_with = "model_id", add = ArgValueCandidates::new(complete_model_names))]
+        model_name: Option<String>,
+        /// Wait for the result and download it into this directory as <id>.zip
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR")]
+        output_dir: Option<String>,
+        /// Suppress informational logs
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
+    /// Upscale a result on the backend, or locally if the backend cannot
+    Upscale {
+        /// The animation result to upscale (an animation_result_id)
+        result_id: String,
+        /// Scale factor (2 or 4)
+        #[arg(long = "factor", value_name = "N", default_value_t = 2)]
+        factor: u32,
+        /// Where to save the upscaled frames: a .zip or a directory (defaults to upscaled_<result_id>.zip)
+        #[arg(short = 'o', long = "output", value_name = "PATH")]
+        output: Option<String>,
+        /// Scale locally without asking the backend
+        #[arg(long = "on-device")]
+        on_device: bool,
+        /// Resampling filter for local scaling (nearest, linear, cubic, lanczos)
+        #[arg(long = "filter", value_name = "FILTER", default_value = "lanczos")]
+        filter: process::ScaleFilter,
+        /// Snap to the detected pixel grid before scaling locally
+        #[arg(long = "pixel-snap")]
+        pixel_snap: bool,
+        /// Suppress informational logs
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
+    /// Explore around a good result: submit variations of it, optionally waiting for and downloading each
+    Variations {
+        /// The animation result to vary (an animation_result_id)
+        result_id: String,
+        /// Number of variations (at most 8)
+        #[arg(short = 'n', long = "count", value_name = "N", default_value_t = 4)]
+        count: u32,
+        /// How far the variations may stray from the result (0 to 1)
+        #[arg(long = "strength", value_name = "STRENGTH", default_value_t = 0.5)]
+        strength: f32,
+        /// Wait for the variations and download each result into this directory as <id>.zip
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR")]
+        output_dir: Option<String>,
+        /// Suppress the progress line
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
     /// Regenerate an animation (note: this takes an animation_id, **not** an animation_result_id)
     Regenerate {
         /// The identifier of the animation to regenerate
+        #[arg(add = ArgValueCandidates::new(complete_animation_ids), required_unless_present = "all_failed")]
+        animation_id: Option<String>,
+        /// Regenerate every failed animation instead, printing the new IDs
+        #[arg(long = "all-failed", conflicts_with_all = ["animation_id", "diff", "seed"])]
+        all_failed: bool,
+        /// With --all-failed, only animations created after this (YYYY-MM-DD, Unix timestamp, or an age like 24h)
+        #[arg(long = "since", value_name = "WHEN", requires = "all_failed")]
+        since: Option<String>,
+        /// With --all-failed, how many regenerations to submit at once
+        #[arg(long = "concurrency", value_name = "N", default_value_t = animations::DEFAULT_REGENERATE_CONCURRENCY, requires = "all_failed")]
+        concurrency: usize,
+        /// Wait for the new result, then download both results and write a comparison sheet plus SSIM/PSNR metrics
+        #[arg(long = "diff")]
+        diff: bool,
+        /// Directory for --diff output (defaults to regenerate_<old>_<new>)
+        #[arg(long = "diff-dir", value_name = "DIR", requires = "diff")]
+        diff_dir: Option<String>,
+        /// Render with this seed instead of the original's
+        #[arg(long = "seed", value_name = "SEED")]
+        seed: Option<u64>,
+        /// Suppress informational logs
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
+    /// Put two results side by side, as a GIF and a sheet, with SSIM/PSNR and changed-pixel metrics
+    Compare {
+        /// The first animation result (an animation_result_id); shown on the left and top
+        result_a: String,
+        /// The second animation result; shown on the right and bottom
+        result_b: String,
+        /// Directory for the comparison (defaults to compare_<a>_<b>)
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR")]
+        output_dir: Option<String>,
+        /// Suppress informational logs
+        #[arg(short = 's', long = "silent")]
+        silent: bool,
+    },
+    /// Set an animation's name, description or tags
+    Rename {
+        /// The animation to edit
+        #[arg(add = ArgValueCandidates::new(complete_animation_ids))]
         animation_id: String,
+        /// New human-readable name
+        name: Option<String>,
+        /// New description
+        #[arg(long = "description", value_name = "TEXT")]
+        description: Option<String>,
+        /// Tag to set, replacing the current tags (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Remove all tags
+        #[arg(long = "clear-tags", conflicts_with = "tags")]
+        clear_tags: bool,
+    },
+    /// Delete animations by ID, or every animation with a given status
+    Delete {
+        /// Animations to delete
+        #[arg(required_unless_present = "status", add = ArgValueCandidates::new(complete_animation_ids))]
+        ids: Vec<String>,
+        /// Delete every animation with this status (e.g. failed) instead of listing IDs
+        #[arg(long = "status", value_name = "STATUS", value_parser = animations::parse_status, conflicts_with = "ids")]
+        status: Option<i64>,
+        /// Do not ask for confirmation before a --status delete
+        #[arg(short = 'y', long = "yes")]
+        yes: bool,
+    },
+}
+
+/// Input and output shared by every `process` operation.
+#[derive(Args)]
+pub struct ProcessIo {
+    /// Result ZIP or directory of PNG frames
+    input: String,
+    /// Output ZIP (if it ends in .zip) or directory
+    #[arg(short = 'o', long = "output")]
+    output: String,
+}
+
+#[derive(Subcommand)]
+pub enum ProcessCommands {
+    /// Crop all frames to the union bounding box of their visible pixels
+    Trim {
+        #[command(flatten)]
+        io: ProcessIo,
+        /// Extra transparent pixels to keep around the visible area
+        #[arg(long = "margin", default_value_t = 0)]
+        margin: u32,
+    },
+    /// Key out a solid background colour, writing RGBA frames
+    RemoveBg {
+        #[command(flatten)]
+        io: ProcessIo,
+        /// Background colour as #rrggbb (sampled from the frame corners if omitted)
+        #[arg(long = "color", value_name = "HEX")]
+        color: Option<process::Rgb>,
+        /// Maximum RGB distance from the key colour that becomes transparent
+        #[arg(long = "tolerance", default_value_t = 24.0)]
+        tolerance: f32,
+        /// Width of the partially transparent edge band beyond the tolerance
+        #[arg(long = "softness", default_value_t = 0.0)]
+        softness: f32,
+    },
+    /// Reduce frames to a shared palette, or lock them to an existing one
+    Palettize {
+        #[command(flatten)]
+        io: ProcessIo,
+        /// Number of colours to quantize to (without it, the workspace palette is used)
+        #[arg(long = "colors", value_name = "N", conflicts_with = "palette")]
+        colors: Option<usize>,
+        /// Quantization method for --colors (median-cut, kmeans)
+        #[arg(long = "method", value_name = "METHOD", default_value = "median-cut", conflicts_with = "palette")]
+        method: process::QuantizeMethod,
+        /// Palette to remap to (.png, JASC .pal, or one #rrggbb per line)
+        #[arg(long = "palette", value_name = "FILE")]
+        palette: Option<String>,
+    },
+    /// Resize frames, optionally snapping them to their pixel grid first
+    Scale {
+        #[command(flatten)]
+        io: ProcessIo,
+        /// Scale factor (e.g. 2 doubles the size, 0.5 halves it)
+        #[arg(long = "factor", default_value_t = 1.0)]
+        factor: f32,
+        /// Resampling filter (nearest, linear, cubic, lanczos)
+        #[arg(long = "filter", value_name = "FILTER", default_value = "nearest")]
+        filter: process::ScaleFilter,
+        /// Collapse each logical pixel of the detected pixel grid to one colour before scaling
+        #[arg(long = "pixel-snap")]
+        pixel_snap: bool,
+        /// Pixel grid cell size for --pixel-snap (detected if omitted)
+        #[arg(long = "grid", value_name = "PIXELS", requires = "pixel_snap")]
+        grid: Option<u32>,
     },
+    /// Merge runs of identical or near-identical frames into longer frames
+    Dedupe {
+        #[command(flatten)]
+        io: ProcessIo,
+        /// PSNR (dB) at or above which neighbouring frames are merged ("inf" for exact duplicates only)
+        #[arg(long = "min-psnr", value_name = "DB", default_value_t = process::DEFAULT_MIN_PSNR)]
+        min_psnr: f64,
+        /// Resample to this constant frame rate before merging
+        #[arg(long = "fps")]
+        fps: Option<u32>,
+    },
+    /// Make the animation loop seamlessly by ping-ponging or crossfading its ends
+    Loop {
+        #[command(flatten)]
+        io: ProcessIo,
+        /// How to close the loop (pingpong, crossfade)
+        #[arg(long = "mode", value_name = "MODE", default_value = "pingpong")]
+        mode: process::LoopMode,
+        /// Number of end frames to crossfade into the start (crossfade mode)
+        #[arg(long = "frames", value_name = "N")]
+        frames: Option<usize>,
+    },
+    /// Attach named animation events (`hit: frame 7`) to the frames
+    Markers {
+        #[command(flatten)]
+        io: ProcessIo,
+        /// Markers file, one `name: frame N` or `name: frames A, B` per line
+        #[arg(long = "file", value_name = "FILE")]
+        file: String,
+    },
+    /// Write a PNG grid of all frames with their indices, for reviewing timing at a glance
+    ContactSheet {
+        /// Result ZIP or directory of PNG frames
+        input: String,
+        /// Contact sheet PNG to write
+        #[arg(short = 'o', long = "output")]
+        sheet: String,
+        /// Number of columns (defaults to a roughly square grid)
+        #[arg(long = "columns", value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        columns: Option<u32>,
+        /// Pixels between neighbouring cells
+        #[arg(long = "padding", value_name = "PIXELS", default_value_t = 4)]
+        padding: u32,
+        /// Leave out the frame indices
+        #[arg(long = "no-labels")]
+        no_labels: bool,
+        /// Also write an onion-skin PNG: frames overlaid, earlier ones fainter and tinted blue
+        #[arg(long = "onion-skin", value_name = "FILE")]
+        onion_skin: Option<String>,
+        /// Most frames to overlay in the onion skin (evenly spaced)
+        #[arg(long = "onion-frames", value_name = "N", default_value_t = 8, requires = "onion_skin")]
+        onion_frames: usize,
+    },
+    /// Run the steps of a pipeline file (trim, remove-bg, palettize, pack, export, ...)
+    Run {
+        /// Pipeline YAML file
+        pipeline: String,
+        /// Result ZIP or directory of PNG frames
+        input: String,
+        /// Also write the final frames to this ZIP or directory
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+        /// Base name for exported files (defaults to the input's file stem)
+        #[arg(long = "name")]
+        name: Option<String>,
+    },
+}
+
+impl ProcessCommands {
+    fn input(&self) -> &str {
+        match self {
+            ProcessCommands::Trim { io, .. }
+            | ProcessCommands::RemoveBg { io, .. }
+            | ProcessCommands::Palettize { io, .. }
+            | ProcessCommands::Scale { io, .. }
+            | ProcessCommands::Dedupe { io, .. }
+            | ProcessCommands::Loop { io, .. }
+            | ProcessCommands::Markers { io, .. } => &io.input,
+            ProcessCommands::ContactSheet { input, .. } | ProcessCommands::Run { input, .. } => input,
+        }
+    }
+
+    /// Where to save the processed frames; a pipeline saves only if asked.
+    fn output(&self) -> Option<String> {
+        match self {
+            ProcessCommands::Trim { io, .. }
+            | ProcessCommands::RemoveBg { io, .. }
+            | ProcessCommands::Palettize { io, .. }
+            | ProcessCommands::Scale { io, .. }
+            | ProcessCommands::Dedupe { io, .. }
+            | ProcessCommands::Loop { io, .. }
+            | ProcessCommands::Markers { io, .. } => Some(io.output.clone()),
+            ProcessCommands::ContactSheet { .. } => None,
+            ProcessCommands::Run { output, .. } => output.clone(),
+        }
+    }
+}
+
+impl AnimationCommands {
+    /// Whether the command talks to the API (and therefore needs a key).
+    fn needs_api_key(&self, offline: bool) -> bool {
+        match self {
+            // Preview asks for a key itself when given a result ID.
+            AnimationCommands::Crop { .. }
+            | AnimationCommands::Metrics { .. }
+            | AnimationCommands::Open { .. }
+            | AnimationCommands::Preview { .. } => false,
+            AnimationCommands::Generate { dry_run, .. } => !offline && !dry_run,
+            AnimationCommands::Get { .. } => !offline,
+            _ => true,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() {
+    // Answer completion requests from the script `completions` installs.
+    clap_complete::CompleteEnv::with_factory(Cli::command).complete();
+
     // Parse CLI arguments
     let cli = Cli::parse();
+    PORCELAIN.store(cli.porcelain, Ordering::Relaxed);
 
-    // Retrieve API key from environment variable
-    let api_key = env::var("GAMETORCH_API_KEY").unwrap_or_else(|_| {
-        eprintln!(
-            "Error: environment variable GAMETORCH_API_KEY not set.\n\
-            Please set it before using this CLI."
-        );
-        std::process::exit(1);
+    let retry = RetryPolicy {
+        max_attempts: cli.retries.saturating_add(1),
+        ..Default::default()
+    };
+    let mut client = ClientBuilder::new().retry_policy(retry);
+    if let Some(rate) = cli.rate_limit {
+        client = client.rate_limit(rate);
+    }
+    if let Some(path) = &cli.debug_http {
+        client = client.transcript(path);
+    }
+    if let Some(path) = &cli.record {
+        client = client.record(path);
+    }
+    if let Some(path) = &cli.replay {
+        client = client.replay(path);
+    }
+    // Cassettes hold full responses; a cached 304 would not replay.
+    if !cli.no_cache
+        && cli.record.is_none()
+        && cli.replay.is_none()
+        && let Ok(dir) = gametorch::paths::data_dir()
+    {
+        client = client.cache(dir.join(gametorch::http_cache::CACHE_DIR));
+    }
+    // A broken config.toml should not stop offline commands such as export.
+    let config = Config::load_default().unwrap_or_else(|err| {
+        eprintln!("Warning: ignoring the configuration: {}", err);
+        Config::default()
     });
-
-    // Determine base URL depending on --local flag
-    let base_url = if cli.local {
-        "http://localhost:8000"
-    } else {
-        "https://gametorch.app"
-    };
-
-    // Helper to map numeric status to human string.
-    fn replace_status_recursive(value: &mut serde_json::Value) {
-        use serde_json::Value;
-        match value {
-            Value::Object(map) => {
-                if let Some(status_val) = map.get_mut("status") {
-                    if let Some(num) = status_val.as_i64() {
-                        let new_str = match num {
-                            1 => "generating",
-                            2 => "complete",
-                            3 => "failed and refunded",
-                            _ => return,
-                        };
-                        *status_val = Value::String(new_str.to_string());
-                    }
-                }
-                for v in map.values_mut() {
-                    replace_status_recursive(v);
-                }
+    if config.stats.enabled {
+        match UsageLog::open_default() {
+            Ok(log) => {
+                let _ = RUN.set(usage::Run::start(log, command_name()));
             }
-            Value::Array(arr) => {
-                for v in arr {
-                    replace_status_recursive(v);
-                }
+            Err(err) => eprintln!("Warning: usage stats disabled: {}", err),
+        }
+    }
+    let _finished = Finished;
+    if config.store.enabled
+        && let Err(err) = Store::open_default().and_then(Store::install)
+    {
+        eprintln!("Warning: asset store disabled: {}", err);
+    }
+    // A key in the environment wins over configured ones.
+    if env::var(API_KEY_ENV).is_err() && !config.keys.is_empty() {
+        match auth::active_key().and_then(|active| KeyRing::new(config.keys.clone(), active.as_deref())) {
+            Ok(keys) => {
+                let _ = CONFIGURED_KEY.set(keys.current().key.clone());
+                client = client.keys(keys);
+            }
+            Err(err) => {
+                fail("Failed to load API keys", err);
             }
-            _ => {}
         }
     }
+    if let Err(err) = config
+        .http
+        .apply(client)
+        .and_then(ClientBuilder::build)
+        .and_then(Client::install)
+    {
+        fail("Failed to set up the HTTP client", err);
+    }
+
+    if let Some(jobs) = cli.jobs
+        && let Err(err) = process::set_jobs(jobs)
+    {
+        invalid(err);
+    }
+
+    let workspace = match Workspace::current() {
+        Ok(workspace) => workspace,
+        Err(err) => {
+            fail("Failed to load workspace", err);
+        }
+    };
+
+    // Flags win over the environment, which wins over production.
+    let base_url = match (&cli.base_url, cli.local) {
+        (Some(url), _) => gametorch::client::base_url(url),
+        (None, true) => Ok(gametorch::client::LOCAL_BASE_URL.to_string()),
+        (None, false) => match env::var(gametorch::client::BASE_URL_ENV) {
+            Ok(url) if !url.trim().is_empty() => gametorch::client::base_url(&url)
+                .map_err(|e| format!("{}: {}", gametorch::client::BASE_URL_ENV, e).into()),
+            _ => Ok(gametorch::client::DEFAULT_BASE_URL.to_string()),
+        },
+    };
+    let base_url = match &base_url {
+        Ok(url) => url.as_str(),
+        Err(err) => {
+            invalid(err);
+        }
+    };
+
+    // Listings are for reading unless piped or asked for porcelain.
+    let list_format = match (cli.porcelain, cli.format) {
+        (true, _) => OutputFormat::Json,
+        (false, Some(format)) => format,
+        (false, None) => OutputFormat::detect(),
+    };
+    let outputs = Output::new(cli.format.unwrap_or(OutputFormat::Json), cli.query.as_deref())
+        .and_then(|format| Ok((format, Output::new(list_format, cli.query.as_deref())?)));
+    let (format, list_format) = match outputs {
+        Ok(outputs) => outputs,
+        Err(err) => {
+            invalid(err);
+        }
+    };
 
     // Dispatch based on the parsed commands
     match cli.command {
-        Commands::Animations { action } => match action {
-            AnimationCommands::Get { id } => {
-                if let Some(id) = id {
-                    match animations::get(&api_key, base_url, &id).await {
-                        Ok(mut json) => {
-                            if !cli.porcelain {
-                                replace_status_recursive(&mut json);
+        Commands::Animations { action } => {
+            // Retrieve API key from environment variable
+            let api_key = if action.needs_api_key(cli.offline) {
+                require_api_key()
+            } else {
+                String::new()
+            };
+
+            match action {
+                AnimationCommands::Get { id, status, limit, page, since, search, tag, sort } => {
+                    if let Some(id) = id {
+                        let fetched = if cli.offline {
+                            get_offline(&id)
+                        } else {
+                            animations::get(&api_key, base_url, &id).await
+                        };
+                        match fetched {
+                            Ok(mut json) => {
+                                #[cfg(feature = "index")]
+                                if let Ok(animation_id) = id.parse() {
+                                    update_index(|index| index.upsert_results(animation_id, &json));
+                                }
+                                if !cli.porcelain {
+                                    animations::humanize_statuses(&mut json);
+                                }
+                                emit(&json, &format);
+                            }
+                            Err(err) => {
+                                fail("Failed to fetch animation", err);
                             }
-                            println!("{}", serde_json::to_string_pretty(&json).unwrap());
                         }
-                        Err(err) => {
-                            eprintln!("Failed to fetch animation: {}", err);
-                            std::process::exit(1);
+                    } else {
+                        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                        let created_after = match since.map(|s| animations::parse_since(&s, now)).transpose() {
+                            Ok(after) => after,
+                            Err(err) => {
+                                invalid(err);
+                            }
+                        };
+                        let options = animations::ListOptions {
+                            page,
+                            per_page: limit,
+                            status,
+                            created_after,
+                            search,
+                            tag,
+                            sort,
+                        };
+                        let listed = if cli.offline {
+                            list_offline(&options)
+                        } else {
+                            let listed = animations::list(&api_key, base_url, &options).await;
+                            #[cfg(feature = "index")]
+                            if let Ok(json) = &listed {
+                                update_index(|index| index.upsert_animations(json));
+                            }
+                            listed
+                        };
+                        match listed {
+                            Ok(json) if list_format.format == OutputFormat::Table && !list_format.has_query() => {
+                                print!("{}", output::animation_table(&json, output::use_color()));
+                            }
+                            Ok(mut json) => {
+                                if !cli.porcelain {
+                                    animations::humanize_statuses(&mut json);
+                                }
+                                emit(&json, &list_format);
+                            }
+                            Err(err) => {
+                                fail("Failed to list animations", err);
+                            }
+                        }
+                    }
+                }
+                AnimationCommands::Generate { prompt, again, block, output_file, output_dir, export, input_image, preprocess, compress_input, asset, copy_result, model_id, model_name, webhook_url, silent, duration, skip_model_checks, dry_run, negative_prompt, seed, count, retry_on_failure } => {
+                    if let Some(name) = &asset {
+                        if workspace.is_none() {
+                            invalid("--as needs a workspace (run `gametorch init` at the project root)");
+                        }
+                        if let Err(err) = gametorch::manifest::validate_name(name) {
+                            invalid(err);
+                        }
+                    }
+                    // Checked now so a queued (--offline) request cannot fail on it later.
+                    if let Some(url) = &webhook_url
+                        && let Err(err) = webhooks::validate_url(url)
+                    {
+                        invalid(err);
+                    }
+                    if retry_on_failure > 0 && !block && count == 1 {
+                        invalid("--retry-on-failure needs --block or --count");
+                    }
+                    let Replay { prompt, input_image, model_id, model_name, duration, seed, negative_prompt } = match again {
+                        Some(n) => match replay(n, prompt, input_image, model_id, model_name, duration, seed, negative_prompt) {
+                            Ok(replay) => replay,
+                            Err(err) => {
+                                invalid(err);
+                            }
+                        },
+                        None => Replay {
+                            prompt: prompt.unwrap_or_default(),
+                            input_image,
+                            model_id,
+                            model_name,
+                            duration: duration
+                                .or(workspace.as_ref().and_then(|ws| ws.config.duration))
+                                .unwrap_or(5),
+                            seed,
+                            negative_prompt,
+                        },
+                    };
+                    let (model_id, model_name) = match (&workspace, model_id, model_name) {
+                        (Some(ws), None, None) => ws.config.model(),
+                        (_, id, name) => (id, name),
+                    };
+                    let request = GenerateRequest {
+                        prompt: prompt.clone(),
+                        duration_seconds: duration,
+                        input_image: input_image.clone(),
+                        preprocess,
+                        compress: compress_input,
+                        model_id,
+                        model_name: model_name.clone(),
+                        webhook_url: webhook_url.clone(),
+                        seed,
+                        negative_prompt: negative_prompt.clone(),
+                        skip_model_checks,
+                        retry_on_failure,
+                    };
+                    if dry_run {
+                        match animations::dry_run(base_url, &request).await {
+                            Ok(json) => emit(&json, &format),
+                            Err(err) => {
+                                fail("Dry run failed", err);
+                            }
+                        }
+                        return;
+                    }
+                    if count > 1 {
+                        if cli.offline {
+                            invalid("--count cannot be queued with --offline");
+                        }
+                        let dir = output_file.map(PathBuf::from).unwrap_or_else(|| {
+                            let parent = workspace.as_ref().map(|ws| ws.output_dir()).unwrap_or_default();
+                            let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                            parent.join(format!("{}_{}", gametorch::slug::slug(&prompt), now))
+                        });
+                        // Read the input once rather than once per candidate.
+                        gametorch::input::prefetch(input_image.clone(), gametorch::input::PREFETCH_MAX_BYTES).await;
+                        let mut jobs = Vec::new();
+                        for i in 0..count {
+                            let submitted = animations::generate_from(&api_key, base_url, &request, false, None, silent || i > 0).await;
+                            let (id, returned_seed) = match submitted {
+                                Ok(json) => (
+                                    json.get("animation_id").and_then(|v| v.as_i64()),
+                                    json.get("seed").and_then(|v| v.as_u64()),
+                                ),
+                                Err(err) => {
+                                    fail(&format!("Failed to submit generation {} of {}", i + 1, count), err);
+                                }
+                            };
+                            if let Some(id) = id {
+                                let mut job = Job::submitted(id, "generate");
+                                job.prompt = Some(prompt.clone());
+                                job.slug = Some(gametorch::slug::slug(&prompt));
+                                job.model = model_name.clone().or_else(|| model_id.map(|id| id.to_string()));
+                                job.duration_seconds = Some(duration);
+                                job.input_image = input_image.clone();
+                                job.negative_prompt = negative_prompt.clone();
+                                job.seed = returned_seed.or(seed);
+                                record_job(&job);
+                                jobs.push(job);
+                            }
+                        }
+                        let ids: Vec<String> = jobs.iter().map(|job| job.animation_id.to_string()).collect();
+                        let items = wait_and_download(&api_key, base_url, &ids, Some(&dir), retry_on_failure, silent).await;
+                        for (mut job, item) in jobs.into_iter().zip(items.as_array().into_iter().flatten()) {
+                            // A failed render may have been replaced by a regeneration.
+                            if let Some(id) = item["animation_id"].as_str().and_then(|id| id.parse::<i64>().ok())
+                                && id != job.animation_id
+                            {
+                                job = Job {
+                                    animation_id: id,
+                                    kind: "regenerate".to_string(),
+                                    source_animation_id: Some(job.animation_id),
+                                    ..job
+                                };
+                            }
+                            if let Some(path) = item.get("zip_path").and_then(|v| v.as_str()) {
+                                record_job(&job.complete(item.get("result_id").and_then(|v| v.as_i64()), Some(path.to_string())));
+                            }
                         }
+                        let index = candidate_index(&prompt, &dir, items);
+                        let index_path = dir.join("index.json");
+                        if let Err(err) = serde_json::to_vec_pretty(&index).map_err(std::io::Error::other).and_then(|json| std::fs::write(&index_path, json)) {
+                            fail(&format!("Failed to write {}", index_path.display()), err);
+                        }
+                        emit(&index, &format);
+                        exit_if_any_failed(&index["candidates"]);
+                        return;
+                    }
+                    // Workspace results land in its output directory.
+                    let output_file = match (&workspace, output_file) {
+                        // Staged, then moved into the layout once the IDs are known.
+                        (_, None) if output_dir.is_some() => output_dir
+                            .as_ref()
+                            .map(|dir| dir.join(format!(".generate-{}.zip", std::process::id())).display().to_string()),
+                        (Some(ws), None) if block && !cli.offline => {
+                            let dir = ws.output_dir();
+                            if let Err(err) = std::fs::create_dir_all(&dir) {
+                                fail(&format!("Failed to create {}", dir.display()), err);
+                            }
+                            let file = asset.clone().unwrap_or_else(|| gametorch::slug::slug(&prompt));
+                            Some(dir.join(format!("{}.zip", file)).display().to_string())
+                        }
+                        (_, file) => file,
+                    };
+                    if cli.offline {
+                        if retry_on_failure > 0 {
+                            invalid("--retry-on-failure cannot be queued with --offline");
+                        }
+                        let job = PendingJob {
+                            prompt,
+                            duration_seconds: duration,
+                            input_image,
+                            preprocess,
+                            compress: compress_input,
+                            model_id,
+                            model_name,
+                            webhook_url,
+                            seed,
+                            negative_prompt,
+                            skip_model_checks,
+                            queued_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+                        };
+                        match queue_generation(job, block || copy_result) {
+                            Ok(json) => emit(&json, &format),
+                            Err(err) => {
+                                fail("Failed to queue animation", err);
+                            }
+                        }
+                        return;
                     }
-                } else {
-                    match animations::list(&api_key, base_url).await {
+                    let cancel = cancel_on_ctrl_c();
+                    let submitted_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                    if let Some(dir) = &output_dir
+                        && let Err(err) = std::fs::create_dir_all(dir)
+                    {
+                        fail(&format!("Failed to create {}", dir.display()), err);
+                    }
+                    match animations::generate_with_progress(&api_key, base_url, &request, block, output_file.as_deref(), silent, &(), &cancel).await {
                         Ok(mut json) => {
+                            if let Some(dir) = &output_dir {
+                                let metadata = serde_json::json!({
+                                    "prompt": prompt,
+                                    "negative_prompt": negative_prompt,
+                                    "model": model_name.clone().or_else(|| model_id.map(|id| id.to_string())),
+                                    "duration_seconds": duration,
+                                    "seed": json.get("seed").and_then(|v| v.as_u64()).or(seed),
+                                    "input_image": input_image,
+                                    "submitted_at": submitted_at,
+                                    "completed_at": SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+                                });
+                                let name = asset.clone().unwrap_or_else(|| gametorch::slug::slug(&prompt));
+                                if let Err(err) = lay_out_result(dir, &mut json, metadata, &name, &export) {
+                                    fail(&format!("Failed to write the result into {}", dir.display()), err);
+                                }
+                            }
+                            if let Some(id) = json.get("animation_id").and_then(|v| v.as_i64()) {
+                                let mut job = Job::submitted(id, "generate");
+                                job.prompt = Some(prompt.clone());
+                                job.slug = Some(gametorch::slug::slug(&prompt));
+                                job.model = model_name.clone().or_else(|| model_id.map(|id| id.to_string()));
+                                job.duration_seconds = Some(duration);
+                                job.input_image = input_image.clone();
+                                job.negative_prompt = negative_prompt.clone();
+                                job.seed = json.get("seed").and_then(|v| v.as_u64()).or(seed);
+                                if block {
+                                    job = job.complete(
+                                        json.get("result_id").and_then(|v| v.as_i64()),
+                                        json.get("zip_path")
+                                            .and_then(|v| v.as_str())
+                                            .filter(|p| *p != animations::STDIO_PATH)
+                                            .map(str::to_string),
+                                    );
+                                }
+                                if let (Some(ws), Some(zip_path)) = (&workspace, &job.zip_path) {
+                                    let name = asset.clone().unwrap_or_else(|| gametorch::slug::slug(&prompt));
+                                    record_asset(ws, &name, id, job.result_id, &prompt, Path::new(zip_path));
+                                    json["asset"] = serde_json::json!(name);
+                                }
+                                #[cfg(feature = "index")]
+                                update_index(|index| {
+                                    index.upsert_animations(&serde_json::json!({ "id": id, "prompt": prompt }))?;
+                                    match &job.zip_path {
+                                        Some(path) => index.record_file(path, id, job.result_id),
+                                        None => Ok(()),
+                                    }
+                                });
+                                record_job(&job);
+                            }
+                            if copy_result {
+                                let zip_path = json.get("zip_path").and_then(|v| v.as_str()).unwrap_or_default();
+                                if zip_path == animations::STDIO_PATH {
+                                    eprintln!("Warning: --copy-result ignored; the ZIP was written to stdout");
+                                } else if let Err(err) = gametorch::clipboard::copy_first_frame(zip_path) {
+                                    fail("Failed to copy the result to the clipboard", err);
+                                }
+                            }
+                            // The ZIP went to stdout; don't append JSON to it.
+                            if block && output_file.as_deref() == Some(animations::STDIO_PATH) {
+                                return;
+                            }
                             if !cli.porcelain {
-                                replace_status_recursive(&mut json);
+                                animations::humanize_statuses(&mut json);
                             }
-                            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+                            emit(&json, &format);
                         }
                         Err(err) => {
-                            eprintln!("Failed to list animations: {}", err);
-                            std::process::exit(1);
+                            if let Some(Cancelled { animation_id: Some(id) }) = err.downcast_ref::<Cancelled>()
+                                && let Ok(id) = id.parse::<i64>()
+                            {
+                                let mut job = Job::submitted(id, "generate");
+                                job.prompt = Some(prompt.clone());
+                                job.slug = Some(gametorch::slug::slug(&prompt));
+                                job.model = model_name.clone().or_else(|| model_id.map(|id| id.to_string()));
+                                job.duration_seconds = Some(duration);
+                                job.input_image = input_image.clone();
+                                record_job(&job);
+                                let interrupted = Interrupted {
+                                    animation_id: id,
+                                    prompt,
+                                    // Resume may run from another directory.
+                                    output_file: output_dir
+                                        .as_ref()
+                                        .map(|dir| dir.join(format!("{}.zip", id)).display().to_string())
+                                        .or(output_file)
+                                        .filter(|f| f != animations::STDIO_PATH)
+                                        .map(|f| std::path::absolute(&f).map(|p| p.display().to_string()).unwrap_or(f)),
+                                    asset,
+                                    interrupted_at: job.submitted_at,
+                                };
+                                eprintln!("Interrupted; animation {} is still rendering.", id);
+                                match ResumeFile::open_default().and_then(|file| file.push(interrupted)) {
+                                    Ok(()) => eprintln!("Continue with: gametorch resume"),
+                                    Err(err) => {
+                                        eprintln!("Warning: failed to write the resume file: {}", err);
+                                        eprintln!("Continue with: gametorch animations wait {}", id);
+                                    }
+                                }
+                                exit(resume::EXIT_INTERRUPTED);
+                            }
+                            fail("Failed to generate animation", err);
                         }
                     }
-                    // Apply human-readable status mapping for list as well
-                    if !cli.porcelain {
-                        // After successful listing above, json is already printed
-                        // We handled inside Ok branch before printing.
+                }
+                AnimationCommands::Open { id, crop, print } => {
+                    let url = if crop {
+                        animations::crop_url(base_url, &id)
+                    } else {
+                        animations::web_url(base_url, &id)
+                    };
+                    if print {
+                        println!("{}", url);
+                    } else if let Err(err) = open::that_detached(&url) {
+                        fail(&format!("Failed to open a browser; open {} instead", url), err);
+                    } else {
+                        eprintln!("Opened {}", url);
                     }
                 }
-            }
-            AnimationCommands::Generate { prompt, block, output_file, input_image, model_id, model_name, silent, duration } => {
-                match animations::generate(&api_key, base_url, &prompt, duration, block, output_file.as_deref(), input_image.as_deref(), model_id, model_name.as_deref(), silent).await {
-                    Ok(mut json) => {
-                        if !cli.porcelain {
-                            replace_status_recursive(&mut json);
+                AnimationCommands::Crop { animation_result_id } => {
+                    match animation_result_id {
+                        Some(id) => {
+                            println!(
+                                "Open this page in your browser: {} (or run `gametorch animations open --crop {}`)",
+                                animations::crop_url(base_url, &id),
+                                id
+                            );
+                        }
+                        None => {
+                            println!(
+                                "Cropping is only available through the GameTorch web UI.\n");
+                            println!(
+                                "1. Open this link in your browser: {}/sprite-animator.\n2. Select the animation that contains the desired result.\n3. Choose the specific animation result and click \"Crop & Trim\".\n",
+                                base_url
+                            );
                         }
-                        println!("{}", serde_json::to_string_pretty(&json).unwrap());
                     }
-                    Err(err) => {
-                        eprintln!("Failed to generate animation: {}", err);
-                        std::process::exit(1);
+                }
+                AnimationCommands::Preview { result, play, loops, width, protocol } => {
+                    let sequence = if Path::new(&result).exists() {
+                        Sequence::load(&result)
+                    } else if result.parse::<i64>().is_ok() {
+                        let api_key = require_api_key();
+                        if play {
+                            match animations::download_zip(&api_key, base_url, &result, true).await {
+                                Ok(zip) => Sequence::from_zip_bytes(&zip),
+                                Err(err) => Err(err),
+                            }
+                        } else {
+                            // One frame is all that is shown.
+                            animations::thumbnail(&api_key, base_url, &result)
+                                .await
+                                .and_then(|png| Ok(image::load_from_memory_with_format(&png, image::ImageFormat::Png)?.into_rgba8()))
+                                .and_then(|frame| Sequence::from_images(vec![frame], gametorch::frames::DEFAULT_FPS))
+                        }
+                    } else {
+                        Err(format!("{} is neither a file nor a result ID", result).into())
+                    };
+                    let shown = sequence.and_then(|sequence| preview::show(&sequence, protocol, width, play, loops));
+                    if let Err(err) = shown {
+                        fail(&format!("Failed to preview {}", result), err);
                     }
                 }
-            }
-            AnimationCommands::Crop { animation_result_id } => {
-                match animation_result_id {
-                    Some(id) => {
-                        println!(
-                            "Open this page in your browser: https://gametorch.app/sprite-animator/crop-and-trim/{}",
-                            id
-                        );
+                AnimationCommands::History { animation_id } => {
+                    match animations::get(&api_key, base_url, &animation_id.to_string()).await {
+                        Ok(results) => {
+                            let job = History::open_default()
+                                .and_then(|history| history.job(animation_id))
+                                .ok()
+                                .flatten();
+                            let timeline = timeline::timeline(animation_id, &results, job.as_ref());
+                            emit(&serde_json::json!(timeline), &format);
+                        }
+                        Err(err) => {
+                            fail("Failed to fetch animation results", err);
+                        }
                     }
-                    None => {
-                        println!(
-                            "Cropping is only available through the GameTorch web UI.\n");
-                        println!(
-                            "1. Open this link in your browser: https://gametorch.app/sprite-animator.\n2. Select the animation that contains the desired result.\n3. Choose the specific animation result and click \"Crop & Trim\".\n",
-                        );
+                }
+                AnimationCommands::DownloadAll { animation_id, dir, silent } => {
+                    let dir = dir.unwrap_or_else(|| PathBuf::from(format!("animation_{}", animation_id)));
+                    match download_all(&api_key, base_url, animation_id, &dir, silent).await {
+                        Ok(json) => {
+                            emit(&json, &format);
+                            if json["failed"].as_array().is_some_and(|failed| !failed.is_empty()) {
+                                exit(1);
+                            }
+                        }
+                        Err(err) => {
+                            fail("Failed to download results", err);
+                        }
                     }
                 }
-            }
-            AnimationCommands::Regenerate { animation_id } => {
-                match animations::regenerate(&api_key, base_url, &animation_id).await {
-                    Ok(json) => {
-                        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+                AnimationCommands::Metrics { inputs } => {
+                    let mut reports = Vec::new();
+                    for input in &inputs {
+                        match Sequence::load(input) {
+                            Ok(sequence) => {
+                                let report = metrics::quality(&sequence);
+                                reports.push(serde_json::json!({ "input": input, "report": report }));
+                            }
+                            Err(err) => {
+                                fail(&format!("Failed to load {}", input), err);
+                            }
+                        }
                     }
-                    Err(err) => {
-                        eprintln!("Failed to regenerate animation: {}", err);
-                        std::process::exit(1);
+                    let json = if reports.len() == 1 {
+                        reports.remove(0)
+                    } else {
+                        serde_json::Value::Array(reports)
+                    };
+                    emit(&json, &format);
+                }
+                AnimationCommands::Bulk { filter, add_tags, remove_tags, alias, move_to, dry_run } => {
+                    let edit = BulkEdit { add_tags, remove_tags, alias, move_to };
+                    match bulk_organize(&api_key, base_url, &filter, &edit, dry_run).await {
+                        Ok(json) => {
+                            emit(&json, &format);
+                        }
+                        Err(err) => {
+                            fail("Failed to update animations", err);
+                        }
                     }
                 }
-            }
-        },
-    }
+                AnimationCommands::Rename { animation_id, name, description, tags, clear_tags } => {
+                    let metadata = animations::Metadata {
+                        name,
+                        description,
+                        tags: (clear_tags || !tags.is_empty()).then_some(tags),
+                    };
+                    if metadata.is_empty() {
+                        invalid("nothing to change; pass a name, --description, --tag or --clear-tags");
+                    }
+                    match animations::update_metadata(&api_key, base_url, &animation_id, &metadata).await {
+                        Ok(mut json) => {
+                            #[cfg(feature = "index")]
+                            update_index(|index| index.upsert_animations(&json));
+                            if !cli.porcelain {
+                                animations::humanize_statuses(&mut json);
+                            }
+                            emit(&json, &format);
+                        }
+                        Err(err) => {
+                            fail("Failed to update animation", err);
+                        }
+                    }
+                }
+                AnimationCommands::Delete { ids, status, yes } => {
+                    let ids = match status {
+                        Some(status) => {
+                            let options = animations::ListOptions { status: Some(status), ..Default::default() };
+                            let list = match animations::list(&api_key, base_url, &options).await {
+                                Ok(list) => list,
+                                Err(err) => {
+                                    fail("Failed to list animations", err);
+                                }
+                            };
+                            let ids: Vec<String> = list
+                                .as_array()
+                                .into_iter()
+                                .flatten()
+                                .filter_map(|item| item.get("id").map(catalog::id_string))
+                                .collect();
+                            if ids.is_empty() {
+                                emit(&serde_json::json!({ "deleted": [], "failed": [] }), &format);
+                                return;
+                            }
+                            if !yes && !confirm(&format!(
+                                    "Delete {} {} animation(s)?",
+                                    ids.len(),
+                                    animations::status_name(status).unwrap_or("matching")
+                                )) {
+                                eprintln!("Aborted; nothing was deleted.");
+                                exit(1);
+                            }
+                            ids
+                        }
+                        None => ids,
+                    };
+                    let json = delete_animations(&api_key, base_url, &ids).await;
+                    emit(&json, &format);
+                    if json["failed"].as_array().is_some_and(|failed| !failed.is_empty()) {
+                        exit(1);
+                    }
+                }
+                AnimationCommands::Wait { ids, output_dir, silent } => {
+                    let json = wait_and_download(&api_key, base_url, &ids, output_dir.as_deref().map(Path::new), 0, silent).await;
+                    emit(&json, &format);
+                    exit_if_any_failed(&json);
+                }
+                AnimationCommands::Inpaint { input_image, mask, prompt, duration, skip_model_checks, preprocess, model_id, model_name, output_dir, silent } => {
+                    let mut request = GenerateRequest::new(prompt.clone())
+                        .duration(duration)
+                        .input_image(input_image.clone())
+                        .preprocess(preprocess)
+                        .skip_model_checks(skip_model_checks);
+                    if let Some(model) = model_id.map(Model::Id).or_else(|| model_name.clone().map(Model::Name)) {
+                        request = request.model(model);
+                    }
+                    let json = match animations::inpaint(&api_key, base_url, &request, &mask, silent).await {
+                        Ok(json) => json,
+                        Err(err) => {
+                            fail("Failed to submit inpainting", err);
+                        }
+                    };
+                    let Some(id) = json.get("animation_id").and_then(|v| v.as_i64()) else {
+                        emit(&json, &format);
+                        return;
+                    };
+                    let mut job = Job::submitted(id, "inpaint");
+                    job.prompt = Some(prompt.clone());
+                    job.model = model_name.clone().or_else(|| model_id.map(|id| id.to_string()));
+                    job.duration_seconds = Some(duration);
+                    job.input_image = Some(input_image.clone());
+                    record_job(&job);
+                    let Some(dir) = output_dir else {
+                        emit(&json, &format);
+                        return;
+                    };
+                    let json = wait_and_download(&api_key, base_url, &[id.to_string()], Some(Path::new(&dir)), 0, silent).await;
+                    let item = json[0].clone();
+                    if let Some(path) = item.get("zip_path").and_then(|v| v.as_str()) {
+                        record_job(&job.complete(item.get("result_id").and_then(|v| v.as_i64()), Some(path.to_string())));
+                    }
+                    emit(&item, &format);
+                    exit_if_any_failed(&json);
+                }
+                AnimationCommands::Upscale { result_id, factor, output, on_device, filter, pixel_snap, silent } => {
+                    let output = output.unwrap_or_else(|| format!("upscaled_{}.zip", result_id));
+                    let options = process::ScaleOptions {
+                        factor: factor as f32,
+                        filter,
+                        pixel_snap,
+                        grid: None,
+                    };
+                    match upscale_result(&api_key, base_url, &result_id, factor, on_device, &options, &output, silent).await {
+                        Ok(json) => emit(&json, &format),
+                        Err(err) => {
+                            fail("Failed to upscale result", err);
+                        }
+                    }
+                }
+                AnimationCommands::Variations { result_id, count, strength, output_dir, silent } => {
+                    let json = match animations::variations(&api_key, base_url, &result_id, count, strength).await {
+                        Ok(json) => json,
+                        Err(err) => {
+                            fail("Failed to submit variations", err);
+                        }
+                    };
+                    let ids: Vec<i64> = json
+                        .get("animation_ids")
+                        .and_then(|ids| ids.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|id| id.as_i64())
+                        .collect();
+                    for &id in &ids {
+                        record_job(&Job::submitted(id, "variation"));
+                    }
+                    let Some(dir) = output_dir else {
+                        emit(&json, &format);
+                        return;
+                    };
+                    let ids: Vec<String> = ids.iter().map(i64::to_string).collect();
+                    let json = wait_and_download(&api_key, base_url, &ids, Some(Path::new(&dir)), 0, silent).await;
+                    emit(&json, &format);
+                    exit_if_any_failed(&json);
+                }
+                AnimationCommands::Compare { result_a, result_b, output_dir, silent } => {
+                    match compare_results(&api_key, base_url, &result_a, &result_b, output_dir, silent).await {
+                        Ok(json) => emit(&json, &format),
+                        Err(err) => {
+                            fail("Failed to compare results", err);
+                        }
+                    }
+                }
+                AnimationCommands::Regenerate { animation_id, all_failed, since, concurrency, diff, diff_dir, seed, silent } => {
+                    if all_failed {
+                        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                        let created_after = match since.map(|s| animations::parse_since(&s, now)).transpose() {
+                            Ok(after) => after,
+                            Err(err) => {
+                                invalid(err);
+                            }
+                        };
+                        match animations::regenerate_all_failed(&api_key, base_url, created_after, concurrency).await {
+                            Ok(resubmitted) => {
+                                for item in &resubmitted {
+                                    if let Some(id) = item.new_animation_id {
+                                        let mut job = Job::submitted(id, "regenerate");
+                                        job.source_animation_id = Some(item.animation_id);
+                                        job.prompt = item.prompt.clone();
+                                        record_job(&job);
+                                    }
+                                }
+                                let rejected = resubmitted.iter().filter(|item| item.error.is_some()).count();
+                                if !silent {
+                                    eprintln!(
+                                        "Regenerated {} of {} failed animations.",
+                                        resubmitted.len() - rejected,
+                                        resubmitted.len()
+                                    );
+                                }
+                                emit(&serde_json::json!(resubmitted), &list_format);
+                                if rejected > 0 {
+                                    exit(1);
+                                }
+                            }
+                            Err(err) => {
+                                fail("Failed to regenerate failed animations", err);
+                            }
+                        }
+                        return;
+                    }
+                    let animation_id = animation_id.expect("clap requires an animation_id without --all-failed");
+                    if diff {
+                        match regenerate_diff(&api_key, base_url, &animation_id, diff_dir, seed, silent).await {
+                            Ok(json) => {
+                                emit(&json, &format);
+                            }
+                            Err(err) => {
+                                fail("Failed to regenerate animation", err);
+                            }
+                        }
+                        return;
+                    }
+                    match animations::regenerate(&api_key, base_url, &animation_id, seed).await {
+                        Ok(json) => {
+                            if let Some(id) = json.get("animation_id").and_then(|v| v.as_i64()) {
+                                let mut job = Job::submitted(id, "regenerate");
+                                job.source_animation_id = animation_id.parse().ok();
+                                job.seed = json.get("seed").and_then(|v| v.as_u64()).or(seed);
+                                record_job(&job);
+                            }
+                            emit(&json, &format);
+                        }
+                        Err(err) => {
+                            fail("Failed to regenerate animation", err);
+                        }
+                    }
+                }
+            }
+        }
+        Commands::Process { backend, action } => {
+            let output = action.output();
+            let input = action.input().to_string();
+            let mut sequence = match Sequence::load(&input) {
+                Ok(sequence) => sequence,
+                Err(err) => {
+                    fail("Failed to load frames", err);
+                }
+            };
+            let result = match action {
+                ProcessCommands::Trim { margin, .. } => {
+                    process::trim(&mut sequence, margin).map(|report| serde_json::json!({ "trim": report }))
+                }
+                ProcessCommands::RemoveBg { color, tolerance, softness, .. } => {
+                    let options = process::BackgroundOptions { color, tolerance, softness, backend };
+                    process::remove_background(&mut sequence, &options)
+                        .map(|report| serde_json::json!({ "remove_bg": report }))
+                }
+                ProcessCommands::Palettize { colors, method, palette, .. } => {
+                    let palette = match (palette, colors) {
+                        (Some(path), _) => process::Palette::load(&path),
+                        (None, Some(colors)) => process::Palette::quantize(&sequence, colors, method),
+                        (None, None) => match workspace.as_ref().and_then(Workspace::palette) {
+                            Some(path) => process::Palette::load(&path),
+                            None => Err("pass --colors or --palette (or set a palette in gametorch.toml)".into()),
+                        },
+                    };
+                    palette.map(|palette| serde_json::json!({ "palettize": process::palettize(&mut sequence, &palette) }))
+                }
+                ProcessCommands::Scale { factor, filter, pixel_snap, grid, .. } => {
+                    let options = process::ScaleOptions { factor, filter, pixel_snap, grid };
+                    process::scale(&mut sequence, &options).map(|report| serde_json::json!({ "scale": report }))
+                }
+                ProcessCommands::Dedupe { min_psnr, fps, .. } => {
+                    let options = process::DedupeOptions { min_psnr, fps };
+                    process::dedupe(&mut sequence, &options).map(|report| serde_json::json!({ "dedupe": report }))
+                }
+                ProcessCommands::Loop { mode, frames, .. } => process::make_loop(&mut sequence, mode, frames)
+                    .map(|report| serde_json::json!({ "loop": report })),
+                ProcessCommands::Markers { file, .. } => markers::load(&file)
+                    .and_then(|markers| sequence.set_markers(markers))
+                    .map(|()| serde_json::json!({ "markers": sequence.markers })),
+                ProcessCommands::ContactSheet { sheet, columns, padding, no_labels, onion_skin, onion_frames, .. } => {
+                    let options = gametorch::sheet::ContactSheetOptions { columns, padding, labels: !no_labels };
+                    let (image, layout) = gametorch::sheet::contact_sheet(&sequence, &options);
+                    image
+                        .save(&sheet)
+                        .map_err(Into::into)
+                        .and_then(|()| match &onion_skin {
+                            Some(path) => gametorch::sheet::onion_skin(&sequence, onion_frames).save(path).map_err(Into::into),
+                            None => Ok(()),
+                        })
+                        .map(|()| {
+                            serde_json::json!({
+                                "contact_sheet": {
+                                    "path": sheet,
+                                    "width": layout.width,
+                                    "height": layout.height,
+                                    "columns": layout.columns,
+                                    "rows": layout.rows,
+                                },
+                                "onion_skin": onion_skin,
+                            })
+                        })
+                }
+                ProcessCommands::Run { pipeline, name, .. } => {
+                    let name = name.unwrap_or_else(|| default_export_name(&input));
+                    Pipeline::load(&pipeline)
+                        .map(|pipeline| match backend {
+                            process::Backend::Auto => pipeline,
+                            backend => pipeline.with_backend(backend),
+                        })
+                        .and_then(|pipeline| pipeline.run(&mut sequence, &name))
+                        .map(|steps| serde_json::json!({ "pipeline": pipeline, "steps": steps }))
+                }
+            };
+            match result.and_then(|mut json| {
+                if let Some(output) = &output {
+                    sequence.save(output)?;
+                }
+                json["output"] = serde_json::json!(output);
+                json["frames"] = serde_json::json!(sequence.len());
+                Ok(json)
+            }) {
+                Ok(json) => {
+                    emit(&json, &format);
+                }
+                Err(err) => {
+                    fail("Failed to process frames", err);
+                }
+            }
+        }
+        Commands::History { limit } => {
+            match History::open_default().and_then(|history| history.prompts()) {
+                Ok(jobs) => {
+                    let json: Vec<serde_json::Value> = jobs
+                        .iter()
+                        .take(limit)
+                        .enumerate()
+                        .map(|(i, job)| {
+                            serde_json::json!({
+                                "n": i + 1,
+                                "animation_id": job.animation_id,
+                                "status": job.status,
+                                "prompt": job.prompt,
+                                "negative_prompt": job.negative_prompt,
+                                "model": job.model,
+                                "duration_seconds": job.duration_seconds,
+                                "input_image": job.input_image,
+                                "seed": job.seed,
+                                "submitted_at": job.submitted_at,
+                            })
+                        })
+                        .collect();
+                    emit(&serde_json::Value::Array(json), &list_format);
+                }
+                Err(err) => {
+                    fail("Failed to read history", err);
+                }
+            }
+        }
+        Commands::Init { dir, name, model, duration, palette, output_dir, target, force } => {
+            let config = WorkspaceConfig {
+                name,
+                model,
+                duration,
+                palette,
+                output_dir: Some(output_dir),
+                target: target.map(|t| t.as_str().to_string()),
+                lint: None,
+            };
+            match Workspace::init(&dir, config, force) {
+                Ok(workspace) => {
+                    let json = serde_json::json!({
+                        "workspace": workspace.path().display().to_string(),
+                        "output_dir": workspace.output_dir().display().to_string(),
+                    });
+                    emit(&json, &format);
+                }
+                Err(err) => {
+                    fail("Failed to initialize workspace", err);
+                }
+            }
+        }
+        Commands::Assets { action } => {
+            let Some(ws) = &workspace else {
+                invalid("not in a workspace (run `gametorch init` at the project root)");
+            };
+            let manifest = match Manifest::load(ws) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    fail("Failed to load the asset manifest", err);
+                }
+            };
+            match action {
+                AssetCommands::List => {
+                    let json = serde_json::to_value(&manifest.assets).unwrap_or_default();
+                    emit(&json, &format);
+                }
+                AssetCommands::Verify => match manifest.verify() {
+                    Ok(problems) => {
+                        emit(&serde_json::json!({ "ok": problems.is_empty(), "problems": problems }), &format);
+                        if !problems.is_empty() {
+                            exit(1);
+                        }
+                    }
+                    Err(err) => {
+                        fail("Failed to verify assets", err);
+                    }
+                },
+                AssetCommands::Update { name, prompt, input_image, preprocess, duration } => {
+                    let api_key = require_api_key();
+                    let update = AssetUpdate { name, prompt, input_image, preprocess, duration };
+                    match update_asset(&api_key, base_url, ws, manifest, update).await {
+                        Ok(json) => emit(&json, &format),
+                        Err(err) => {
+                            fail("Failed to update asset", err);
+                        }
+                    }
+                }
+                AssetCommands::Fetch => {
+                    let api_key = require_api_key();
+                    let json = fetch_assets(&api_key, base_url, &manifest).await;
+                    emit(&json, &format);
+                    if json["failed"].as_array().is_some_and(|failed| !failed.is_empty()) {
+                        exit(1);
+                    }
+                }
+                AssetCommands::Check { names, regenerate, min_ssim, max_changed, diff_dir } => {
+                    if let Some(name) = names.iter().find(|name| !manifest.assets.contains_key(*name)) {
+                        invalid(format!("no asset named '{}' in {}", name, manifest.path().display()));
+                    }
+                    let api_key = require_api_key();
+                    let check = AssetCheck { names, regenerate, min_ssim, max_changed, diff_dir };
+                    let json = check_assets(&api_key, base_url, &manifest, &check).await;
+                    emit(&json, &format);
+                    if json["failed"].as_array().is_some_and(|failed| !failed.is_empty()) {
+                        exit(1);
+                    }
+                }
+            }
+        }
+        Commands::Flush => {
+            let api_key = require_api_key();
+            match flush_pending(&api_key, base_url).await {
+                Ok(json) => {
+                    emit(&json, &format);
+                    if json["failed"].as_array().is_some_and(|failed| !failed.is_empty()) {
+                        exit(1);
+                    }
+                }
+                Err(err) => {
+                    fail("Failed to flush pending jobs", err);
+                }
+            }
+        }
+        Commands::Estimate { model_id, model_name, duration, count, batch, pending } => {
+            let jobs: Result<Vec<pricing::Job>, Box<dyn std::error::Error + Send + Sync>> = if let Some(path) = batch {
+                std::fs::read_to_string(&path)
+                    .map_err(|e| format!("failed to read {}: {}", path.display(), e).into())
+                    .and_then(|text| pricing::parse_batch(&text))
+            } else if pending {
+                Queue::open_default().and_then(|queue| queue.jobs()).map(|jobs| {
+                    jobs.into_iter()
+                        .map(|job| pricing::Job::new(job.model_id, job.model_name, job.duration_seconds))
+                        .collect()
+                })
+            } else {
+                let (model_id, model_name) = match (&workspace, model_id, model_name) {
+                    (Some(ws), None, None) => ws.config.model(),
+                    (_, id, name) => (id, name),
+                };
+                let duration = duration
+                    .or(workspace.as_ref().and_then(|ws| ws.config.duration))
+                    .unwrap_or(5);
+                Ok(vec![pricing::Job {
+                    count,
+                    ..pricing::Job::new(model_id, model_name, duration)
+                }])
+            };
+            let jobs = match jobs {
+                Ok(jobs) => jobs,
+                Err(err) => {
+                    invalid(err);
+                }
+            };
+            match pricing::estimate(base_url, &jobs).await {
+                Ok(json) => emit(&json, &format),
+                Err(err) => {
+                    fail("Failed to estimate cost", err);
+                }
+            }
+        }
+        Commands::Resume { animation_id, silent } => {
+            let api_key = require_api_key();
+            match resume_generation(&api_key, base_url, animation_id, workspace.as_ref(), silent).await {
+                Ok(mut json) => {
+                    if !cli.porcelain {
+                        animations::humanize_statuses(&mut json);
+                    }
+                    emit(&json, &format);
+                }
+                Err(err) => {
+                    if err.is::<Cancelled>() {
+                        eprintln!("Interrupted; run `gametorch resume` again to continue.");
+                        exit(resume::EXIT_INTERRUPTED);
+                    }
+                    fail("Failed to resume", err);
+                }
+            }
+        }
+        Commands::Sync { output_dir, dry_run } => {
+            let api_key = require_api_key();
+            match sync_library(&api_key, base_url, Path::new(&output_dir), dry_run).await {
+                Ok(json) => {
+                    emit(&json, &format);
+                    if json["failed"].as_array().is_some_and(|failed| !failed.is_empty()) {
+                        exit(1);
+                    }
+                }
+                Err(err) => {
+                    fail("Failed to sync", err);
+                }
+            }
+        }
+        Commands::Ui => {
+            let api_key = require_api_key();
+            #[cfg(feature = "tui")]
+            if let Err(err) = gametorch::ui::run(&api_key, base_url).await {
+                fail("Failed to run the terminal UI", err);
+            }
+            #[cfg(not(feature = "tui"))]
+            {
+                let _ = api_key;
+                invalid("this build has no terminal UI (rebuild with --features tui)");
+            }
+        }
+        Commands::Serve { bind, .. } => {
+            let history = match History::open_default() {
+                Ok(history) => history,
+                Err(err) => {
+                    fail("Failed to open history", err);
+                }
+            };
+            eprintln!("Serving {} read-only on http://{}", history.path().display(), bind);
+            if let Err(err) = serve::serve_history(&bind, history).await {
+                fail("Failed to serve history", err);
+            }
+        }
+        Commands::Verify { target } => match verify::verify(&target, workspace.as_ref()) {
+            Ok(checks) => {
+                let ok = checks.iter().all(|check| check.ok);
+                emit(&serde_json::json!({ "ok": ok, "files": checks }), &format);
+                if !ok {
+                    exit(1);
+                }
+            }
+            Err(err) => {
+                fail("Failed to verify", err);
+            }
+        },
+        Commands::Images { action } => {
+            let api_key = require_api_key();
+            match action {
+                ImageCommands::Get { id } => match images::get(&api_key, base_url, &id).await {
+                    Ok(mut json) => {
+                        if !cli.porcelain {
+                            animations::humanize_statuses(&mut json);
+                        }
+                        emit(&json, &format);
+                    }
+                    Err(err) => {
+                        fail("Failed to get image", err);
+                    }
+                },
+                ImageCommands::Generate { prompt, block, output_file, input_image, preprocess, model_name, silent } => {
+                    match images::generate(&api_key, base_url, &prompt, block, output_file.as_deref(), input_image.as_deref(), preprocess, model_name.as_deref(), silent).await {
+                        // The PNG went to stdout; don't append JSON to it.
+                        Ok(_) if block && output_file.as_deref() == Some(animations::STDIO_PATH) => {}
+                        Ok(json) => emit(&json, &format),
+                        Err(err) => {
+                            fail("Failed to generate image", err);
+                        }
+                    }
+                }
+            }
+        }
+        Commands::Auth { action } => match action {
+            AuthCommands::Check => {
+                let api_key = require_api_key();
+                match auth::check(&api_key, base_url).await {
+                    Ok(status) => {
+                        let mut json = serde_json::json!(status);
+                        if let Some(name) = gametorch::client::shared().current_key() {
+                            json["key"] = serde_json::json!(name);
+                        }
+                        emit(&json, &format);
+                        if !status.valid {
+                            exit(ErrorKind::Auth.exit_code());
+                        }
+                    }
+                    Err(err) => {
+                        fail("Failed to check API key", err);
+                    }
+                }
+            }
+            AuthCommands::Use { name } => match auth::use_key(&name, &config.keys) {
+                Ok(path) => emit(&serde_json::json!({ "active": name, "path": path.display().to_string() }), &format),
+                Err(err) => {
+                    fail("Failed to switch API key", err);
+                }
+            },
+        },
+        Commands::Webhooks { action } => {
+            let api_key = require_api_key();
+            match action {
+                WebhookCommands::List => match webhooks::list(&api_key, base_url).await {
+                    Ok(json) => emit(&json, &list_format),
+                    Err(err) => {
+                        fail("Failed to list webhooks", err);
+                    }
+                },
+                WebhookCommands::Delete { ids } => {
+                    for id in &ids {
+                        if let Err(err) = webhooks::delete(&api_key, base_url, id).await {
+                            fail(&format!("Failed to delete webhook {}", id), err);
+                        }
+                    }
+                    emit(&serde_json::json!({ "deleted": ids }), &format);
+                }
+            }
+        }
+        Commands::Completions { shell } => {
+            let shells = clap_complete::env::Shells::builtins();
+            let completer = shells.completer(&shell).expect("shell names are validated by clap");
+            let bin = Cli::command().get_name().to_string();
+            if let Err(err) = completer.write_registration("COMPLETE", &bin, &bin, &bin, &mut std::io::stdout()) {
+                fail("Failed to write completions", err);
+            }
+        }
+        Commands::Queue { watch, interval } => {
+            use std::io::IsTerminal;
+            let api_key = require_api_key();
+            loop {
+                // Re-read each time, so jobs finishing meanwhile sharpen the estimates.
+                let jobs = History::open_default().and_then(|history| history.jobs()).unwrap_or_default();
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                match render_queue::generating(&api_key, base_url, &jobs, now).await {
+                    Ok(queued) => {
+                        if watch && std::io::stdout().is_terminal() {
+                            print!("\x1b[2J\x1b[H");
+                        }
+                        emit(&serde_json::json!(queued), &list_format);
+                    }
+                    Err(err) => {
+                        fail("Failed to list generating animations", err);
+                    }
+                }
+                if !watch {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(interval.max(1))).await;
+            }
+        }
+        Commands::Gc { dry_run, older_than, max_size } => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            let cutoff = match older_than.map(|s| animations::parse_since(&s, now)).transpose() {
+                Ok(cutoff) => cutoff.map(|unix| UNIX_EPOCH + std::time::Duration::from_secs(unix)),
+                Err(err) => {
+                    invalid(err);
+                }
+            };
+            let http_cache = HttpCache::open_default().and_then(|cache| cache.prune(cutoff, max_size, dry_run));
+            let http_cache = match http_cache {
+                Ok(pruned) => pruned,
+                Err(err) => {
+                    fail("Failed to prune the HTTP cache", err);
+                }
+            };
+            match Store::open_default().and_then(|store| store.gc(cutoff, max_size, dry_run)) {
+                Ok(store) => emit(
+                    &serde_json::json!({ "http_cache": http_cache, "store": store }),
+                    &format,
+                ),
+                Err(err) => {
+                    fail("Failed to clean the asset store", err);
+                }
+            }
+        }
+        Commands::Inspect { file } => match inspect::inspect(&file) {
+            Ok(inspection) => emit(&serde_json::json!(inspection), &format),
+            Err(err) => {
+                fail(&format!("Failed to inspect {}", file.display()), err);
+            }
+        },
+        Commands::Lint { inputs, grid, max_fringe, background, max_colors, allow_mixed_sizes } => {
+            let mut rules = workspace
+                .as_ref()
+                .and_then(|ws| ws.config.lint.clone())
+                .unwrap_or_default();
+            rules.grid = grid.or(rules.grid);
+            rules.max_fringe = max_fringe.or(rules.max_fringe);
+            rules.background = background.or(rules.background);
+            rules.max_colors = max_colors.or(rules.max_colors);
+            if allow_mixed_sizes {
+                rules.uniform_size = false;
+            }
+            if let Err(err) = rules.validate() {
+                invalid(err);
+            }
+            let mut results = Vec::new();
+            for input in &inputs {
+                let violations = match Sequence::load(input).and_then(|sequence| lint::lint(&sequence, &rules)) {
+                    Ok(violations) => violations,
+                    Err(err) => {
+                        fail(&format!("Failed to lint {}", input), err);
+                    }
+                };
+                results.push(serde_json::json!({
+                    "input": input,
+                    "ok": violations.is_empty(),
+                    "violations": violations,
+                }));
+            }
+            let ok = results.iter().all(|result| result["ok"] == true);
+            emit(&serde_json::json!({ "ok": ok, "rules": rules, "results": results }), &format);
+            if !ok {
+                exit(1);
+            }
+        }
+        Commands::Stats { weeks } => {
+            let logs = History::open_default()
+                .and_then(|history| history.jobs())
+                .and_then(|jobs| Ok((jobs, UsageLog::open_default()?.runs()?)));
+            match logs {
+                Ok((jobs, runs)) => {
+                    let prices = pricing::table(base_url).await;
+                    let mut json = serde_json::json!(usage::summarize(&jobs, &runs, &prices, weeks));
+                    json["enabled"] = serde_json::json!(config.stats.enabled);
+                    emit(&json, &format);
+                }
+                Err(err) => {
+                    fail("Failed to read usage stats", err);
+                }
+            }
+        }
+        Commands::Storage { paths, local_only } => {
+            let mut local = Vec::new();
+            for path in &paths {
+                match storage::local_usage(Path::new(path)) {
+                    Ok(usage) => local.push(usage),
+                    Err(err) => {
+                        fail(&format!("Failed to scan {}", path), err);
+                    }
+                }
+            }
+            let remote = if local_only {
+                None
+            } else {
+                let api_key = require_api_key();
+                match storage::remote_usage(&api_key, base_url).await {
+                    Ok(usage) => Some(usage),
+                    Err(err) => {
+                        fail("Failed to fetch remote usage", err);
+                    }
+                }
+            };
+            let json = serde_json::json!({
+                "remote": remote,
+                "local": local,
+                "local_total_bytes": local.iter().map(|u| u.total_bytes).sum::<u64>(),
+                "reclaimable_bytes": local.iter().map(|u| u.reclaimable_bytes).sum::<u64>(),
+            });
+            emit(&json, &format);
+        }
+        Commands::Export {
+            input,
+            target,
+            output_dir,
+            drop,
+            engine,
+            drop_root,
+            drop_dir,
+            drop_file,
+            name,
+            columns,
+            padding,
+            fps,
+            edit_list,
+            markers,
+            fix_frames,
+            fix_mode,
+            yy,
+            snippet,
+            profile,
+            no_profile,
+        } => {
+            let profile = if no_profile {
+                Ok(None)
+            } else if let Some(path) = profile {
+                Profile::load(&path).map(Some)
+            } else {
+                Profile::active()
+            };
+            // Profiles are validated on load, so their settings resolve below.
+            let profile = match profile {
+                Ok(profile) => profile.unwrap_or_default(),
+                Err(err) => {
+                    fail("Failed to load export profile", err);
+                }
+            };
+            let targets = match target {
+                Some(target) => vec![target],
+                None => profile.targets().expect("validated profile"),
+            };
+            let targets = match workspace.as_ref().and_then(|ws| ws.config.target().expect("validated workspace")) {
+                Some(target) if targets.is_empty() => vec![target],
+                _ => targets,
+            };
+            let output_dir = output_dir
+                .map(PathBuf::from)
+                .or_else(|| workspace.as_ref().map(Workspace::output_dir))
+                .unwrap_or_else(|| PathBuf::from("."));
+            let engine = engine.or(profile.engine().expect("validated profile"));
+            if drop.is_some() && engine.is_none() {
+                invalid("--drop needs --engine (or an engine in the export profile)");
+            }
+            if drop.is_none() && targets.is_empty() {
+                invalid("--target is required (or targets in the export profile or workspace)");
+            }
+
+            let mut sequence = match Sequence::load(&input) {
+                Ok(sequence) => sequence,
+                Err(err) => {
+                    fail("Failed to load frames", err);
+                }
+            };
+            if let Some(fps) = fps.or(profile.fps) {
+                sequence.set_fps(fps);
+            }
+            if let Some(path) = markers
+                && let Err(err) = markers::load(&path).and_then(|markers| sequence.set_markers(markers))
+            {
+                fail("Failed to load markers", err);
+            }
+            if let Some(path) = edit_list {
+                match process::EditList::load(&path).and_then(|list| list.apply(&sequence)) {
+                    Ok(edited) => sequence = edited,
+                    Err(err) => {
+                        fail("Failed to apply edit list", err);
+                    }
+                }
+            }
+            let name = name.unwrap_or_else(|| default_export_name(&input));
+            let asset_name = name.clone();
+            let dropped = drop.is_some();
+            let processed = match profile.pipeline().expect("validated profile") {
+                Some(pipeline) => match pipeline.run(&mut sequence, &name) {
+                    Ok(steps) => Some(steps),
+                    Err(err) => {
+                        fail("Failed to run the profile pipeline", err);
+                    }
+                },
+                None => None,
+            };
+            let fixed = fix_frames.then(|| process::fix_frames(&mut sequence, fix_mode));
+            let pack = PackOptions {
+                columns: columns.or(profile.pack.columns),
+                padding: padding.unwrap_or(profile.pack.padding),
+            };
+            let provenance = provenance_of(&input);
+
+            let result = if let (Some(project), Some(engine)) = (drop, engine) {
+                let mut preset = EnginePreset::for_engine(engine);
+                if let Some(root) = drop_root {
+                    preset.root = root;
+                }
+                if let Some(dir) = drop_dir {
+                    preset.dir = dir;
+                }
+                if let Some(file) = drop_file {
+                    preset.file = file;
+                }
+                engine_drop::drop_into(Path::new(&project), &preset, &name, &sequence, &pack).map(|dropped| {
+                    let scripts: Vec<_> = dropped.installed_scripts.iter().map(|p| p.display().to_string()).collect();
+                    serde_json::json!({
+                        "engine": engine.as_str(),
+                        "asset": dropped.asset,
+                        "manifest": dropped.manifest.display().to_string(),
+                        "installed_scripts": scripts,
+                    })
+                })
+            } else if snippet {
+                if targets != [export::Target::Bevy] {
+                    invalid("--snippet is only available with --target bevy");
+                }
+                let options = ExportOptions { name, pack, provenance, ..Default::default() };
+                match export::bevy::sprite_sheet(&sequence, &output_dir, &options) {
+                    Ok((_, atlas)) => {
+                        print!("{}", atlas.snippet());
+                        return;
+                    }
+                    Err(err) => Err(err),
+                }
+            } else {
+                let options = ExportOptions {
+                    name,
+                    pack,
+                    gamemaker_yy: yy,
+                    provenance,
+                };
+                targets
+                    .iter()
+                    .map(|&target| {
+                        export::export(&sequence, target, &output_dir, &options).map(|exported| {
+                            let files: Vec<_> = exported.files.iter().map(|p| p.display().to_string()).collect();
+                            serde_json::json!({
+                                "target": target.as_str(),
+                                "files": files,
+                                "frame_width": exported.layout.frame_width,
+                                "frame_height": exported.layout.frame_height,
+                                "columns": exported.layout.columns,
+                                "rows": exported.layout.rows,
+                            })
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|mut exports| {
+                        if exports.len() == 1 {
+                            exports.remove(0)
+                        } else {
+                            serde_json::json!({ "exports": exports })
+                        }
+                    })
+            };
+            match result {
+                Ok(mut json) => {
+                    if let Some(name) = &profile.name {
+                        json["profile"] = serde_json::json!(name);
+                    }
+                    if let Some(steps) = processed {
+                        json["pipeline"] = serde_json::json!(steps);
+                    }
+                    if let Some(fixed) = fixed {
+                        json["fixed_frames"] = serde_json::json!(fixed);
+                    }
+                    if let Some(ws) = &workspace
+                        && !dropped
+                        && let Some(asset) = record_exports(ws, Path::new(&input), &asset_name, &json)
+                    {
+                        json["asset"] = serde_json::json!(asset);
+                    }
+                    emit(&json, &format);
+                }
+                Err(err) => {
+                    fail("Failed to export", err);
+                }
+            }
+        }
+        Commands::Watch {
+            pipeline,
+            output_dir,
+            interval,
+            filter,
+            all,
+            once,
+        } => {
+            let api_key = require_api_key();
+            let pipeline = match Pipeline::load(&pipeline) {
+                Ok(pipeline) => pipeline,
+                Err(err) => {
+                    fail("Failed to load pipeline", err);
+                }
+            };
+            let options = WatchOptions {
+                output_dir: PathBuf::from(output_dir),
+                interval,
+                filter,
+                all,
+                once,
+                // One line per result suits a long-running stream.
+                output: match Output::new(cli.format.unwrap_or(OutputFormat::Ndjson), cli.query.as_deref()) {
+                    Ok(output) => output,
+                    Err(err) => {
+                        invalid(err);
+                    }
+                },
+            };
+            if let Err(err) = watch_results(&api_key, base_url, &pipeline, &options).await {
+                fail("Watch failed", err);
+            }
+        }
+        Commands::WatchDir {
+            dir,
+            prompt_from_filename,
+            prompt,
+            duration,
+            preprocess,
+            model_id,
+            model_name,
+            existing,
+        } => {
+            let api_key = require_api_key();
+            let (model_id, model_name) = match (&workspace, model_id, model_name) {
+                (Some(ws), None, None) => ws.config.model(),
+                (_, id, name) => (id, name),
+            };
+            let options = WatchDirOptions {
+                dir: PathBuf::from(dir),
+                prompt_from_filename,
+                prompt,
+                duration: duration
+                    .or(workspace.as_ref().and_then(|ws| ws.config.duration))
+                    .unwrap_or(5),
+                preprocess,
+                model_id,
+                model_name,
+                existing,
+                // One line per submission suits a long-running stream.
+                output: match Output::new(cli.format.unwrap_or(OutputFormat::Ndjson), cli.query.as_deref()) {
+                    Ok(output) => output,
+                    Err(err) => {
+                        invalid(err);
+                    }
+                },
+            };
+            if let Err(err) = watch_directory(&api_key, base_url, &options).await {
+                fail("Watch failed", err);
+            }
+        }
+        Commands::Listen { pipeline, output_dir, bind, secret } => {
+            let api_key = require_api_key();
+            let Some(secret) = secret.or_else(|| env::var(listen::SECRET_ENV).ok()).filter(|s| !s.is_empty()) else {
+                invalid(format!("set the webhook secret with --secret or {}", listen::SECRET_ENV));
+            };
+            let pipeline = match Pipeline::load(&pipeline) {
+                Ok(pipeline) => pipeline,
+                Err(err) => {
+                    fail("Failed to load pipeline", err);
+                }
+            };
+            let output = match Output::new(cli.format.unwrap_or(OutputFormat::Ndjson), cli.query.as_deref()) {
+                Ok(output) => output,
+                Err(err) => {
+                    invalid(err);
+                }
+            };
+            let output_dir = PathBuf::from(output_dir);
+            if let Err(err) = std::fs::create_dir_all(&output_dir) {
+                fail(&format!("Failed to create {}", output_dir.display()), err);
+            }
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            eprintln!("Listening for completion webhooks on {}", bind);
+            let server = tokio::spawn(async move { listen::listen(&bind, secret.into_bytes(), tx).await });
+            while let Some(completion) = rx.recv().await {
+                match process_completion(&api_key, base_url, &pipeline, &output_dir, &completion).await {
+                    Ok(Some(json)) => emit(&json, &output),
+                    Ok(None) => {}
+                    Err(err) => eprintln!("Failed to process animation {}: {}", completion.animation_id, err),
+                }
+            }
+            match server.await {
+                Ok(Err(err)) => eprintln!("Listener failed: {}", err),
+                Err(err) => eprintln!("Listener failed: {}", err),
+                Ok(Ok(())) => {}
+            }
+            exit(1);
+        }
+        Commands::ExportProfile { action } => {
+            let result = match action {
+                ProfileCommands::Save {
+                    file,
+                    name,
+                    description,
+                    targets,
+                    engine,
+                    columns,
+                    padding,
+                    fps,
+                    pipeline,
+                } => {
+                    let mut profile = Profile {
+                        name,
+                        description,
+                        targets: targets.iter().map(|t| t.as_str().to_string()).collect(),
+                        engine: engine.map(|e| e.as_str().to_string()),
+                        fps,
+                        pack: profile::PackSection { columns, padding },
+                        ..Default::default()
+                    };
+                    pipeline
+                        .map_or(Ok(()), |path| profile.set_pipeline_file(&path))
+                        .and_then(|()| profile.save(&file))
+                        .map(|()| serde_json::json!({ "saved": file, "profile": profile }))
+                }
+                ProfileCommands::Load { file } => Profile::load(&file).and_then(|profile| {
+                    let path = profile.activate()?;
+                    Ok(serde_json::json!({ "active": path.display().to_string(), "profile": profile }))
+                }),
+                ProfileCommands::Show => Profile::active().map(|profile| serde_json::json!({ "profile": profile })),
+            };
+            match result {
+                Ok(json) => emit(&json, &format),
+                Err(err) => {
+                    fail("Export profile error", err);
+                }
+            }
+        }
+    }
+}
+
+/// Upscale `result_id` into `output`: on the backend, waiting for the new
+/// result, or locally with `options` when the backend cannot (or
+/// `on_device` says not to ask it).
+#[allow(clippy::too_many_arguments)]
+async fn upscale_result(
+    api_key: &str,
+    base_url: &str,
+    result_id: &str,
+    factor: u32,
+    on_device: bool,
+    options: &process::ScaleOptions,
+    output: &str,
+    silent: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let upscaled = if on_device {
+        None
+    } else {
+        animations::upscale(api_key, base_url, result_id, factor).await?
+    };
+    if let Some(upscaled) = upscaled {
+        let new_id = upscaled
+            .get("animation_id")
+            .and_then(|v| v.as_i64())
+            .ok_or("animation_id missing from response")?;
+        if !silent {
+            println!("Upscaling started (ID: {}).", new_id);
+        }
+        let job = Job::submitted(new_id, "upscale");
+        record_job(&job);
+        let results = animations::wait(api_key, base_url, &new_id.to_string(), silent).await?;
+        let new_result_id = animations::first_result_id(&results).ok_or("result id missing")?;
+        let zip = animations::download_zip(api_key, base_url, &new_result_id.to_string(), silent).await?;
+        Sequence::from_zip_bytes(&zip)?.save(output)?;
+        record_job(&job.complete(Some(new_result_id), Some(output.to_string())));
+        return Ok(serde_json::json!({
+            "source": "backend",
+            "animation_id": new_id,
+            "result_id": new_result_id,
+            "output": output,
+        }));
+    }
+
+    if !on_device && !silent {
+        println!("The backend does not offer upscaling; scaling locally...");
+    }
+    let zip = animations::download_zip(api_key, base_url, result_id, silent).await?;
+    let mut sequence = Sequence::from_zip_bytes(&zip)?;
+    let report = process::scale(&mut sequence, options)?;
+    sequence.save(output)?;
+    Ok(serde_json::json!({
+        "source": "local",
+        "scale": report,
+        "output": output,
+    }))
+}
+
+/// Regenerate `old_id`, wait for the new result and compare it with the old one.
+///
+/// Writes both ZIPs, a two-row comparison sheet (old on top) and the per-frame
+/// metrics into the diff directory, and returns a JSON summary.
+async fn regenerate_diff(
+    api_key: &str,
+    base_url: &str,
+    old_id: &str,
+    diff_dir: Option<String>,
+    seed: Option<u64>,
+    silent: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    // Check the original before spending credits on a regeneration.
+    let old_results = animations::get(api_key, base_url, old_id).await?;
+    if animations::first_status(&old_results) != Some(2) {
+        return Err("the original animation has no completed result to compare against".into());
+    }
+    let old_result_id = animations::first_result_id(&old_results).ok_or("result id missing")?;
+
+    let regen = animations::regenerate(api_key, base_url, old_id, seed).await?;
+    let new_id = regen
+        .get("animation_id")
+        .and_then(|v| v.as_i64())
+        .ok_or("animation_id missing from response")?;
+    if !silent {
+        println!("Regeneration started (ID: {}).", new_id);
+    }
+
+    let mut job = Job::submitted(new_id, "regenerate");
+    job.source_animation_id = old_id.parse().ok();
+    job.seed = regen.get("seed").and_then(|v| v.as_u64()).or(seed);
+    record_job(&job);
+
+    let new_results = animations::wait(api_key, base_url, &new_id.to_string(), silent).await?;
+    let new_result_id = animations::first_result_id(&new_results).ok_or("result id missing")?;
+
+    if !silent {
+        println!("Render complete, downloading both ZIPs...");
+    }
+    let old_zip = animations::download_zip(api_key, base_url, &old_result_id.to_string(), silent).await?;
+    let new_zip = animations::download_zip(api_key, base_url, &new_result_id.to_string(), silent).await?;
+
+    let dir = PathBuf::from(diff_dir.unwrap_or_else(|| format!("regenerate_{}_{}", old_id, new_id)));
+    std::fs::create_dir_all(&dir)?;
+    store::save(&dir.join("old.zip"), &old_zip)?;
+    store::save(&dir.join("new.zip"), &new_zip)?;
+    record_job(&job.complete(Some(new_result_id), Some(dir.join("new.zip").display().to_string())));
+
+    let old = Sequence::from_zip_bytes(&old_zip)?;
+    let new = Sequence::from_zip_bytes(&new_zip)?;
+    let comparison = metrics::compare(&old, &new);
+
+    let sheet_path = dir.join("comparison.png");
+    metrics::comparison_sheet(&old, &new).save(&sheet_path)?;
+    let metrics_path = dir.join("metrics.json");
+    std::fs::write(&metrics_path, serde_json::to_string_pretty(&comparison)?)?;
+
+    Ok(serde_json::json!({
+        "animation_id": new_id,
+        "old": { "animation_id": old_id.parse::<i64>().ok(), "result_id": old_result_id },
+        "new": { "animation_id": new_id, "result_id": new_result_id },
+        "comparison_sheet": sheet_path.display().to_string(),
+        "metrics_file": metrics_path.display().to_string(),
+        "mean_ssim": comparison.mean_ssim,
+        "mean_psnr": comparison.mean_psnr,
+    }))
+}
+
+/// Downloads two results and writes a side-by-side GIF, a two-row sheet and
+/// the per-frame metrics into the output directory, returning a JSON summary.
+/// The GIF carries the first result's provenance.
+async fn compare_results(
+    api_key: &str,
+    base_url: &str,
+    result_a: &str,
+    result_b: &str,
+    output_dir: Option<String>,
+    silent: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let zip_a = animations::download_zip(api_key, base_url, result_a, silent).await?;
+    let zip_b = animations::download_zip(api_key, base_url, result_b, silent).await?;
+    let a = Sequence::from_zip_bytes(&zip_a)?;
+    let b = Sequence::from_zip_bytes(&zip_b)?;
+    let comparison = metrics::compare(&a, &b);
+
+    let dir = PathBuf::from(output_dir.unwrap_or_else(|| format!("compare_{}_{}", result_a, result_b)));
+    std::fs::create_dir_all(&dir)?;
+    let gif_path = dir.join("comparison.gif");
+    let gif = metrics::side_by_side(&a, &b).to_gif_bytes()?;
+    let gif = match result_provenance(result_a) {
+        Some(provenance) => gametorch::provenance::embed_gif(&gif, &provenance)?,
+        None => gif,
+    };
+    std::fs::write(&gif_path, gif)?;
+    let sheet_path = dir.join("comparison.png");
+    metrics::comparison_sheet(&a, &b).save(&sheet_path)?;
+    let metrics_path = dir.join("metrics.json");
+    std::fs::write(&metrics_path, serde_json::to_string_pretty(&comparison)?)?;
+
+    Ok(serde_json::json!({
+        "a": { "result_id": result_a, "frames": comparison.frames_a },
+        "b": { "result_id": result_b, "frames": comparison.frames_b },
+        "comparison_gif": gif_path.display().to_string(),
+        "comparison_sheet": sheet_path.display().to_string(),
+        "metrics_file": metrics_path.display().to_string(),
+        "mean_ssim": comparison.mean_ssim,
+        "mean_psnr": comparison.mean_psnr,
+        "mean_changed": comparison.mean_changed,
+    }))
+}
+
+/// Catalog changes requested by `animations bulk`.
+struct BulkEdit {
+    add_tags: Vec<String>,
+    remove_tags: Vec<String>,
+    alias: Option<String>,
+    move_to: Option<String>,
+}
+
+/// Apply `edit` to every animation matching `filter` as one catalog batch.
+async fn bulk_organize(
+    api_key: &str,
+    base_url: &str,
+    filter: &Filter,
+    edit: &BulkEdit,
+    dry_run: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    if edit.add_tags.is_empty() && edit.remove_tags.is_empty() && edit.alias.is_none() && edit.move_to.is_none() {
+        return Err("nothing to do: pass --add-tag, --remove-tag, --alias or --move-to".into());
+    }
+    let workspace = match edit.move_to.as_deref() {
+        Some(target) => match target.split_once(':') {
+            Some(("workspace", name)) if !name.is_empty() => Some(name.to_string()),
+            _ => return Err(format!("--move-to expects workspace:NAME, got '{}'", target).into()),
+        },
+        None => None,
+    };
+
+    let catalog = Catalog::load_default()?;
+    let list = animations::list(api_key, base_url, &animations::ListOptions::default()).await?;
+    let items = list.as_array().cloned().unwrap_or_else(|| vec![list.clone()]);
+    let mut batch = catalog.batch();
+    let mut matched = 0;
+    for item in items.iter().map(|item| catalog.annotate(item)) {
+        let Some(id) = item.get("id").map(catalog::id_string) else {
+            continue;
+        };
+        if !filter.matches(&item) {
+            continue;
+        }
+        matched += 1;
+        let slug = item
+            .get("prompt")
+            .and_then(|p| p.as_str())
+            .map(gametorch::slug::slug)
+            .unwrap_or_else(|| id.clone());
+        batch.update(&id, |entry| {
+            entry.tags.extend(edit.add_tags.iter().cloned());
+            entry.tags.retain(|t| !edit.remove_tags.contains(t));
+            if let Some(template) = &edit.alias {
+                entry.alias = Some(template.replace("{id}", &id).replace("{slug}", &slug));
+            }
+            if let Some(workspace) = &workspace {
+                entry.workspace = Some(workspace.clone());
+            }
+        });
+    }
+
+    batch.validate()?;
+    let changes = if dry_run { batch.changes().to_vec() } else { batch.commit()? };
+    Ok(serde_json::json!({
+        "filter": filter.to_string(),
+        "matched": matched,
+        "changed": changes.len(),
+        "dry_run": dry_run,
+        "catalog": catalog.path().display().to_string(),
+        "changes": changes,
+    }))
+}
+
+/// Settings for `gametorch watch-dir`.
+struct WatchDirOptions {
+    dir: PathBuf,
+    prompt_from_filename: bool,
+    prompt: Option<String>,
+    duration: u32,
+    preprocess: bool,
+    model_id: Option<u32>,
+    model_name: Option<String>,
+    existing: bool,
+    output: Output,
+}
+
+/// Submit a generation for every image that lands in `options.dir`, until
+/// interrupted.
+///
+/// Prints one line per submitted image (NDJSON by default). Each file is
+/// submitted once per modification time; images without a prompt, and
+/// failed submissions, are reported and skipped.
+async fn watch_directory(
+    api_key: &str,
+    base_url: &str,
+    options: &WatchDirOptions,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Watch before listing so nothing landing in between is missed.
+    let mut watcher = watch_dir::DirWatcher::new(&options.dir)?;
+    let mut queued = std::collections::VecDeque::new();
+    if options.existing {
+        queued.extend(watch_dir::existing_images(&options.dir)?);
+    }
+    eprintln!("Watching {} for new images", options.dir.display());
+
+    let mut submitted = std::collections::HashSet::new();
+    loop {
+        let path = match queued.pop_front() {
+            Some(path) => path,
+            None => match watcher.next().await {
+                Some(path) => path,
+                None => return Ok(()),
+            },
+        };
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if !submitted.insert((path.clone(), modified)) {
+            continue;
+        }
+        let prompt = match watch_dir::prompt_for(&path, options.prompt_from_filename, options.prompt.as_deref()) {
+            Ok(Some(prompt)) => prompt,
+            Ok(None) => {
+                eprintln!(
+                    "Skipping {}: no prompt (add {} or pass --prompt-from-filename)",
+                    path.display(),
+                    watch_dir::sidecar_path(&path).display()
+                );
+                continue;
+            }
+            Err(err) => {
+                eprintln!("Skipping {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let input_image = path.display().to_string();
+        let mut request = GenerateRequest::new(prompt.clone())
+            .duration(options.duration)
+            .input_image(input_image.clone())
+            .preprocess(options.preprocess);
+        request.model_id = options.model_id;
+        request.model_name = options.model_name.clone();
+        let json = match animations::generate_from(api_key, base_url, &request, false, None, true).await {
+            Ok(json) => json,
+            Err(err) => {
+                eprintln!("Failed to submit {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let animation_id = json.get("animation_id").and_then(|v| v.as_i64());
+        if let Some(id) = animation_id {
+            let mut job = Job::submitted(id, "generate");
+            job.prompt = Some(prompt.clone());
+            job.slug = Some(gametorch::slug::slug(&prompt));
+            job.model = options.model_name.clone().or_else(|| options.model_id.map(|id| id.to_string()));
+            job.duration_seconds = Some(options.duration);
+            job.input_image = Some(input_image.clone());
+            job.seed = json.get("seed").and_then(|v| v.as_u64());
+            record_job(&job);
+        }
+        let line = serde_json::json!({
+            "file": input_image,
+            "prompt": prompt,
+            "animation_id": animation_id,
+        });
+        emit(&line, &options.output);
+    }
+}
+
+/// Settings for `gametorch watch`.
+struct WatchOptions {
+    output_dir: PathBuf,
+    interval: u64,
+    filter: Filter,
+    all: bool,
+    once: bool,
+    output: Output,
+}
+
+/// Poll the animations list and run `pipeline` on every newly completed result.
+///
+/// Prints one result per processed animation (NDJSON by default). A fresh
+/// watch state starts from the results already complete (unless `--all`), so
+/// only new ones are processed. Failed downloads are retried on the next poll; a failing
+/// pipeline is reported once and the animation skipped from then on.
+async fn watch_results(
+    api_key: &str,
+    base_url: &str,
+    pipeline: &Pipeline,
+    options: &WatchOptions,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let catalog = Catalog::load_default()?;
+    let mut state = WatchState::load_default()?;
+    let baseline = state.is_fresh() && !options.all;
+    std::fs::create_dir_all(&options.output_dir)?;
+    eprintln!(
+        "Watching for completed animations every {}s (state: {})",
+        options.interval,
+        state.path().display()
+    );
+
+    let mut first_poll = true;
+    loop {
+        let list = match animations::list(api_key, base_url, &animations::ListOptions::default()).await {
+            Ok(list) => list,
+            Err(err) if !options.once => {
+                eprintln!("Warning: failed to list animations: {}", err);
+                tokio::time::sleep(std::time::Duration::from_secs(options.interval)).await;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        let items = list.as_array().cloned().unwrap_or_else(|| vec![list.clone()]);
+        for item in items.iter().map(|item| catalog.annotate(item)) {
+            let Some(id) = item.get("id").map(catalog::id_string) else {
+                continue;
+            };
+            if state.is_processed(&id) || !watch::maybe_complete(&item) || !options.filter.matches(&item) {
+                continue;
+            }
+            let results = match animations::get(api_key, base_url, &id).await {
+                Ok(results) => results,
+                Err(err) => {
+                    eprintln!("Warning: failed to fetch animation {}: {}", id, err);
+                    continue;
+                }
+            };
+            match animations::first_status(&results) {
+                Some(2) => {}
+                // Failed renders will never have a result.
+                Some(3) => {
+                    state.mark_processed(&id);
+                    continue;
+                }
+                _ => continue,
+            }
+            if baseline && first_poll {
+                state.mark_processed(&id);
+                continue;
+            }
+            let Some(result_id) = animations::first_result_id(&results) else {
+                continue;
+            };
+            let zip = match animations::download_zip(api_key, base_url, &result_id.to_string(), true).await {
+                Ok(zip) => zip,
+                Err(err) => {
+                    eprintln!("Warning: failed to download animation {}: {}", id, err);
+                    continue;
+                }
+            };
+            let zip_path = options.output_dir.join(format!("{}.zip", id));
+            let name = catalog.entry(&id).alias.unwrap_or_else(|| {
+                item.get("prompt")
+                    .and_then(|p| p.as_str())
+                    .map(gametorch::slug::slug)
+                    .unwrap_or_else(|| id.clone())
+            });
+            let processed = store::save(&zip_path, &zip)
+                .and_then(|()| Sequence::from_zip_bytes(&zip))
+                .and_then(|mut sequence| pipeline.run(&mut sequence, &name));
+            state.mark_processed(&id);
+            match processed {
+                Ok(steps) => {
+                    let json = serde_json::json!({
+                        "animation_id": id,
+                        "result_id": result_id,
+                        "zip_path": zip_path.display().to_string(),
+                        "name": name,
+                        "steps": steps,
+                    });
+                    emit(&json, &options.output);
+                }
+                Err(err) => eprintln!("Failed to process animation {}: {}", id, err),
+            }
+            state.save()?;
+        }
+        if first_poll {
+            state.save()?;
+            first_poll = false;
+        }
+        if options.once {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(options.interval)).await;
+    }
+}
+
+/// Download the result a webhook reported and run `pipeline` on it. Failed
+/// renders are skipped (`None`).
+async fn process_completion(
+    api_key: &str,
+    base_url: &str,
+    pipeline: &Pipeline,
+    output_dir: &Path,
+    completion: &Completion,
+) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error + Send + Sync>> {
+    if completion.status == Some(3) {
+        eprintln!("Animation {} failed; skipping", completion.animation_id);
+        return Ok(None);
+    }
+    let id = completion.animation_id.to_string();
+    let results = animations::get(api_key, base_url, &id).await?;
+    let result_id = match completion.result_id {
+        Some(result_id) => result_id,
+        None => animations::first_result_id(&results).ok_or("result id missing")?,
+    };
+    let zip = animations::download_zip(api_key, base_url, &result_id.to_string(), true).await?;
+    let zip_path = output_dir.join(format!("{}.zip", id));
+    store::save(&zip_path, &zip)?;
+    let catalog = Catalog::load_default()?;
+    let name = catalog.entry(&id).alias.unwrap_or_else(|| {
+        animations::first_result(&results)
+            .and_then(|r| r.get("prompt"))
+            .and_then(|p| p.as_str())
+            .map(gametorch::slug::slug)
+            .unwrap_or_else(|| id.clone())
+    });
+    let mut sequence = Sequence::from_zip_bytes(&zip)?;
+    let steps = pipeline.run(&mut sequence, &name)?;
+    Ok(Some(serde_json::json!({
+        "animation_id": id,
+        "result_id": result_id,
+        "zip_path": zip_path.display().to_string(),
+        "name": name,
+        "steps": steps,
+    })))
+}
+
+/// Download every result ZIP in the manifest that is missing locally,
+/// checking each against its recorded hash.
+async fn fetch_assets(api_key: &str, base_url: &str, manifest: &Manifest) -> serde_json::Value {
+    let mut fetched = Vec::new();
+    let mut failed = Vec::new();
+    for (name, asset) in &manifest.assets {
+        let Some(zip) = &asset.zip else {
+            continue;
+        };
+        let path = manifest.resolve(zip);
+        if path.exists() {
+            continue;
+        }
+        let Some(result_id) = asset.result_id else {
+            failed.push(serde_json::json!({ "asset": name, "error": "no result ID recorded" }));
+            continue;
+        };
+        let written = animations::download_zip(api_key, base_url, &result_id.to_string(), true)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                if gametorch::manifest::hash_bytes(&bytes) != zip.sha256 {
+                    return Err("downloaded ZIP does not match the recorded hash".to_string());
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                std::fs::write(&path, bytes).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+            });
+        match written {
+            Ok(()) => fetched.push(serde_json::json!({ "asset": name, "path": zip.path })),
+            Err(error) => failed.push(serde_json::json!({ "asset": name, "error": error })),
+        }
+    }
+    serde_json::json!({ "fetched": fetched, "failed": failed })
+}
+
+/// How `assets check` finds and judges fresh results.
+struct AssetCheck {
+    names: Vec<String>,
+    regenerate: bool,
+    min_ssim: f64,
+    max_changed: f64,
+    diff_dir: Option<String>,
+}
+
+/// Compare the committed result ZIP of every asset `check` selects with a
+/// fresh copy, leaving the committed files untouched.
+async fn check_assets(api_key: &str, base_url: &str, manifest: &Manifest, check: &AssetCheck) -> serde_json::Value {
+    let mut passed = Vec::new();
+    let mut failed = Vec::new();
+    for (name, asset) in &manifest.assets {
+        if !check.names.is_empty() && !check.names.contains(name) {
+            continue;
+        }
+        let Some(zip) = &asset.zip else {
+            if !check.names.is_empty() {
+                failed.push(serde_json::json!({ "asset": name, "error": "no result ZIP recorded" }));
+            }
+            continue;
+        };
+        match check_asset(api_key, base_url, manifest, name, asset, zip, check).await {
+            Ok(json) if json["ok"] == true => passed.push(json),
+            Ok(json) => failed.push(json),
+            Err(err) => failed.push(serde_json::json!({ "asset": name, "error": err.to_string() })),
+        }
+    }
+    serde_json::json!({ "passed": passed, "failed": failed })
+}
+
+async fn check_asset(
+    api_key: &str,
+    base_url: &str,
+    manifest: &Manifest,
+    name: &str,
+    asset: &gametorch::manifest::Asset,
+    zip: &gametorch::manifest::AssetFile,
+    check: &AssetCheck,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let committed = Sequence::load(manifest.resolve(zip))?;
+    let (animation_id, result_id) = if check.regenerate {
+        let regen = animations::regenerate(api_key, base_url, &asset.animation_id.to_string(), None).await?;
+        let new_id = regen
+            .get("animation_id")
+            .and_then(|v| v.as_i64())
+            .ok_or("animation_id missing from response")?;
+        let mut job = Job::submitted(new_id, "regenerate");
+        job.source_animation_id = Some(asset.animation_id);
+        job.seed = regen.get("seed").and_then(|v| v.as_u64());
+        record_job(&job);
+        let results = animations::wait(api_key, base_url, &new_id.to_string(), true).await?;
+        let result_id = animations::first_result_id(&results).ok_or("result id missing")?;
+        record_job(&job.complete(Some(result_id), None));
+        (new_id, result_id)
+    } else {
+        (asset.animation_id, asset.result_id.ok_or("no result ID recorded")?)
+    };
+    let fresh = Sequence::from_zip_bytes(
+        &animations::download_zip(api_key, base_url, &result_id.to_string(), true).await?,
+    )?;
+
+    let comparison = metrics::compare(&committed, &fresh);
+    let ok = comparison.frames_a == comparison.frames_b
+        && comparison.mean_ssim >= check.min_ssim
+        && comparison.mean_changed <= check.max_changed;
+    let mut sheet = None;
+    if !ok && let Some(dir) = &check.diff_dir {
+        std::fs::create_dir_all(dir)?;
+        let path = Path::new(dir).join(format!("{}.png", name));
+        metrics::comparison_sheet(&committed, &fresh).save(&path)?;
+        sheet = Some(path.display().to_string());
+    }
+    Ok(serde_json::json!({
+        "asset": name,
+        "ok": ok,
+        "animation_id": animation_id,
+        "result_id": result_id,
+        "frames": comparison.frames_a,
+        "fresh_frames": comparison.frames_b,
+        "mean_ssim": comparison.mean_ssim,
+        "mean_changed": comparison.mean_changed,
+        "comparison_sheet": sheet,
+    }))
+}
+
+/// What `assets update` changes.
+struct AssetUpdate {
+    name: String,
+    prompt: Option<String>,
+    input_image: Option<String>,
+    preprocess: bool,
+    duration: Option<u32>,
+}
+
+/// Regenerate asset `update.name` into its file and point the manifest at
+/// the new result.
+async fn update_asset(
+    api_key: &str,
+    base_url: &str,
+    workspace: &Workspace,
+    mut manifest: Manifest,
+    update: AssetUpdate,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    gametorch::manifest::validate_name(&update.name)?;
+    let current = manifest.assets.get(&update.name);
+    let prompt = update
+        .prompt
+        .or_else(|| current.and_then(|asset| asset.prompt.clone()))
+        .ok_or_else(|| format!("asset '{}' has no prompt to reuse; pass --prompt", update.name))?;
+    let zip_path = match current.and_then(|asset| asset.zip.as_ref()) {
+        Some(zip) => manifest.resolve(zip),
+        None => workspace.output_dir().join(format!("{}.zip", update.name)),
+    };
+    if let Some(parent) = zip_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let duration = update.duration.or(workspace.config.duration).unwrap_or(5);
+    let (model_id, model_name) = workspace.config.model();
+    let zip = zip_path.display().to_string();
+    let mut request = GenerateRequest::new(prompt.clone()).duration(duration).preprocess(update.preprocess);
+    request.input_image = update.input_image.clone();
+    request.model_id = model_id;
+    request.model_name = model_name.clone();
+    let json = animations::generate_from(api_key, base_url, &request, true, Some(&zip), true).await?;
+    let animation_id = json.get("animation_id").and_then(|v| v.as_i64()).ok_or("animation_id missing from response")?;
+    let result_id = json.get("result_id").and_then(|v| v.as_i64());
+
+    let mut job = Job::submitted(animation_id, "generate");
+    job.prompt = Some(prompt.clone());
+    job.slug = Some(gametorch::slug::slug(&prompt));
+    job.model = model_name.or_else(|| model_id.map(|id| id.to_string()));
+    job.duration_seconds = Some(duration);
+    job.input_image = update.input_image;
+    record_job(&job.complete(result_id, Some(zip.clone())));
+
+    let previous = manifest.assets.get(&update.name).map(|asset| asset.animation_id);
+    manifest.set_result(&update.name, animation_id, result_id, Some(prompt), &zip_path)?;
+    manifest.save()?;
+    Ok(serde_json::json!({
+        "asset": update.name,
+        "animation_id": animation_id,
+        "result_id": result_id,
+        "previous_animation_id": previous,
+        "zip_path": zip,
+    }))
+}
+
+/// Queue a generation request for `gametorch flush`.
+fn queue_generation(
+    mut job: PendingJob,
+    block: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    if block {
+        return Err("--block and --copy-result need a connection; queue without them and run `gametorch flush`".into());
+    }
+    job.input_image = match job.input_image.take() {
+        Some(image) if image == animations::STDIO_PATH || image == gametorch::clipboard::CLIPBOARD_INPUT => {
+            return Err(format!("an input image from {} cannot be queued; save it to a file first", image).into());
+        }
+        Some(image) if gametorch::input::is_url(&image) => Some(image),
+        Some(image) => Some(std::path::absolute(&image)?.display().to_string()),
+        None => None,
+    };
+    let queue = Queue::open_default()?;
+    queue.push(&job)?;
+    let pending = queue.jobs()?.len();
+    eprintln!("Queued; run `gametorch flush` once online ({} pending)", pending);
+    Ok(serde_json::json!({ "queued": job, "pending": pending }))
+}
+
+/// Submit every queued generation request, keeping the ones that fail.
+async fn flush_pending(
+    api_key: &str,
+    base_url: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let queue = Queue::open_default()?;
+    let mut submitted = Vec::new();
+    let mut failed = Vec::new();
+    let mut remaining = Vec::new();
+    let jobs = queue.jobs()?;
+    gametorch::input::prefetch(
+        jobs.iter().filter_map(|job| job.input_image.clone()),
+        gametorch::input::PREFETCH_MAX_BYTES,
+    )
+    .await;
+    for job in jobs {
+        let request = GenerateRequest {
+            prompt: job.prompt.clone(),
+            duration_seconds: job.duration_seconds,
+            input_image: job.input_image.clone(),
+            preprocess: job.preprocess,
+            compress: job.compress,
+            model_id: job.model_id,
+            model_name: job.model_name.clone(),
+            webhook_url: job.webhook_url.clone(),
+            seed: job.seed,
+            negative_prompt: job.negative_prompt.clone(),
+            skip_model_checks: job.skip_model_checks,
+            retry_on_failure: 0,
+        };
+        let result = animations::generate_from(api_key, base_url, &request, false, None, true).await;
+        match result.as_ref().ok().and_then(|json| json.get("animation_id")).and_then(|v| v.as_i64()) {
+            Some(id) => {
+                let mut record = Job::submitted(id, "generate");
+                record.prompt = Some(job.prompt.clone());
+                record.slug = Some(gametorch::slug::slug(&job.prompt));
+                record.model = job.model_name.clone().or_else(|| job.model_id.map(|id| id.to_string()));
+                record.duration_seconds = Some(job.duration_seconds);
+                record.input_image = job.input_image.clone();
+                record.negative_prompt = job.negative_prompt.clone();
+                record.seed = result
+                    .as_ref()
+                    .ok()
+                    .and_then(|json| json.get("seed"))
+                    .and_then(|v| v.as_u64())
+                    .or(job.seed);
+                record_job(&record);
+                #[cfg(feature = "index")]
+                update_index(|index| index.upsert_animations(&serde_json::json!({ "id": id, "prompt": job.prompt })));
+                submitted.push(serde_json::json!({ "animation_id": id, "prompt": job.prompt }));
+            }
+            None => {
+                let error = match result {
+                    Err(err) => err.to_string(),
+                    Ok(_) => "animation_id missing from response".to_string(),
+                };
+                failed.push(serde_json::json!({ "prompt": job.prompt, "error": error }));
+                remaining.push(job);
+            }
+        }
+    }
+    queue.replace(&remaining)?;
+    Ok(serde_json::json!({ "submitted": submitted, "failed": failed, "pending": remaining.len() }))
+}
+
+/// Finish an interrupted blocking generation: wait for it, save the ZIP
+/// where the original run would have, and record it like `generate` does.
+async fn resume_generation(
+    api_key: &str,
+    base_url: &str,
+    animation_id: Option<i64>,
+    workspace: Option<&Workspace>,
+    silent: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let file = ResumeFile::open_default()?;
+    let jobs = file.jobs()?;
+    let job = match animation_id {
+        Some(id) => jobs.into_iter().find(|j| j.animation_id == id),
+        None => jobs.into_iter().last(),
+    }
+    .ok_or_else(|| match animation_id {
+        Some(id) => format!("animation {} is not in {}", id, file.path().display()),
+        None => "no interrupted generations to resume".to_string(),
+    })?;
+    let id = job.animation_id.to_string();
+    let cancel = cancel_on_ctrl_c();
+
+    let results = animations::wait_with_progress(api_key, base_url, &id, silent, &(), &cancel).await?;
+    let result_id = animations::first_result_id(&results).ok_or("result id missing")?;
+    if !silent {
+        println!("Render complete, downloading ZIP...");
+    }
+    let bytes = animations::download_zip_with_progress(api_key, base_url, &result_id.to_string(), silent, &(), &cancel).await?;
+    let path = job
+        .output_file
+        .clone()
+        .unwrap_or_else(|| format!("animation_{}_{}.zip", id, result_id));
+    std::fs::write(&path, &bytes).map_err(|e| format!("failed to write {}: {}", path, e))?;
+    if !silent {
+        println!("ZIP saved to {}", path);
+    }
+
+    let mut record = Job::submitted(job.animation_id, "generate");
+    record.prompt = Some(job.prompt.clone());
+    record.slug = Some(gametorch::slug::slug(&job.prompt));
+    record.submitted_at = job.interrupted_at;
+    record = record.complete(Some(result_id), Some(path.clone()));
+    record_job(&record);
+    let mut json = serde_json::json!({
+        "animation_id": job.animation_id,
+        "slug": gametorch::slug::slug(&job.prompt),
+        "result_id": result_id,
+        "zip_path": path,
+    });
+    if let Some(ws) = workspace {
+        let name = job.asset.clone().unwrap_or_else(|| gametorch::slug::slug(&job.prompt));
+        record_asset(ws, &name, job.animation_id, Some(result_id), &job.prompt, Path::new(&path));
+        json["asset"] = serde_json::json!(name);
+    }
+    #[cfg(feature = "index")]
+    update_index(|index| {
+        index.upsert_animations(&serde_json::json!({ "id": job.animation_id, "prompt": job.prompt }))?;
+        index.record_file(&path, job.animation_id, Some(result_id))
+    });
+    if let Err(err) = file.remove(job.animation_id) {
+        eprintln!("Warning: failed to update the resume file: {}", err);
+    }
+    Ok(json)
+}
+
+/// Most generations one `generate --count` may submit.
+const MAX_CANDIDATES: i64 = 10;
+
+/// The comparison index for a `generate --count` run in `dir`: every
+/// candidate from [`wait_and_download`], numbered from 1, with summary
+/// quality metrics for each downloaded result.
+fn candidate_index(prompt: &str, dir: &Path, items: serde_json::Value) -> serde_json::Value {
+    let mut candidates = match items {
+        serde_json::Value::Array(items) => items,
+        other => vec![other],
+    };
+    for (i, candidate) in candidates.iter_mut().enumerate() {
+        candidate["candidate"] = serde_json::json!(i + 1);
+        let Some(path) = candidate.get("zip_path").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        match Sequence::load(path) {
+            Ok(sequence) => {
+                let report = metrics::quality(&sequence);
+                candidate["metrics"] = serde_json::json!({
+                    "frame_count": report.frame_count,
+                    "flicker_score": report.flicker_score,
+                    "mean_sharpness": report.mean_sharpness,
+                    "palette_size": report.palette_size,
+                    "mean_alpha_noise": report.mean_alpha_noise,
+                    "outlier_frames": report.outlier_frames.len(),
+                });
+            }
+            Err(err) => candidate["metrics_error"] = serde_json::json!(err.to_string()),
+        }
+    }
+    serde_json::json!({
+        "prompt": prompt,
+        "directory": dir.display().to_string(),
+        "candidates": candidates,
+    })
+}
+
+/// Wait for `ids` concurrently, then download the completed results into
+/// `output_dir` (also concurrently). One entry per ID, in order.
+async fn wait_and_download(
+    api_key: &str,
+    base_url: &str,
+    ids: &[String],
+    output_dir: Option<&Path>,
+    retry_on_failure: u32,
+    silent: bool,
+) -> serde_json::Value {
+    let outcomes = animations::wait_all_regenerating(api_key, base_url, ids, retry_on_failure, silent).await;
+    let mut items: Vec<serde_json::Value> = outcomes
+        .iter()
+        .map(|retried| {
+            let id = &retried.animation_id;
+            let mut item = match &retried.outcome {
+                Ok(results) => serde_json::json!({
+                    "animation_id": id,
+                    "status": "complete",
+                    "result_id": animations::first_result_id(results),
+                }),
+                Err(err) => serde_json::json!({
+                    "animation_id": id,
+                    "status": "failed",
+                    "error": err.to_string(),
+                    "error_kind": ErrorKind::of(err.as_ref()).as_str(),
+                }),
+            };
+            if !retried.failed_ids.is_empty() {
+                item["failed_animation_ids"] = serde_json::json!(retried.failed_ids);
+            }
+            item
+        })
+        .collect();
+    let Some(output_dir) = output_dir else {
+        return serde_json::Value::Array(items);
+    };
+    if let Err(err) = std::fs::create_dir_all(output_dir) {
+        fail(&format!("Failed to create {}", output_dir.display()), err);
+    }
+    let mut downloads = tokio::task::JoinSet::new();
+    for (i, item) in items.iter().enumerate() {
+        let Some(result_id) = item["result_id"].as_i64() else {
+            continue;
+        };
+        let (api_key, base_url) = (api_key.to_string(), base_url.to_string());
+        let path = output_dir.join(format!("{}.zip", outcomes[i].animation_id));
+        downloads.spawn(async move {
+            let written = match animations::download_zip(&api_key, &base_url, &result_id.to_string(), true).await {
+                Ok(zip) => store::save(&path, &zip),
+                Err(err) => Err(err),
+            };
+            (i, written.map(|()| path))
+        });
+    }
+    while let Some(joined) = downloads.join_next().await {
+        let Ok((i, written)) = joined else {
+            continue;
+        };
+        match written {
+            Ok(path) => items[i]["zip_path"] = serde_json::json!(path.display().to_string()),
+            Err(err) => {
+                items[i]["error"] = serde_json::json!(err.to_string());
+                items[i]["error_kind"] = serde_json::json!(ErrorKind::of(err.as_ref()).as_str());
+            }
+        }
+    }
+    serde_json::Value::Array(items)
+}
+
+/// Exit with the code for the first failed item's error kind (7 for a
+/// failed render), if any of `items` from [`wait_and_download`] failed.
+fn exit_if_any_failed(items: &serde_json::Value) {
+    let Some(item) = items.as_array().into_iter().flatten().find(|item| item.get("error").is_some()) else {
+        return;
+    };
+    let kind: ErrorKind = item["error_kind"].as_str().and_then(|kind| kind.parse().ok()).unwrap_or(ErrorKind::Other);
+    exit(kind.exit_code());
+}
+
+/// Move the ZIP `generate --output-dir` staged (`json["zip_path"]`) into
+/// `dir/<animation_id>/<result_id>/` next to its extracted `frames/`, a
+/// `metadata.json` and an `export/` per target, and point `json` at them.
+fn lay_out_result(
+    dir: &Path,
+    json: &mut serde_json::Value,
+    mut metadata: serde_json::Value,
+    name: &str,
+    targets: &[export::Target],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (Some(animation_id), Some(result_id), Some(staged)) = (
+        json.get("animation_id").and_then(|v| v.as_i64()),
+        json.get("result_id").and_then(|v| v.as_i64()),
+        json.get("zip_path").and_then(|v| v.as_str()).map(PathBuf::from),
+    ) else {
+        return Err("the result has no ZIP to lay out".into());
+    };
+    let result_dir = dir.join(animation_id.to_string()).join(result_id.to_string());
+    std::fs::create_dir_all(&result_dir).map_err(|e| format!("failed to create {}: {}", result_dir.display(), e))?;
+
+    let zip = std::fs::read(&staged).map_err(|e| format!("failed to read {}: {}", staged.display(), e))?;
+    let zip_path = result_dir.join(format!("{}.zip", name));
+    store::save(&zip_path, &zip)?;
+    std::fs::remove_file(&staged).map_err(|e| format!("failed to remove {}: {}", staged.display(), e))?;
+
+    let sequence = Sequence::from_zip_bytes(&zip)?;
+    let frames_dir = result_dir.join("frames");
+    sequence.save_dir(&frames_dir)?;
+
+    metadata["animation_id"] = serde_json::json!(animation_id);
+    metadata["result_id"] = serde_json::json!(result_id);
+    metadata["frames"] = serde_json::json!(sequence.len());
+
+    let mut exports = Vec::new();
+    let options = ExportOptions {
+        name: name.to_string(),
+        provenance: serde_json::from_value(metadata.clone()).ok(),
+        ..Default::default()
+    };
+    for &target in targets {
+        let exported = export::export(&sequence, target, &result_dir.join("export").join(target.as_str()), &options)?;
+        let files: Vec<String> = exported.files.iter().map(|p| p.display().to_string()).collect();
+        exports.push(serde_json::json!({ "target": target.as_str(), "files": files }));
+    }
+
+    let metadata_path = result_dir.join("metadata.json");
+    std::fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)? + "\n")
+        .map_err(|e| format!("failed to write {}: {}", metadata_path.display(), e))?;
+
+    json["zip_path"] = serde_json::json!(zip_path.display().to_string());
+    json["output_dir"] = serde_json::json!(result_dir.display().to_string());
+    json["frames_dir"] = serde_json::json!(frames_dir.display().to_string());
+    json["metadata"] = serde_json::json!(metadata_path.display().to_string());
+    if !exports.is_empty() {
+        json["exports"] = serde_json::json!(exports);
+    }
+    Ok(())
+}
+
+/// File `animations download-all` lists the downloaded results in.
+const DOWNLOAD_MANIFEST: &str = "manifest.json";
+
+/// Download every completed result of `animation_id` into `dir` as
+/// `<result_id>.zip`, concurrently, then write a [`DOWNLOAD_MANIFEST`] there
+/// with each file's size and SHA-256 (and the results that failed).
+async fn download_all(
+    api_key: &str,
+    base_url: &str,
+    animation_id: i64,
+    dir: &Path,
+    silent: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let results = animations::get(api_key, base_url, &animation_id.to_string()).await?;
+    let items = match results {
+        serde_json::Value::Array(items) => items,
+        other => vec![other],
+    };
+    let result_ids: Vec<i64> = items
+        .iter()
+        .filter(|item| item.get("status").and_then(|s| s.as_i64()) == Some(animations::Status::Complete.code()))
+        .filter_map(|item| item.get("id").and_then(|id| id.as_i64()))
+        .collect();
+    if result_ids.is_empty() {
+        return Err(format!("animation {} has no completed results", animation_id).into());
+    }
+    std::fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+
+    let progress = std::sync::Arc::new(DownloadProgress::new(&result_ids, silent));
+    let cancel = cancel_on_ctrl_c();
+    let mut downloads = tokio::task::JoinSet::new();
+    for (i, &result_id) in result_ids.iter().enumerate() {
+        let (api_key, base_url) = (api_key.to_string(), base_url.to_string());
+        let (progress, cancel) = (progress.clone(), cancel.clone());
+        let path = dir.join(format!("{}.zip", result_id));
+        downloads.spawn(async move {
+            let sink = |event: gametorch::progress::ProgressEvent| {
+                if let gametorch::progress::ProgressEvent::Downloading { bytes, total } = event {
+                    progress.update(i, bytes, total);
+                }
+            };
+            let written = animations::download_zip_with_progress(&api_key, &base_url, &result_id.to_string(), true, &sink, &cancel)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|zip| {
+                    store::save(&path, &zip).map_err(|e| e.to_string())?;
+                    Ok(serde_json::json!({
+                        "result_id": result_id,
+                        "path": path.display().to_string(),
+                        "bytes": zip.len(),
+                        "sha256": gametorch::manifest::hash_bytes(&zip),
+                    }))
+                });
+            progress.finish(i, written.as_ref().err().map(String::as_str));
+            (i, written)
+        });
+    }
+    let mut outcomes: Vec<Option<Result<serde_json::Value, String>>> = result_ids.iter().map(|_| None).collect();
+    while let Some(joined) = downloads.join_next().await {
+        let (i, written) = joined.map_err(|e| format!("download task failed: {}", e))?;
+        outcomes[i] = Some(written);
+    }
+
+    let mut files = Vec::new();
+    let mut failed = Vec::new();
+    for (result_id, outcome) in result_ids.iter().zip(outcomes) {
+        match outcome {
+            Some(Ok(file)) => files.push(file),
+            Some(Err(error)) => failed.push(serde_json::json!({ "result_id": result_id, "error": error })),
+            None => {}
+        }
+    }
+    let manifest_path = dir.join(DOWNLOAD_MANIFEST);
+    let manifest = serde_json::json!({
+        "animation_id": animation_id,
+        "files": files,
+        "failed": failed,
+    });
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)? + "\n")
+        .map_err(|e| format!("failed to write {}: {}", manifest_path.display(), e))?;
+    let mut json = manifest;
+    json["manifest"] = serde_json::json!(manifest_path.display().to_string());
+    Ok(json)
+}
+
+/// Per-file progress of concurrent downloads on stderr: one line redrawn
+/// in place on a terminal, a line per finished file otherwise.
+struct DownloadProgress {
+    names: Vec<String>,
+    /// Bytes received, size if known, and whether the download is over.
+    state: std::sync::Mutex<Vec<(u64, Option<u64>, bool)>>,
+    interactive: bool,
+    silent: bool,
+}
+
+impl DownloadProgress {
+    fn new(result_ids: &[i64], silent: bool) -> DownloadProgress {
+        use std::io::IsTerminal;
+        DownloadProgress {
+            names: result_ids.iter().map(|id| format!("{}.zip", id)).collect(),
+            state: std::sync::Mutex::new(vec![(0, None, false); result_ids.len()]),
+            interactive: std::io::stderr().is_terminal(),
+            silent,
+        }
+    }
+
+    fn update(&self, i: usize, bytes: u64, total: Option<u64>) {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state[i].0 = bytes;
+        state[i].1 = total;
+        self.redraw(&state);
+    }
+
+    fn finish(&self, i: usize, error: Option<&str>) {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state[i].2 = true;
+        if self.silent {
+            return;
+        }
+        if !self.interactive {
+            match error {
+                Some(error) => eprintln!("{}: failed: {}", self.names[i], error),
+                None => eprintln!("{}: {} bytes", self.names[i], state[i].0),
+            }
+            return;
+        }
+        self.redraw(&state);
+        if state.iter().all(|(_, _, done)| *done) {
+            eprintln!();
+        }
+    }
+
+    fn redraw(&self, state: &[(u64, Option<u64>, bool)]) {
+        if self.silent || !self.interactive {
+            return;
+        }
+        let files: Vec<String> = self
+            .names
+            .iter()
+            .zip(state)
+            .map(|(name, (bytes, total, _))| match total {
+                Some(total) if *total > 0 => format!("{} {}%", name, bytes * 100 / total),
+                _ => format!("{} {} KB", name, bytes / 1024),
+            })
+            .collect();
+        eprint!("\r\x1b[2K{}", files.join("  "));
+    }
+}
+
+/// One-way backup of the library into `output_dir`.
+///
+/// Downloads every completed result without a `<id>.zip` there, and reports
+/// local ZIPs (and, with the index, indexed animations) the server no longer
+/// has. Nothing local is ever deleted.
+async fn sync_library(
+    api_key: &str,
+    base_url: &str,
+    output_dir: &Path,
+    dry_run: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let list = animations::list(api_key, base_url, &animations::ListOptions::default()).await?;
+    #[cfg(feature = "index")]
+    update_index(|index| index.upsert_animations(&list));
+    let items = list.as_array().cloned().unwrap_or_else(|| vec![list.clone()]);
+    let remote: std::collections::BTreeSet<String> =
+        items.iter().filter_map(|item| item.get("id").map(catalog::id_string)).collect();
+
+    // Local backups the server no longer has.
+    let mut deleted = Vec::new();
+    if output_dir.is_dir() {
+        for entry in std::fs::read_dir(output_dir)? {
+            let path = entry?.path();
+            let id = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+            let numeric = !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit());
+            if path.extension().is_some_and(|e| e == "zip") && numeric && !remote.contains(&id) {
+                deleted.push(id);
+            }
+        }
+    }
+    #[cfg(feature = "index")]
+    update_index(|index| {
+        for id in index.live_ids()? {
+            if !remote.contains(&id.to_string()) {
+                if !deleted.contains(&id.to_string()) {
+                    deleted.push(id.to_string());
+                }
+                if !dry_run {
+                    index.mark_deleted(id)?;
+                }
+            }
+        }
+        Ok(())
+    });
+    deleted.sort_by_key(|id| id.parse::<i64>().unwrap_or(i64::MAX));
+
+    if !dry_run {
+        std::fs::create_dir_all(output_dir)?;
+    }
+    let mut downloaded = Vec::new();
+    let mut present = 0;
+    let mut failed = Vec::new();
+    for item in &items {
+        let Some(id) = item.get("id").map(catalog::id_string) else {
+            continue;
+        };
+        if !watch::maybe_complete(item) {
+            continue;
+        }
+        let zip_path = output_dir.join(format!("{}.zip", id));
+        if zip_path.exists() {
+            present += 1;
+            continue;
+        }
+        let results = match animations::get(api_key, base_url, &id).await {
+            Ok(results) => results,
+            Err(err) => {
+                failed.push(serde_json::json!({ "id": id, "error": err.to_string() }));
+                continue;
+            }
+        };
+        let (Some(2), Some(result_id)) = (animations::first_status(&results), animations::first_result_id(&results))
+        else {
+            continue;
+        };
+        if dry_run {
+            downloaded.push(serde_json::json!({ "id": id, "result_id": result_id, "zip_path": zip_path }));
+            continue;
+        }
+        let written = match animations::download_zip(api_key, base_url, &result_id.to_string(), true).await {
+            Ok(zip) => store::save(&zip_path, &zip).map_err(|e| e.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+        match written {
+            Ok(()) => {
+                #[cfg(feature = "index")]
+                if let Ok(animation_id) = id.parse() {
+                    update_index(|index| {
+                        index.upsert_results(animation_id, &results)?;
+                        index.record_file(&zip_path, animation_id, Some(result_id))
+                    });
+                }
+                eprintln!("Downloaded {}", zip_path.display());
+                downloaded.push(serde_json::json!({ "id": id, "result_id": result_id, "zip_path": zip_path }));
+            }
+            Err(error) => failed.push(serde_json::json!({ "id": id, "error": error })),
+        }
+    }
+    Ok(serde_json::json!({
+        "dry_run": dry_run,
+        "downloaded": downloaded,
+        "up_to_date": present,
+        "deleted_on_server": deleted,
+        "failed": failed,
+    }))
+}
+
+/// Delete `ids` one by one, reporting which succeeded, and drop their catalog entries.
+async fn delete_animations(api_key: &str, base_url: &str, ids: &[String]) -> serde_json::Value {
+    let mut deleted = Vec::new();
+    let mut failed = Vec::new();
+    for id in ids {
+        match animations::delete(api_key, base_url, id).await {
+            Ok(()) => deleted.push(id.clone()),
+            Err(err) => failed.push(serde_json::json!({ "id": id, "error": err.to_string() })),
+        }
+    }
+    let forgotten = Catalog::load_default().and_then(|catalog| {
+        let mut batch = catalog.batch();
+        for id in &deleted {
+            batch.update(id, |entry| *entry = catalog::Entry::default());
+        }
+        batch.commit()
+    });
+    if let Err(err) = forgotten {
+        eprintln!("Warning: failed to update the catalog: {}", err);
+    }
+    #[cfg(feature = "index")]
+    update_index(|index| {
+        for id in deleted.iter().filter_map(|id| id.parse().ok()) {
+            index.remove_animation(id)?;
+        }
+        Ok(())
+    });
+    serde_json::json!({ "deleted": deleted, "failed": failed })
+}
+
+/// Ask a yes/no question on the terminal; anything but "y"/"yes" (or no
+/// terminal at all) is a no.
+fn confirm(question: &str) -> bool {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        eprintln!("{} Refusing without a terminal; pass --yes to confirm.", question);
+        return false;
+    }
+    eprint!("{} [y/N] ", question);
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+/// Print a command result in the selected output format.
+fn emit(value: &serde_json::Value, out: &Output) {
+    match out.render(value, output::use_color()) {
+        Ok(text) => print!("{}", text),
+        Err(err) => {
+            fail("Failed to format output", err);
+        }
+    }
+}
+
+/// Generation settings, possibly taken from an earlier job.
+struct Replay {
+    prompt: String,
+    input_image: Option<String>,
+    model_id: Option<u32>,
+    model_name: Option<String>,
+    duration: u32,
+    seed: Option<u64>,
+    negative_prompt: Option<String>,
+}
+
+/// Settings of the `n`th most recent prompt in the history, with any given
+/// on the command line taking precedence.
+#[allow(clippy::too_many_arguments)]
+fn replay(
+    n: usize,
+    prompt: Option<String>,
+    input_image: Option<String>,
+    model_id: Option<u32>,
+    model_name: Option<String>,
+    duration: Option<u32>,
+    seed: Option<u64>,
+    negative_prompt: Option<String>,
+) -> Result<Replay, Box<dyn std::error::Error + Send + Sync>> {
+    let jobs = History::open_default()?.prompts()?;
+    let job = n
+        .checked_sub(1)
+        .and_then(|i| jobs.get(i))
+        .ok_or_else(|| format!("no prompt #{} in the history ({} recorded)", n, jobs.len()))?;
+    let input_image = match input_image {
+        Some(image) => Some(image),
+        None if job.input_image.as_deref() == Some(animations::STDIO_PATH) => {
+            return Err(format!("prompt #{} read its input image from stdin; pass --input-image again", n).into());
+        }
+        None => job.input_image.clone(),
+    };
+    let (model_id, model_name) = match (model_id, model_name, &job.model) {
+        (None, None, Some(model)) => match model.parse::<u32>() {
+            Ok(id) => (Some(id), None),
+            Err(_) => (None, Some(model.clone())),
+        },
+        (id, name, _) => (id, name),
+    };
+    Ok(Replay {
+        prompt: prompt.or_else(|| job.prompt.clone()).unwrap_or_default(),
+        input_image,
+        model_id,
+        model_name,
+        duration: duration.or(job.duration_seconds).unwrap_or(5),
+        seed: seed.or(job.seed),
+        negative_prompt: negative_prompt.or_else(|| job.negative_prompt.clone()),
+    })
+}
+
+/// Apply `update` to the local index, warning (not failing) if that is impossible.
+#[cfg(feature = "index")]
+fn update_index(update: impl FnOnce(&mut Index) -> Result<(), Box<dyn std::error::Error + Send + Sync>>) {
+    if let Err(err) = Index::open_default().and_then(|mut index| update(&mut index)) {
+        eprintln!("Warning: failed to update the local index: {}", err);
+    }
+}
+
+/// Stored results of one animation from the local index.
+fn get_offline(id: &str) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    #[cfg(feature = "index")]
+    {
+        let animation_id: i64 = id.parse().map_err(|_| format!("invalid animation ID '{}'", id))?;
+        Index::open_default()?
+            .results(animation_id)?
+            .ok_or_else(|| format!("animation {} is not in the local index", id).into())
+    }
+    #[cfg(not(feature = "index"))]
+    {
+        let _ = id;
+        Err("this build has no local index (rebuild with --features index)".into())
+    }
+}
+
+/// List animations from the local index.
+fn list_offline(
+    options: &animations::ListOptions,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    #[cfg(feature = "index")]
+    {
+        Index::open_default()?.list(options)
+    }
+    #[cfg(not(feature = "index"))]
+    {
+        let _ = options;
+        Err("this build has no local index (rebuild with --features index)".into())
+    }
+}
+
+/// Record a downloaded result as asset `name` in the workspace manifest,
+/// warning (not failing) if that is impossible.
+fn record_asset(workspace: &Workspace, name: &str, animation_id: i64, result_id: Option<i64>, prompt: &str, zip: &Path) {
+    let recorded = Manifest::load(workspace).and_then(|mut manifest| {
+        manifest.set_result(name, animation_id, result_id, Some(prompt.to_string()), zip)?;
+        manifest.save()
+    });
+    if let Err(err) = recorded {
+        eprintln!("Warning: failed to update the asset manifest: {}", err);
+    }
+}
+
+/// Add the files of an export to the manifest asset of its input (found by
+/// the input ZIP, else by export name). Returns the asset's name.
+fn record_exports(workspace: &Workspace, input: &Path, name: &str, json: &serde_json::Value) -> Option<String> {
+    let exports = match json.get("exports").and_then(|e| e.as_array()) {
+        Some(exports) => exports.iter().collect(),
+        None => vec![json],
+    };
+    let files: Vec<PathBuf> = exports
+        .iter()
+        .filter_map(|export| export.get("files").and_then(|f| f.as_array()))
+        .flatten()
+        .filter_map(|f| f.as_str().map(PathBuf::from))
+        .collect();
+    let recorded = Manifest::load(workspace).and_then(|mut manifest| {
+        let asset = manifest.asset_for_zip(input).unwrap_or(name).to_string();
+        if manifest.add_files(&asset, &files)? {
+            manifest.save()?;
+            Ok(Some(asset))
+        } else {
+            Ok(None)
+        }
+    });
+    recorded.unwrap_or_else(|err| {
+        eprintln!("Warning: failed to update the asset manifest: {}", err);
+        None
+    })
+}
+
+/// A token cancelled by the first Ctrl+C; a second one exits immediately.
+fn cancel_on_ctrl_c() -> CancellationToken {
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            token.cancel();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                exit(130);
+            }
+        }
+    });
+    cancel
+}
+
+/// Append `job` to the local history, warning (not failing) if that is impossible.
+fn record_job(job: &Job) {
+    if let Err(err) = History::open_default().and_then(|history| history.record(job)) {
+        eprintln!("Warning: failed to record job history: {}", err);
+    }
+}
+
+fn complete_animation_ids() -> Vec<CompletionCandidate> {
+    gametorch::completions::animation_ids()
+        .into_iter()
+        .map(|(id, prompt)| CompletionCandidate::new(id).help(prompt.map(Into::into)))
+        .collect()
+}
+
+fn complete_model_names() -> Vec<CompletionCandidate> {
+    gametorch::completions::model_names().into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// Environment variable holding the API key.
+const API_KEY_ENV: &str = "GAMETORCH_API_KEY";
+
+/// The active key from config.toml, used when `GAMETORCH_API_KEY` is not set.
+static CONFIGURED_KEY: OnceLock<String> = OnceLock::new();
+
+/// Read the API key from `GAMETORCH_API_KEY`, else the active configured
+/// key, exiting if there is neither.
+fn require_api_key() -> String {
+    env::var(API_KEY_ENV)
+        .ok()
+        .or_else(|| CONFIGURED_KEY.get().cloned())
+        .unwrap_or_else(|| {
+            exit_with(
+                ErrorKind::Auth,
+                None,
+                "environment variable GAMETORCH_API_KEY not set.\nPlease set it, or list keys in config.toml, before using this CLI.",
+            )
+        })
+}
+
+/// This run, when usage stats are enabled.
+static RUN: OnceLock<usage::Run> = OnceLock::new();
+
+/// Record this run's outcome in the usage stats (if enabled) and exit.
+fn exit(code: i32) -> ! {
+    if let Some(run) = RUN.get() {
+        run.finish(code);
+    }
+    std::process::exit(code)
+}
+
+/// Records a run that returns from `main` instead of calling [`exit`].
+struct Finished;
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        if let Some(run) = RUN.get() {
+            run.finish(if std::thread::panicking() { 101 } else { 0 });
+        }
+    }
+}
+
+/// The subcommand path of this invocation, e.g. `animations generate`.
+fn command_name() -> String {
+    let mut names = Vec::new();
+    if let Ok(matches) = Cli::command().try_get_matches() {
+        let mut current = &matches;
+        while let Some((name, sub)) = current.subcommand() {
+            names.push(name.to_string());
+            current = sub;
+        }
+    }
+    names.join(" ")
+}
+
+/// Whether errors are reported as JSON (`--porcelain`).
+static PORCELAIN: AtomicBool = AtomicBool::new(false);
+
+/// Report a failure and exit with the code for its [kind](ErrorKind).
+fn fail(context: &str, err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ! {
+    let err = err.into();
+    exit_with(ErrorKind::of(err.as_ref()), Some(context), &err.to_string())
+}
+
+/// Report a problem with the arguments and exit with the validation code.
+fn invalid(message: impl std::fmt::Display) -> ! {
+    exit_with(ErrorKind::Validation, None, &message.to_string())
+}
+
+/// Print `context: message` (or, with `--porcelain`, one line of JSON:
+/// `{"error": {"kind", "exit_code", "context", "message"}}`) to stderr and
+/// exit with `kind`'s code.
+fn exit_with(kind: ErrorKind, context: Option<&str>, message: &str) -> ! {
+    if PORCELAIN.load(Ordering::Relaxed) {
+        let error = serde_json::json!({
+            "error": {
+                "kind": kind.as_str(),
+                "exit_code": kind.exit_code(),
+                "context": context,
+                "message": message,
+            }
+        });
+        eprintln!("{}", error);
+    } else {
+        eprintln!("{}: {}", context.unwrap_or("Error"), message);
+    }
+    exit(kind.exit_code())
+}
+
+/// Where an export input came from: the history's record of the job that
+/// saved this very ZIP, if any.
+fn provenance_of(input: &str) -> Option<Provenance> {
+    let path = Path::new(input);
+    if !path.is_file() {
+        return None;
+    }
+    let hash = gametorch::manifest::hash_file(path).ok()?;
+    let jobs = History::open_default().and_then(|history| history.jobs()).ok()?;
+    jobs.iter()
+        .rev()
+        .find(|job| job.zip_sha256.as_deref() == Some(hash.as_str()))
+        .map(Provenance::from_job)
+}
+
+/// Where a result came from, for the left-hand side of a comparison: the
+/// history's record of its job, or else just its ID.
+fn result_provenance(result_id: &str) -> Option<Provenance> {
+    let result_id = result_id.parse::<i64>().ok()?;
+    let jobs = History::open_default().and_then(|history| history.jobs()).unwrap_or_default();
+    let provenance = jobs
+        .iter()
+        .rev()
+        .find(|job| job.result_id == Some(result_id))
+        .map(Provenance::from_job)
+        .unwrap_or(Provenance {
+            result_id: Some(result_id),
+            ..Default::default()
+        });
+    Some(provenance)
+}
+
+/// Derive an export name from the input path (`walk.zip` -> `walk`).
+fn default_export_name(input: &str) -> String {
+    Path::new(input.trim_end_matches('/'))
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("animation")
+        .to_string()
 }
@@ -0,0 +1,231 @@
+//! The asset manifest of a [workspace](crate::workspace).
+//!
+//! `assets.json` at the project root maps logical asset names (`hero_walk`)
+//! to the GameTorch result they came from and the files they produced, each
+//! with its SHA-256. Build pipelines can check the files with
+//! `gametorch assets verify` and restore missing result ZIPs with
+//! `gametorch assets fetch`, so a checkout builds the same assets everywhere;
+//! `gametorch assets check` compares them with fresh renders.
+//!
+//! The CLI updates the manifest whenever it generates or exports inside a
+//! workspace. Assets are named after their prompt unless given a name with
+//! `generate --as hero_walk`; `gametorch assets update hero_walk` then
+//! regenerates the asset in place, so the game keeps referring to the same
+//! name and file. Paths are stored relative to the project root with `/`
+//! separators, so the file can be committed.
+
+use crate::workspace::Workspace;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File name of the manifest at a workspace root.
+pub const MANIFEST_FILE: &str = "assets.json";
+
+const VERSION: u32 = 1;
+
+/// A file produced for an asset.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetFile {
+    /// Relative to the project root.
+    pub path: String,
+    /// Hex SHA-256 of the contents.
+    pub sha256: String,
+}
+
+/// What the manifest records about one asset.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Asset {
+    pub animation_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// The downloaded result ZIP, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zip: Option<AssetFile>,
+    /// Files exported from the result (sheets, atlases, ...).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<AssetFile>,
+    /// Unix timestamp (seconds) of the last change.
+    pub updated_at: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ManifestFile {
+    version: u32,
+    assets: BTreeMap<String, Asset>,
+}
+
+/// A problem found by [`Manifest::verify`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    pub asset: String,
+    pub path: String,
+    /// `missing` or `modified`.
+    pub kind: &'static str,
+}
+
+/// The manifest of one workspace.
+#[derive(Clone, Debug)]
+pub struct Manifest {
+    root: PathBuf,
+    pub assets: BTreeMap<String, Asset>,
+}
+
+impl Manifest {
+    /// Load the manifest of `workspace`; a missing file is an empty manifest.
+    pub fn load(workspace: &Workspace) -> Result<Manifest, Box<dyn std::error::Error + Send + Sync>> {
+        let root = workspace.root.clone();
+        let path = root.join(MANIFEST_FILE);
+        let assets = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let file: ManifestFile =
+                    serde_json::from_slice(&bytes).map_err(|e| format!("invalid manifest {}: {}", path.display(), e))?;
+                if file.version > VERSION {
+                    return Err(format!("{} was written by a newer gametorch (version {})", path.display(), file.version).into());
+                }
+                file.assets
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("failed to read {}: {}", path.display(), e).into()),
+        };
+        Ok(Manifest { root, assets })
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.root.join(MANIFEST_FILE)
+    }
+
+    /// Describe `path` (absolute or relative to the current directory) as
+    /// an entry: its project-relative path and hash.
+    pub fn file(&self, path: &Path) -> Result<AssetFile, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(AssetFile {
+            sha256: hash_file(&std::path::absolute(path)?)?,
+            path: self.relative(path)?,
+        })
+    }
+
+    /// `path` (absolute or relative to the current directory) as the
+    /// manifest writes it: relative to the project root, `/`-separated.
+    pub fn relative(&self, path: &Path) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let absolute = std::path::absolute(path)?;
+        let relative = absolute
+            .strip_prefix(&self.root)
+            .map_err(|_| format!("{} is outside the workspace {}", path.display(), self.root.display()))?;
+        Ok(relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"))
+    }
+
+    /// Record a downloaded result ZIP as asset `name`, replacing whatever the
+    /// name referred to before.
+    pub fn set_result(
+        &mut self,
+        name: &str,
+        animation_id: i64,
+        result_id: Option<i64>,
+        prompt: Option<String>,
+        zip: &Path,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let zip = self.file(zip)?;
+        self.assets.insert(
+            name.to_string(),
+            Asset {
+                animation_id,
+                result_id,
+                prompt,
+                zip: Some(zip),
+                files: Vec::new(),
+                updated_at: crate::history::unix_now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Add exported files to asset `name`, replacing earlier entries for the
+    /// same paths. Does nothing if there is no such asset.
+    pub fn add_files(&mut self, name: &str, paths: &[PathBuf]) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.assets.contains_key(name) {
+            return Ok(false);
+        }
+        let files = paths.iter().map(|p| self.file(p)).collect::<Result<Vec<_>, _>>()?;
+        let asset = self.assets.get_mut(name).expect("checked above");
+        asset.files.retain(|old| !files.iter().any(|new| new.path == old.path));
+        asset.files.extend(files);
+        asset.files.sort_by(|a, b| a.path.cmp(&b.path));
+        asset.updated_at = crate::history::unix_now();
+        Ok(true)
+    }
+
+    /// The asset whose result ZIP is at `path`, if any.
+    pub fn asset_for_zip(&self, path: &Path) -> Option<&str> {
+        let file = self.file(path).ok()?;
+        self.assets
+            .iter()
+            .find(|(_, asset)| asset.zip.as_ref().is_some_and(|zip| zip.path == file.path))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Absolute path of a manifest file entry.
+    pub fn resolve(&self, file: &AssetFile) -> PathBuf {
+        self.root.join(&file.path)
+    }
+
+    /// Files that are missing or no longer match their hash.
+    pub fn verify(&self) -> Result<Vec<Problem>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut problems = Vec::new();
+        for (name, asset) in &self.assets {
+            for file in asset.zip.iter().chain(&asset.files) {
+                let path = self.resolve(file);
+                let kind = if !path.exists() {
+                    "missing"
+                } else if hash_file(&path)? != file.sha256 {
+                    "modified"
+                } else {
+                    continue;
+                };
+                problems.push(Problem {
+                    asset: name.clone(),
+                    path: file.path.clone(),
+                    kind,
+                });
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Write the manifest (via a temporary file and rename).
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let file = ManifestFile {
+            version: VERSION,
+            assets: self.assets.clone(),
+        };
+        let path = self.path();
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&file)? + "\n")?;
+        std::fs::rename(&tmp, &path).map_err(|e| format!("failed to write {}: {}", path.display(), e).into())
+    }
+}
+
+/// Check `name` can name an asset: letters, digits, `_` and `-`.
+pub fn validate_name(name: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("invalid asset name '{}' (use letters, digits, '_' and '-')", name).into());
+    }
+    Ok(())
+}
+
+/// Hex SHA-256 of a file's contents.
+pub fn hash_file(path: &Path) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let bytes = std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    Ok(hash_bytes(&bytes))
+}
+
+/// Hex SHA-256 of `bytes`.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,77 @@
+//! Named animation events ("markers") attached to frames.
+//!
+//! A markers file lists one event per line, naming zero-based frames:
+//!
+//! ```text
+//! # gameplay hooks for walk.zip
+//! hit: frame 7
+//! footstep: frames 3, 9
+//! ```
+//!
+//! Markers are stored in the `sequence.json` sidecar, so they follow the
+//! frames through processing steps, and are written into engine exports as
+//! animation events.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A named event fired when playback reaches `frame`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Marker {
+    pub name: String,
+    pub frame: usize,
+}
+
+/// Read a markers file from disk.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Marker>, Box<dyn std::error::Error + Send + Sync>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+/// Parse `name: frame N` / `name: frames A, B` lines, sorted by frame.
+///
+/// The `frame`/`frames` keyword is optional, and a YAML-style list
+/// (`footstep: [3, 9]`) is accepted too.
+pub fn parse(text: &str) -> Result<Vec<Marker>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut markers = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let invalid = |why: &str| format!("line {}: {} in '{}'", n + 1, why, line);
+
+        let (name, frames) = line.split_once(':').ok_or_else(|| invalid("expected 'name: frame N'"))?;
+        let name = name.trim().trim_matches(|c| c == '"' || c == '\'');
+        if name.is_empty() {
+            return Err(invalid("missing marker name").into());
+        }
+        let frames = frames.trim();
+        let frames = frames
+            .strip_prefix("frames")
+            .or_else(|| frames.strip_prefix("frame"))
+            .unwrap_or(frames)
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+
+        let mut any = false;
+        for frame in frames.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let frame = frame
+                .parse()
+                .map_err(|_| invalid(&format!("'{}' is not a frame number", frame)))?;
+            markers.push(Marker {
+                name: name.to_string(),
+                frame,
+            });
+            any = true;
+        }
+        if !any {
+            return Err(invalid("no frames listed").into());
+        }
+    }
+    markers.sort_by(|a, b| a.frame.cmp(&b.frame).then_with(|| a.name.cmp(&b.name)));
+    Ok(markers)
+}
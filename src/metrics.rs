@@ -0,0 +1,436 @@
+//! Objective image metrics for comparing and checking results.
+//!
+//! SSIM is computed on luma (over premultiplied colour, so fully transparent
+//! pixels compare equal regardless of their RGB) using 8x8 windows. PSNR is
+//! computed over all four RGBA channels. The changed-pixel fraction counts
+//! pixels whose channels differ by more than [`PIXEL_TOLERANCE`].
+//!
+//! [`quality`] computes single-sequence QA signals and flags frames that are
+//! statistical outliers compared with the rest of the animation.
+
+use crate::frames::{Frame, Sequence, DEFAULT_FPS};
+use image::{imageops, RgbaImage};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+const SSIM_WINDOW: u32 = 8;
+const SSIM_STRIDE: u32 = 4;
+
+/// Largest per-channel difference still counted as the same pixel, so
+/// compression noise does not show up as change.
+pub const PIXEL_TOLERANCE: u8 = 8;
+
+/// Modified z-score above which a frame is reported as an outlier
+/// (Iglewicz and Hoaglin's recommended cut-off).
+pub const OUTLIER_THRESHOLD: f64 = 3.5;
+
+/// Similarity between the same frame index in two sequences.
+#[derive(Clone, Debug, Serialize)]
+pub struct FrameDiff {
+    pub index: usize,
+    pub ssim: f64,
+    /// Peak signal-to-noise ratio in dB; `null` in JSON when frames are identical.
+    pub psnr: f64,
+    /// Fraction of pixels that differ, in `[0, 1]`.
+    pub changed: f64,
+}
+
+/// Frame-by-frame comparison of two sequences.
+#[derive(Clone, Debug, Serialize)]
+pub struct Comparison {
+    pub frames_a: usize,
+    pub frames_b: usize,
+    pub mean_ssim: f64,
+    /// PSNR of the mean squared error over all compared frames; `null` when
+    /// every frame is identical.
+    pub mean_psnr: f64,
+    pub mean_changed: f64,
+    pub frames: Vec<FrameDiff>,
+}
+
+/// Compare `a` and `b` frame by frame.
+///
+/// Only the overlapping prefix is compared when frame counts differ; frames of
+/// `b` are resized to match `a` when dimensions differ.
+pub fn compare(a: &Sequence, b: &Sequence) -> Comparison {
+    let mut total_mse = 0.0;
+    let frames: Vec<FrameDiff> = a
+        .frames
+        .iter()
+        .zip(&b.frames)
+        .enumerate()
+        .map(|(index, (fa, fb))| {
+            let fb = match_size(&fb.image, fa.image.width(), fa.image.height());
+            let frame_mse = mse(&fa.image, &fb);
+            total_mse += frame_mse;
+            FrameDiff {
+                index,
+                ssim: ssim(&fa.image, &fb),
+                psnr: psnr_from_mse(frame_mse),
+                changed: changed_fraction(&fa.image, &fb),
+            }
+        })
+        .collect();
+
+    let mean_ssim = mean(frames.iter().map(|f| f.ssim));
+    let mean_changed = mean(frames.iter().map(|f| f.changed));
+    // Pool the error over all frames rather than averaging dB values, which
+    // would be undefined whenever a single frame is identical.
+    let mean_psnr = if frames.is_empty() {
+        f64::NAN
+    } else {
+        psnr_from_mse(total_mse / frames.len() as f64)
+    };
+
+    Comparison {
+        frames_a: a.len(),
+        frames_b: b.len(),
+        mean_ssim,
+        mean_psnr,
+        mean_changed,
+        frames,
+    }
+}
+
+/// Structural similarity of two equally sized images, in `[-1, 1]`.
+pub fn ssim(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let (w, h) = a.dimensions();
+    let la = luma(a);
+    let lb = luma(b);
+
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let win_w = SSIM_WINDOW.min(w);
+    let win_h = SSIM_WINDOW.min(h);
+    let mut total = 0.0;
+    let mut windows = 0u32;
+
+    let mut y = 0;
+    while y + win_h <= h {
+        let mut x = 0;
+        while x + win_w <= w {
+            let n = (win_w * win_h) as f64;
+            let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for yy in y..y + win_h {
+                for xx in x..x + win_w {
+                    let i = (yy * w + xx) as usize;
+                    let (pa, pb) = (la[i], lb[i]);
+                    sa += pa;
+                    sb += pb;
+                    saa += pa * pa;
+                    sbb += pb * pb;
+                    sab += pa * pb;
+                }
+            }
+            let (ma, mb) = (sa / n, sb / n);
+            let va = saa / n - ma * ma;
+            let vb = sbb / n - mb * mb;
+            let cov = sab / n - ma * mb;
+            total += ((2.0 * ma * mb + C1) * (2.0 * cov + C2))
+                / ((ma * ma + mb * mb + C1) * (va + vb + C2));
+            windows += 1;
+            x += SSIM_STRIDE;
+        }
+        y += SSIM_STRIDE;
+    }
+
+    if windows == 0 { 1.0 } else { total / windows as f64 }
+}
+
+/// Peak signal-to-noise ratio in dB; infinite for identical images.
+pub fn psnr(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    psnr_from_mse(mse(a, b))
+}
+
+/// Mean squared error over all RGBA channels of two equally sized images.
+pub fn mse(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let mut sum = 0.0;
+    for (pa, pb) in a.as_raw().iter().zip(b.as_raw()) {
+        let d = *pa as f64 - *pb as f64;
+        sum += d * d;
+    }
+    sum / a.as_raw().len().max(1) as f64
+}
+
+/// Fraction of pixels of two equally sized images that differ by more than
+/// [`PIXEL_TOLERANCE`] in any channel.
+pub fn changed_fraction(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let changed = a
+        .pixels()
+        .zip(b.pixels())
+        .filter(|(pa, pb)| pa.0.iter().zip(pb.0).any(|(ca, cb)| ca.abs_diff(cb) > PIXEL_TOLERANCE))
+        .count();
+    changed as f64 / (a.width() as usize * a.height() as usize).max(1) as f64
+}
+
+fn psnr_from_mse(mse: f64) -> f64 {
+    if mse == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 / mse).log10()
+    }
+}
+
+/// QA signals for one frame.
+#[derive(Clone, Debug, Serialize)]
+pub struct FrameQuality {
+    pub index: usize,
+    /// Mean absolute luma difference to the closer of the neighbouring
+    /// frames. Smooth motion scores low; a frame that pops scores high.
+    pub flicker: f64,
+    /// Variance of the Laplacian of luma; higher is sharper.
+    pub sharpness: f64,
+    /// Distinct RGBA colours among visible pixels.
+    pub palette_size: usize,
+    /// Share of visible pixels that are isolated specks or faint haze
+    /// (alpha below 32).
+    pub alpha_noise: f64,
+    /// Metrics for which this frame is an outlier.
+    pub outliers: Vec<&'static str>,
+}
+
+/// QA report for a whole sequence.
+#[derive(Clone, Debug, Serialize)]
+pub struct QualityReport {
+    pub frame_count: usize,
+    /// Mean per-frame flicker.
+    pub flicker_score: f64,
+    pub mean_sharpness: f64,
+    /// Distinct colours across all frames.
+    pub palette_size: usize,
+    pub mean_alpha_noise: f64,
+    /// Indices of frames with at least one outlier metric.
+    pub outlier_frames: Vec<usize>,
+    pub frames: Vec<FrameQuality>,
+}
+
+/// Compute QA signals for every frame and flag outliers.
+pub fn quality(sequence: &Sequence) -> QualityReport {
+    let (w, h) = sequence.frame_size();
+    let lumas: Vec<Vec<f64>> = sequence
+        .frames
+        .iter()
+        .map(|f| luma(&match_size(&f.image, w, h)))
+        .collect();
+
+    let mut all_colors: HashSet<[u8; 4]> = HashSet::new();
+    let mut frames: Vec<FrameQuality> = sequence
+        .frames
+        .iter()
+        .enumerate()
+        .map(|(index, frame)| {
+            let colors = visible_colors(&frame.image);
+            let palette_size = colors.len();
+            all_colors.extend(colors);
+            FrameQuality {
+                index,
+                flicker: flicker(&lumas, index),
+                sharpness: laplacian_variance(&lumas[index], w, h),
+                palette_size,
+                alpha_noise: alpha_noise(&frame.image),
+                outliers: Vec::new(),
+            }
+        })
+        .collect();
+
+    let checks: [(&'static str, Vec<f64>); 4] = [
+        ("flicker", frames.iter().map(|f| f.flicker).collect()),
+        ("sharpness", frames.iter().map(|f| f.sharpness).collect()),
+        ("palette_size", frames.iter().map(|f| f.palette_size as f64).collect()),
+        ("alpha_noise", frames.iter().map(|f| f.alpha_noise).collect()),
+    ];
+    for (name, values) in &checks {
+        for (frame, z) in frames.iter_mut().zip(modified_z_scores(values)) {
+            if z.abs() > OUTLIER_THRESHOLD {
+                frame.outliers.push(name);
+            }
+        }
+    }
+
+    QualityReport {
+        frame_count: frames.len(),
+        flicker_score: mean(frames.iter().map(|f| f.flicker)),
+        mean_sharpness: mean(frames.iter().map(|f| f.sharpness)),
+        palette_size: all_colors.len(),
+        mean_alpha_noise: mean(frames.iter().map(|f| f.alpha_noise)),
+        outlier_frames: frames
+            .iter()
+            .filter(|f| !f.outliers.is_empty())
+            .map(|f| f.index)
+            .collect(),
+        frames,
+    }
+}
+
+/// Two-row sheet with `a` on top and `b` underneath, frame for frame.
+pub fn comparison_sheet(a: &Sequence, b: &Sequence) -> RgbaImage {
+    let (wa, ha) = a.frame_size();
+    let (wb, hb) = b.frame_size();
+    let (cell_w, cell_h) = (wa.max(wb), ha.max(hb));
+    let columns = a.len().max(b.len()) as u32;
+
+    let mut sheet = RgbaImage::new(cell_w * columns, cell_h * 2);
+    for (row, sequence) in [a, b].into_iter().enumerate() {
+        for (i, frame) in sequence.frames.iter().enumerate() {
+            let x = i as u32 * cell_w + (cell_w - frame.image.width()) / 2;
+            let y = row as u32 * cell_h + (cell_h - frame.image.height()) / 2;
+            imageops::replace(&mut sheet, &frame.image, x as i64, y as i64);
+        }
+    }
+    sheet
+}
+
+/// `a` and `b` played next to each other, `a` on the left.
+///
+/// Each frame takes the timing of `a` (or of `b` once `a` has run out); the
+/// shorter sequence holds its last frame until the longer one finishes.
+pub fn side_by_side(a: &Sequence, b: &Sequence) -> Sequence {
+    let (wa, ha) = a.frame_size();
+    let (wb, hb) = b.frame_size();
+    let (cell_w, cell_h) = (wa.max(wb), ha.max(hb));
+    let frames = (0..a.len().max(b.len()))
+        .map(|i| {
+            let mut image = RgbaImage::new(cell_w * 2, cell_h);
+            let mut duration_ms = None;
+            for (column, sequence) in [a, b].into_iter().enumerate() {
+                let Some(frame) = sequence.frames.get(i).or(sequence.frames.last()) else {
+                    continue;
+                };
+                let x = column as u32 * cell_w + (cell_w - frame.image.width()) / 2;
+                let y = (cell_h - frame.image.height()) / 2;
+                imageops::replace(&mut image, &frame.image, x as i64, y as i64);
+                if i < sequence.len() {
+                    duration_ms = duration_ms.or(Some(frame.duration_ms));
+                }
+            }
+            Frame {
+                image,
+                duration_ms: duration_ms.unwrap_or(1000 / DEFAULT_FPS),
+            }
+        })
+        .collect();
+    Sequence {
+        frames,
+        markers: Vec::new(),
+    }
+}
+
+fn luma(image: &RgbaImage) -> Vec<f64> {
+    image
+        .pixels()
+        .map(|p| {
+            let [r, g, b, a] = p.0;
+            let alpha = a as f64 / 255.0;
+            (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64) * alpha
+        })
+        .collect()
+}
+
+fn flicker(lumas: &[Vec<f64>], index: usize) -> f64 {
+    let diff = |other: &Vec<f64>| {
+        let current = &lumas[index];
+        current.iter().zip(other).map(|(a, b)| (a - b).abs()).sum::<f64>() / current.len().max(1) as f64
+    };
+    let prev = index.checked_sub(1).map(|i| diff(&lumas[i]));
+    let next = lumas.get(index + 1).map(diff);
+    match (prev, next) {
+        (Some(p), Some(n)) => p.min(n),
+        (Some(only), None) | (None, Some(only)) => only,
+        (None, None) => 0.0,
+    }
+}
+
+fn laplacian_variance(luma: &[f64], w: u32, h: u32) -> f64 {
+    let (w, h) = (w as usize, h as usize);
+    if w < 3 || h < 3 {
+        return 0.0;
+    }
+    let mut values = Vec::with_capacity((w - 2) * (h - 2));
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let i = y * w + x;
+            values.push(luma[i - 1] + luma[i + 1] + luma[i - w] + luma[i + w] - 4.0 * luma[i]);
+        }
+    }
+    let m = mean(values.iter().copied());
+    mean(values.iter().map(|v| (v - m) * (v - m)))
+}
+
+fn visible_colors(image: &RgbaImage) -> HashSet<[u8; 4]> {
+    image.pixels().filter(|p| p.0[3] > 0).map(|p| p.0).collect()
+}
+
+fn alpha_noise(image: &RgbaImage) -> f64 {
+    let (w, h) = image.dimensions();
+    let visible = |x: i64, y: i64| {
+        x >= 0 && y >= 0 && x < w as i64 && y < h as i64 && image.get_pixel(x as u32, y as u32).0[3] > 0
+    };
+
+    let mut total = 0usize;
+    let mut noisy = 0usize;
+    for (x, y, p) in image.enumerate_pixels() {
+        let alpha = p.0[3];
+        if alpha == 0 {
+            continue;
+        }
+        total += 1;
+        let (x, y) = (x as i64, y as i64);
+        let isolated = (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| (dx, dy) != (0, 0))
+            .all(|(dx, dy)| !visible(x + dx, y + dy));
+        if isolated || alpha < 32 {
+            noisy += 1;
+        }
+    }
+    if total == 0 { 0.0 } else { noisy as f64 / total as f64 }
+}
+
+/// Robust z-scores based on the median absolute deviation, falling back to
+/// the mean absolute deviation when more than half the values are equal.
+fn modified_z_scores(values: &[f64]) -> Vec<f64> {
+    let median = median(values);
+    let deviations: Vec<f64> = values.iter().map(|v| (v - median).abs()).collect();
+    let mad = median_of(deviations.clone());
+    if mad > 0.0 {
+        return values.iter().map(|v| 0.6745 * (v - median) / mad).collect();
+    }
+    let mean_ad = mean(deviations.iter().copied());
+    if mean_ad > 0.0 {
+        values.iter().map(|v| (v - median) / (1.253314 * mean_ad)).collect()
+    } else {
+        vec![0.0; values.len()]
+    }
+}
+
+fn median(values: &[f64]) -> f64 {
+    median_of(values.to_vec())
+}
+
+fn median_of(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+fn match_size(image: &RgbaImage, width: u32, height: u32) -> Cow<'_, RgbaImage> {
+    if image.dimensions() == (width, height) {
+        Cow::Borrowed(image)
+    } else {
+        Cow::Owned(imageops::resize(image, width, height, imageops::FilterType::Triangle))
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0usize), |(s, c), v| (s + v, c + 1));
+    if count == 0 { f64::NAN } else { sum / count as f64 }
+}
@@ -0,0 +1,313 @@
+//! An in-process stand-in for the GameTorch API, for tests.
+//!
+//! [`MockGametorch`] runs a [wiremock] server that plays out canned
+//! animation lifecycles: every `POST /api/animation` creates an animation
+//! that reports "generating" for [`Lifecycle::generating_polls`] polls and
+//! then completes (or fails), after which its result ZIP answers `202` for
+//! [`Lifecycle::zip_pending_polls`] polls (and `500` for
+//! [`Lifecycle::zip_server_errors`] more) before it can be downloaded. Point
+//! the library at [`uri`](MockGametorch::uri) as the base URL and
+//! authenticate with [`MOCK_API_KEY`]:
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! use gametorch::animations::{self, GenerateRequest};
+//! use gametorch::mock::{MockGametorch, MOCK_API_KEY};
+//!
+//! let mock = MockGametorch::start().await;
+//! let request = GenerateRequest::new("a knight walking");
+//! let json = animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, Some("knight.zip"), true).await?;
+//! assert_eq!(json["animation_id"], 1000);
+//! assert_eq!(mock.submitted().await, 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `POST /api/animation/regenerate/<id>` creates a new animation the same
+//! way.
+//!
+//! Endpoints the mock does not know, such as `/api/animation_models`, answer
+//! `404`, so the library falls back to its bundled tables. Requires the
+//! `test-util` feature.
+
+use crate::frames::{Frame, Sequence, DEFAULT_FPS};
+use image::{Rgba, RgbaImage};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// The API key the mock accepts; anything else gets `401`.
+pub const MOCK_API_KEY: &str = "gt_test_key";
+
+/// ID of the first animation the mock creates; later ones count up.
+pub const FIRST_ANIMATION_ID: i64 = 1000;
+
+/// How each animation the mock creates plays out.
+#[derive(Clone, Debug)]
+pub struct Lifecycle {
+    /// Results polls answered with status 1 (generating) before the last.
+    pub generating_polls: u32,
+    /// End in status 3 (failed and refunded) instead of 2 (complete).
+    pub fails: bool,
+    /// The first this many animations created fail even without `fails`.
+    pub failed_attempts: u32,
+    /// ZIP downloads answered with `202` before the ZIP is served.
+    pub zip_pending_polls: u32,
+    /// ZIP downloads answered with `500` after those, before the ZIP is
+    /// served.
+    pub zip_server_errors: u32,
+    /// The result ZIP; defaults to [`sample_zip`].
+    pub zip: Vec<u8>,
+    /// Seed reported with each result.
+    pub seed: u64,
+}
+
+impl Default for Lifecycle {
+    /// Completes on the first poll, with the ZIP ready straight away.
+    fn default() -> Self {
+        Lifecycle {
+            generating_polls: 0,
+            fails: false,
+            failed_attempts: 0,
+            zip_pending_polls: 0,
+            zip_server_errors: 0,
+            zip: sample_zip(),
+            seed: 42,
+        }
+    }
+}
+
+impl Lifecycle {
+    /// The ID of the result of animation `animation_id`.
+    pub fn result_id(animation_id: i64) -> i64 {
+        animation_id * 10
+    }
+
+    fn fails(&self, animation_id: i64) -> bool {
+        self.fails || animation_id - FIRST_ANIMATION_ID < i64::from(self.failed_attempts)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Animations created so far, with the results polls each has seen.
+    results_polls: HashMap<i64, u32>,
+    /// ZIP download attempts per result.
+    zip_polls: HashMap<i64, u32>,
+}
+
+/// A running mock API; shut down when dropped.
+pub struct MockGametorch {
+    server: MockServer,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockGametorch {
+    /// Start a mock whose animations follow the default [`Lifecycle`].
+    pub async fn start() -> MockGametorch {
+        MockGametorch::with_lifecycle(Lifecycle::default()).await
+    }
+
+    /// Start a mock whose animations follow `lifecycle`.
+    pub async fn with_lifecycle(lifecycle: Lifecycle) -> MockGametorch {
+        let server = MockServer::start().await;
+        let state = Arc::new(Mutex::new(State::default()));
+        let lifecycle = Arc::new(lifecycle);
+        Mock::given(method("POST"))
+            .and(path("/api/animation"))
+            .respond_with(Create { state: state.clone() })
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/api/animation/regenerate/\d+$"))
+            .respond_with(Create { state: state.clone() })
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/api/animation_results/\d+$"))
+            .respond_with(Results {
+                state: state.clone(),
+                lifecycle: lifecycle.clone(),
+            })
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/api/animation_result_zip/\d+$"))
+            .respond_with(Zip {
+                state: state.clone(),
+                lifecycle,
+            })
+            .mount(&server)
+            .await;
+        MockGametorch { server, state }
+    }
+
+    /// The base URL to pass to the library.
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The underlying server, for mounting extra endpoints.
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Every request received so far.
+    pub async fn requests(&self) -> Vec<Request> {
+        self.server.received_requests().await.unwrap_or_default()
+    }
+
+    /// Bodies of the generation requests received so far, as JSON (`null`
+    /// for multipart uploads).
+    pub async fn submissions(&self) -> Vec<Value> {
+        self.requests()
+            .await
+            .into_iter()
+            .filter(|r| r.method.as_str() == "POST" && r.url.path() == "/api/animation")
+            .map(|r| serde_json::from_slice(&r.body).unwrap_or(Value::Null))
+            .collect()
+    }
+
+    /// How many generation requests have been received.
+    pub async fn submitted(&self) -> usize {
+        self.submissions().await.len()
+    }
+
+    /// How many regenerations have been requested.
+    pub async fn regenerated(&self) -> usize {
+        self.requests()
+            .await
+            .iter()
+            .filter(|r| r.method.as_str() == "POST" && r.url.path().starts_with("/api/animation/regenerate/"))
+            .count()
+    }
+
+    /// How many times the results of `animation_id` have been polled.
+    pub fn results_polls(&self, animation_id: i64) -> u32 {
+        lock(&self.state).results_polls.get(&animation_id).copied().unwrap_or(0)
+    }
+
+    /// How many times the ZIP of `result_id` has been requested.
+    pub fn zip_polls(&self, result_id: i64) -> u32 {
+        lock(&self.state).zip_polls.get(&result_id).copied().unwrap_or(0)
+    }
+}
+
+/// A small result ZIP: four 64×64 frames, each a different shade.
+pub fn sample_zip() -> Vec<u8> {
+    let frames = (0..4u8)
+        .map(|i| Frame {
+            image: RgbaImage::from_pixel(64, 64, Rgba([64 * i, 128, 255 - 64 * i, 255])),
+            duration_ms: 1000 / DEFAULT_FPS,
+        })
+        .collect();
+    Sequence {
+        frames,
+        markers: Vec::new(),
+    }
+    .to_zip_bytes()
+    .expect("encoding the sample ZIP cannot fail")
+}
+
+struct Create {
+    state: Arc<Mutex<State>>,
+}
+
+impl Respond for Create {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        if let Some(denied) = unauthorized(request) {
+            return denied;
+        }
+        let mut state = lock(&self.state);
+        let animation_id = FIRST_ANIMATION_ID + state.results_polls.len() as i64;
+        state.results_polls.insert(animation_id, 0);
+        ResponseTemplate::new(200).set_body_json(json!({ "animation_id": animation_id }))
+    }
+}
+
+struct Results {
+    state: Arc<Mutex<State>>,
+    lifecycle: Arc<Lifecycle>,
+}
+
+impl Respond for Results {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        if let Some(denied) = unauthorized(request) {
+            return denied;
+        }
+        let Some(animation_id) = trailing_id(request) else {
+            return ResponseTemplate::new(404);
+        };
+        let mut state = lock(&self.state);
+        let Some(polls) = state.results_polls.get_mut(&animation_id) else {
+            return ResponseTemplate::new(404);
+        };
+        *polls += 1;
+        let status = match (*polls > self.lifecycle.generating_polls, self.lifecycle.fails(animation_id)) {
+            (false, _) => 1,
+            (true, false) => 2,
+            (true, true) => 3,
+        };
+        ResponseTemplate::new(200).set_body_json(json!([{
+            "id": Lifecycle::result_id(animation_id),
+            "animation_id": animation_id,
+            "status": status,
+            "seed": self.lifecycle.seed,
+        }]))
+    }
+}
+
+struct Zip {
+    state: Arc<Mutex<State>>,
+    lifecycle: Arc<Lifecycle>,
+}
+
+impl Respond for Zip {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        if let Some(denied) = unauthorized(request) {
+            return denied;
+        }
+        let Some(result_id) = trailing_id(request) else {
+            return ResponseTemplate::new(404);
+        };
+        let mut state = lock(&self.state);
+        let complete = state
+            .results_polls
+            .get(&(result_id / 10))
+            .is_some_and(|&polls| result_id % 10 == 0 && polls > self.lifecycle.generating_polls);
+        if !complete || self.lifecycle.fails(result_id / 10) {
+            return ResponseTemplate::new(404);
+        }
+        let polls = state.zip_polls.entry(result_id).or_default();
+        *polls += 1;
+        if *polls <= self.lifecycle.zip_pending_polls {
+            return ResponseTemplate::new(202).insert_header("Retry-After", "0");
+        }
+        if *polls <= self.lifecycle.zip_pending_polls + self.lifecycle.zip_server_errors {
+            return ResponseTemplate::new(500)
+                .insert_header("Retry-After", "0")
+                .set_body_string("zip storage unavailable");
+        }
+        ResponseTemplate::new(200)
+            .insert_header("Content-Type", "application/zip")
+            .set_body_bytes(self.lifecycle.zip.clone())
+    }
+}
+
+fn unauthorized(request: &Request) -> Option<ResponseTemplate> {
+    let expected = format!("Bearer {}", MOCK_API_KEY);
+    match request.headers.get("authorization").and_then(|v| v.to_str().ok()) {
+        Some(auth) if auth == expected => None,
+        _ => Some(ResponseTemplate::new(401).set_body_json(json!({ "detail": "invalid API key" }))),
+    }
+}
+
+fn trailing_id(request: &Request) -> Option<i64> {
+    request.url.path().rsplit('/').next()?.parse().ok()
+}
+
+fn lock(state: &Mutex<State>) -> std::sync::MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,153 @@
+//! What each animation model can do.
+//!
+//! Models differ in the durations they render and the input image sizes
+//! they take. Their capabilities come from the backend
+//! (`GET /api/animation_models`, no API key needed), or from a table bundled
+//! with this release when it cannot be reached, and every generation is
+//! [checked](Capabilities::check_duration) against them before anything is
+//! uploaded. Models neither table knows are left to the backend to judge,
+//! and [`GenerateRequest::skip_model_checks`](crate::animations::GenerateRequest::skip_model_checks)
+//! skips the checks when the tables are behind it.
+
+use crate::completions::DEFAULT_MODEL_NAME;
+use crate::error::{Error, ErrorKind};
+use crate::pricing::{Source, DEFAULT_MODEL_ID};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Capabilities known when this release was made.
+const BUNDLED: &[(u32, &str, &[u32], Option<u32>)] = &[(DEFAULT_MODEL_ID, DEFAULT_MODEL_NAME, &[5, 10], None)];
+
+/// What one model can do.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub model_id: u32,
+    pub model_name: String,
+    /// Durations it renders, in seconds.
+    pub durations: Vec<u32>,
+    /// Largest input image width and height, in pixels, if the backend
+    /// reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_resolution: Option<u32>,
+}
+
+impl Capabilities {
+    /// Fail unless the model renders `seconds`-long animations.
+    pub fn check_duration(&self, seconds: u32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.durations.contains(&seconds) {
+            return Ok(());
+        }
+        let allowed: Vec<String> = self.durations.iter().map(u32::to_string).collect();
+        Err(Error::new(
+            ErrorKind::Validation,
+            format!(
+                "model {} renders {} second animations, not {}",
+                self.model_name,
+                allowed.join(" or "),
+                seconds
+            ),
+        )
+        .into())
+    }
+
+    /// Fail if a `width`×`height` input image is too large for the model.
+    pub fn check_resolution(&self, width: u32, height: u32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(max_resolution) = self.max_resolution else {
+            return Ok(());
+        };
+        if width.max(height) <= max_resolution {
+            return Ok(());
+        }
+        Err(Error::new(
+            ErrorKind::Validation,
+            format!(
+                "input image is {}x{}; model {} takes at most {} pixels a side",
+                width, height, self.model_name, max_resolution
+            ),
+        )
+        .into())
+    }
+}
+
+/// Every known model.
+#[derive(Clone, Debug)]
+pub struct CapabilityTable {
+    pub source: Source,
+    pub models: Vec<Capabilities>,
+}
+
+impl CapabilityTable {
+    /// The table bundled with this release.
+    pub fn bundled() -> CapabilityTable {
+        CapabilityTable {
+            source: Source::Bundled,
+            models: BUNDLED
+                .iter()
+                .map(|&(model_id, model_name, durations, max_resolution)| Capabilities {
+                    model_id,
+                    model_name: model_name.to_string(),
+                    durations: durations.to_vec(),
+                    max_resolution,
+                })
+                .collect(),
+        }
+    }
+
+    /// A model by ID or name (the default model if neither is given), or
+    /// `None` if the table does not know it.
+    pub fn find(&self, model_id: Option<u32>, model_name: Option<&str>) -> Option<&Capabilities> {
+        let model_id = match (model_id, model_name) {
+            (None, None) => Some(DEFAULT_MODEL_ID),
+            (id, _) => id,
+        };
+        self.models.iter().find(|model| match (model_id, model_name) {
+            (Some(id), _) => model.model_id == id,
+            (None, Some(name)) => model.model_name == name,
+            (None, None) => false,
+        })
+    }
+}
+
+/// The last table fetched, with the base URL it came from; fetched once per
+/// process rather than once per generation.
+static CACHE: Mutex<Option<(String, CapabilityTable)>> = Mutex::new(None);
+
+/// The backend's model capabilities, or the bundled table if it cannot be
+/// reached.
+pub async fn table(base_url: &str) -> CapabilityTable {
+    if let Some((url, table)) = CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_ref()
+        && url == base_url
+    {
+        return table.clone();
+    }
+    let table = match fetch(base_url).await {
+        Ok(models) if !models.is_empty() => CapabilityTable {
+            source: Source::Backend,
+            models,
+        },
+        _ => return CapabilityTable::bundled(),
+    };
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = Some((base_url.to_string(), table.clone()));
+    table
+}
+
+/// Hits `GET /api/animation_models`, which answers with a list of
+/// [`Capabilities`] (bare or under `models`).
+async fn fetch(base_url: &str) -> Result<Vec<Capabilities>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let url = format!("{}/api/animation_models", base_url);
+
+    let json: Value = client
+        .send(client.get(&url))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let models = match json {
+        Value::Object(mut map) => map.remove("models").unwrap_or_default(),
+        other => other,
+    };
+    Ok(serde_json::from_value(models)?)
+}
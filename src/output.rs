@@ -0,0 +1,320 @@
+//! Rendering command results for people or programs.
+//!
+//! Every command produces a JSON value, printed in the [`OutputFormat`]
+//! selected with `--format`: pretty JSON (the default), YAML, CSV for
+//! spreadsheets, NDJSON (one compact JSON value per line, for `jq` and
+//! `xargs`) or an aligned table. Listings default to a table when stdout is a
+//! terminal. A JMESPath `--query` can pick out parts of the value first
+//! (`[].id`, `[?status=='complete'].{id: id, prompt: prompt}`).
+
+use serde_json::Value;
+use std::fmt;
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+/// How a command prints its result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Csv,
+    /// Newline-delimited JSON: one compact value per list element.
+    Ndjson,
+    Table,
+}
+
+impl OutputFormat {
+    pub const ALL: &'static [OutputFormat] = &[
+        OutputFormat::Json,
+        OutputFormat::Yaml,
+        OutputFormat::Csv,
+        OutputFormat::Ndjson,
+        OutputFormat::Table,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Table => "table",
+        }
+    }
+
+    /// The format to use when none was asked for: a table on a terminal,
+    /// JSON when piped.
+    pub fn detect() -> OutputFormat {
+        if std::io::stdout().is_terminal() {
+            OutputFormat::Table
+        } else {
+            OutputFormat::Json
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OutputFormat::ALL
+            .iter()
+            .copied()
+            .find(|f| f.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = OutputFormat::ALL.iter().map(|f| f.as_str()).collect();
+                format!("unknown format '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// An output format plus an optional JMESPath query applied before rendering.
+pub struct Output {
+    pub format: OutputFormat,
+    query: Option<jmespath::Expression<'static>>,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat, query: Option<&str>) -> Result<Output, String> {
+        let query = query
+            .map(jmespath::compile)
+            .transpose()
+            .map_err(|e| format!("invalid --query: {}", e))?;
+        Ok(Output { format, query })
+    }
+
+    pub fn has_query(&self) -> bool {
+        self.query.is_some()
+    }
+
+    /// `value` with the query applied.
+    pub fn select(&self, value: &Value) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(query) = &self.query else {
+            return Ok(value.clone());
+        };
+        let result = query
+            .search(value.clone())
+            .map_err(|e| format!("--query failed: {}", e))?;
+        Ok(serde_json::to_value(&*result)?)
+    }
+
+    /// Apply the query and render the result.
+    pub fn render(&self, value: &Value, color: bool) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        render(&self.select(value)?, self.format, color)
+    }
+}
+
+/// Render `value` as `format`, ending in a newline.
+///
+/// Lists (JSON arrays) become one CSV/table row or NDJSON line per element,
+/// with a column per key; a single object becomes one row (CSV), one line
+/// (NDJSON) or a two-column key/value table. Nested values are written as
+/// compact JSON.
+pub fn render(
+    value: &Value,
+    format: OutputFormat,
+    color: bool,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value)? + "\n",
+        OutputFormat::Yaml => serde_yaml::to_string(value)?,
+        OutputFormat::Ndjson => match value {
+            Value::Array(items) => items.iter().map(|item| item.to_string() + "\n").collect(),
+            other => other.to_string() + "\n",
+        },
+        OutputFormat::Csv => {
+            let (headers, rows) = rows(value);
+            std::iter::once(headers)
+                .chain(rows)
+                .map(|row| row.iter().map(|cell| csv_escape(cell)).collect::<Vec<_>>().join(",") + "\n")
+                .collect()
+        }
+        OutputFormat::Table => match value {
+            Value::Object(map) => {
+                let rows: Vec<Vec<String>> = map.iter().map(|(k, v)| vec![k.clone(), cell(v)]).collect();
+                table(&["KEY".to_string(), "VALUE".to_string()], &rows, color)
+            }
+            value => {
+                let (headers, rows) = rows(value);
+                let headers: Vec<String> = headers.iter().map(|h| h.to_uppercase()).collect();
+                table(&headers, &rows, color)
+            }
+        },
+    })
+}
+
+/// Column names (keys in first-seen order) and cells of a list.
+fn rows(value: &Value) -> (Vec<String>, Vec<Vec<String>>) {
+    let items: Vec<&Value> = match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    let mut headers: Vec<String> = Vec::new();
+    for item in &items {
+        match item {
+            Value::Object(map) => {
+                for key in map.keys() {
+                    if !headers.contains(key) {
+                        headers.push(key.clone());
+                    }
+                }
+            }
+            _ if !headers.iter().any(|h| h == "value") => headers.push("value".to_string()),
+            _ => {}
+        }
+    }
+    let rows = items
+        .iter()
+        .map(|item| {
+            headers
+                .iter()
+                .map(|h| match item {
+                    Value::Object(map) => map.get(h).map(cell).unwrap_or_default(),
+                    other if h == "value" => cell(other),
+                    _ => String::new(),
+                })
+                .collect()
+        })
+        .collect();
+    (headers, rows)
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Whether to colour terminal output (honours `NO_COLOR`).
+pub fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// Longest prompt shown in place of a missing name.
+const MAX_NAME_CHARS: usize = 40;
+
+/// An animation listing as a table of ID, name, status, duration, model and
+/// creation time.
+pub fn animation_table(list: &Value, color: bool) -> String {
+    let items: Vec<&Value> = match list {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    let headers: Vec<String> = ["ID", "NAME", "STATUS", "DURATION", "MODEL", "CREATED"]
+        .map(str::to_string)
+        .to_vec();
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .map(|item| {
+            vec![
+                field(item, &["id"]),
+                name(item),
+                status(item),
+                field(item, &["duration_seconds", "duration"])
+                    .parse::<f64>()
+                    .map(|s| format!("{}s", s))
+                    .unwrap_or_default(),
+                field(item, &["model_name", "model", "model_id"]),
+                field(item, &["created_at"]),
+            ]
+        })
+        .collect();
+    table(&headers, &rows, color)
+}
+
+fn table(headers: &[String], rows: &[Vec<String>], color: bool) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    let header: Vec<String> = headers
+        .iter()
+        .zip(&widths)
+        .map(|(h, &w)| paint(&pad(h, w), "1", color))
+        .collect();
+    out.push_str(header.join("  ").trim_end());
+    out.push('\n');
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .zip(headers)
+            .map(|((cell, &w), h)| {
+                let padded = pad(cell, w);
+                match (h.as_str(), cell.as_str()) {
+                    ("STATUS", "complete") => paint(&padded, "32", color),
+                    ("STATUS", "generating") => paint(&padded, "33", color),
+                    ("STATUS", "failed") => paint(&padded, "31", color),
+                    _ => padded,
+                }
+            })
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.chars().count())))
+}
+
+/// Wrap `text` in an ANSI SGR sequence (`code`) when colouring.
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// The first of `keys` present on `item`, as text.
+fn field(item: &Value, keys: &[&str]) -> String {
+    keys.iter()
+        .find_map(|key| match item.get(key)? {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        })
+        .unwrap_or_default()
+}
+
+fn name(item: &Value) -> String {
+    let name = field(item, &["name"]);
+    if !name.is_empty() {
+        return name;
+    }
+    let prompt = field(item, &["prompt"]);
+    if prompt.chars().count() > MAX_NAME_CHARS {
+        let cut: String = prompt.chars().take(MAX_NAME_CHARS - 1).collect();
+        format!("{}…", cut)
+    } else {
+        prompt
+    }
+}
+
+fn status(item: &Value) -> String {
+    match crate::animations::Status::of(item) {
+        Some(status) => status.to_string(),
+        None => field(item, &["status"]),
+    }
+}
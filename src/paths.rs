@@ -0,0 +1,21 @@
+//! Where the CLI keeps local state.
+//!
+//! Everything lives under one data directory: `$GAMETORCH_HOME` if set,
+//! otherwise `.gametorch` in the user's home directory.
+
+use std::path::PathBuf;
+
+/// Environment variable overriding the data directory.
+pub const HOME_ENV: &str = "GAMETORCH_HOME";
+
+/// The data directory (not created).
+pub fn data_dir() -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(dir) = std::env::var_os(HOME_ENV).filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|d| !d.is_empty())
+        .map(|home| PathBuf::from(home).join(".gametorch"))
+        .ok_or_else(|| format!("cannot find a home directory; set {}", HOME_ENV).into())
+}
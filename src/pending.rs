@@ -0,0 +1,108 @@
+//! Generation requests queued while offline.
+//!
+//! `generate --offline` appends the request to `pending.jsonl` in the
+//! [data directory](crate::paths::data_dir) instead of submitting it;
+//! `gametorch flush` submits the queue later, keeping whatever still fails
+//! for the next attempt.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// File name of the queue inside the data directory.
+pub const PENDING_FILE: &str = "pending.jsonl";
+
+/// A generation request waiting to be submitted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingJob {
+    pub prompt: String,
+    pub duration_seconds: u32,
+    /// Input image file (absolute) or URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_image: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub preprocess: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compress: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_model_checks: bool,
+    /// Unix timestamp (seconds) the request was queued.
+    pub queued_at: u64,
+}
+
+/// The queue file at a given path.
+#[derive(Clone, Debug)]
+pub struct Queue {
+    path: PathBuf,
+}
+
+impl Queue {
+    pub fn new(path: impl Into<PathBuf>) -> Queue {
+        Queue { path: path.into() }
+    }
+
+    /// The queue in the default data directory.
+    pub fn open_default() -> Result<Queue, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Queue::new(crate::paths::data_dir()?.join(PENDING_FILE)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a request to the queue.
+    pub fn push(&self, job: &PendingJob) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let line = serde_json::to_string(job)? + "\n";
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| format!("failed to write {}: {}", self.path.display(), e).into())
+    }
+
+    /// Queued requests, oldest first. A missing file is an empty queue.
+    pub fn jobs(&self) -> Result<Vec<PendingJob>, Box<dyn std::error::Error + Send + Sync>> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("failed to read {}: {}", self.path.display(), e).into()),
+        };
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| format!("invalid entry in {}: {}", self.path.display(), e).into()))
+            .collect()
+    }
+
+    /// Replace the queue with `jobs` (via a temporary file and rename);
+    /// an empty list removes the file.
+    pub fn replace(&self, jobs: &[PendingJob]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if jobs.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        let mut text = String::new();
+        for job in jobs {
+            text.push_str(&serde_json::to_string(job)?);
+            text.push('\n');
+        }
+        let tmp = self.path.with_extension("jsonl.tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, &self.path).map_err(|e| format!("failed to write {}: {}", self.path.display(), e).into())
+    }
+}
@@ -0,0 +1,460 @@
+//! Chained post-processing.
+//!
+//! A [`Pipeline`] is an ordered list of [`Step`]s run over one sequence:
+//! processing steps modify the frames in place, `pack` sets the sheet layout
+//! for later exports, and `export`/`save` write results. Pipelines are built
+//! in code or loaded from YAML:
+//!
+//! ```yaml
+//! steps:
+//!   - trim: { margin: 2 }
+//!   - remove-bg: { color: "#00ff00", tolerance: 30 }
+//!   - palettize: { colors: 16 }
+//!   - pack: { columns: 8, padding: 1 }
+//!   - export: { target: godot, output_dir: assets/sprites }
+//! ```
+//!
+//! Steps without options may be written as a bare name (`- trim`), and steps
+//! with a single obvious option take it directly (`- fps: 12`,
+//! `- export: godot`, `- save: out.zip`). Paths are relative to the working
+//! directory.
+
+use crate::export::{self, ExportOptions, Target};
+use crate::frames::Sequence;
+use crate::markers;
+use crate::process::{
+    self, Backend, BackgroundOptions, DedupeOptions, EditList, FixMode, LoopMode, Palette,
+    QuantizeMethod, ScaleOptions,
+};
+use crate::sheet::PackOptions;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Where a palettize step gets its palette.
+#[derive(Clone, Debug)]
+pub enum PaletteSource {
+    Quantize { colors: usize, method: QuantizeMethod },
+    File(PathBuf),
+}
+
+/// One pipeline step.
+#[derive(Clone, Debug)]
+pub enum Step {
+    Trim { margin: u32 },
+    RemoveBackground(BackgroundOptions),
+    Palettize(PaletteSource),
+    Scale(ScaleOptions),
+    Dedupe(DedupeOptions),
+    Loop { mode: LoopMode, frames: Option<usize> },
+    FixFrames(FixMode),
+    Markers(PathBuf),
+    EditList(PathBuf),
+    Fps(u32),
+    /// Sheet layout for the `Export` steps that follow.
+    Pack(PackOptions),
+    Export {
+        target: Target,
+        output_dir: PathBuf,
+        /// Base name for written files; defaults to the run's name.
+        name: Option<String>,
+        gamemaker_yy: bool,
+    },
+    /// Write the frames as they are at this point (ZIP or directory).
+    Save(PathBuf),
+}
+
+impl Step {
+    /// The step's name as written in pipeline files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Step::Trim { .. } => "trim",
+            Step::RemoveBackground(_) => "remove-bg",
+            Step::Palettize(_) => "palettize",
+            Step::Scale(_) => "scale",
+            Step::Dedupe(_) => "dedupe",
+            Step::Loop { .. } => "loop",
+            Step::FixFrames(_) => "fix-frames",
+            Step::Markers(_) => "markers",
+            Step::EditList(_) => "edit-list",
+            Step::Fps(_) => "fps",
+            Step::Pack(_) => "pack",
+            Step::Export { .. } => "export",
+            Step::Save(_) => "save",
+        }
+    }
+}
+
+/// An ordered list of steps; see the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct Pipeline {
+    pub steps: Vec<Step>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    pub fn step(mut self, step: Step) -> Pipeline {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn trim(self, margin: u32) -> Pipeline {
+        self.step(Step::Trim { margin })
+    }
+
+    pub fn remove_background(self, options: BackgroundOptions) -> Pipeline {
+        self.step(Step::RemoveBackground(options))
+    }
+
+    pub fn palettize(self, source: PaletteSource) -> Pipeline {
+        self.step(Step::Palettize(source))
+    }
+
+    pub fn scale(self, options: ScaleOptions) -> Pipeline {
+        self.step(Step::Scale(options))
+    }
+
+    pub fn pack(self, options: PackOptions) -> Pipeline {
+        self.step(Step::Pack(options))
+    }
+
+    pub fn export(self, target: Target, output_dir: impl Into<PathBuf>) -> Pipeline {
+        self.step(Step::Export {
+            target,
+            output_dir: output_dir.into(),
+            name: None,
+            gamemaker_yy: false,
+        })
+    }
+
+    pub fn save(self, path: impl Into<PathBuf>) -> Pipeline {
+        self.step(Step::Save(path.into()))
+    }
+
+    /// Run every per-pixel step on `backend`.
+    pub fn with_backend(mut self, backend: Backend) -> Pipeline {
+        for step in &mut self.steps {
+            if let Step::RemoveBackground(options) = step {
+                options.backend = backend;
+            }
+        }
+        self
+    }
+
+    /// Read a pipeline file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Pipeline, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Parse pipeline YAML (or JSON).
+    pub fn parse(text: &str) -> Result<Pipeline, Box<dyn std::error::Error + Send + Sync>> {
+        let file: PipelineFile = serde_yaml::from_str(text)?;
+        Self::from_values(&file.steps)
+    }
+
+    /// Build a pipeline from already-parsed step entries (a bare name or a
+    /// single-key map of name to options).
+    pub fn from_values(values: &[serde_yaml::Value]) -> Result<Pipeline, Box<dyn std::error::Error + Send + Sync>> {
+        let steps = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| parse_step(value).map_err(|e| format!("step {}: {}", i + 1, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if steps.is_empty() {
+            return Err("pipeline has no steps".into());
+        }
+        Ok(Pipeline { steps })
+    }
+
+    /// Run every step over `sequence`, returning one report per step.
+    ///
+    /// `name` is the default base name for exported files.
+    pub fn run(
+        &self,
+        sequence: &mut Sequence,
+        name: &str,
+    ) -> Result<Vec<Value>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut pack = PackOptions::default();
+        let mut reports = Vec::with_capacity(self.steps.len());
+        for (i, step) in self.steps.iter().enumerate() {
+            let report = run_step(step, sequence, name, &mut pack)
+                .map_err(|e| format!("step {} ({}): {}", i + 1, step.name(), e))?;
+            reports.push(serde_json::json!({
+                "step": step.name(),
+                "frames": sequence.len(),
+                "report": report,
+            }));
+        }
+        Ok(reports)
+    }
+}
+
+fn run_step(
+    step: &Step,
+    sequence: &mut Sequence,
+    name: &str,
+    pack: &mut PackOptions,
+) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match step {
+        Step::Trim { margin } => serde_json::json!(process::trim(sequence, *margin)?),
+        Step::RemoveBackground(options) => serde_json::json!(process::remove_background(sequence, options)?),
+        Step::Palettize(source) => {
+            let palette = match source {
+                PaletteSource::Quantize { colors, method } => Palette::quantize(sequence, *colors, *method)?,
+                PaletteSource::File(path) => Palette::load(path)?,
+            };
+            serde_json::json!(process::palettize(sequence, &palette))
+        }
+        Step::Scale(options) => serde_json::json!(process::scale(sequence, options)?),
+        Step::Dedupe(options) => serde_json::json!(process::dedupe(sequence, options)?),
+        Step::Loop { mode, frames } => serde_json::json!(process::make_loop(sequence, *mode, *frames)?),
+        Step::FixFrames(mode) => serde_json::json!(process::fix_frames(sequence, *mode)),
+        Step::Markers(path) => {
+            sequence.set_markers(markers::load(path)?)?;
+            serde_json::json!(sequence.markers)
+        }
+        Step::EditList(path) => {
+            *sequence = EditList::load(path)?.apply(sequence)?;
+            Value::Null
+        }
+        Step::Fps(fps) => {
+            if *fps == 0 {
+                return Err("fps must be greater than zero".into());
+            }
+            sequence.set_fps(*fps);
+            Value::Null
+        }
+        Step::Pack(options) => {
+            *pack = options.clone();
+            Value::Null
+        }
+        Step::Export { target, output_dir, name: export_name, gamemaker_yy } => {
+            let options = ExportOptions {
+                name: export_name.clone().unwrap_or_else(|| name.to_string()),
+                pack: pack.clone(),
+                gamemaker_yy: *gamemaker_yy,
+                ..Default::default()
+            };
+            let exported = export::export(sequence, *target, output_dir, &options)?;
+            let files: Vec<_> = exported.files.iter().map(|p| p.display().to_string()).collect();
+            serde_json::json!({ "target": target.as_str(), "files": files })
+        }
+        Step::Save(path) => {
+            sequence.save(path)?;
+            serde_json::json!({ "output": path.display().to_string() })
+        }
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PipelineFile {
+    steps: Vec<serde_yaml::Value>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TrimStep {
+    margin: u32,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RemoveBgStep {
+    color: Option<String>,
+    tolerance: Option<f32>,
+    softness: Option<f32>,
+    backend: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PalettizeStep {
+    colors: Option<usize>,
+    method: Option<String>,
+    palette: Option<PathBuf>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ScaleStep {
+    factor: Option<f32>,
+    filter: Option<String>,
+    pixel_snap: bool,
+    grid: Option<u32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DedupeStep {
+    min_psnr: Option<f64>,
+    fps: Option<u32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LoopStep {
+    mode: Option<String>,
+    frames: Option<usize>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FixStep {
+    mode: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PackStep {
+    columns: Option<u32>,
+    padding: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExportStep {
+    target: String,
+    #[serde(default)]
+    output_dir: Option<PathBuf>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    yy: bool,
+}
+
+fn parse_step(value: &serde_yaml::Value) -> Result<Step, Box<dyn std::error::Error + Send + Sync>> {
+    use serde_yaml::Value as Yaml;
+
+    let (name, options) = match value {
+        Yaml::String(name) => (name.as_str(), Yaml::Null),
+        Yaml::Mapping(map) if map.len() == 1 => {
+            let (key, options) = map.iter().next().expect("map has one entry");
+            let name = key.as_str().ok_or("step name must be a string")?;
+            (name, options.clone())
+        }
+        _ => return Err("expected a step name or a single `name: options` entry".into()),
+    };
+
+    Ok(match name {
+        "trim" => Step::Trim {
+            margin: options_or_default::<TrimStep>(options)?.margin,
+        },
+        "remove-bg" => {
+            let o: RemoveBgStep = options_or_default(options)?;
+            let defaults = BackgroundOptions::default();
+            Step::RemoveBackground(BackgroundOptions {
+                color: parse_opt(o.color)?,
+                tolerance: o.tolerance.unwrap_or(defaults.tolerance),
+                softness: o.softness.unwrap_or(defaults.softness),
+                backend: parse_opt(o.backend)?.unwrap_or_default(),
+            })
+        }
+        "palettize" => {
+            let o: PalettizeStep = options_or_default(options)?;
+            match (o.palette, o.colors) {
+                (Some(_), Some(_)) => return Err("palettize takes either colors or palette, not both".into()),
+                (Some(path), None) => Step::Palettize(PaletteSource::File(path)),
+                (None, Some(colors)) => Step::Palettize(PaletteSource::Quantize {
+                    colors,
+                    method: parse_opt(o.method)?.unwrap_or_default(),
+                }),
+                (None, None) => return Err("palettize needs colors or palette".into()),
+            }
+        }
+        "scale" => {
+            let o: ScaleStep = options_or_default(options)?;
+            let defaults = ScaleOptions::default();
+            Step::Scale(ScaleOptions {
+                factor: o.factor.unwrap_or(defaults.factor),
+                filter: parse_opt(o.filter)?.unwrap_or_default(),
+                pixel_snap: o.pixel_snap,
+                grid: o.grid,
+            })
+        }
+        "dedupe" => {
+            let o: DedupeStep = options_or_default(options)?;
+            Step::Dedupe(DedupeOptions {
+                min_psnr: o.min_psnr.unwrap_or(process::DEFAULT_MIN_PSNR),
+                fps: o.fps,
+            })
+        }
+        "loop" => {
+            let o: LoopStep = options_or_default(options)?;
+            Step::Loop {
+                mode: parse_opt(o.mode)?.unwrap_or_default(),
+                frames: o.frames,
+            }
+        }
+        "fix-frames" => {
+            let o: FixStep = options_or_default(options)?;
+            Step::FixFrames(parse_opt(o.mode)?.unwrap_or_default())
+        }
+        "markers" => Step::Markers(scalar(options, "file")?),
+        "edit-list" => Step::EditList(scalar(options, "file")?),
+        "fps" => Step::Fps(scalar(options, "fps")?),
+        "save" => Step::Save(scalar(options, "path")?),
+        "pack" => {
+            let o: PackStep = options_or_default(options)?;
+            Step::Pack(PackOptions {
+                columns: o.columns,
+                padding: o.padding,
+            })
+        }
+        "export" => {
+            let o: ExportStep = match options {
+                serde_yaml::Value::String(target) => ExportStep {
+                    target,
+                    output_dir: None,
+                    name: None,
+                    yy: false,
+                },
+                options => serde_yaml::from_value(options)?,
+            };
+            Step::Export {
+                target: o.target.parse::<Target>()?,
+                output_dir: o.output_dir.unwrap_or_else(|| PathBuf::from(".")),
+                name: o.name,
+                gamemaker_yy: o.yy,
+            }
+        }
+        other => return Err(format!("unknown step '{}'", other).into()),
+    })
+}
+
+fn options_or_default<T: DeserializeOwned + Default>(
+    options: serde_yaml::Value,
+) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    if options.is_null() {
+        Ok(T::default())
+    } else {
+        Ok(serde_yaml::from_value(options)?)
+    }
+}
+
+/// A step's single required option, given directly or as `{ key: value }`.
+fn scalar<T: DeserializeOwned>(
+    options: serde_yaml::Value,
+    key: &str,
+) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    let value = match options {
+        serde_yaml::Value::Mapping(mut map) if map.len() == 1 => map
+            .remove(key)
+            .ok_or_else(|| format!("expected `{}`", key))?,
+        serde_yaml::Value::Null => return Err(format!("missing `{}`", key).into()),
+        other => other,
+    };
+    Ok(serde_yaml::from_value(value)?)
+}
+
+fn parse_opt<T: FromStr<Err = String>>(value: Option<String>) -> Result<Option<T>, String> {
+    value.map(|v| v.parse()).transpose()
+}
@@ -0,0 +1,124 @@
+//! Polling until something is ready.
+//!
+//! Waiting for a render and waiting for its ZIP are the same loop: ask,
+//! sleep, ask again. [`poll_until`] runs it with the interval, backoff and
+//! timeout from [`PollOptions`], and gives up with a [`PollTimeout`] that
+//! says how long it waited and what it last saw.
+
+use crate::clock::{self, Instant};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+/// What one poll found.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Poll<T> {
+    /// Done, with the value to return.
+    Ready(T),
+    /// Not yet; the status seen (reported if the wait times out).
+    Pending(String),
+    /// Not yet, and the server said when to ask again (`Retry-After`); used
+    /// instead of the next scheduled delay.
+    RetryAfter(String, Duration),
+}
+
+/// How often and how long to poll.
+#[derive(Clone, Debug)]
+pub struct PollOptions {
+    /// Delay before the second poll.
+    pub interval: Duration,
+    /// Each further delay is the previous one times `multiplier`.
+    pub multiplier: f64,
+    /// Upper bound for any single delay.
+    pub max_interval: Duration,
+    /// Give up once this much time has passed; `None` polls forever.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for PollOptions {
+    /// Every 5 seconds, forever.
+    fn default() -> Self {
+        PollOptions {
+            interval: Duration::from_secs(5),
+            multiplier: 1.0,
+            max_interval: Duration::from_secs(5),
+            timeout: None,
+        }
+    }
+}
+
+impl PollOptions {
+    /// Poll every `interval`.
+    pub fn every(interval: Duration) -> PollOptions {
+        PollOptions {
+            interval,
+            max_interval: interval,
+            ..Default::default()
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> PollOptions {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Grow the delay by `multiplier` after each poll, up to `max_interval`.
+    pub fn backoff(mut self, multiplier: f64, max_interval: Duration) -> PollOptions {
+        self.multiplier = multiplier;
+        self.max_interval = max_interval;
+        self
+    }
+}
+
+/// The error returned when [`poll_until`] runs out of time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PollTimeout {
+    pub elapsed: Duration,
+    /// What the last poll saw.
+    pub last_status: String,
+}
+
+impl fmt::Display for PollTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out after {} seconds (last status: {})", self.elapsed.as_secs(), self.last_status)
+    }
+}
+
+impl std::error::Error for PollTimeout {}
+
+/// Call `poll` until it is [`Ready`](Poll::Ready), sleeping between calls as
+/// `options` say. `poll` gets the time elapsed since the first call (zero
+/// for the first call itself); its errors end the wait.
+pub async fn poll_until<T, F, Fut>(options: &PollOptions, mut poll: F) -> Result<T, Box<dyn std::error::Error + Send + Sync>>
+where
+    F: FnMut(Duration) -> Fut,
+    Fut: Future<Output = Result<Poll<T>, Box<dyn std::error::Error + Send + Sync>>>,
+{
+    let start = Instant::now();
+    let mut elapsed = Duration::ZERO;
+    let mut delay = options.interval;
+    loop {
+        let (status, retry_after) = match poll(elapsed).await? {
+            Poll::Ready(value) => return Ok(value),
+            Poll::Pending(status) => (status, None),
+            Poll::RetryAfter(status, after) => (status, Some(after)),
+        };
+        if let Some(timeout) = options.timeout
+            && start.elapsed() >= timeout
+        {
+            return Err(PollTimeout {
+                elapsed: start.elapsed(),
+                last_status: status,
+            }
+            .into());
+        }
+        match retry_after {
+            Some(after) => clock::sleep(after).await,
+            None => {
+                clock::sleep(delay).await;
+                delay = delay.mul_f64(options.multiplier.max(1.0)).min(options.max_interval.max(options.interval));
+            }
+        }
+        elapsed = start.elapsed();
+    }
+}
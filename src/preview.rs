@@ -0,0 +1,295 @@
+//! Showing result frames in the terminal.
+//!
+//! `animations preview` draws the first frame of a result (or plays every
+//! frame) with the best graphics protocol the terminal supports: the kitty
+//! graphics protocol, iTerm2 inline images or sixel. Anywhere else it falls
+//! back to Unicode half blocks in 24-bit colour, two pixels per cell.
+//!
+//! Terminals are [detected](Protocol::detect) from the environment they set;
+//! `--protocol` overrides the guess.
+
+use crate::frames::Sequence;
+use base64::{engine::general_purpose, Engine as _};
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Default preview width in terminal columns.
+pub const DEFAULT_COLUMNS: u32 = 40;
+
+/// Assumed size of a terminal cell in pixels, for protocols that draw in
+/// pixels (sixel) or need a row count up front.
+const CELL_WIDTH: u32 = 10;
+const CELL_HEIGHT: u32 = 20;
+
+/// Largest base64 payload per kitty escape sequence.
+const KITTY_CHUNK: usize = 4096;
+
+/// How an image is drawn in the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Protocol {
+    /// Pick one from the environment.
+    #[default]
+    Auto,
+    Kitty,
+    Iterm,
+    Sixel,
+    /// Unicode half blocks; works in any terminal with 24-bit colour.
+    Blocks,
+}
+
+impl Protocol {
+    pub const ALL: &'static [Protocol] = &[
+        Protocol::Auto,
+        Protocol::Kitty,
+        Protocol::Iterm,
+        Protocol::Sixel,
+        Protocol::Blocks,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::Auto => "auto",
+            Protocol::Kitty => "kitty",
+            Protocol::Iterm => "iterm",
+            Protocol::Sixel => "sixel",
+            Protocol::Blocks => "blocks",
+        }
+    }
+
+    /// The protocol of the terminal we are running in, judged by `TERM`,
+    /// `TERM_PROGRAM` and friends; [`Protocol::Blocks`] when unsure.
+    pub fn detect() -> Protocol {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+        if term == "xterm-kitty" || std::env::var_os("KITTY_WINDOW_ID").is_some() || program == "ghostty" {
+            Protocol::Kitty
+        } else if program == "iTerm.app" || program == "WezTerm" || std::env::var_os("ITERM_SESSION_ID").is_some() {
+            Protocol::Iterm
+        } else if term.contains("sixel") || term.starts_with("foot") || term == "mlterm" || program == "mlterm" {
+            Protocol::Sixel
+        } else {
+            Protocol::Blocks
+        }
+    }
+
+    /// This protocol, with [`Protocol::Auto`] resolved.
+    pub fn resolve(self) -> Protocol {
+        match self {
+            Protocol::Auto => Protocol::detect(),
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Protocol::ALL
+            .iter()
+            .copied()
+            .find(|p| p.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = Protocol::ALL.iter().map(|p| p.as_str()).collect();
+                format!("unknown protocol '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Terminal rows an image `columns` cells wide takes up.
+pub fn rows(image: &RgbaImage, columns: u32) -> u32 {
+    let (width, height) = image.dimensions();
+    let pixel_width = u64::from(columns * CELL_WIDTH);
+    let pixel_height = (pixel_width * u64::from(height)).div_ceil(u64::from(width.max(1)));
+    pixel_height.div_ceil(u64::from(CELL_HEIGHT)).max(1) as u32
+}
+
+/// The escape sequences (or text) drawing `image` `columns` cells wide,
+/// leaving the cursor on the line below it.
+pub fn render(
+    image: &RgbaImage,
+    protocol: Protocol,
+    columns: u32,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let columns = columns.max(1);
+    let rows = rows(image, columns);
+    let mut out = Vec::new();
+    match protocol.resolve() {
+        Protocol::Kitty => {
+            let png = general_purpose::STANDARD.encode(png(image)?);
+            let chunks: Vec<&[u8]> = png.as_bytes().chunks(KITTY_CHUNK).collect();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(i + 1 < chunks.len());
+                if i == 0 {
+                    write!(out, "\x1b_Gf=100,a=T,q=2,c={},r={},m={};", columns, rows, more)?;
+                } else {
+                    write!(out, "\x1b_Gm={};", more)?;
+                }
+                out.extend_from_slice(chunk);
+                out.extend_from_slice(b"\x1b\\");
+            }
+            out.push(b'\n');
+        }
+        Protocol::Iterm => {
+            let png = png(image)?;
+            writeln!(
+                out,
+                "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+                png.len(),
+                columns,
+                rows,
+                general_purpose::STANDARD.encode(&png)
+            )?;
+        }
+        Protocol::Sixel => {
+            let scaled = scale(image, columns * CELL_WIDTH);
+            sixel(&scaled, &mut out)?;
+            out.push(b'\n');
+        }
+        Protocol::Blocks | Protocol::Auto => {
+            let (width, height) = image.dimensions();
+            let pixel_rows = (u64::from(columns) * u64::from(height)).div_ceil(u64::from(width.max(1))) as u32;
+            let scaled = image::imageops::resize(image, columns, pixel_rows.max(1), FilterType::Nearest);
+            blocks(&scaled, &mut out)?;
+        }
+    }
+    Ok(out)
+}
+
+/// Draw the first frame, or with `play` every frame in turn at its own
+/// duration, `loops` times (`0` loops until interrupted).
+pub fn show(
+    sequence: &Sequence,
+    protocol: Protocol,
+    columns: u32,
+    play: bool,
+    loops: u32,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let first = sequence.frames.first().ok_or("the result has no frames")?;
+    let protocol = protocol.resolve();
+    let mut stdout = std::io::stdout().lock();
+    if !play || sequence.len() == 1 {
+        stdout.write_all(&render(&first.image, protocol, columns)?)?;
+        return Ok(stdout.flush()?);
+    }
+    // Render up front so playback keeps time.
+    let frames = sequence
+        .frames
+        .iter()
+        .map(|frame| Ok((render(&frame.image, protocol, columns)?, frame.duration_ms)))
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error + Send + Sync>>>()?;
+    let height = match protocol {
+        Protocol::Blocks => {
+            let (width, height) = first.image.dimensions();
+            ((u64::from(columns) * u64::from(height)).div_ceil(u64::from(width.max(1)))).div_ceil(2) as u32
+        }
+        _ => rows(&first.image, columns),
+    };
+    let mut round = 0;
+    while loops == 0 || round < loops {
+        for (i, (frame, duration_ms)) in frames.iter().enumerate() {
+            if round > 0 || i > 0 {
+                // Back to the top of the previous frame.
+                write!(stdout, "\x1b[{}A\r", height)?;
+                if protocol == Protocol::Kitty {
+                    stdout.write_all(b"\x1b_Ga=d,d=a,q=2\x1b\\")?;
+                }
+            }
+            stdout.write_all(frame)?;
+            stdout.flush()?;
+            std::thread::sleep(Duration::from_millis(u64::from((*duration_ms).max(20))));
+        }
+        round += 1;
+    }
+    Ok(())
+}
+
+fn png(image: &RgbaImage) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+/// `image` scaled (nearest neighbour, which keeps pixel art crisp) to `width`.
+fn scale(image: &RgbaImage, width: u32) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    let height = (u64::from(width) * u64::from(h)).div_ceil(u64::from(w.max(1))).max(1) as u32;
+    image::imageops::resize(image, width.max(1), height, FilterType::Nearest)
+}
+
+/// Two pixels per cell: the upper as the foreground of `▀`, the lower as
+/// the background. Transparent pixels keep the terminal's colours.
+fn blocks(image: &RgbaImage, out: &mut Vec<u8>) -> std::io::Result<()> {
+    let opaque = |p: &Rgba<u8>| p[3] >= 128;
+    for y in (0..image.height()).step_by(2) {
+        for x in 0..image.width() {
+            let top = image.get_pixel(x, y);
+            let bottom = (y + 1 < image.height()).then(|| image.get_pixel(x, y + 1)).filter(|p| opaque(p));
+            match (opaque(top), bottom) {
+                (true, Some(b)) => write!(out, "\x1b[38;2;{};{};{};48;2;{};{};{}m▀", top[0], top[1], top[2], b[0], b[1], b[2])?,
+                (true, None) => write!(out, "\x1b[38;2;{};{};{};49m▀", top[0], top[1], top[2])?,
+                (false, Some(b)) => write!(out, "\x1b[38;2;{};{};{};49m▄", b[0], b[1], b[2])?,
+                (false, None) => write!(out, "\x1b[0m ")?,
+            }
+        }
+        out.extend_from_slice(b"\x1b[0m\n");
+    }
+    Ok(())
+}
+
+/// Encode `image` as sixel with a 6×6×6 colour cube; transparent pixels are
+/// left undrawn.
+fn sixel(image: &RgbaImage, out: &mut Vec<u8>) -> std::io::Result<()> {
+    let level = |c: u8| (u16::from(c) * 5 + 127) / 255;
+    let index = |p: &Rgba<u8>| (p[3] >= 128).then(|| level(p[0]) * 36 + level(p[1]) * 6 + level(p[2]));
+    let (width, height) = image.dimensions();
+
+    write!(out, "\x1bPq\"1;1;{};{}", width, height)?;
+    for i in 0..216u16 {
+        let pct = |l: u16| l * 100 / 5;
+        write!(out, "#{};2;{};{};{}", i, pct(i / 36), pct(i / 6 % 6), pct(i % 6))?;
+    }
+    for band in (0..height).step_by(6) {
+        // Sixel bits per column for every colour present in this band.
+        let mut colours: BTreeMap<u16, Vec<u8>> = BTreeMap::new();
+        for dy in 0..6.min(height - band) {
+            for x in 0..width {
+                if let Some(i) = index(image.get_pixel(x, band + dy)) {
+                    colours.entry(i).or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << dy;
+                }
+            }
+        }
+        for (i, bits) in &colours {
+            write!(out, "#{}", i)?;
+            let mut run = (bits[0], 0usize);
+            for &b in bits.iter().chain(std::iter::once(&u8::MAX)) {
+                if b == run.0 {
+                    run.1 += 1;
+                    continue;
+                }
+                let ch = (run.0 + 63) as char;
+                if run.1 > 3 {
+                    write!(out, "!{}{}", run.1, ch)?;
+                } else {
+                    write!(out, "{}", ch.to_string().repeat(run.1))?;
+                }
+                run = (b, 1);
+            }
+            out.push(b'$');
+        }
+        out.push(b'-');
+    }
+    out.extend_from_slice(b"\x1b\\");
+    Ok(())
+}
@@ -0,0 +1,206 @@
+//! What animations cost, in credits.
+//!
+//! Prices come from the backend (`GET /api/animation_pricing`, no API key
+//! needed). When it cannot be reached, a table bundled with this release is
+//! used instead; it may lag behind the backend, so estimates say which one
+//! they came from. [`estimate`] totals the cost of a batch of jobs up front,
+//! e.g. the offline queue or a file of planned generations.
+
+use crate::completions::DEFAULT_MODEL_NAME;
+use crate::error::{Error, ErrorKind};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The model ID `generate` uses when none is given.
+pub const DEFAULT_MODEL_ID: u32 = 6;
+
+/// Prices known when this release was made.
+const BUNDLED: &[(u32, &str, u32, u32)] = &[
+    (DEFAULT_MODEL_ID, DEFAULT_MODEL_NAME, 5, 5),
+    (DEFAULT_MODEL_ID, DEFAULT_MODEL_NAME, 10, 10),
+];
+
+/// The cost of one model/duration combination.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Price {
+    pub model_id: u32,
+    pub model_name: String,
+    pub duration_seconds: u32,
+    pub credits: u32,
+}
+
+/// Where a [`PriceTable`] came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Backend,
+    Bundled,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Backend => "backend",
+            Source::Bundled => "bundled",
+        }
+    }
+}
+
+/// Every known price.
+#[derive(Clone, Debug)]
+pub struct PriceTable {
+    pub source: Source,
+    pub prices: Vec<Price>,
+}
+
+impl PriceTable {
+    /// The table bundled with this release.
+    pub fn bundled() -> PriceTable {
+        PriceTable {
+            source: Source::Bundled,
+            prices: BUNDLED
+                .iter()
+                .map(|&(model_id, model_name, duration_seconds, credits)| Price {
+                    model_id,
+                    model_name: model_name.to_string(),
+                    duration_seconds,
+                    credits,
+                })
+                .collect(),
+        }
+    }
+
+    /// The price of a job, looked up by model ID or name (the default model
+    /// if neither is given).
+    pub fn price(&self, job: &Job) -> Result<&Price, Box<dyn std::error::Error + Send + Sync>> {
+        let model_id = match (job.model_id, &job.model_name) {
+            (None, None) => Some(DEFAULT_MODEL_ID),
+            (id, _) => id,
+        };
+        self.prices
+            .iter()
+            .find(|price| {
+                price.duration_seconds == job.duration_seconds
+                    && match (model_id, &job.model_name) {
+                        (Some(id), _) => price.model_id == id,
+                        (None, Some(name)) => &price.model_name == name,
+                        (None, None) => false,
+                    }
+            })
+            .ok_or_else(|| {
+                let model = job
+                    .model_name
+                    .clone()
+                    .or(model_id.map(|id| id.to_string()))
+                    .unwrap_or_default();
+                Error::new(
+                    ErrorKind::Validation,
+                    format!("no price for model {} at {} seconds", model, job.duration_seconds),
+                )
+                .into()
+            })
+    }
+}
+
+/// A planned generation, as far as pricing is concerned.
+///
+/// Reads the offline queue's entries and `generate` request bodies as well
+/// (`animation_model_id`, `animation_model_name`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    #[serde(default, alias = "animation_model_id", skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<u32>,
+    #[serde(default, alias = "animation_model_name", skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    #[serde(default = "default_duration")]
+    pub duration_seconds: u32,
+    /// How many times the job is run.
+    #[serde(default = "default_count")]
+    pub count: u32,
+}
+
+fn default_duration() -> u32 {
+    5
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+impl Job {
+    pub fn new(model_id: Option<u32>, model_name: Option<String>, duration_seconds: u32) -> Job {
+        Job {
+            model_id,
+            model_name,
+            duration_seconds,
+            count: 1,
+        }
+    }
+}
+
+/// Parse a batch file: a JSON array of jobs, or one job per line.
+pub fn parse_batch(text: &str) -> Result<Vec<Job>, Box<dyn std::error::Error + Send + Sync>> {
+    if text.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(text)?);
+    }
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("invalid job on line {}: {}", i + 1, e).into()))
+        .collect()
+}
+
+/// The backend's prices, or the bundled table if it cannot be reached.
+pub async fn table(base_url: &str) -> PriceTable {
+    match fetch(base_url).await {
+        Ok(prices) if !prices.is_empty() => PriceTable {
+            source: Source::Backend,
+            prices,
+        },
+        _ => PriceTable::bundled(),
+    }
+}
+
+/// Hits `GET /api/animation_pricing`, which answers with a list of
+/// [`Price`]s (bare or under `prices`).
+async fn fetch(base_url: &str) -> Result<Vec<Price>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let url = format!("{}/api/animation_pricing", base_url);
+
+    let json: Value = client
+        .send(client.get(&url))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let prices = match json {
+        Value::Object(mut map) => map.remove("prices").unwrap_or_default(),
+        other => other,
+    };
+    Ok(serde_json::from_value(prices)?)
+}
+
+/// The cost of `jobs`, one line per job plus the total:
+/// `{ "source", "jobs": [{ "model_id", "model_name", "duration_seconds", "count", "credits" }], "total_credits" }`.
+pub async fn estimate(base_url: &str, jobs: &[Job]) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let table = table(base_url).await;
+    let mut total: u64 = 0;
+    let mut lines = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        let price = table.price(job)?;
+        let credits = u64::from(price.credits) * u64::from(job.count);
+        total += credits;
+        lines.push(serde_json::json!({
+            "model_id": price.model_id,
+            "model_name": price.model_name,
+            "duration_seconds": price.duration_seconds,
+            "count": job.count,
+            "credits": credits,
+        }));
+    }
+    Ok(serde_json::json!({
+        "source": table.source.as_str(),
+        "jobs": lines,
+        "total_credits": total,
+    }))
+}
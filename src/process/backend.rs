@@ -0,0 +1,123 @@
+//! Choosing between the CPU and the optional GPU implementation of an
+//! operation.
+
+use crate::frames::Sequence;
+use std::fmt;
+use std::str::FromStr;
+
+/// Below this many pixels, uploading to the GPU costs more than it saves.
+#[cfg(feature = "gpu")]
+const AUTO_GPU_MIN_PIXELS: u64 = 4 * 1024 * 1024;
+
+/// Where per-pixel operations run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// The GPU for large jobs when one is available, otherwise the CPU.
+    #[default]
+    Auto,
+    Cpu,
+    /// The GPU, failing if there is none (requires the `gpu` feature).
+    Gpu,
+}
+
+impl Backend {
+    pub const ALL: &'static [Backend] = &[Backend::Auto, Backend::Cpu, Backend::Gpu];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Backend::Auto => "auto",
+            Backend::Cpu => "cpu",
+            Backend::Gpu => "gpu",
+        }
+    }
+
+    /// The GPU to use for a job touching `pixels` pixels, or `None` for the CPU.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn select(
+        &self,
+        pixels: u64,
+    ) -> Result<Option<&'static super::gpu::Gpu>, Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            Backend::Cpu => Ok(None),
+            Backend::Auto if pixels < AUTO_GPU_MIN_PIXELS => Ok(None),
+            Backend::Auto => Ok(super::gpu::shared().filter(|gpu| !gpu.is_software())),
+            Backend::Gpu => super::gpu::shared()
+                .map(Some)
+                .ok_or_else(|| "no usable GPU adapter found".into()),
+        }
+    }
+
+    /// Fail early when the GPU was asked for but this build cannot use one.
+    pub(crate) fn check_supported(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if *self == Backend::Gpu && !cfg!(feature = "gpu") {
+            return Err("this build has no GPU support (rebuild with --features gpu)".into());
+        }
+        Ok(())
+    }
+}
+
+/// A kernel that ran on the GPU: its final counter and the adapter used.
+pub(crate) struct GpuRun {
+    pub count: u32,
+    pub adapter: String,
+}
+
+/// Run a per-pixel WGSL `kernel` (see the `gpu` module) over every frame if
+/// `backend` picks the GPU.
+///
+/// Returns `Ok(None)` when the caller should do the work on the CPU instead,
+/// including when [`Backend::Auto`] hits a GPU error; frames are left
+/// untouched in that case.
+pub(crate) fn map_pixels(
+    backend: Backend,
+    sequence: &mut Sequence,
+    kernel: &str,
+    params: [f32; 8],
+) -> Result<Option<GpuRun>, Box<dyn std::error::Error + Send + Sync>> {
+    backend.check_supported()?;
+    #[cfg(feature = "gpu")]
+    {
+        let pixels = sequence
+            .frames
+            .iter()
+            .map(|f| f.image.width() as u64 * f.image.height() as u64)
+            .sum();
+        if let Some(gpu) = backend.select(pixels)? {
+            let mut images: Vec<_> = sequence.frames.iter_mut().map(|f| &mut f.image).collect();
+            match gpu.map_pixels(kernel, params, &mut images) {
+                Ok(count) => {
+                    return Ok(Some(GpuRun {
+                        count,
+                        adapter: gpu.name().to_string(),
+                    }));
+                }
+                Err(_) if backend == Backend::Auto => {}
+                Err(err) => return Err(format!("GPU backend ({}) failed: {}", gpu.name(), err).into()),
+            }
+        }
+    }
+    #[cfg(not(feature = "gpu"))]
+    let _ = (sequence, kernel, params);
+    Ok(None)
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Backend::ALL
+            .iter()
+            .copied()
+            .find(|b| b.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = Backend::ALL.iter().map(|b| b.as_str()).collect();
+                format!("unknown backend '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
@@ -0,0 +1,197 @@
+//! Background removal by chroma keying.
+//!
+//! Pixels close to the key colour (Euclidean RGB distance) become fully
+//! transparent; an optional softness band fades alpha between the tolerance
+//! and tolerance + softness. Without an explicit colour the key is sampled
+//! from the frame corners, which is where a baked-in background shows.
+
+use super::backend::{self, Backend};
+use crate::frames::Sequence;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// An opaque RGB colour, written as `#rrggbb`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rgb(pub [u8; 3]);
+
+impl Rgb {
+    pub(crate) fn distance(&self, other: [u8; 3]) -> f32 {
+        let d: i32 = (0..3)
+            .map(|c| {
+                let diff = self.0[c] as i32 - other[c] as i32;
+                diff * diff
+            })
+            .sum();
+        (d as f32).sqrt()
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim_start_matches('#');
+        let invalid = || format!("invalid colour '{}' (expected #rrggbb)", s);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+        Ok(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Options for [`remove_background`].
+#[derive(Clone, Debug)]
+pub struct BackgroundOptions {
+    /// Key colour; sampled from the frame corners when `None`.
+    pub color: Option<Rgb>,
+    /// Maximum RGB distance (0-442) keyed out completely.
+    pub tolerance: f32,
+    /// Width of the partially transparent band beyond `tolerance`.
+    pub softness: f32,
+    pub backend: Backend,
+}
+
+impl Default for BackgroundOptions {
+    fn default() -> Self {
+        BackgroundOptions {
+            color: None,
+            tolerance: 24.0,
+            softness: 0.0,
+            backend: Backend::Auto,
+        }
+    }
+}
+
+/// What [`remove_background`] keyed out.
+#[derive(Clone, Debug, Serialize)]
+pub struct BackgroundReport {
+    pub color: Rgb,
+    /// Whether `color` was sampled from the frame corners.
+    pub sampled: bool,
+    /// Pixels made fully transparent, across all frames.
+    pub removed_pixels: u64,
+    /// Where the keying ran (`cpu` or `gpu`).
+    pub backend: &'static str,
+    /// The GPU adapter used, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<String>,
+}
+
+/// WGSL version of the CPU loop below; `params.a.rgb` is the key colour in
+/// 0-255 and `params.b.xy` the tolerance and softness.
+const KEY_KERNEL: &str = r#"
+fn kernel(p: vec4<f32>) -> vec4<f32> {
+    if (p.a == 0.0) {
+        return p;
+    }
+    let d = distance(round(p.rgb * 255.0), params.a.rgb);
+    if (d <= params.b.x) {
+        atomicAdd(&counter, 1u);
+        return vec4<f32>(p.rgb, 0.0);
+    }
+    if (d < params.b.x + params.b.y) {
+        return vec4<f32>(p.rgb, p.a * (d - params.b.x) / params.b.y);
+    }
+    return p;
+}
+"#;
+
+/// Key out the background of every frame in place.
+pub fn remove_background(
+    sequence: &mut Sequence,
+    options: &BackgroundOptions,
+) -> Result<BackgroundReport, Box<dyn std::error::Error + Send + Sync>> {
+    let (color, sampled) = match options.color {
+        Some(color) => (color, false),
+        None => (sample_corners(sequence, options.tolerance)?, true),
+    };
+
+    let [r, g, b] = color.0.map(f32::from);
+    let params = [r, g, b, 0.0, options.tolerance, options.softness, 0.0, 0.0];
+    if let Some(run) = backend::map_pixels(options.backend, sequence, KEY_KERNEL, params)? {
+        return Ok(BackgroundReport {
+            color,
+            sampled,
+            removed_pixels: run.count as u64,
+            backend: Backend::Gpu.as_str(),
+            adapter: Some(run.adapter),
+        });
+    }
+
+    let mut removed_pixels = 0;
+    for frame in &mut sequence.frames {
+        for pixel in frame.image.pixels_mut() {
+            let [r, g, b, a] = pixel.0;
+            if a == 0 {
+                continue;
+            }
+            let d = color.distance([r, g, b]);
+            if d <= options.tolerance {
+                pixel.0[3] = 0;
+                removed_pixels += 1;
+            } else if d < options.tolerance + options.softness {
+                let keep = (d - options.tolerance) / options.softness;
+                pixel.0[3] = (a as f32 * keep).round() as u8;
+            }
+        }
+    }
+
+    Ok(BackgroundReport {
+        color,
+        sampled,
+        removed_pixels,
+        backend: Backend::Cpu.as_str(),
+        adapter: None,
+    })
+}
+
+/// Most common opaque corner colour, provided most corners agree with it.
+fn sample_corners(
+    sequence: &Sequence,
+    tolerance: f32,
+) -> Result<Rgb, Box<dyn std::error::Error + Send + Sync>> {
+    let mut samples = Vec::new();
+    for frame in &sequence.frames {
+        let (w, h) = frame.image.dimensions();
+        if w == 0 || h == 0 {
+            continue;
+        }
+        for (x, y) in [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)] {
+            let [r, g, b, a] = frame.image.get_pixel(x, y).0;
+            if a == 255 {
+                samples.push(Rgb([r, g, b]));
+            }
+        }
+    }
+
+    let mut counts: HashMap<Rgb, usize> = HashMap::new();
+    for s in &samples {
+        *counts.entry(*s).or_default() += 1;
+    }
+    let candidate = counts
+        .into_iter()
+        .max_by_key(|(_, n)| *n)
+        .map(|(c, _)| c)
+        .ok_or("frame corners are transparent; there is no background to remove")?;
+
+    let agreeing = samples.iter().filter(|s| candidate.distance(s.0) <= tolerance).count();
+    if agreeing * 2 < samples.len() {
+        return Err("frame corners disagree on the background colour; pass --color".into());
+    }
+    Ok(candidate)
+}
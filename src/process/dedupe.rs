@@ -0,0 +1,131 @@
+//! Frame deduplication and retiming.
+//!
+//! Generated animations often hold a pose for several identical (or all but
+//! identical) frames. Merging each run into a single frame that is shown for
+//! the whole run keeps playback unchanged while shrinking the sheet.
+//! Retiming resamples the timeline at a constant target frame rate first, so
+//! the merged durations stay multiples of the target frame time.
+
+use crate::frames::{Frame, Sequence};
+use crate::metrics;
+use serde::Serialize;
+
+/// PSNR (dB) at or above which neighbouring frames count as duplicates.
+pub const DEFAULT_MIN_PSNR: f64 = 45.0;
+
+/// Options for [`dedupe`].
+#[derive(Clone, Debug)]
+pub struct DedupeOptions {
+    /// Frames this similar (PSNR in dB) to the start of their run are merged;
+    /// `f64::INFINITY` merges exact duplicates only.
+    pub min_psnr: f64,
+    /// Retime to this frame rate before merging.
+    pub fps: Option<u32>,
+}
+
+impl Default for DedupeOptions {
+    fn default() -> Self {
+        DedupeOptions {
+            min_psnr: DEFAULT_MIN_PSNR,
+            fps: None,
+        }
+    }
+}
+
+/// What [`dedupe`] did.
+#[derive(Clone, Debug, Serialize)]
+pub struct DedupeReport {
+    pub original_frames: usize,
+    /// Frame count after retiming, if a frame rate was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retimed_frames: Option<usize>,
+    /// Indices (before merging) of frames folded into the previous frame.
+    pub merged: Vec<usize>,
+    pub frames: usize,
+}
+
+/// Optionally retime `sequence`, then merge runs of near-identical
+/// consecutive frames into longer frames.
+///
+/// A merged frame's markers move to the frame it was folded into.
+pub fn dedupe(
+    sequence: &mut Sequence,
+    options: &DedupeOptions,
+) -> Result<DedupeReport, Box<dyn std::error::Error + Send + Sync>> {
+    let original_frames = sequence.len();
+    let retimed_frames = match options.fps {
+        Some(fps) => {
+            retime(sequence, fps)?;
+            Some(sequence.len())
+        }
+        None => None,
+    };
+
+    let mut merged = Vec::new();
+    let mut kept: Vec<Frame> = Vec::with_capacity(sequence.len());
+    // Index in `kept` that each original frame ended up in.
+    let mut target = Vec::with_capacity(sequence.len());
+    for (i, frame) in std::mem::take(&mut sequence.frames).into_iter().enumerate() {
+        match kept.last_mut() {
+            Some(last) if is_duplicate(&last.image, &frame.image, options.min_psnr) => {
+                last.duration_ms += frame.duration_ms;
+                merged.push(i);
+            }
+            _ => kept.push(frame),
+        }
+        target.push(kept.len() - 1);
+    }
+    sequence.frames = kept;
+    for marker in &mut sequence.markers {
+        marker.frame = target[marker.frame];
+    }
+
+    Ok(DedupeReport {
+        original_frames,
+        retimed_frames,
+        merged,
+        frames: sequence.len(),
+    })
+}
+
+/// Resample `sequence` at a constant `fps`, keeping its total duration.
+///
+/// Each output frame shows whichever source frame is on screen at its start
+/// time, so source frames may be repeated or skipped. Markers move to the
+/// first output frame at or after their frame's start time.
+pub fn retime(sequence: &mut Sequence, fps: u32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if fps == 0 {
+        return Err("fps must be greater than zero".into());
+    }
+    if sequence.is_empty() {
+        return Ok(());
+    }
+    let frame_ms = 1000.0 / fps as f64;
+    let total_ms = sequence.total_duration_ms() as f64;
+    let count = ((total_ms / frame_ms).round() as usize).max(1);
+
+    let starts: Vec<f64> = (0..sequence.len()).map(|i| sequence.frame_start_ms(i) as f64).collect();
+
+    let mut frames = Vec::with_capacity(count);
+    let mut source = 0;
+    for k in 0..count {
+        let t = k as f64 * frame_ms;
+        while source + 1 < starts.len() && starts[source + 1] <= t {
+            source += 1;
+        }
+        frames.push(Frame {
+            image: sequence.frames[source].image.clone(),
+            duration_ms: 1000 / fps,
+        });
+    }
+
+    for marker in &mut sequence.markers {
+        marker.frame = ((starts[marker.frame] / frame_ms).ceil() as usize).min(count - 1);
+    }
+    sequence.frames = frames;
+    Ok(())
+}
+
+fn is_duplicate(a: &image::RgbaImage, b: &image::RgbaImage, min_psnr: f64) -> bool {
+    a.dimensions() == b.dimensions() && (a == b || metrics::psnr(a, b) >= min_psnr)
+}
@@ -0,0 +1,67 @@
+//! Edit lists: reorder, hold and skip frames without an external tool.
+//!
+//! An edit list is a YAML (or JSON) file naming source frames by index in
+//! playback order:
+//!
+//! ```yaml
+//! frames: [0, 1, 2, 2, 3, 5]   # hold frame 2, skip frame 4
+//! ```
+
+use crate::frames::Sequence;
+use crate::markers::Marker;
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EditList {
+    /// Zero-based indices into the source sequence, in playback order.
+    pub frames: Vec<usize>,
+}
+
+impl EditList {
+    pub fn load(path: impl AsRef<Path>) -> Result<EditList, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    pub fn parse(text: &str) -> Result<EditList, Box<dyn std::error::Error + Send + Sync>> {
+        let list: EditList = serde_yaml::from_str(text)?;
+        if list.frames.is_empty() {
+            return Err("edit list must name at least one frame".into());
+        }
+        Ok(list)
+    }
+
+    /// Rebuild `sequence` in edit-list order. Repeated indices become holds,
+    /// each copy keeping the source frame's duration.
+    ///
+    /// Markers move to the first position showing their source frame and are
+    /// dropped with frames the edit list skips.
+    pub fn apply(&self, sequence: &Sequence) -> Result<Sequence, Box<dyn std::error::Error + Send + Sync>> {
+        let frames = self
+            .frames
+            .iter()
+            .map(|&i| {
+                sequence.frames.get(i).cloned().ok_or_else(|| {
+                    format!("edit list refers to frame {} but the sequence has {} frames", i, sequence.len())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut markers: Vec<Marker> = sequence
+            .markers
+            .iter()
+            .filter_map(|m| {
+                let frame = self.frames.iter().position(|&i| i == m.frame)?;
+                Some(Marker {
+                    name: m.name.clone(),
+                    frame,
+                })
+            })
+            .collect();
+        markers.sort_by_key(|m| m.frame);
+        Ok(Sequence { frames, markers })
+    }
+}
@@ -0,0 +1,148 @@
+//! Automatic repair of single-frame artifacts.
+//!
+//! A frame is considered broken when its flicker (see
+//! [`crate::metrics::quality`]) is a statistical outlier on the high side,
+//! i.e. it differs sharply from both neighbours while the rest of the
+//! animation moves smoothly.
+
+use crate::frames::{Frame, Sequence};
+use crate::metrics;
+use image::{imageops, RgbaImage};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// How broken frames are repaired.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FixMode {
+    /// Replace the frame with a blend of its nearest good neighbours.
+    #[default]
+    Blend,
+    /// Remove the frame and give its display time to the previous frame.
+    Drop,
+}
+
+impl FixMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FixMode::Blend => "blend",
+            FixMode::Drop => "drop",
+        }
+    }
+}
+
+impl fmt::Display for FixMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FixMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "blend" => Ok(FixMode::Blend),
+            "drop" => Ok(FixMode::Drop),
+            _ => Err(format!("unknown fix mode '{}' (expected blend or drop)", s)),
+        }
+    }
+}
+
+/// Frames touched by [`fix_frames`], as indices into the original sequence.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FixReport {
+    pub mode: String,
+    pub frames: Vec<usize>,
+}
+
+/// Detect and repair single-frame artifacts in place.
+pub fn fix_frames(sequence: &mut Sequence, mode: FixMode) -> FixReport {
+    let bad = broken_frames(sequence);
+    // Repairing needs at least one good frame to borrow from.
+    if bad.is_empty() || bad.len() == sequence.len() {
+        return FixReport {
+            mode: mode.to_string(),
+            frames: Vec::new(),
+        };
+    }
+
+    match mode {
+        FixMode::Blend => {
+            let is_bad = |i: usize| bad.binary_search(&i).is_ok();
+            for &i in &bad {
+                let prev = (0..i).rev().find(|&j| !is_bad(j));
+                let next = (i + 1..sequence.len()).find(|&j| !is_bad(j));
+                let (w, h) = (sequence.frames[i].image.width(), sequence.frames[i].image.height());
+                let image = match (prev, next) {
+                    (Some(p), Some(n)) => blend(&sequence.frames[p].image, &sequence.frames[n].image, w, h),
+                    (Some(only), None) | (None, Some(only)) => resized(&sequence.frames[only].image, w, h),
+                    (None, None) => unreachable!("at least one frame is good"),
+                };
+                sequence.frames[i].image = image;
+            }
+        }
+        FixMode::Drop => {
+            let mut kept: Vec<Frame> = Vec::with_capacity(sequence.len() - bad.len());
+            let mut carried_ms = 0;
+            for (i, frame) in std::mem::take(&mut sequence.frames).into_iter().enumerate() {
+                if bad.binary_search(&i).is_ok() {
+                    match kept.last_mut() {
+                        Some(last) => last.duration_ms += frame.duration_ms,
+                        None => carried_ms += frame.duration_ms,
+                    }
+                } else {
+                    let mut frame = frame;
+                    frame.duration_ms += std::mem::take(&mut carried_ms);
+                    kept.push(frame);
+                }
+            }
+            sequence.frames = kept;
+            // A marker on a dropped frame fires on the kept frame now covering its time.
+            for marker in &mut sequence.markers {
+                let kept_before = marker.frame - bad.partition_point(|&b| b < marker.frame);
+                marker.frame = if bad.binary_search(&marker.frame).is_ok() {
+                    kept_before.saturating_sub(1)
+                } else {
+                    kept_before
+                };
+            }
+        }
+    }
+
+    FixReport {
+        mode: mode.to_string(),
+        frames: bad,
+    }
+}
+
+/// Indices (ascending) of frames whose flicker is a high outlier.
+fn broken_frames(sequence: &Sequence) -> Vec<usize> {
+    let report = metrics::quality(sequence);
+    report
+        .frames
+        .iter()
+        .filter(|f| f.outliers.contains(&"flicker") && f.flicker > report.flicker_score)
+        .map(|f| f.index)
+        .collect()
+}
+
+fn blend(a: &RgbaImage, b: &RgbaImage, w: u32, h: u32) -> RgbaImage {
+    let a = resized(a, w, h);
+    let b = resized(b, w, h);
+    let mut out = RgbaImage::new(w, h);
+    for ((o, pa), pb) in out.pixels_mut().zip(a.pixels()).zip(b.pixels()) {
+        for c in 0..4 {
+            o.0[c] = ((pa.0[c] as u16 + pb.0[c] as u16).div_ceil(2)) as u8;
+        }
+    }
+    out
+}
+
+fn resized(image: &RgbaImage, w: u32, h: u32) -> RgbaImage {
+    if image.dimensions() == (w, h) {
+        image.clone()
+    } else {
+        imageops::resize(image, w, h, imageops::FilterType::Triangle)
+    }
+}
@@ -0,0 +1,251 @@
+//! wgpu compute backend for per-pixel operations.
+//!
+//! Frames are uploaded as packed RGBA8 words, a kernel runs once per pixel,
+//! and the results are read back. Operations supply a WGSL `kernel` function
+//! which is appended to [`PRELUDE`]; it sees the uniform `params` and may
+//! count pixels with `atomicAdd(&counter, 1u)`.
+
+use image::RgbaImage;
+use std::sync::OnceLock;
+
+const WORKGROUP_SIZE: u32 = 256;
+const MAX_WORKGROUPS_PER_DIM: u32 = 65535;
+
+const PRELUDE: &str = r#"
+struct Params {
+    a: vec4<f32>,
+    b: vec4<f32>,
+    len: vec4<u32>,
+}
+
+@group(0) @binding(0) var<storage, read_write> pixels: array<u32>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> counter: atomic<u32>;
+
+@compute @workgroup_size(256)
+fn main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let i = id.x + id.y * groups.x * 256u;
+    if (i >= params.len.x) {
+        return;
+    }
+    pixels[i] = pack4x8unorm(kernel(unpack4x8unorm(pixels[i])));
+}
+"#;
+
+pub(crate) struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    info: wgpu::AdapterInfo,
+    /// Largest number of pixels a single dispatch may bind.
+    max_pixels: usize,
+}
+
+/// The process-wide GPU, initialised on first use; `None` without an adapter.
+pub(crate) fn shared() -> Option<&'static Gpu> {
+    static GPU: OnceLock<Option<Gpu>> = OnceLock::new();
+    GPU.get_or_init(|| pollster::block_on(Gpu::new())).as_ref()
+}
+
+impl Gpu {
+    async fn new() -> Option<Gpu> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+            .ok()?;
+        let limits = adapter.limits();
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("gametorch"),
+                required_limits: limits.clone(),
+                ..Default::default()
+            })
+            .await
+            .ok()?;
+        let max_bytes = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        Some(Gpu {
+            device,
+            queue,
+            info: adapter.get_info(),
+            max_pixels: (max_bytes / 4) as usize,
+        })
+    }
+
+    /// Software rasterisers (llvmpipe, WARP) are slower than the CPU path.
+    pub(crate) fn is_software(&self) -> bool {
+        self.info.device_type == wgpu::DeviceType::Cpu
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    /// Run `kernel` over every pixel of `images`, returning the final counter.
+    ///
+    /// Images are only updated once every batch has succeeded, so a failure
+    /// leaves them untouched for a CPU retry. Validation and out-of-memory
+    /// errors are caught in error scopes rather than left to wgpu's default
+    /// handler, which panics.
+    pub(crate) fn map_pixels(
+        &self,
+        kernel: &str,
+        params: [f32; 8],
+        images: &mut [&mut RgbaImage],
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+        self.device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut outputs = Vec::with_capacity(images.len());
+        let run = self.run(kernel, params, images, &mut outputs);
+        let validation = pollster::block_on(self.device.pop_error_scope());
+        let out_of_memory = pollster::block_on(self.device.pop_error_scope());
+        if let Some(err) = validation.or(out_of_memory) {
+            return Err(err.to_string().into());
+        }
+        let count = run?;
+
+        for (image, data) in images.iter_mut().zip(outputs) {
+            image.copy_from_slice(&data);
+        }
+        Ok(count)
+    }
+
+    /// Dispatch every batch, pushing each image's new pixels to `outputs`
+    /// and returning the final counter.
+    fn run(
+        &self,
+        kernel: &str,
+        params: [f32; 8],
+        images: &[&mut RgbaImage],
+        outputs: &mut Vec<Vec<u8>>,
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+        let module = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("gametorch kernel"),
+            source: wgpu::ShaderSource::Wgsl(format!("{}\n{}", PRELUDE, kernel).into()),
+        });
+        let pipeline = self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("gametorch kernel"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let counter = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("counter"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.queue.write_buffer(&counter, 0, &0u32.to_le_bytes());
+
+        let mut start = 0;
+        let mut count = 0;
+        while start < images.len() {
+            // Whole frames per batch; an oversized frame goes alone and lets
+            // the driver report the failure.
+            let mut end = start + 1;
+            let mut pixels = images[start].as_raw().len() / 4;
+            while end < images.len() && pixels + images[end].as_raw().len() / 4 <= self.max_pixels {
+                pixels += images[end].as_raw().len() / 4;
+                end += 1;
+            }
+            let mut batch = Vec::with_capacity(pixels * 4);
+            for image in &images[start..end] {
+                batch.extend_from_slice(image.as_raw());
+            }
+            let (data, counted) = self.dispatch(&pipeline, &counter, params, batch)?;
+            count = counted;
+            let mut offset = 0;
+            for image in &images[start..end] {
+                let len = image.as_raw().len();
+                outputs.push(data[offset..offset + len].to_vec());
+                offset += len;
+            }
+            start = end;
+        }
+        Ok(count)
+    }
+
+    fn dispatch(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        counter: &wgpu::Buffer,
+        params: [f32; 8],
+        data: Vec<u8>,
+    ) -> Result<(Vec<u8>, u32), Box<dyn std::error::Error + Send + Sync>> {
+        use wgpu::BufferUsages as U;
+
+        let pixels = (data.len() / 4) as u32;
+        if pixels == 0 {
+            return Ok((data, 0));
+        }
+        let storage = self.buffer(data.len() as u64, U::STORAGE | U::COPY_SRC | U::COPY_DST);
+        self.queue.write_buffer(&storage, 0, &data);
+
+        let mut uniform = Vec::with_capacity(48);
+        for v in params {
+            uniform.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [pixels, 0, 0, 0] {
+            uniform.extend_from_slice(&v.to_le_bytes());
+        }
+        let uniforms = self.buffer(uniform.len() as u64, U::UNIFORM | U::COPY_DST);
+        self.queue.write_buffer(&uniforms, 0, &uniform);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: storage.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: uniforms.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: counter.as_entire_binding() },
+            ],
+        });
+
+        let pixel_readback = self.buffer(data.len() as u64, U::MAP_READ | U::COPY_DST);
+        let counter_readback = self.buffer(4, U::MAP_READ | U::COPY_DST);
+
+        let groups = pixels.div_ceil(WORKGROUP_SIZE);
+        let x = groups.min(MAX_WORKGROUPS_PER_DIM);
+        let y = groups.div_ceil(x);
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(x, y, 1);
+        }
+        encoder.copy_buffer_to_buffer(&storage, 0, &pixel_readback, 0, data.len() as u64);
+        encoder.copy_buffer_to_buffer(counter, 0, &counter_readback, 0, 4);
+        self.queue.submit([encoder.finish()]);
+
+        let out = self.read(&pixel_readback)?;
+        let counted = self.read(&counter_readback)?;
+        Ok((out, u32::from_le_bytes([counted[0], counted[1], counted[2], counted[3]])))
+    }
+
+    fn buffer(&self, size: u64, usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn read(&self, buffer: &wgpu::Buffer) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let slice = buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(wgpu::PollType::Wait)?;
+        rx.recv()??;
+        let data = slice.get_mapped_range().to_vec();
+        buffer.unmap();
+        Ok(data)
+    }
+}
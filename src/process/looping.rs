@@ -0,0 +1,182 @@
+//! Making animations loop seamlessly.
+//!
+//! Results rarely end on the pose they start with, so looping them pops on
+//! the wrap-around. Ping-pong plays the frames forwards then backwards;
+//! crossfade folds the first few frames into the last few, fading the tail
+//! into the head so the wrap-around lands on the next unblended frame.
+
+use crate::frames::{Frame, Sequence};
+use crate::markers::Marker;
+use image::RgbaImage;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Frames crossfaded when no count is given, for sequences long enough.
+pub const DEFAULT_CROSSFADE_FRAMES: usize = 4;
+
+/// How [`make_loop`] closes the loop.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Append the frames in reverse, without repeating either end frame.
+    #[default]
+    PingPong,
+    /// Blend the last frames into the first ones and drop the originals.
+    Crossfade,
+}
+
+impl LoopMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoopMode::PingPong => "pingpong",
+            LoopMode::Crossfade => "crossfade",
+        }
+    }
+}
+
+impl fmt::Display for LoopMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LoopMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pingpong" | "ping-pong" => Ok(LoopMode::PingPong),
+            "crossfade" => Ok(LoopMode::Crossfade),
+            _ => Err(format!("unknown loop mode '{}' (expected pingpong or crossfade)", s)),
+        }
+    }
+}
+
+/// What [`make_loop`] did.
+#[derive(Clone, Debug, Serialize)]
+pub struct LoopReport {
+    pub mode: String,
+    pub original_frames: usize,
+    /// Frames blended (crossfade only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crossfaded: Option<usize>,
+    pub frames: usize,
+}
+
+/// Rework `sequence` in place so it loops without a visible seam.
+///
+/// `crossfade_frames` defaults to [`DEFAULT_CROSSFADE_FRAMES`], capped so at
+/// least one unblended frame remains. Ping-pong copies markers onto the
+/// mirrored frames; crossfade moves markers of the folded head frames onto
+/// the blends that replace them.
+pub fn make_loop(
+    sequence: &mut Sequence,
+    mode: LoopMode,
+    crossfade_frames: Option<usize>,
+) -> Result<LoopReport, Box<dyn std::error::Error + Send + Sync>> {
+    let original_frames = sequence.len();
+    let crossfaded = match mode {
+        LoopMode::PingPong => {
+            ping_pong(sequence);
+            None
+        }
+        LoopMode::Crossfade => {
+            let n = match crossfade_frames {
+                Some(n) if n == 0 || 2 * n >= sequence.len() => {
+                    return Err(format!(
+                        "cannot crossfade {} frames of a {}-frame sequence (need fewer than half)",
+                        n,
+                        sequence.len()
+                    )
+                    .into());
+                }
+                Some(n) => n,
+                None => DEFAULT_CROSSFADE_FRAMES.min(sequence.len().saturating_sub(1) / 2),
+            };
+            if n == 0 {
+                return Err("sequence is too short to crossfade".into());
+            }
+            crossfade(sequence, n);
+            Some(n)
+        }
+    };
+    Ok(LoopReport {
+        mode: mode.to_string(),
+        original_frames,
+        crossfaded,
+        frames: sequence.len(),
+    })
+}
+
+fn ping_pong(sequence: &mut Sequence) {
+    let len = sequence.len();
+    if len < 3 {
+        return;
+    }
+    // Forward 0..len, then back len-2..=1; index len-1+k mirrors len-1-k.
+    let reversed: Vec<Frame> = sequence.frames[1..len - 1].iter().rev().cloned().collect();
+    sequence.frames.extend(reversed);
+    let mirrored: Vec<Marker> = sequence
+        .markers
+        .iter()
+        .filter(|m| m.frame > 0 && m.frame < len - 1)
+        .map(|m| Marker {
+            name: m.name.clone(),
+            frame: 2 * (len - 1) - m.frame,
+        })
+        .collect();
+    sequence.markers.extend(mirrored);
+    sequence
+        .markers
+        .sort_by(|a, b| a.frame.cmp(&b.frame).then_with(|| a.name.cmp(&b.name)));
+}
+
+fn crossfade(sequence: &mut Sequence, n: usize) {
+    let len = sequence.len();
+    let frames = std::mem::take(&mut sequence.frames);
+    let (head, rest) = frames.split_at(n);
+    let (middle, tail) = rest.split_at(len - 2 * n);
+
+    let mut out: Vec<Frame> = middle.to_vec();
+    for (i, (t, h)) in tail.iter().zip(head).enumerate() {
+        let weight = (i + 1) as f32 / (n + 1) as f32;
+        out.push(Frame {
+            image: lerp(&t.image, &h.image, weight),
+            duration_ms: t.duration_ms,
+        });
+    }
+    sequence.frames = out;
+
+    // Head frame i and tail frame i both become blend i.
+    let blend_start = len - 2 * n;
+    for marker in &mut sequence.markers {
+        marker.frame = if marker.frame < n {
+            blend_start + marker.frame
+        } else if marker.frame < len - n {
+            marker.frame - n
+        } else {
+            blend_start + marker.frame - (len - n)
+        };
+    }
+    sequence
+        .markers
+        .sort_by(|a, b| a.frame.cmp(&b.frame).then_with(|| a.name.cmp(&b.name)));
+    sequence.markers.dedup();
+}
+
+/// `a` faded towards `b` by `weight` (0 = `a`, 1 = `b`). `b` is sampled at
+/// `a`'s size, anchored top-left.
+fn lerp(a: &RgbaImage, b: &RgbaImage, weight: f32) -> RgbaImage {
+    let mut out = a.clone();
+    for (x, y, pixel) in out.enumerate_pixels_mut() {
+        let other = if x < b.width() && y < b.height() {
+            b.get_pixel(x, y).0
+        } else {
+            [0; 4]
+        };
+        for (c, &o) in pixel.0.iter_mut().zip(&other) {
+            *c = (*c as f32 + (o as f32 - *c as f32) * weight).round() as u8;
+        }
+    }
+    out
+}
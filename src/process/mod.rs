@@ -0,0 +1,48 @@
+//! Post-processing operations on frame sequences.
+//!
+//! Each operation takes a [`Sequence`](crate::frames::Sequence) and modifies
+//! it in place, so steps can be chained before packing and exporting.
+//!
+//! Per-pixel operations can run on the GPU when the crate is built with the
+//! `gpu` feature; see [`Backend`].
+//!
+//! On the CPU, frames are processed in parallel on rayon's global thread
+//! pool, one frame per task; a 10-second result has hundreds of frames, so
+//! this scales with the cores available. [`set_jobs`] limits the pool (the
+//! CLI's `--jobs`).
+
+mod backend;
+mod background;
+mod dedupe;
+mod edit;
+mod fix;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod looping;
+mod palette;
+mod scale;
+mod trim;
+
+pub use backend::Backend;
+pub use background::{remove_background, BackgroundOptions, BackgroundReport, Rgb};
+pub use dedupe::{dedupe, retime, DedupeOptions, DedupeReport, DEFAULT_MIN_PSNR};
+pub use edit::EditList;
+pub use fix::{fix_frames, FixMode, FixReport};
+pub use looping::{make_loop, LoopMode, LoopReport, DEFAULT_CROSSFADE_FRAMES};
+pub use palette::{palettize, Palette, PaletteReport, QuantizeMethod};
+pub use scale::{detect_grid, detect_offsets, scale, PixelGrid, ScaleFilter, ScaleOptions, ScaleReport};
+pub use trim::{trim, visible_bounds, TrimReport};
+
+/// Process frames on `jobs` threads instead of one per core.
+///
+/// Must be called before any frames are processed; the pool cannot be
+/// resized once it has started.
+pub fn set_jobs(jobs: usize) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if jobs == 0 {
+        return Err("--jobs must be at least 1".into());
+    }
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build_global()
+        .map_err(|e| format!("failed to start {} worker threads: {}", jobs, e).into())
+}
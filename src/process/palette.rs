@@ -0,0 +1,335 @@
+//! Palette quantization and palette locking.
+//!
+//! Frames are remapped so every visible pixel takes the nearest colour (by
+//! RGB distance) of a single palette shared by the whole sequence. The
+//! palette is either derived from the frames themselves (median cut, or
+//! k-means refined from a median cut) or loaded from a file so results match
+//! a game's existing pixel-art palette. Alpha is left untouched.
+
+use super::background::Rgb;
+use crate::frames::Sequence;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Largest palette [`Palette::quantize`] will build.
+pub const MAX_COLORS: usize = 256;
+
+/// Lloyd iterations run by [`QuantizeMethod::KMeans`] before giving up on
+/// convergence.
+const KMEANS_ITERATIONS: usize = 16;
+
+/// How [`Palette::quantize`] picks colours.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuantizeMethod {
+    /// Recursively split the colour box with the widest channel range.
+    #[default]
+    MedianCut,
+    /// Median cut refined with k-means; slower, usually closer to the source.
+    KMeans,
+}
+
+impl QuantizeMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuantizeMethod::MedianCut => "median-cut",
+            QuantizeMethod::KMeans => "kmeans",
+        }
+    }
+}
+
+impl fmt::Display for QuantizeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for QuantizeMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "median-cut" | "mediancut" => Ok(QuantizeMethod::MedianCut),
+            "kmeans" | "k-means" => Ok(QuantizeMethod::KMeans),
+            _ => Err(format!("unknown quantize method '{}' (expected median-cut or kmeans)", s)),
+        }
+    }
+}
+
+/// An ordered list of opaque colours.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: Vec<Rgb>,
+}
+
+impl Palette {
+    /// Read a palette file.
+    ///
+    /// A `.png` contributes its distinct opaque colours in scan order;
+    /// anything else is parsed as text, see [`Palette::parse`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Palette, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let is_png = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+        if is_png {
+            let image = image::open(path)
+                .map_err(|e| format!("failed to read {}: {}", path.display(), e))?
+                .into_rgba8();
+            let mut colors: Vec<Rgb> = Vec::new();
+            for p in image.pixels().filter(|p| p.0[3] == 255) {
+                let color = Rgb([p.0[0], p.0[1], p.0[2]]);
+                if !colors.contains(&color) {
+                    if colors.len() == MAX_COLORS {
+                        return Err(format!("{}: more than {} colours", path.display(), MAX_COLORS).into());
+                    }
+                    colors.push(color);
+                }
+            }
+            if colors.is_empty() {
+                return Err(format!("{}: no opaque pixels", path.display()).into());
+            }
+            return Ok(Palette { colors });
+        }
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Parse a JASC-PAL file (`JASC-PAL`, version, count, then `r g b` lines)
+    /// or a plain list of `#rrggbb` colours, one per line.
+    pub fn parse(text: &str) -> Result<Palette, Box<dyn std::error::Error + Send + Sync>> {
+        let mut lines = text
+            .lines()
+            .map(|l| l.split(';').next().unwrap_or("").trim())
+            .filter(|l| !l.is_empty())
+            .peekable();
+        let jasc = lines.peek() == Some(&"JASC-PAL");
+        if jasc {
+            // Skip the header, version and colour count; the count is implied
+            // by the lines that follow.
+            lines.nth(2);
+        }
+
+        let mut colors = Vec::new();
+        for line in lines {
+            let color = if jasc {
+                let channels: Vec<u8> = line
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid colour '{}' (expected 'r g b')", line))?;
+                match channels[..] {
+                    [r, g, b] | [r, g, b, _] => Rgb([r, g, b]),
+                    _ => return Err(format!("invalid colour '{}' (expected 'r g b')", line).into()),
+                }
+            } else {
+                line.parse()?
+            };
+            colors.push(color);
+        }
+        if colors.is_empty() {
+            return Err("palette has no colours".into());
+        }
+        Ok(Palette { colors })
+    }
+
+    /// Build a palette of at most `colors` colours from the visible pixels of
+    /// every frame.
+    pub fn quantize(
+        sequence: &Sequence,
+        colors: usize,
+        method: QuantizeMethod,
+    ) -> Result<Palette, Box<dyn std::error::Error + Send + Sync>> {
+        if !(1..=MAX_COLORS).contains(&colors) {
+            return Err(format!("colour count must be between 1 and {}", MAX_COLORS).into());
+        }
+        let histogram = histogram(sequence);
+        if histogram.is_empty() {
+            return Err("every frame is fully transparent".into());
+        }
+        let mut centroids = median_cut(histogram.clone(), colors);
+        if method == QuantizeMethod::KMeans {
+            kmeans(&histogram, &mut centroids);
+        }
+        let mut colors: Vec<Rgb> = centroids.into_iter().map(to_rgb).collect();
+        colors.sort_by_key(|c| c.0);
+        colors.dedup();
+        Ok(Palette { colors })
+    }
+
+    /// Index of the colour closest to `color`.
+    fn nearest(&self, color: [u8; 3]) -> usize {
+        self.colors
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.distance(color).total_cmp(&b.distance(color)))
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+}
+
+/// What [`palettize`] did.
+#[derive(Clone, Debug, Serialize)]
+pub struct PaletteReport {
+    pub colors: Vec<Rgb>,
+    /// Visible pixels whose colour changed, across all frames.
+    pub remapped_pixels: u64,
+}
+
+/// Remap every visible pixel to its nearest colour in `palette`.
+pub fn palettize(sequence: &mut Sequence, palette: &Palette) -> PaletteReport {
+    // One cache per frame: frames are remapped in parallel.
+    let remapped_pixels = sequence
+        .frames
+        .par_iter_mut()
+        .map(|frame| {
+            let mut cache: HashMap<[u8; 3], [u8; 3]> = HashMap::new();
+            let mut remapped: u64 = 0;
+            for pixel in frame.image.pixels_mut() {
+                let [r, g, b, a] = pixel.0;
+                if a == 0 {
+                    continue;
+                }
+                let mapped = *cache
+                    .entry([r, g, b])
+                    .or_insert_with(|| palette.colors[palette.nearest([r, g, b])].0);
+                if mapped != [r, g, b] {
+                    pixel.0 = [mapped[0], mapped[1], mapped[2], a];
+                    remapped += 1;
+                }
+            }
+            remapped
+        })
+        .sum();
+    PaletteReport {
+        colors: palette.colors.clone(),
+        remapped_pixels,
+    }
+}
+
+/// Distinct visible colours with their pixel counts, sorted by colour so the
+/// result does not depend on hash order.
+fn histogram(sequence: &Sequence) -> Vec<([u8; 3], u64)> {
+    let counts = sequence
+        .frames
+        .par_iter()
+        .map(|frame| {
+            let mut counts: HashMap<[u8; 3], u64> = HashMap::new();
+            for p in frame.image.pixels().filter(|p| p.0[3] > 0) {
+                *counts.entry([p.0[0], p.0[1], p.0[2]]).or_default() += 1;
+            }
+            counts
+        })
+        .reduce(HashMap::new, |mut a, b| {
+            for (color, n) in b {
+                *a.entry(color).or_default() += n;
+            }
+            a
+        });
+    let mut histogram: Vec<_> = counts.into_iter().collect();
+    histogram.sort_unstable();
+    histogram
+}
+
+/// Split the colour space into at most `colors` boxes and return each box's
+/// weighted mean.
+fn median_cut(histogram: Vec<([u8; 3], u64)>, colors: usize) -> Vec<[f64; 3]> {
+    let mut boxes = vec![histogram];
+    while boxes.len() < colors {
+        // The splittable box with the widest channel range.
+        let Some((index, channel, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, range) = widest_channel(b);
+                (i, channel, range)
+            })
+            .max_by_key(|&(i, _, range)| (range, std::cmp::Reverse(i)))
+        else {
+            break;
+        };
+        let mut entries = boxes.swap_remove(index);
+        entries.sort_by_key(|(c, _)| c[channel]);
+        let total: u64 = entries.iter().map(|(_, n)| n).sum();
+        let mut seen = 0;
+        let split = entries
+            .iter()
+            .position(|(_, n)| {
+                seen += n;
+                seen * 2 >= total
+            })
+            .map_or(1, |i| i + 1)
+            .clamp(1, entries.len() - 1);
+        let upper = entries.split_off(split);
+        boxes.push(entries);
+        boxes.push(upper);
+    }
+    boxes.iter().map(|b| weighted_mean(b)).collect()
+}
+
+fn widest_channel(entries: &[([u8; 3], u64)]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let min = entries.iter().map(|(color, _)| color[c]).min().unwrap_or(0);
+            let max = entries.iter().map(|(color, _)| color[c]).max().unwrap_or(0);
+            (c, max - min)
+        })
+        .max_by_key(|&(c, range)| (range, std::cmp::Reverse(c)))
+        .unwrap_or((0, 0))
+}
+
+fn weighted_mean(entries: &[([u8; 3], u64)]) -> [f64; 3] {
+    let total: u64 = entries.iter().map(|(_, n)| n).sum();
+    let mut mean = [0.0; 3];
+    for (color, n) in entries {
+        for (m, &v) in mean.iter_mut().zip(color) {
+            *m += v as f64 * *n as f64;
+        }
+    }
+    mean.map(|v| v / total.max(1) as f64)
+}
+
+/// Refine `centroids` in place with weighted Lloyd iterations.
+fn kmeans(histogram: &[([u8; 3], u64)], centroids: &mut [[f64; 3]]) {
+    let distance = |a: &[f64; 3], b: [u8; 3]| -> f64 {
+        (0..3).map(|c| (a[c] - b[c] as f64).powi(2)).sum()
+    };
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![([0.0f64; 3], 0u64); centroids.len()];
+        for &(color, n) in histogram {
+            let nearest = (0..centroids.len())
+                .min_by(|&a, &b| distance(&centroids[a], color).total_cmp(&distance(&centroids[b], color)))
+                .unwrap_or(0);
+            for (s, &v) in sums[nearest].0.iter_mut().zip(&color) {
+                *s += v as f64 * n as f64;
+            }
+            sums[nearest].1 += n;
+        }
+
+        let mut moved = false;
+        for (centroid, (sum, n)) in centroids.iter_mut().zip(sums) {
+            // An empty cluster keeps its colour rather than collapsing.
+            if n == 0 {
+                continue;
+            }
+            let next = sum.map(|v| v / n as f64);
+            if to_rgb(next) != to_rgb(*centroid) {
+                moved = true;
+            }
+            *centroid = next;
+        }
+        if !moved {
+            break;
+        }
+    }
+}
+
+fn to_rgb(color: [f64; 3]) -> Rgb {
+    Rgb(color.map(|v| v.round().clamp(0.0, 255.0) as u8))
+}
@@ -0,0 +1,286 @@
+//! Scaling and pixel-grid snapping.
+//!
+//! AI-generated pixel art is rarely on a clean grid: each logical pixel
+//! covers a block of roughly `k`×`k` image pixels, with soft or jittered
+//! edges. Pixel snapping finds that grid from where colour edges line up
+//! across all frames, collapses every cell to its median colour and paints it
+//! back at the original size, so a later nearest-neighbour scale stays crisp.
+
+use crate::frames::Sequence;
+use image::{imageops, RgbaImage};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Largest grid cell [`detect_grid`] considers, in pixels.
+pub const MAX_CELL: u32 = 16;
+
+/// Share of all edge strength that must fall on grid lines for a cell size
+/// to be accepted.
+const GRID_THRESHOLD: f64 = 0.7;
+
+/// Resampling filter used by [`scale`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScaleFilter {
+    /// Repeat or drop whole pixels; the right choice for pixel art.
+    #[default]
+    Nearest,
+    Linear,
+    Cubic,
+    Lanczos,
+}
+
+impl ScaleFilter {
+    pub const ALL: &'static [ScaleFilter] = &[
+        ScaleFilter::Nearest,
+        ScaleFilter::Linear,
+        ScaleFilter::Cubic,
+        ScaleFilter::Lanczos,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScaleFilter::Nearest => "nearest",
+            ScaleFilter::Linear => "linear",
+            ScaleFilter::Cubic => "cubic",
+            ScaleFilter::Lanczos => "lanczos",
+        }
+    }
+
+    fn filter_type(&self) -> imageops::FilterType {
+        match self {
+            ScaleFilter::Nearest => imageops::FilterType::Nearest,
+            ScaleFilter::Linear => imageops::FilterType::Triangle,
+            ScaleFilter::Cubic => imageops::FilterType::CatmullRom,
+            ScaleFilter::Lanczos => imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
+impl fmt::Display for ScaleFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ScaleFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ScaleFilter::ALL
+            .iter()
+            .copied()
+            .find(|f| f.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = ScaleFilter::ALL.iter().map(|f| f.as_str()).collect();
+                format!("unknown scale filter '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// The logical pixel grid of a sequence: cells of `cell_width`×`cell_height`
+/// starting at (`offset_x`, `offset_y`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct PixelGrid {
+    pub cell_width: u32,
+    pub cell_height: u32,
+    pub offset_x: u32,
+    pub offset_y: u32,
+}
+
+/// Options for [`scale`].
+#[derive(Clone, Debug)]
+pub struct ScaleOptions {
+    pub factor: f32,
+    pub filter: ScaleFilter,
+    /// Snap to the pixel grid before scaling.
+    pub pixel_snap: bool,
+    /// Cell size to snap to instead of detecting it.
+    pub grid: Option<u32>,
+}
+
+impl Default for ScaleOptions {
+    fn default() -> Self {
+        ScaleOptions {
+            factor: 1.0,
+            filter: ScaleFilter::Nearest,
+            pixel_snap: false,
+            grid: None,
+        }
+    }
+}
+
+/// What [`scale`] did.
+#[derive(Clone, Debug, Serialize)]
+pub struct ScaleReport {
+    pub factor: f32,
+    pub filter: String,
+    /// The grid snapped to, if `pixel_snap` was set and one was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grid: Option<PixelGrid>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Optionally snap every frame to its pixel grid, then resize by `factor`.
+pub fn scale(
+    sequence: &mut Sequence,
+    options: &ScaleOptions,
+) -> Result<ScaleReport, Box<dyn std::error::Error + Send + Sync>> {
+    if !options.factor.is_finite() || options.factor <= 0.0 {
+        return Err("scale factor must be greater than zero".into());
+    }
+    if options.grid.is_some_and(|g| g == 0) {
+        return Err("grid cell size must be greater than zero".into());
+    }
+
+    let grid = if options.pixel_snap {
+        let grid = match options.grid {
+            Some(size) => Some(detect_offsets(sequence, size)),
+            None => detect_grid(sequence),
+        };
+        if let Some(grid) = grid {
+            sequence.frames.par_iter_mut().for_each(|frame| {
+                frame.image = snap(&frame.image, &grid);
+            });
+        }
+        grid
+    } else {
+        None
+    };
+
+    if options.factor != 1.0 {
+        sequence.frames.par_iter_mut().for_each(|frame| {
+            let (w, h) = frame.image.dimensions();
+            let width = ((w as f32 * options.factor).round() as u32).max(1);
+            let height = ((h as f32 * options.factor).round() as u32).max(1);
+            frame.image = imageops::resize(&frame.image, width, height, options.filter.filter_type());
+        });
+    }
+
+    let (width, height) = sequence.frame_size();
+    Ok(ScaleReport {
+        factor: options.factor,
+        filter: options.filter.to_string(),
+        grid,
+        width,
+        height,
+    })
+}
+
+/// Find the pixel grid shared by all frames, or `None` if the frames are
+/// already one image pixel per logical pixel.
+pub fn detect_grid(sequence: &Sequence) -> Option<PixelGrid> {
+    let (columns, rows) = edge_profiles(sequence);
+    let (cell_width, offset_x) = detect_axis(&columns, None);
+    let (cell_height, offset_y) = detect_axis(&rows, None);
+    (cell_width > 1 || cell_height > 1).then_some(PixelGrid {
+        cell_width,
+        cell_height,
+        offset_x,
+        offset_y,
+    })
+}
+
+/// The best-aligned grid of square `size` cells.
+pub fn detect_offsets(sequence: &Sequence, size: u32) -> PixelGrid {
+    let (columns, rows) = edge_profiles(sequence);
+    let (_, offset_x) = detect_axis(&columns, Some(size));
+    let (_, offset_y) = detect_axis(&rows, Some(size));
+    PixelGrid {
+        cell_width: size,
+        cell_height: size,
+        offset_x,
+        offset_y,
+    }
+}
+
+/// Colour change between neighbouring columns and rows, summed over every
+/// frame. Entry `i` measures the edge just before column (row) `i`.
+fn edge_profiles(sequence: &Sequence) -> (Vec<u64>, Vec<u64>) {
+    let (w, h) = sequence.frame_size();
+    let mut columns = vec![0u64; w as usize];
+    let mut rows = vec![0u64; h as usize];
+    let diff = |a: &image::Rgba<u8>, b: &image::Rgba<u8>| -> u64 {
+        a.0.iter().zip(b.0).map(|(&x, y)| x.abs_diff(y) as u64).sum()
+    };
+    for frame in &sequence.frames {
+        let image = &frame.image;
+        for (x, y, p) in image.enumerate_pixels() {
+            if x > 0 {
+                columns[x as usize] += diff(p, image.get_pixel(x - 1, y));
+            }
+            if y > 0 {
+                rows[y as usize] += diff(p, image.get_pixel(x, y - 1));
+            }
+        }
+    }
+    (columns, rows)
+}
+
+/// Cell size and offset along one axis: the largest cell size whose grid
+/// lines carry most of the edge strength, or `size` if given.
+fn detect_axis(profile: &[u64], size: Option<u32>) -> (u32, u32) {
+    let total: u64 = profile.iter().sum();
+    let on_grid = |k: u32, offset: u32| -> u64 {
+        profile.iter().skip(offset as usize).step_by(k as usize).sum()
+    };
+    let best_offset = |k: u32| (0..k).max_by_key(|&o| (on_grid(k, o), std::cmp::Reverse(o))).unwrap_or(0);
+
+    if let Some(k) = size {
+        return (k, best_offset(k));
+    }
+    let mut best = (1, 0);
+    if total == 0 {
+        return best;
+    }
+    let largest = MAX_CELL.min(profile.len() as u32 / 2);
+    for k in 2..=largest {
+        let offset = best_offset(k);
+        if on_grid(k, offset) as f64 >= GRID_THRESHOLD * total as f64 {
+            best = (k, offset);
+        }
+    }
+    best
+}
+
+/// Fill every grid cell with the per-channel median of its pixels.
+fn snap(image: &RgbaImage, grid: &PixelGrid) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    let starts = |len: u32, cell: u32, offset: u32| -> Vec<u32> {
+        let first = offset % cell;
+        let mut starts = if first > 0 { vec![0] } else { Vec::new() };
+        starts.extend((first..len).step_by(cell as usize));
+        starts
+    };
+    let xs = starts(w, grid.cell_width, grid.offset_x);
+    let ys = starts(h, grid.cell_height, grid.offset_y);
+
+    let mut out = RgbaImage::new(w, h);
+    let mut channels: [Vec<u8>; 4] = Default::default();
+    for (j, &y0) in ys.iter().enumerate() {
+        let y1 = ys.get(j + 1).copied().unwrap_or(h);
+        for (i, &x0) in xs.iter().enumerate() {
+            let x1 = xs.get(i + 1).copied().unwrap_or(w);
+            channels.iter_mut().for_each(Vec::clear);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    for (channel, &v) in channels.iter_mut().zip(&image.get_pixel(x, y).0) {
+                        channel.push(v);
+                    }
+                }
+            }
+            let median = channels.each_mut().map(|c| {
+                c.sort_unstable();
+                c[c.len() / 2]
+            });
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    out.put_pixel(x, y, image::Rgba(median));
+                }
+            }
+        }
+    }
+    out
+}
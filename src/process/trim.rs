@@ -0,0 +1,80 @@
+//! Trimming of transparent borders.
+//!
+//! The crop rectangle is the union of the visible pixels of *all* frames, and
+//! every frame is cropped to that same rectangle, so the character stays
+//! registered in place from frame to frame.
+
+use crate::frames::Sequence;
+use crate::sheet::Rect;
+use image::imageops;
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// Result of [`trim`]: the rectangle kept, relative to the original frames.
+#[derive(Clone, Debug, Serialize)]
+pub struct TrimReport {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub original_width: u32,
+    pub original_height: u32,
+}
+
+/// Crop every frame to the union bounding box of its non-transparent pixels,
+/// keeping `margin` extra pixels where available.
+pub fn trim(
+    sequence: &mut Sequence,
+    margin: u32,
+) -> Result<TrimReport, Box<dyn std::error::Error + Send + Sync>> {
+    let (original_width, original_height) = sequence.frame_size();
+    let bounds = visible_bounds(sequence).ok_or("every frame is fully transparent")?;
+
+    let x = bounds.x.saturating_sub(margin);
+    let y = bounds.y.saturating_sub(margin);
+    let width = (bounds.x + bounds.width + margin).min(original_width) - x;
+    let height = (bounds.y + bounds.height + margin).min(original_height) - y;
+
+    sequence.frames.par_iter_mut().for_each(|frame| {
+        frame.image = imageops::crop_imm(&frame.image, x, y, width, height).to_image();
+    });
+
+    Ok(TrimReport {
+        x,
+        y,
+        width,
+        height,
+        original_width,
+        original_height,
+    })
+}
+
+/// Union of the visible pixels across all frames, or `None` if there are none.
+pub fn visible_bounds(sequence: &Sequence) -> Option<Rect> {
+    let (min, max) = sequence
+        .frames
+        .par_iter()
+        .map(|frame| {
+            let mut min = (u32::MAX, u32::MAX);
+            let mut max = (0u32, 0u32);
+            for (x, y, p) in frame.image.enumerate_pixels() {
+                if p.0[3] > 0 {
+                    min = (min.0.min(x), min.1.min(y));
+                    max = (max.0.max(x), max.1.max(y));
+                }
+            }
+            (min, max)
+        })
+        .reduce(
+            || ((u32::MAX, u32::MAX), (0, 0)),
+            |(min_a, max_a), (min_b, max_b)| {
+                ((min_a.0.min(min_b.0), min_a.1.min(min_b.1)), (max_a.0.max(max_b.0), max_a.1.max(max_b.1)))
+            },
+        );
+    (min.0 != u32::MAX).then(|| Rect {
+        x: min.0,
+        y: min.1,
+        width: max.0 - min.0 + 1,
+        height: max.1 - min.1 + 1,
+    })
+}
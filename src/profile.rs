@@ -0,0 +1,186 @@
+//! Export profiles: shareable export settings.
+//!
+//! A profile bundles the export targets, sheet packing, playback rate, drop
+//! engine and post-processing steps a team agrees on into one TOML file, so an
+//! art lead can hand out `pixel16.toml` and every CLI exports identically:
+//!
+//! ```toml
+//! name = "pixel16"
+//! targets = ["godot"]
+//! engine = "godot"
+//! fps = 12
+//! pipeline = ["trim", { palettize = { colors = 16 } }]
+//!
+//! [pack]
+//! columns = 8
+//! padding = 1
+//! ```
+//!
+//! `pipeline` takes the processing steps of a [pipeline file](crate::pipeline);
+//! output steps (`pack`, `export`, `save`) are not allowed, since the profile
+//! itself decides what gets written. Loading a profile makes it the active one
+//! (`profile.toml` in the [data directory](crate::paths::data_dir)), whose
+//! settings `export` uses wherever no flag overrides them.
+
+use crate::export::Target;
+use crate::export::engine_drop::Engine;
+use crate::pipeline::{Pipeline, Step};
+use crate::sheet::PackOptions;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the active profile inside the data directory.
+pub const ACTIVE_PROFILE_FILE: &str = "profile.toml";
+
+/// Sheet packing constraints.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PackSection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<u32>,
+    pub padding: u32,
+}
+
+/// An export profile as written on disk.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Export targets (`unity`, `godot`, ...), written in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    /// Engine for `export --drop`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fps: Option<u32>,
+    /// Processing steps run before packing.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pipeline: Vec<toml::Value>,
+    pub pack: PackSection,
+}
+
+impl Profile {
+    /// Read and validate a profile file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Profile, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Parse and validate profile TOML.
+    pub fn parse(text: &str) -> Result<Profile, Box<dyn std::error::Error + Send + Sync>> {
+        let profile: Profile = toml::from_str(text)?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    /// The active profile, if one has been loaded.
+    pub fn active() -> Result<Option<Profile>, Box<dyn std::error::Error + Send + Sync>> {
+        let path = active_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Profile::load(&path).map(Some)
+    }
+
+    /// Check every name and step resolves.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.targets()?;
+        self.engine()?;
+        if self.fps == Some(0) {
+            return Err("fps must be greater than zero".into());
+        }
+        self.pipeline()?;
+        Ok(())
+    }
+
+    pub fn targets(&self) -> Result<Vec<Target>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self
+            .targets
+            .iter()
+            .map(|t| t.parse::<Target>())
+            .collect::<Result<_, _>>()?)
+    }
+
+    pub fn engine(&self) -> Result<Option<Engine>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.engine.as_deref().map(str::parse::<Engine>).transpose()?)
+    }
+
+    pub fn pack(&self) -> PackOptions {
+        PackOptions {
+            columns: self.pack.columns,
+            padding: self.pack.padding,
+        }
+    }
+
+    /// The profile's processing steps; `None` when it has none.
+    pub fn pipeline(&self) -> Result<Option<Pipeline>, Box<dyn std::error::Error + Send + Sync>> {
+        if self.pipeline.is_empty() {
+            return Ok(None);
+        }
+        let values = self
+            .pipeline
+            .iter()
+            .map(serde_yaml::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let pipeline = Pipeline::from_values(&values).map_err(|e| format!("pipeline: {}", e))?;
+        if let Some(step) = pipeline
+            .steps
+            .iter()
+            .find(|s| matches!(s, Step::Pack(_) | Step::Export { .. } | Step::Save(_)))
+        {
+            return Err(format!(
+                "pipeline: '{}' steps are not allowed in a profile; use its targets and [pack] instead",
+                step.name()
+            )
+            .into());
+        }
+        Ok(Some(pipeline))
+    }
+
+    /// Take the steps of a pipeline file into the profile.
+    pub fn set_pipeline_file(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        #[derive(Deserialize)]
+        struct Steps {
+            steps: Vec<serde_yaml::Value>,
+        }
+        let steps: Steps = serde_yaml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        self.pipeline = steps
+            .steps
+            .iter()
+            .map(toml::Value::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// Validate and write the profile to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        self.validate()?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e).into())
+    }
+
+    /// Make this the active profile, returning where it was written.
+    pub fn activate(&self) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+        let path = active_path()?;
+        self.save(&path)?;
+        Ok(path)
+    }
+}
+
+/// Where the active profile lives.
+pub fn active_path() -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(crate::paths::data_dir()?.join(ACTIVE_PROFILE_FILE))
+}
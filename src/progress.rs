@@ -0,0 +1,77 @@
+//! Progress reporting for library callers.
+//!
+//! [`generate_with_progress`](crate::animations::generate_with_progress)
+//! reports each stage of a generation as a [`ProgressEvent`] to a
+//! [`ProgressSink`], so GUIs and editor plugins can show real progress
+//! instead of parsing stdout. A sink can be a closure or a
+//! [`std::sync::mpsc::Sender`]:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! use gametorch::progress::ProgressEvent;
+//!
+//! let (tx, rx) = std::sync::mpsc::channel::<ProgressEvent>();
+//! std::thread::spawn(move || {
+//!     for event in rx {
+//!         println!("{:?}", event);
+//!     }
+//! });
+//! let mut request = gametorch::animations::GenerateRequest::new("walking to the left");
+//! request.input_image = Some("mule.png".to_string());
+//! gametorch::animations::generate_with_progress(
+//!     "api-key", "https://gametorch.app", &request, true, None, true, &tx,
+//!     &gametorch::cancel::CancellationToken::new(),
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+/// A stage of a generation job.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgressEvent {
+    /// The job was accepted by the API.
+    Submitted { animation_id: i64 },
+    /// Still rendering; sent after every poll.
+    Polling { elapsed: Duration },
+    /// Rendering finished; the result archive is being prepared.
+    Rendering,
+    /// Part of the result archive has arrived. `total` is known when the
+    /// server sends a length.
+    Downloading { bytes: u64, total: Option<u64> },
+    /// The job finished. `zip_path` is set when the result was saved.
+    Done {
+        animation_id: i64,
+        result_id: Option<i64>,
+        zip_path: Option<String>,
+    },
+}
+
+/// Receives [`ProgressEvent`]s. Sinks must not block for long; events are
+/// delivered on the task doing the work.
+pub trait ProgressSink: Send + Sync {
+    fn event(&self, event: ProgressEvent);
+}
+
+/// Discards every event.
+impl ProgressSink for () {
+    fn event(&self, _event: ProgressEvent) {}
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(ProgressEvent) + Send + Sync,
+{
+    fn event(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+/// Events for a receiver that has gone away are dropped.
+impl ProgressSink for std::sync::mpsc::Sender<ProgressEvent> {
+    fn event(&self, event: ProgressEvent) {
+        let _ = self.send(event);
+    }
+}
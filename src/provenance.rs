@@ -0,0 +1,258 @@
+//! Generation metadata embedded in exported images.
+//!
+//! Exported sheets carry the prompt, model, seed and GameTorch IDs they came
+//! from, so an asset found in a game project can be traced back to its
+//! generation (`gametorch inspect <file>`). PNGs get one text chunk per
+//! field (`tEXt`, or uncompressed `iTXt` for text outside Latin-1) under
+//! `gametorch:` keywords, which image viewers and `exiftool` show as well.
+//! GIFs get a comment holding `gametorch ` and the fields as JSON.
+
+use crate::history::Job;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the PNG keywords.
+const KEYWORD_PREFIX: &str = "gametorch:";
+
+/// Start of the GIF comment.
+const COMMENT_PREFIX: &str = "gametorch ";
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Where an asset came from.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub animation_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl Provenance {
+    /// What the history recorded about a job.
+    pub fn from_job(job: &Job) -> Provenance {
+        Provenance {
+            animation_id: Some(job.animation_id),
+            result_id: job.result_id,
+            prompt: job.prompt.clone(),
+            model: job.model.clone(),
+            seed: job.seed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Provenance::default()
+    }
+
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(id) = self.animation_id {
+            fields.push(("animation_id", id.to_string()));
+        }
+        if let Some(id) = self.result_id {
+            fields.push(("result_id", id.to_string()));
+        }
+        if let Some(prompt) = &self.prompt {
+            fields.push(("prompt", prompt.clone()));
+        }
+        if let Some(model) = &self.model {
+            fields.push(("model", model.clone()));
+        }
+        if let Some(seed) = self.seed {
+            fields.push(("seed", seed.to_string()));
+        }
+        fields
+    }
+
+    fn set(&mut self, field: &str, value: String) {
+        match field {
+            "animation_id" => self.animation_id = value.parse().ok(),
+            "result_id" => self.result_id = value.parse().ok(),
+            "prompt" => self.prompt = Some(value),
+            "model" => self.model = Some(value),
+            "seed" => self.seed = value.parse().ok(),
+            _ => {}
+        }
+    }
+}
+
+/// `png` with a text chunk per field of `provenance` after its header.
+pub fn embed_png(png: &[u8], provenance: &Provenance) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    if !png.starts_with(PNG_SIGNATURE) {
+        return Err("not a PNG".into());
+    }
+    // IHDR always comes first: signature, length, type, 13 bytes, CRC.
+    let header_end = PNG_SIGNATURE.len() + 12 + 13;
+    if png.len() < header_end || &png[12..16] != b"IHDR" {
+        return Err("PNG has no IHDR chunk".into());
+    }
+    let mut out = png[..header_end].to_vec();
+    for (field, value) in provenance.fields() {
+        let keyword = format!("{}{}", KEYWORD_PREFIX, field);
+        let mut data = keyword.into_bytes();
+        data.push(0);
+        match latin1(&value) {
+            Some(text) => {
+                data.extend(text);
+                push_chunk(&mut out, b"tEXt", &data);
+            }
+            None => {
+                // Uncompressed, no language tag, no translated keyword.
+                data.extend([0, 0, 0, 0]);
+                data.extend(value.as_bytes());
+                push_chunk(&mut out, b"iTXt", &data);
+            }
+        }
+    }
+    out.extend_from_slice(&png[header_end..]);
+    Ok(out)
+}
+
+/// `gif` with a comment holding `provenance` before its trailer.
+pub fn embed_gif(gif: &[u8], provenance: &Provenance) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    if !gif.starts_with(b"GIF8") || gif.last() != Some(&0x3B) {
+        return Err("not a GIF".into());
+    }
+    let comment = format!("{}{}", COMMENT_PREFIX, serde_json::to_string(provenance)?);
+    let mut out = gif[..gif.len() - 1].to_vec();
+    out.extend([0x21, 0xFE]);
+    for block in comment.as_bytes().chunks(255) {
+        out.push(block.len() as u8);
+        out.extend_from_slice(block);
+    }
+    out.extend([0x00, 0x3B]);
+    Ok(out)
+}
+
+/// The provenance embedded in a PNG or GIF, if any.
+pub fn read(bytes: &[u8]) -> Option<Provenance> {
+    let provenance = if bytes.starts_with(PNG_SIGNATURE) {
+        read_png(bytes)
+    } else if bytes.starts_with(b"GIF8") {
+        read_gif(bytes)
+    } else {
+        None
+    }?;
+    (!provenance.is_empty()).then_some(provenance)
+}
+
+fn read_png(png: &[u8]) -> Option<Provenance> {
+    let mut provenance = Provenance::default();
+    let mut at = PNG_SIGNATURE.len();
+    while at + 12 <= png.len() {
+        let len = u32::from_be_bytes(png[at..at + 4].try_into().ok()?) as usize;
+        let kind = &png[at + 4..at + 8];
+        let data = png.get(at + 8..at + 8 + len)?;
+        let text = match kind {
+            b"tEXt" => data
+                .iter()
+                .position(|&b| b == 0)
+                .map(|nul| (&data[..nul], data[nul + 1..].iter().map(|&b| char::from(b)).collect::<String>())),
+            b"iTXt" => parse_itxt(data),
+            b"IEND" => break,
+            _ => None,
+        };
+        if let Some((keyword, value)) = text
+            && let Some(field) = std::str::from_utf8(keyword).ok().and_then(|k| k.strip_prefix(KEYWORD_PREFIX))
+        {
+            provenance.set(field, value);
+        }
+        at += 12 + len;
+    }
+    Some(provenance)
+}
+
+/// Keyword and text of an uncompressed `iTXt` chunk.
+fn parse_itxt(data: &[u8]) -> Option<(&[u8], String)> {
+    let nul = data.iter().position(|&b| b == 0)?;
+    let (keyword, rest) = (&data[..nul], &data[nul + 1..]);
+    let (&[compressed, _method], rest) = rest.split_first_chunk::<2>()?;
+    if compressed != 0 {
+        return None;
+    }
+    // Skip the language tag and the translated keyword.
+    let lang_end = rest.iter().position(|&b| b == 0)?;
+    let rest = &rest[lang_end + 1..];
+    let translated_end = rest.iter().position(|&b| b == 0)?;
+    let text = std::str::from_utf8(&rest[translated_end + 1..]).ok()?;
+    Some((keyword, text.to_string()))
+}
+
+fn read_gif(gif: &[u8]) -> Option<Provenance> {
+    // Header and logical screen descriptor, then the global color table.
+    let flags = *gif.get(10)?;
+    let mut at = 13 + color_table_len(flags);
+    while let Some(&block) = gif.get(at) {
+        match block {
+            0x21 => {
+                let label = *gif.get(at + 1)?;
+                let (data, end) = sub_blocks(gif, at + 2)?;
+                if label == 0xFE
+                    && let Some(json) = std::str::from_utf8(&data).ok().and_then(|c| c.strip_prefix(COMMENT_PREFIX))
+                {
+                    return serde_json::from_str(json).ok();
+                }
+                at = end;
+            }
+            0x2C => {
+                let flags = *gif.get(at + 9)?;
+                // Descriptor, local color table, LZW code size, then the data.
+                let (_, end) = sub_blocks(gif, at + 10 + color_table_len(flags) + 1)?;
+                at = end;
+            }
+            _ => break,
+        }
+    }
+    Some(Provenance::default())
+}
+
+fn color_table_len(flags: u8) -> usize {
+    if flags & 0x80 == 0 {
+        0
+    } else {
+        3 << ((flags & 0x07) + 1)
+    }
+}
+
+/// The concatenated sub-blocks starting at `at`, and the offset after them.
+fn sub_blocks(gif: &[u8], mut at: usize) -> Option<(Vec<u8>, usize)> {
+    let mut data = Vec::new();
+    loop {
+        let len = *gif.get(at)? as usize;
+        at += 1;
+        if len == 0 {
+            return Some((data, at));
+        }
+        data.extend_from_slice(gif.get(at..at + len)?);
+        at += len;
+    }
+}
+
+/// `text` in Latin-1, if it fits.
+fn latin1(text: &str) -> Option<Vec<u8>> {
+    text.chars().map(|c| u8::try_from(u32::from(c)).ok()).collect()
+}
+
+fn push_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(kind);
+    out.extend(data);
+    out.extend(crc32(&[kind.as_slice(), data].concat()).to_be_bytes());
+}
+
+/// CRC-32 (ISO 3309) as PNG chunks use it.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,130 @@
+//! What the account is rendering right now, for `gametorch queue`.
+//!
+//! [`generating`] lists the account's animations that are still generating,
+//! with how long each has been going and when it should be done. Estimates
+//! come from the local job [history](crate::history): the median render time
+//! of completed jobs with the same model and duration, or of all completed
+//! jobs when there are none like it. Nothing is estimated without history.
+
+use crate::animations::{self, ListOptions, Status};
+use crate::history::Job;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Default seconds between refreshes with `--watch`.
+pub const DEFAULT_REFRESH_SECS: u64 = 5;
+
+/// Render times of completed jobs.
+#[derive(Clone, Debug, Default)]
+pub struct Estimator {
+    /// Sorted render seconds keyed by model and duration.
+    by_setting: HashMap<(Option<String>, Option<u32>), Vec<u64>>,
+    /// Sorted render seconds of every completed job.
+    all: Vec<u64>,
+}
+
+impl Estimator {
+    pub fn from_history(jobs: &[Job]) -> Estimator {
+        let mut estimator = Estimator::default();
+        for job in jobs.iter().filter(|job| job.status == "complete") {
+            let Some(seconds) = job.render_seconds() else {
+                continue;
+            };
+            estimator
+                .by_setting
+                .entry((job.model.clone(), job.duration_seconds))
+                .or_default()
+                .push(seconds);
+            estimator.all.push(seconds);
+        }
+        estimator.by_setting.values_mut().for_each(|times| times.sort_unstable());
+        estimator.all.sort_unstable();
+        estimator
+    }
+
+    /// Expected render seconds of a job with `model` and `duration`.
+    pub fn estimate(&self, model: Option<&str>, duration: Option<u32>) -> Option<u64> {
+        let alike = self.by_setting.get(&(model.map(str::to_string), duration));
+        median(alike.map_or(&self.all, |times| times))
+    }
+}
+
+fn median(sorted: &[u64]) -> Option<u64> {
+    sorted.get(sorted.len() / 2).copied()
+}
+
+/// One animation still generating.
+#[derive(Clone, Debug, Serialize)]
+pub struct Queued {
+    pub animation_id: i64,
+    pub prompt: Option<String>,
+    pub model: Option<String>,
+    pub duration_seconds: Option<u32>,
+    /// Seconds since submission.
+    pub elapsed_seconds: Option<u64>,
+    /// Expected total render time.
+    pub estimated_seconds: Option<u64>,
+    /// Expected seconds left; `0` once the estimate is exceeded.
+    pub remaining_seconds: Option<u64>,
+}
+
+/// The account's generating animations, longest running first. `jobs` is
+/// the local history, which fills in what the listing leaves out; `now` is
+/// the current Unix time.
+pub async fn generating(
+    api_key: &str,
+    base_url: &str,
+    jobs: &[Job],
+    now: u64,
+) -> Result<Vec<Queued>, Box<dyn std::error::Error + Send + Sync>> {
+    let options = ListOptions {
+        status: Some(Status::Generating.code()),
+        ..Default::default()
+    };
+    let list = animations::list(api_key, base_url, &options).await?;
+    let estimator = Estimator::from_history(jobs);
+    let local: HashMap<i64, &Job> = jobs.iter().map(|job| (job.animation_id, job)).collect();
+
+    let items = match &list {
+        Value::Array(items) => items.as_slice(),
+        _ => &[],
+    };
+    let mut queued: Vec<Queued> = items
+        .iter()
+        .filter_map(|item| {
+            let animation_id = item.get("id").or_else(|| item.get("animation_id"))?.as_i64()?;
+            let job = local.get(&animation_id).copied();
+            let text = |field: &str| item.get(field).and_then(Value::as_str).map(str::to_string);
+            let model = text("animation_model_name")
+                .or_else(|| text("model_name"))
+                .or_else(|| job.and_then(|job| job.model.clone()));
+            let duration_seconds = item
+                .get("duration_seconds")
+                .and_then(Value::as_u64)
+                .and_then(|d| u32::try_from(d).ok())
+                .or(job.and_then(|job| job.duration_seconds));
+            let submitted = match item.get("created_at") {
+                Some(Value::Number(n)) => n.as_u64(),
+                Some(Value::String(s)) => animations::parse_date(s),
+                _ => None,
+            }
+            .or(job.map(|job| job.submitted_at));
+            let elapsed_seconds = submitted.map(|at| now.saturating_sub(at));
+            let estimated_seconds = estimator.estimate(model.as_deref(), duration_seconds);
+            Some(Queued {
+                animation_id,
+                prompt: text("prompt").or_else(|| job.and_then(|job| job.prompt.clone())),
+                model,
+                duration_seconds,
+                elapsed_seconds,
+                estimated_seconds,
+                remaining_seconds: estimated_seconds
+                    .zip(elapsed_seconds)
+                    .map(|(estimate, elapsed)| estimate.saturating_sub(elapsed)),
+            })
+        })
+        .collect();
+    queued.sort_by_key(|q| std::cmp::Reverse(q.elapsed_seconds));
+    Ok(queued)
+}
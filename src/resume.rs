@@ -0,0 +1,94 @@
+//! Blocking generations interrupted with Ctrl+C.
+//!
+//! When `generate --block` is interrupted after the job was submitted, the
+//! CLI records it in `resume.json` in the [data directory](crate::paths::data_dir)
+//! and exits with [`EXIT_INTERRUPTED`]. `gametorch resume` picks the job up
+//! again: it waits for the render and saves the ZIP where the interrupted run
+//! would have.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the resume file inside the data directory.
+pub const RESUME_FILE: &str = "resume.json";
+
+/// Exit status of a run interrupted with Ctrl+C (128 + SIGINT).
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// A submitted job whose blocking run was interrupted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Interrupted {
+    pub animation_id: i64,
+    pub prompt: String,
+    /// Where the ZIP was to be saved; the default name when `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_file: Option<String>,
+    /// Workspace asset name given with `--as`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+    /// Unix timestamp (seconds) of the interruption.
+    pub interrupted_at: u64,
+}
+
+/// The resume file at a given path, holding every interrupted job.
+#[derive(Clone, Debug)]
+pub struct ResumeFile {
+    path: PathBuf,
+}
+
+impl ResumeFile {
+    pub fn new(path: impl Into<PathBuf>) -> ResumeFile {
+        ResumeFile { path: path.into() }
+    }
+
+    /// The resume file in the default data directory.
+    pub fn open_default() -> Result<ResumeFile, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(ResumeFile::new(crate::paths::data_dir()?.join(RESUME_FILE)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Interrupted jobs, oldest first. A missing file has none.
+    pub fn jobs(&self) -> Result<Vec<Interrupted>, Box<dyn std::error::Error + Send + Sync>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("invalid {}: {}", self.path.display(), e).into()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("failed to read {}: {}", self.path.display(), e).into()),
+        }
+    }
+
+    /// Record an interrupted job, replacing any earlier entry for the same
+    /// animation.
+    pub fn push(&self, job: Interrupted) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut jobs = self.jobs()?;
+        jobs.retain(|j| j.animation_id != job.animation_id);
+        jobs.push(job);
+        self.save(&jobs)
+    }
+
+    /// Forget the job for `animation_id`.
+    pub fn remove(&self, animation_id: i64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut jobs = self.jobs()?;
+        jobs.retain(|j| j.animation_id != animation_id);
+        self.save(&jobs)
+    }
+
+    /// Write `jobs` (via a temporary file and rename); an empty list removes
+    /// the file.
+    fn save(&self, jobs: &[Interrupted]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if jobs.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(jobs)? + "\n")?;
+        std::fs::rename(&tmp, &self.path).map_err(|e| format!("failed to write {}: {}", self.path.display(), e).into())
+    }
+}
@@ -0,0 +1,189 @@
+//! Read-only HTTP API over the local job [`History`].
+//!
+//! Meant for dashboards (Grafana's JSON data sources, scripts): it needs no
+//! API key and can only read what the CLI recorded locally. It sends no CORS
+//! headers, so web pages open in a browser cannot read the prompts in it.
+//! Every request re-reads the history file, so new jobs show up immediately.
+//!
+//! | Route                  | Response                                         |
+//! |------------------------|--------------------------------------------------|
+//! | `GET /api/jobs`        | jobs, filtered by `status`, `kind`, `since`, `limit` |
+//! | `GET /api/jobs/<id>`   | one job by animation ID                          |
+//! | `GET /api/stats`       | counts by status and kind, jobs per day, render times |
+//! | `GET /health`          | `{"ok": true}`                                   |
+
+use crate::history::{History, Job};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Default address for `gametorch serve`; loopback only.
+pub const DEFAULT_BIND: &str = "127.0.0.1:8787";
+
+/// Requests with a larger head than this are rejected.
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+
+/// Aggregates served at `/api/stats`.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Stats {
+    pub jobs: usize,
+    pub by_status: BTreeMap<String, usize>,
+    pub by_kind: BTreeMap<String, usize>,
+    /// Submissions per UTC day (`YYYY-MM-DD`).
+    pub per_day: BTreeMap<String, usize>,
+    /// Mean submission-to-completion time of completed jobs.
+    pub mean_render_seconds: Option<f64>,
+}
+
+/// Summarize `jobs` for dashboards.
+pub fn stats(jobs: &[Job]) -> Stats {
+    let mut stats = Stats {
+        jobs: jobs.len(),
+        ..Default::default()
+    };
+    let mut render = Vec::new();
+    for job in jobs {
+        *stats.by_status.entry(job.status.clone()).or_default() += 1;
+        *stats.by_kind.entry(job.kind.clone()).or_default() += 1;
+        *stats.per_day.entry(crate::clock::utc_date(job.submitted_at)).or_default() += 1;
+        if job.status == "complete"
+            && let Some(seconds) = job.render_seconds()
+        {
+            render.push(seconds as f64);
+        }
+    }
+    if !render.is_empty() {
+        stats.mean_render_seconds = Some(render.iter().sum::<f64>() / render.len() as f64);
+    }
+    stats
+}
+
+/// Serve the history API on `bind` until the process exits.
+pub async fn serve_history(
+    bind: &str,
+    history: History,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let listener = TcpListener::bind(bind)
+        .await
+        .map_err(|e| format!("failed to listen on {}: {}", bind, e))?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let history = history.clone();
+        tokio::spawn(async move {
+            // A client hanging up mid-request is not worth reporting.
+            let _ = handle(stream, &history).await;
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, history: &History) -> std::io::Result<()> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_REQUEST_BYTES {
+            return respond(&mut stream, 431, &error("request header too large")).await;
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let head = String::from_utf8_lossy(&buf);
+    let mut parts = head.lines().next().unwrap_or("").split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (status, body) = route(method, target, history);
+    respond(&mut stream, status, &body).await
+}
+
+fn route(method: &str, target: &str, history: &History) -> (u16, Value) {
+    if method != "GET" {
+        return (405, error("read-only API: only GET is supported"));
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query: BTreeMap<String, String> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (decode(key), decode(value)))
+        .collect();
+
+    if path == "/health" {
+        return (200, serde_json::json!({ "ok": true }));
+    }
+    let jobs = match history.jobs() {
+        Ok(jobs) => jobs,
+        Err(err) => return (500, error(&err.to_string())),
+    };
+    match path.trim_end_matches('/') {
+        "/api/jobs" => {
+            let since: u64 = query.get("since").and_then(|s| s.parse().ok()).unwrap_or(0);
+            let mut jobs: Vec<&Job> = jobs
+                .iter()
+                .filter(|j| query.get("status").is_none_or(|s| j.status == *s))
+                .filter(|j| query.get("kind").is_none_or(|k| j.kind == *k))
+                .filter(|j| j.submitted_at >= since)
+                .collect();
+            // Newest last, so `limit` keeps the most recent jobs.
+            if let Some(limit) = query.get("limit").and_then(|l| l.parse::<usize>().ok()) {
+                jobs = jobs.split_off(jobs.len().saturating_sub(limit));
+            }
+            (200, serde_json::json!(jobs))
+        }
+        "/api/stats" => (200, serde_json::json!(stats(&jobs))),
+        p => match p.strip_prefix("/api/jobs/").map(str::parse::<i64>) {
+            Some(Ok(id)) => match jobs.into_iter().find(|j| j.animation_id == id) {
+                Some(job) => (200, serde_json::json!(job)),
+                None => (404, error(&format!("no job for animation {}", id))),
+            },
+            Some(Err(_)) => (400, error("job IDs are animation IDs (integers)")),
+            None => (404, error("not found")),
+        },
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: u16, body: &Value) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    };
+    let body = serde_json::to_string_pretty(body).unwrap_or_default();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// A query string component with `+` and `%XX` escapes decoded.
+fn decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if let Some(byte) = component.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) => {
+                out.push(byte);
+                i += 2;
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn error(message: &str) -> Value {
+    serde_json::json!({ "error": message })
+}
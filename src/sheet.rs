@@ -0,0 +1,236 @@
+//! Sprite sheet packing.
+//!
+//! Frames are laid out on a uniform grid, left to right and top to bottom.
+//! Every engine export is built on top of the [`SheetLayout`] produced here.
+//!
+//! For reviewing rather than shipping, a [contact sheet](contact_sheet) puts
+//! the same grid on a checkerboard with each frame's index, and an
+//! [onion skin](onion_skin) stacks frames on top of each other to show the
+//! motion in one image.
+
+use crate::frames::Sequence;
+use image::{imageops, Rgba, RgbaImage};
+
+/// Options controlling how frames are laid out on the sheet.
+#[derive(Clone, Debug, Default)]
+pub struct PackOptions {
+    /// Number of columns; defaults to a roughly square grid.
+    pub columns: Option<u32>,
+    /// Transparent pixels between neighbouring cells.
+    pub padding: u32,
+}
+
+/// A pixel rectangle on the sheet, origin at the top-left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Geometry of a packed sheet.
+#[derive(Clone, Debug)]
+pub struct SheetLayout {
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+    pub padding: u32,
+    pub frame_count: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl SheetLayout {
+    /// Rectangle occupied by the frame at `index`.
+    pub fn rect(&self, index: u32) -> Rect {
+        let col = index % self.columns;
+        let row = index / self.columns;
+        Rect {
+            x: col * (self.frame_width + self.padding),
+            y: row * (self.frame_height + self.padding),
+            width: self.frame_width,
+            height: self.frame_height,
+        }
+    }
+
+    /// Rectangles for every frame, in playback order.
+    pub fn rects(&self) -> Vec<Rect> {
+        (0..self.frame_count).map(|i| self.rect(i)).collect()
+    }
+}
+
+/// Pack a sequence into a single RGBA sheet.
+///
+/// Frames smaller than the largest frame are centred within their cell.
+pub fn pack(sequence: &Sequence, options: &PackOptions) -> (RgbaImage, SheetLayout) {
+    let count = sequence.len() as u32;
+    let (frame_width, frame_height) = sequence.frame_size();
+
+    let columns = options
+        .columns
+        .unwrap_or_else(|| (count as f64).sqrt().ceil() as u32)
+        .clamp(1, count.max(1));
+    let rows = count.div_ceil(columns).max(1);
+    let padding = options.padding;
+
+    let layout = SheetLayout {
+        frame_width,
+        frame_height,
+        columns,
+        rows,
+        padding,
+        frame_count: count,
+        width: columns * frame_width + (columns - 1) * padding,
+        height: rows * frame_height + (rows - 1) * padding,
+    };
+
+    let mut sheet = RgbaImage::new(layout.width, layout.height);
+    for (i, frame) in sequence.frames.iter().enumerate() {
+        let rect = layout.rect(i as u32);
+        let x = rect.x + (frame_width - frame.image.width()) / 2;
+        let y = rect.y + (frame_height - frame.image.height()) / 2;
+        imageops::replace(&mut sheet, &frame.image, x as i64, y as i64);
+    }
+
+    (sheet, layout)
+}
+
+/// Options for [`contact_sheet`].
+#[derive(Clone, Debug)]
+pub struct ContactSheetOptions {
+    /// Number of columns; defaults to a roughly square grid.
+    pub columns: Option<u32>,
+    /// Pixels between neighbouring cells.
+    pub padding: u32,
+    /// Draw each frame's index in the top-left corner of its cell.
+    pub labels: bool,
+}
+
+impl Default for ContactSheetOptions {
+    fn default() -> Self {
+        ContactSheetOptions {
+            columns: None,
+            padding: 4,
+            labels: true,
+        }
+    }
+}
+
+/// Grey between the cells of a contact sheet.
+const GUTTER: Rgba<u8> = Rgba([96, 96, 96, 255]);
+
+/// The two checkerboard tones behind transparent pixels, and their square size.
+const CHECKER: [Rgba<u8>; 2] = [Rgba([204, 204, 204, 255]), Rgba([240, 240, 240, 255])];
+const CHECKER_SIZE: u32 = 8;
+
+/// A review sheet of every frame: the [`pack`] grid on a checkerboard, so
+/// transparency shows, with frame indices unless `labels` is off.
+pub fn contact_sheet(sequence: &Sequence, options: &ContactSheetOptions) -> (RgbaImage, SheetLayout) {
+    let pack_options = PackOptions {
+        columns: options.columns,
+        padding: options.padding,
+    };
+    let (frames, layout) = pack(sequence, &pack_options);
+
+    let mut sheet = RgbaImage::from_pixel(layout.width, layout.height, GUTTER);
+    for rect in layout.rects() {
+        for y in 0..rect.height {
+            for x in 0..rect.width {
+                let tone = ((x / CHECKER_SIZE + y / CHECKER_SIZE) % 2) as usize;
+                sheet.put_pixel(rect.x + x, rect.y + y, CHECKER[tone]);
+            }
+        }
+    }
+    imageops::overlay(&mut sheet, &frames, 0, 0);
+
+    if options.labels {
+        // Glyph pixels grow with the frames so labels stay readable.
+        let scale = (layout.frame_height / 64).max(1);
+        for (i, rect) in layout.rects().into_iter().enumerate() {
+            draw_number(&mut sheet, rect.x, rect.y, i, scale);
+        }
+    }
+    (sheet, layout)
+}
+
+/// Every frame of `sequence` drawn over the previous ones, `frames` of them
+/// at most (evenly spaced, always including the first and last). Earlier
+/// frames are fainter and tinted blue, so the direction of motion reads
+/// from faint blue to the solid last frame.
+pub fn onion_skin(sequence: &Sequence, frames: usize) -> RgbaImage {
+    let (width, height) = sequence.frame_size();
+    let mut skin = RgbaImage::new(width, height);
+    let count = sequence.len();
+    if count == 0 {
+        return skin;
+    }
+
+    let layers = frames.clamp(1, count);
+    let mut picked: Vec<usize> = (0..layers)
+        .map(|i| if layers == 1 { count - 1 } else { i * (count - 1) / (layers - 1) })
+        .collect();
+    picked.dedup();
+
+    for (layer, &index) in picked.iter().enumerate() {
+        // 0 for the first layer, 1 for the last.
+        let t = if picked.len() == 1 { 1.0 } else { layer as f32 / (picked.len() - 1) as f32 };
+        let opacity = 0.2 + 0.8 * t;
+        let tint = 0.6 * (1.0 - t);
+        let frame = &sequence.frames[index].image;
+        let mut layer_image = RgbaImage::new(width, height);
+        let (x0, y0) = ((width - frame.width()) / 2, (height - frame.height()) / 2);
+        for (x, y, p) in frame.enumerate_pixels() {
+            let [r, g, b, a] = p.0;
+            let mix = |c: u8, target: f32| (c as f32 * (1.0 - tint) + target * tint).round() as u8;
+            let alpha = (a as f32 * opacity).round() as u8;
+            layer_image.put_pixel(x0 + x, y0 + y, Rgba([mix(r, 64.0), mix(g, 128.0), mix(b, 255.0), alpha]));
+        }
+        imageops::overlay(&mut skin, &layer_image, 0, 0);
+    }
+    skin
+}
+
+/// 3×5 bitmaps of the digits 0–9, one row per entry, most significant bit
+/// on the left.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Draw `number` in white on a black box with its top-left corner at
+/// (`x`, `y`), each glyph pixel `scale` pixels wide, clipped to the image.
+fn draw_number(image: &mut RgbaImage, x: u32, y: u32, number: usize, scale: u32) {
+    let text = number.to_string();
+    let advance = 4 * scale;
+    let width = text.len() as u32 * advance + scale;
+    fill(image, x, y, width, 7 * scale, Rgba([0, 0, 0, 255]));
+    for (i, digit) in text.bytes().map(|b| (b - b'0') as usize).enumerate() {
+        let left = x + scale + i as u32 * advance;
+        for (row, bits) in DIGITS[digit].iter().enumerate() {
+            for col in 0..3 {
+                if (bits >> (2 - col)) & 1 == 1 {
+                    let top = y + scale + row as u32 * scale;
+                    fill(image, left + col * scale, top, scale, scale, Rgba([255, 255, 255, 255]));
+                }
+            }
+        }
+    }
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
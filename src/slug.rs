@@ -0,0 +1,65 @@
+//! Stable, readable slugs for prompts and asset names.
+//!
+//! A slug is a short kebab-case summary of the text followed by a hash of the
+//! normalized text, e.g. `walking to the left` -> `walking-to-the-left-5050a94e`.
+//! The hash depends only on the text (and seed, if any), never on the machine,
+//! locale or time, so every developer generating from the same prompt gets
+//! the same file and directory names.
+
+use sha2::{Digest, Sha256};
+
+/// Longest readable part of a slug, before the hash.
+pub const MAX_WORDS_LEN: usize = 40;
+
+/// Hex digits of hash appended to every slug.
+pub const HASH_LEN: usize = 8;
+
+/// Slug for `text`.
+///
+/// Case, punctuation and runs of whitespace do not change the result, so
+/// `Walking  to the LEFT!` and `walking to the left` share a slug.
+pub fn slug(text: &str) -> String {
+    build(text, None)
+}
+
+/// Slug for `text` generated with `seed`, so reruns of the same prompt with
+/// different seeds get different (but still reproducible) names.
+pub fn seeded(text: &str, seed: u64) -> String {
+    build(text, Some(seed))
+}
+
+fn build(text: &str, seed: Option<u64>) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+
+    let mut hasher = Sha256::new();
+    hasher.update(words.join(" ").as_bytes());
+    if let Some(seed) = seed {
+        hasher.update(b"\0seed:");
+        hasher.update(seed.to_string().as_bytes());
+    }
+    let mut hash: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    hash.truncate(HASH_LEN);
+
+    // Whole ASCII words only, so the readable part is safe in any file system.
+    let mut readable = String::new();
+    for word in words.iter().filter(|w| w.is_ascii()) {
+        if !readable.is_empty() && readable.len() + 1 + word.len() > MAX_WORDS_LEN {
+            break;
+        }
+        if !readable.is_empty() {
+            readable.push('-');
+        }
+        readable.push_str(word);
+    }
+    readable.truncate(MAX_WORDS_LEN);
+
+    if readable.is_empty() {
+        hash
+    } else {
+        format!("{}-{}", readable, hash)
+    }
+}
@@ -0,0 +1,257 @@
+//! Storage usage reports.
+//!
+//! [`remote_usage`] adds up what the account's results occupy on GameTorch,
+//! using whatever size metadata the API reports. [`local_usage`] walks local
+//! directories and classifies the files this crate writes: downloaded result
+//! ZIPs, saved frame sequences, engine drops and `regenerate --diff` output.
+//! It also points out what could be deleted without losing anything unique.
+
+use crate::animations;
+use crate::export::engine_drop::{DropManifest, MANIFEST_FILE};
+use crate::frames::SEQUENCE_FILE;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Result fields that may carry the archive size, in order of preference.
+const SIZE_FIELDS: &[&str] = &["zip_size_bytes", "size_bytes", "file_size"];
+
+/// Parse a size in bytes, optionally with a binary unit: `512`, `800K`,
+/// `500M`, `2G` (or `500MB`, `2GiB`).
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let trimmed = s.trim();
+    let upper = trimmed.to_ascii_uppercase();
+    let number = upper.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = match &upper[number.len()..] {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => 0,
+    };
+    match number.trim().parse::<u64>() {
+        Ok(n) if unit > 0 => n.checked_mul(unit).ok_or_else(|| format!("size '{}' is too large", trimmed)),
+        _ => Err(format!("invalid size '{}' (expected bytes or a size like 500M or 2G)", trimmed)),
+    }
+}
+
+/// Directories never descended into by [`local_usage`].
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "Library", "Temp"];
+
+/// Remote storage used by the account's results.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RemoteUsage {
+    pub animations: usize,
+    pub results: usize,
+    /// Result counts keyed by numeric status (`1` generating, `2` complete,
+    /// `3` failed and refunded).
+    pub results_by_status: BTreeMap<i64, usize>,
+    /// Sum of the sizes the API reported.
+    pub known_bytes: u64,
+    /// Results whose metadata carried no size.
+    pub results_without_size: usize,
+}
+
+/// Fetch every animation's results and total their reported sizes.
+pub async fn remote_usage(
+    api_key: &str,
+    base_url: &str,
+) -> Result<RemoteUsage, Box<dyn std::error::Error + Send + Sync>> {
+    let list = animations::list(api_key, base_url, &animations::ListOptions::default()).await?;
+    let ids: Vec<String> = items(&list)
+        .iter()
+        .filter_map(|a| a.get("id").or_else(|| a.get("animation_id")))
+        .map(|id| match id {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect();
+
+    let mut usage = RemoteUsage {
+        animations: ids.len(),
+        ..Default::default()
+    };
+    for id in &ids {
+        let results = animations::get(api_key, base_url, id).await?;
+        for result in items(&results) {
+            usage.results += 1;
+            if let Some(status) = result.get("status").and_then(Value::as_i64) {
+                *usage.results_by_status.entry(status).or_default() += 1;
+            }
+            match SIZE_FIELDS.iter().find_map(|f| result.get(*f).and_then(Value::as_u64)) {
+                Some(bytes) => usage.known_bytes += bytes,
+                None => usage.results_without_size += 1,
+            }
+        }
+    }
+    Ok(usage)
+}
+
+/// Bytes and files in one category of local output.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Usage {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+impl Usage {
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Something that could be deleted without losing data.
+#[derive(Clone, Debug, Serialize)]
+pub struct Reclaimable {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub reason: String,
+}
+
+/// Local disk usage under one root directory.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LocalUsage {
+    pub root: PathBuf,
+    /// Downloaded result archives (`*.zip`).
+    pub result_zips: Usage,
+    /// Directories saved by `process` (holding a `sequence.json`).
+    pub sequences: Usage,
+    /// Files listed in engine drop manifests.
+    pub engine_drops: Usage,
+    /// `regenerate --diff` output directories.
+    pub diffs: Usage,
+    pub total_bytes: u64,
+    pub reclaimable: Vec<Reclaimable>,
+    pub reclaimable_bytes: u64,
+}
+
+/// Walk `root` and classify what this crate wrote there.
+///
+/// Hidden directories and common build/cache directories are skipped.
+/// Byte-identical result ZIPs are reported as reclaimable (all but the first
+/// by path), as are diff directories, which only hold copies of downloadable
+/// results plus derived metrics.
+pub fn local_usage(root: &Path) -> Result<LocalUsage, Box<dyn std::error::Error + Send + Sync>> {
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()).into());
+    }
+    let mut usage = LocalUsage {
+        root: root.to_path_buf(),
+        ..Default::default()
+    };
+    let mut zips: Vec<(PathBuf, u64)> = Vec::new();
+    walk(root, &mut usage, &mut zips)?;
+
+    // Group equal-sized archives first so only candidates get hashed.
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (path, bytes) in zips {
+        by_size.entry(bytes).or_default().push(path);
+    }
+    let mut sizes: Vec<_> = by_size.into_iter().filter(|(_, paths)| paths.len() > 1).collect();
+    sizes.sort_by_key(|(bytes, _)| *bytes);
+    for (bytes, mut paths) in sizes {
+        paths.sort();
+        let mut first_by_hash: HashMap<Vec<u8>, PathBuf> = HashMap::new();
+        for path in paths {
+            let hash = Sha256::digest(std::fs::read(&path)?).to_vec();
+            match first_by_hash.get(&hash) {
+                Some(original) => usage.reclaimable.push(Reclaimable {
+                    reason: format!("identical to {}", original.display()),
+                    path,
+                    bytes,
+                }),
+                None => {
+                    first_by_hash.insert(hash, path);
+                }
+            }
+        }
+    }
+
+    usage.reclaimable_bytes = usage.reclaimable.iter().map(|r| r.bytes).sum();
+    Ok(usage)
+}
+
+fn walk(
+    dir: &Path,
+    usage: &mut LocalUsage,
+    zips: &mut Vec<(PathBuf, u64)>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let is_diff = ["old.zip", "new.zip", "metrics.json"].iter().all(|f| dir.join(f).is_file());
+    if is_diff {
+        let bytes = dir_size(dir)?;
+        usage.diffs.add(bytes);
+        usage.total_bytes += bytes;
+        usage.reclaimable.push(Reclaimable {
+            path: dir.to_path_buf(),
+            bytes,
+            reason: "regenerate --diff output".to_string(),
+        });
+        return Ok(());
+    }
+    if dir.join(SEQUENCE_FILE).is_file() {
+        let bytes = dir_size(dir)?;
+        usage.sequences.add(bytes);
+        usage.total_bytes += bytes;
+        return Ok(());
+    }
+    let manifest_path = dir.join(MANIFEST_FILE);
+    if manifest_path.is_file()
+        && let Ok(manifest) = serde_json::from_slice::<DropManifest>(&std::fs::read(&manifest_path)?)
+    {
+        // Manifest paths are relative to the project root, which is an
+        // ancestor of the manifest's directory.
+        for file in manifest.assets.iter().flat_map(|a| &a.files) {
+            if let Some(path) = dir.ancestors().map(|a| a.join(file)).find(|p| p.is_file()) {
+                let bytes = path.metadata()?.len();
+                usage.engine_drops.add(bytes);
+                usage.total_bytes += bytes;
+            }
+        }
+    }
+
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&&*name) {
+                walk(&path, usage, zips)?;
+            }
+        } else if file_type.is_file() && name.to_ascii_lowercase().ends_with(".zip") {
+            let bytes = entry.metadata()?.len();
+            usage.result_zips.add(bytes);
+            usage.total_bytes += bytes;
+            zips.push((path, bytes));
+        }
+    }
+    Ok(())
+}
+
+fn dir_size(dir: &Path) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let mut bytes = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            bytes += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            bytes += entry.metadata()?.len();
+        }
+    }
+    Ok(bytes)
+}
+
+fn items(json: &Value) -> Vec<&Value> {
+    match json {
+        Value::Array(items) => items.iter().collect(),
+        Value::Null => Vec::new(),
+        other => vec![other],
+    }
+}
@@ -0,0 +1,254 @@
+//! Content-addressed store for downloaded ZIPs.
+//!
+//! When enabled in the [configuration file](crate::config)
+//! (`[store] enabled = true`), every result ZIP the CLI writes is kept once
+//! under `store/<sha256>/` in the [data directory](crate::paths::data_dir),
+//! and the path asked for becomes a symlink to it. The same result
+//! downloaded into several projects then takes its space once. Where
+//! symlinks are not available the file is written as usual and only
+//! referenced from the entry.
+//!
+//! Each entry lists the paths referring to it in `refs`, one per line.
+//! [`Store::gc`] (`gametorch gc`) removes the entries none of them refers to
+//! anymore: links deleted, moved or overwritten with something else. Given
+//! an age or size limit it removes only those outside it, keeping recent
+//! ones around in case they are downloaded again.
+//!
+//! Stored content is read-only. [`save`] replaces a link rather than writing
+//! through it, so rewriting one project's copy never changes another's.
+
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+/// Directory of the store inside the data directory.
+pub const STORE_DIR: &str = "store";
+
+/// File holding an entry's content.
+const CONTENT_FILE: &str = "content";
+
+/// File listing the paths referring to an entry.
+const REFS_FILE: &str = "refs";
+
+static INSTALLED: OnceLock<Store> = OnceLock::new();
+
+/// A store rooted at a directory.
+#[derive(Clone, Debug)]
+pub struct Store {
+    root: PathBuf,
+}
+
+impl Store {
+    pub fn new(root: impl Into<PathBuf>) -> Store {
+        Store { root: root.into() }
+    }
+
+    /// The store in the default data directory.
+    pub fn open_default() -> Result<Store, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Store::new(crate::paths::data_dir()?.join(STORE_DIR)))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Route every [`save`] in this process through this store.
+    pub fn install(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        INSTALLED
+            .set(self)
+            .map_err(|_| "a store is already installed".into())
+    }
+
+    /// Keep `bytes` in the store and make `path` refer to them, replacing
+    /// whatever was at `path`. Returns the entry's directory.
+    pub fn save(&self, path: &Path, bytes: &[u8]) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+        let hash = crate::manifest::hash_bytes(bytes);
+        let entry = std::path::absolute(self.root.join(&hash))?;
+        let content = entry.join(CONTENT_FILE);
+        if !content.exists() {
+            std::fs::create_dir_all(&entry).map_err(|e| format!("failed to create {}: {}", entry.display(), e))?;
+            // Written aside and renamed, so a half-written entry is never used.
+            let partial = entry.join(format!("{}.partial", CONTENT_FILE));
+            std::fs::write(&partial, bytes).map_err(|e| format!("failed to write {}: {}", partial.display(), e))?;
+            let mut permissions = std::fs::metadata(&partial)?.permissions();
+            permissions.set_readonly(true);
+            std::fs::set_permissions(&partial, permissions)?;
+            std::fs::rename(&partial, &content).map_err(|e| format!("failed to write {}: {}", content.display(), e))?;
+        }
+
+        if std::fs::read_link(path).ok().as_deref() != Some(content.as_path()) {
+            match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(format!("failed to replace {}: {}", path.display(), e).into());
+                }
+                _ => {}
+            }
+            if symlink(&content, path).is_err() {
+                std::fs::write(path, bytes).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+            }
+        }
+        add_ref(&entry, path)?;
+        Ok(entry)
+    }
+
+    /// Remove the entries nothing refers to anymore and forget references
+    /// that no longer point at their entry. With `cutoff` or `max_bytes`,
+    /// an unreferenced entry is only removed if it was stored before
+    /// `cutoff` or does not fit in `max_bytes` next to the referenced and
+    /// newer entries; referenced entries are always kept. With `dry_run`,
+    /// only report what would be removed.
+    pub fn gc(
+        &self,
+        cutoff: Option<SystemTime>,
+        max_bytes: Option<u64>,
+        dry_run: bool,
+    ) -> Result<GcReport, Box<dyn std::error::Error + Send + Sync>> {
+        let mut report = GcReport {
+            root: self.root.display().to_string(),
+            dry_run,
+            ..Default::default()
+        };
+        let entries = match std::fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(format!("failed to read {}: {}", self.root.display(), e).into()),
+        };
+        let mut unreferenced = Vec::new();
+        for entry in entries {
+            let entry = std::path::absolute(entry?.path())?;
+            let Some(hash) = entry.file_name().and_then(|name| name.to_str()).map(str::to_string) else {
+                continue;
+            };
+            let content = entry.join(CONTENT_FILE);
+            let Ok(metadata) = std::fs::metadata(&content) else {
+                continue;
+            };
+            let refs = read_refs(&entry)?;
+            let live: Vec<&PathBuf> = refs.iter().filter(|path| refers_to(path, &content, &hash)).collect();
+            if !live.is_empty() {
+                report.kept += 1;
+                report.kept_bytes += metadata.len();
+                if !dry_run && live.len() < refs.len() {
+                    write_refs(&entry, &live)?;
+                }
+                continue;
+            }
+            unreferenced.push((metadata.modified()?, metadata, hash, entry));
+        }
+
+        // Newest first, so what is kept comes first.
+        unreferenced.sort_by_key(|(modified, ..)| std::cmp::Reverse(*modified));
+        let limited = cutoff.is_some() || max_bytes.is_some();
+        for (modified, metadata, hash, entry) in unreferenced {
+            let expired = cutoff.is_some_and(|cutoff| modified < cutoff);
+            let over = max_bytes.is_some_and(|max| report.kept_bytes + metadata.len() > max);
+            if limited && !expired && !over {
+                report.kept += 1;
+                report.kept_bytes += metadata.len();
+                continue;
+            }
+            report.removed.push(hash);
+            report.freed_bytes += metadata.len();
+            if !dry_run {
+                // Elsewhere a read-only file cannot be deleted.
+                #[cfg(not(unix))]
+                {
+                    let mut permissions = metadata.permissions();
+                    #[allow(clippy::permissions_set_readonly_false)]
+                    permissions.set_readonly(false);
+                    let _ = std::fs::set_permissions(entry.join(CONTENT_FILE), permissions);
+                }
+                std::fs::remove_dir_all(&entry).map_err(|e| format!("failed to remove {}: {}", entry.display(), e))?;
+            }
+        }
+        report.removed.sort();
+        Ok(report)
+    }
+}
+
+/// What [`Store::gc`] did.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GcReport {
+    pub root: String,
+    pub dry_run: bool,
+    /// Hashes of the entries removed (or that would be).
+    pub removed: Vec<String>,
+    pub freed_bytes: u64,
+    /// Entries kept: those still referred to, and unreferenced ones within
+    /// the limits.
+    pub kept: usize,
+    pub kept_bytes: u64,
+}
+
+/// Write `bytes` to `path`: through the [installed](Store::install) store if
+/// there is one, as a plain file otherwise.
+pub fn save(path: &Path, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match INSTALLED.get() {
+        Some(store) => store.save(path, bytes).map(drop),
+        None => {
+            detach(path)?;
+            std::fs::write(path, bytes).map_err(|e| format!("failed to write {}: {}", path.display(), e).into())
+        }
+    }
+}
+
+/// Remove `path` if it is a symlink, so writing to it does not change what
+/// it points at.
+fn detach(path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            std::fs::remove_file(path).map_err(|e| format!("failed to replace {}: {}", path.display(), e).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn symlink(_target: &Path, _link: &Path) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Whether `path` is a link to `content` or a copy of it.
+fn refers_to(path: &Path, content: &Path, hash: &str) -> bool {
+    match std::fs::read_link(path) {
+        Ok(target) => target == content,
+        Err(_) => path.is_file() && crate::manifest::hash_file(path).is_ok_and(|h| h == hash),
+    }
+}
+
+fn read_refs(entry: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let path = entry.join(REFS_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => Ok(text.lines().filter(|line| !line.is_empty()).map(PathBuf::from).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("failed to read {}: {}", path.display(), e).into()),
+    }
+}
+
+fn write_refs(entry: &Path, refs: &[&PathBuf]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let path = entry.join(REFS_FILE);
+    let text: String = refs.iter().map(|r| format!("{}\n", r.display())).collect();
+    std::fs::write(&path, text).map_err(|e| format!("failed to write {}: {}", path.display(), e).into())
+}
+
+/// Record that `path` refers to `entry`, once.
+fn add_ref(entry: &Path, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let path = std::path::absolute(path)?;
+    if read_refs(entry)?.contains(&path) {
+        return Ok(());
+    }
+    let refs = entry.join(REFS_FILE);
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&refs)
+        .and_then(|mut file| file.write_all(format!("{}\n", path.display()).as_bytes()))
+        .map_err(|e| format!("failed to write {}: {}", refs.display(), e).into())
+}
@@ -0,0 +1,145 @@
+//! Lifecycle timeline of an animation, for `animations history <id>`.
+//!
+//! Built from the timestamps on an animation's results: when it was
+//! submitted, when rendering started, when it completed (or failed) and when
+//! its ZIP was ready. Backends differ in which of these they report, so each
+//! is read from the first of a few field names that is set, as a Unix
+//! timestamp or an ISO 8601 string. The local job [history](crate::history)
+//! fills in submission and completion when the results lack them.
+//!
+//! The gaps between events show where the time went: `queue_seconds` waiting
+//! for a renderer, `render_seconds` rendering.
+
+use crate::history::Job;
+use serde::Serialize;
+use serde_json::Value;
+
+const SUBMITTED_FIELDS: &[&str] = &["created_at", "submitted_at"];
+const STARTED_FIELDS: &[&str] = &["started_at", "rendering_started_at", "processing_started_at"];
+const FINISHED_FIELDS: &[&str] = &["completed_at", "finished_at", "failed_at"];
+const ZIP_FIELDS: &[&str] = &["zip_ready_at", "zip_created_at"];
+
+/// One point in an animation's life.
+#[derive(Clone, Debug, Serialize)]
+pub struct Event {
+    /// `submitted`, `rendering`, `complete`, `failed` or `zip_ready`.
+    pub event: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<i64>,
+    /// UTC, `YYYY-MM-DDTHH:MM:SSZ`.
+    pub at: String,
+    /// Seconds since submission.
+    pub elapsed_seconds: Option<u64>,
+    #[serde(skip)]
+    unix: u64,
+}
+
+/// Events in order, with the durations between them.
+#[derive(Clone, Debug, Serialize)]
+pub struct Timeline {
+    pub animation_id: i64,
+    pub events: Vec<Event>,
+    /// Submission to the first result starting to render.
+    pub queue_seconds: Option<u64>,
+    /// Rendering start (or submission, if unknown) to the last result finishing.
+    pub render_seconds: Option<u64>,
+    /// Submission to the last event.
+    pub total_seconds: Option<u64>,
+}
+
+/// The timeline of `animation_id` from its `results` (as returned by
+/// [`animations::get`](crate::animations::get)) and its local history
+/// record, if any.
+pub fn timeline(animation_id: i64, results: &Value, job: Option<&Job>) -> Timeline {
+    let items: Vec<&Value> = match results {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(map) => match map.get("results").or_else(|| map.get("items")) {
+            Some(Value::Array(items)) => items.iter().collect(),
+            _ => vec![results],
+        },
+        _ => Vec::new(),
+    };
+
+    let submitted = items
+        .iter()
+        .filter_map(|item| timestamp(item, SUBMITTED_FIELDS))
+        .min()
+        .or(job.map(|job| job.submitted_at));
+    let mut events = Vec::new();
+    if let Some(at) = submitted {
+        events.push(event("submitted", None, at));
+    }
+    let single = items.len() == 1;
+    for item in &items {
+        let result_id = item.get("id").and_then(Value::as_i64).filter(|_| !single);
+        if let Some(at) = timestamp(item, STARTED_FIELDS) {
+            events.push(event("rendering", result_id, at));
+        }
+        let finished = timestamp(item, FINISHED_FIELDS).or_else(|| {
+            // The local record only knows when the CLI saw the job finish.
+            job.filter(|_| single).and_then(|job| job.completed_at)
+        });
+        if let Some(at) = finished {
+            let failed = item.get("status").and_then(Value::as_i64) == Some(3);
+            events.push(event(if failed { "failed" } else { "complete" }, result_id, at));
+        }
+        if let Some(at) = timestamp(item, ZIP_FIELDS) {
+            events.push(event("zip_ready", result_id, at));
+        }
+    }
+    if items.is_empty()
+        && let Some(at) = job.and_then(|job| job.completed_at)
+    {
+        let failed = job.is_some_and(|job| job.status == "failed");
+        events.push(event(if failed { "failed" } else { "complete" }, None, at));
+    }
+    events.sort_by_key(|e| e.unix);
+    for e in &mut events {
+        e.elapsed_seconds = submitted.map(|start| e.unix.saturating_sub(start));
+    }
+
+    let started = events.iter().find(|e| e.event == "rendering").map(|e| e.unix);
+    let finished = events
+        .iter()
+        .filter(|e| matches!(e.event, "complete" | "failed"))
+        .map(|e| e.unix)
+        .max();
+    Timeline {
+        animation_id,
+        queue_seconds: submitted.zip(started).map(|(s, r)| r.saturating_sub(s)),
+        render_seconds: started.or(submitted).zip(finished).map(|(s, f)| f.saturating_sub(s)),
+        total_seconds: submitted.zip(events.last()).map(|(s, last)| last.unix.saturating_sub(s)),
+        events,
+    }
+}
+
+fn event(name: &'static str, result_id: Option<i64>, unix: u64) -> Event {
+    Event {
+        event: name,
+        result_id,
+        at: utc_datetime(unix),
+        elapsed_seconds: None,
+        unix,
+    }
+}
+
+/// The first of `fields` set on `item`, as Unix seconds.
+fn timestamp(item: &Value, fields: &[&str]) -> Option<u64> {
+    fields.iter().find_map(|field| match item.get(*field)? {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => crate::animations::parse_date(s),
+        _ => None,
+    })
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ` for a Unix timestamp.
+fn utc_datetime(unix: u64) -> String {
+    let secs = unix % 86_400;
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        crate::clock::utc_date(unix),
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,155 @@
+//! HTTP transcripts for bug reports (`--debug-http FILE`).
+//!
+//! When enabled, the [client](crate::client) appends one JSON line per
+//! attempt to the transcript file: method, URL, request and response headers
+//! and bodies, status and timing. Credentials are never written: the
+//! `Authorization`, `Proxy-Authorization`, `Cookie` and `Set-Cookie` headers
+//! are replaced by `[redacted]`. Binary bodies (such as result ZIPs) are
+//! recorded by size only, and long text bodies are cut short.
+
+use reqwest::header::HeaderMap;
+use reqwest::{Request, Response};
+use serde_json::{Map, Value, json};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Headers whose values are never written.
+const REDACTED_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+/// Longest body, in bytes, written in full.
+const MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// An open transcript file.
+#[derive(Debug)]
+pub struct Transcript {
+    path: PathBuf,
+    file: Mutex<std::fs::File>,
+}
+
+/// What was sent, captured before the request is handed to the connection.
+pub(crate) struct Sent {
+    started: Instant,
+    record: Map<String, Value>,
+}
+
+impl Transcript {
+    /// Open (or create) `path` for appending.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Transcript, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+        Ok(Transcript {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Capture attempt number `attempt` of `request`.
+    pub(crate) fn sent(&self, request: &Request, attempt: u32) -> Sent {
+        let mut record = Map::new();
+        record.insert("time".into(), json!(crate::history::unix_now()));
+        record.insert("attempt".into(), json!(attempt));
+        record.insert("method".into(), json!(request.method().as_str()));
+        record.insert("url".into(), json!(request.url().as_str()));
+        let body = request.body().and_then(|b| b.as_bytes());
+        record.insert(
+            "request".into(),
+            json!({ "headers": headers(request.headers()), "body": body.map(body_value) }),
+        );
+        Sent {
+            started: Instant::now(),
+            record,
+        }
+    }
+
+    /// Record the outcome of `sent`. A response is read in full and handed
+    /// back rebuilt, so the caller sees the same status, headers and body.
+    pub(crate) async fn received(&self, sent: Sent, result: reqwest::Result<Response>) -> reqwest::Result<Response> {
+        let Sent { started, mut record } = sent;
+        let result = match result {
+            Ok(response) => {
+                let status = response.status();
+                let version = response.version();
+                let response_headers = response.headers().clone();
+                match response.bytes().await {
+                    Ok(body) => {
+                        record.insert(
+                            "response".into(),
+                            json!({
+                                "status": status.as_u16(),
+                                "headers": headers(&response_headers),
+                                "body": body_value(&body),
+                            }),
+                        );
+                        let mut rebuilt = http::Response::new(body);
+                        *rebuilt.status_mut() = status;
+                        *rebuilt.version_mut() = version;
+                        *rebuilt.headers_mut() = response_headers;
+                        Ok(Response::from(rebuilt))
+                    }
+                    Err(err) => {
+                        record.insert("response".into(), json!({ "status": status.as_u16() }));
+                        record.insert("error".into(), json!(err.to_string()));
+                        Err(err)
+                    }
+                }
+            }
+            Err(err) => {
+                record.insert("error".into(), json!(err.to_string()));
+                Err(err)
+            }
+        };
+        record.insert("elapsed_ms".into(), json!(started.elapsed().as_millis() as u64));
+        self.write(&Value::Object(record));
+        result
+    }
+
+    fn write(&self, record: &Value) {
+        let line = record.to_string() + "\n";
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            eprintln!("Failed to write HTTP transcript {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Headers as a JSON object, credentials redacted.
+fn headers(map: &HeaderMap) -> Value {
+    let mut out = Map::new();
+    for (name, value) in map {
+        let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+            "[redacted]".to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+        out.insert(name.as_str().to_string(), Value::String(value));
+    }
+    Value::Object(out)
+}
+
+/// A body as text (cut to [`MAX_BODY_BYTES`]) or, if binary, its size.
+fn body_value(body: &[u8]) -> Value {
+    match std::str::from_utf8(body) {
+        Ok(text) if text.len() <= MAX_BODY_BYTES => Value::String(text.to_string()),
+        Ok(text) => {
+            let mut end = MAX_BODY_BYTES;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            Value::String(format!("{}… [{} bytes total]", &text[..end], text.len()))
+        }
+        Err(_) => Value::String(format!("[{} bytes of binary data]", body.len())),
+    }
+}
@@ -0,0 +1,274 @@
+//! Interactive terminal browser for the animation library (`gametorch ui`).
+//!
+//! Lists the user's animations next to the selected one's prompt and
+//! metadata, refreshing statuses in the background every
+//! [`REFRESH_INTERVAL`]. Keys: arrows (or `j`/`k`) to move, `d` to download
+//! the result ZIP into the current directory, `o` to download and open it,
+//! `g` to regenerate, `x` to delete (confirmed with `y`), `r` to refresh
+//! and `q` to quit. Requires the `tui` feature.
+
+use crate::animations::{self, ListOptions};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// How often the listing is re-fetched.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long to wait for a key before checking whether to refresh.
+const TICK: Duration = Duration::from_millis(250);
+
+struct App<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    items: Vec<Value>,
+    state: ListState,
+    /// One-line feedback shown above the key help.
+    message: String,
+    /// Animation awaiting a `y` to be deleted.
+    confirm_delete: Option<String>,
+    refreshed: Instant,
+}
+
+/// Run the browser until the user quits, restoring the terminal afterwards.
+pub async fn run(api_key: &str, base_url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut app = App {
+        api_key,
+        base_url,
+        items: Vec::new(),
+        state: ListState::default(),
+        message: String::new(),
+        confirm_delete: None,
+        refreshed: Instant::now(),
+    };
+    // Fail before touching the terminal if the API is unreachable.
+    app.refresh().await?;
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal).await;
+    ratatui::restore();
+    result
+}
+
+impl App<'_> {
+    async fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if self.refreshed.elapsed() >= REFRESH_INTERVAL {
+                self.refresh_or_report().await;
+            }
+            if !event::poll(TICK)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(id) = self.confirm_delete.take() {
+                if key.code == KeyCode::Char('y') {
+                    self.message = format!("Deleting animation {}...", id);
+                    terminal.draw(|frame| self.draw(frame))?;
+                    self.message = match animations::delete(self.api_key, self.base_url, &id).await {
+                        Ok(()) => format!("Deleted animation {}", id),
+                        Err(err) => format!("Failed to delete animation {}: {}", id, err),
+                    };
+                    self.refresh_or_report().await;
+                } else {
+                    self.message = "Delete cancelled".to_string();
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+                KeyCode::Home => self.state.select_first(),
+                KeyCode::End => self.state.select_last(),
+                KeyCode::Char('r') => {
+                    self.message = "Refreshing...".to_string();
+                    terminal.draw(|frame| self.draw(frame))?;
+                    self.refresh_or_report().await;
+                    self.message = String::new();
+                }
+                KeyCode::Char('d') | KeyCode::Char('o') => {
+                    let Some(id) = self.selected_id() else { continue };
+                    self.message = format!("Downloading animation {}...", id);
+                    terminal.draw(|frame| self.draw(frame))?;
+                    self.message = match self.download(&id).await {
+                        Ok(path) if key.code == KeyCode::Char('o') => match open(&path) {
+                            Ok(()) => format!("Opened {}", path),
+                            Err(err) => format!("Saved {} but could not open it: {}", path, err),
+                        },
+                        Ok(path) => format!("Saved {}", path),
+                        Err(err) => format!("Failed to download animation {}: {}", id, err),
+                    };
+                }
+                KeyCode::Char('g') => {
+                    let Some(id) = self.selected_id() else { continue };
+                    self.message = match animations::regenerate(self.api_key, self.base_url, &id, None).await {
+                        Ok(json) => match json.get("animation_id") {
+                            Some(new_id) => format!("Regenerating animation {} as {}", id, new_id),
+                            None => format!("Regenerating animation {}", id),
+                        },
+                        Err(err) => format!("Failed to regenerate animation {}: {}", id, err),
+                    };
+                    self.refresh_or_report().await;
+                }
+                KeyCode::Char('x') | KeyCode::Delete => {
+                    if let Some(id) = self.selected_id() {
+                        self.message = format!("Delete animation {} and its results? Press y to confirm", id);
+                        self.confirm_delete = Some(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn refresh(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let list = animations::list(self.api_key, self.base_url, &ListOptions::default()).await?;
+        let selected = self.selected_id();
+        self.items = match list {
+            Value::Array(items) => items,
+            other => vec![other],
+        };
+        // Keep the same animation selected across refreshes.
+        let index = selected
+            .and_then(|id| self.items.iter().position(|item| id_of(item).as_deref() == Some(id.as_str())))
+            .or(if self.items.is_empty() { None } else { Some(0) });
+        self.state.select(index);
+        self.refreshed = Instant::now();
+        Ok(())
+    }
+
+    async fn refresh_or_report(&mut self) {
+        if let Err(err) = self.refresh().await {
+            self.message = format!("Failed to refresh: {}", err);
+            // Don't retry on every tick.
+            self.refreshed = Instant::now();
+        }
+    }
+
+    fn selected(&self) -> Option<&Value> {
+        self.items.get(self.state.selected()?)
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.selected().and_then(id_of)
+    }
+
+    /// Save the first result of `id` as `animation_<id>_<result>.zip`.
+    async fn download(&self, id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let results = animations::get(self.api_key, self.base_url, id).await?;
+        match animations::first_status(&results) {
+            Some(2) => {}
+            Some(3) => return Err("the animation failed".into()),
+            _ => return Err("the animation is still rendering".into()),
+        }
+        let result_id = animations::first_result_id(&results).ok_or("result id missing")?;
+        let bytes = animations::download_zip(self.api_key, self.base_url, &result_id.to_string(), true).await?;
+        let path = format!("animation_{}_{}.zip", id, result_id);
+        tokio::fs::write(&path, &bytes).await?;
+        Ok(path)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, message, help] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1), Constraint::Length(1)]).areas(frame.area());
+        let [list_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(main);
+
+        let items: Vec<ListItem> = self
+            .items
+            .iter()
+            .map(|item| {
+                let status = status_of(item);
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:>8}  ", id_of(item).unwrap_or_default())),
+                    Span::styled(format!("{:<10}  ", status), status_style(&status)),
+                    Span::raw(title_of(item)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(format!(" Animations ({}) ", self.items.len())))
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+
+        let detail = match self.selected() {
+            Some(item) => {
+                let mut lines: Vec<Line> = [
+                    ("ID", field(item, "id")),
+                    ("Name", field(item, "name")),
+                    ("Status", status_of(item)),
+                    ("Duration", field(item, "duration_seconds")),
+                    ("Model", field(item, "model_name")),
+                    ("Created", field(item, "created_at")),
+                ]
+                .into_iter()
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(label, value)| Line::from(vec![Span::raw(format!("{:<9} ", label)).bold(), Span::raw(value)]))
+                .collect();
+                lines.push(Line::raw(""));
+                lines.push(Line::raw("Prompt").bold());
+                lines.push(Line::raw(field(item, "prompt")));
+                Paragraph::new(lines)
+            }
+            None => Paragraph::new("No animations"),
+        };
+        frame.render_widget(
+            detail.block(Block::bordered().title(" Details ")).wrap(Wrap { trim: false }),
+            detail_area,
+        );
+
+        frame.render_widget(Paragraph::new(self.message.as_str()), message);
+        frame.render_widget(
+            Paragraph::new("↑/↓ move  d download  o open  g regenerate  x delete  r refresh  q quit").dim(),
+            help,
+        );
+    }
+}
+
+fn field(item: &Value, key: &str) -> String {
+    match item.get(key) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn id_of(item: &Value) -> Option<String> {
+    Some(field(item, "id")).filter(|id| !id.is_empty())
+}
+
+fn title_of(item: &Value) -> String {
+    let name = field(item, "name");
+    if name.is_empty() { field(item, "prompt") } else { name }
+}
+
+fn status_of(item: &Value) -> String {
+    match animations::Status::of(item) {
+        Some(status) => status.to_string(),
+        None => field(item, "status"),
+    }
+}
+
+fn status_style(status: &str) -> Style {
+    match status {
+        "complete" => Style::new().fg(Color::Green),
+        "generating" => Style::new().fg(Color::Yellow),
+        "failed" => Style::new().fg(Color::Red),
+        _ => Style::new(),
+    }
+}
+
+/// Open `path` with the platform's default application.
+fn open(path: &str) -> std::io::Result<()> {
+    open::that_detached(path)
+}
@@ -0,0 +1,236 @@
+//! Local usage statistics, for `gametorch stats`.
+//!
+//! Nothing is sent anywhere. When enabled in the
+//! [configuration file](crate::config) (`[stats] enabled = true`), every CLI
+//! run appends its command, duration and exit code to `usage.jsonl` in the
+//! [data directory](crate::paths::data_dir). [`summarize`] combines those
+//! runs with the job [history](crate::history) into per-week generation
+//! success rates, render times and credits spent.
+
+use crate::history::Job;
+use crate::pricing::{self, PriceTable};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// File name of the usage log inside the data directory.
+pub const USAGE_FILE: &str = "usage.jsonl";
+
+/// One CLI invocation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommandRun {
+    /// Subcommand path, e.g. `animations generate`.
+    pub command: String,
+    /// Unix timestamp (seconds) the run started.
+    pub started_at: u64,
+    pub elapsed_ms: u64,
+    pub exit_code: i32,
+}
+
+/// The usage log at a given path.
+#[derive(Clone, Debug)]
+pub struct UsageLog {
+    path: PathBuf,
+}
+
+impl UsageLog {
+    pub fn new(path: impl Into<PathBuf>) -> UsageLog {
+        UsageLog { path: path.into() }
+    }
+
+    /// The usage log in the default data directory.
+    pub fn open_default() -> Result<UsageLog, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(UsageLog::new(crate::paths::data_dir()?.join(USAGE_FILE)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `run`.
+    pub fn record(&self, run: &CommandRun) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(run)?;
+        line.push('\n');
+        // A single write keeps lines from concurrent appenders intact.
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| format!("failed to write {}: {}", self.path.display(), e).into())
+    }
+
+    /// Every recorded run, oldest first. A missing file is an empty log;
+    /// unreadable lines are skipped.
+    pub fn runs(&self) -> Result<Vec<CommandRun>, Box<dyn std::error::Error + Send + Sync>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(text) => Ok(text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("failed to read {}: {}", self.path.display(), e).into()),
+        }
+    }
+}
+
+/// A run in progress, recorded when it [finishes](Run::finish).
+#[derive(Debug)]
+pub struct Run {
+    log: UsageLog,
+    command: String,
+    started: Instant,
+    started_at: u64,
+}
+
+impl Run {
+    pub fn start(log: UsageLog, command: impl Into<String>) -> Run {
+        Run {
+            log,
+            command: command.into(),
+            started: Instant::now(),
+            started_at: crate::history::unix_now(),
+        }
+    }
+
+    /// Record the run with `exit_code`. Stats are not worth failing a
+    /// command over, so errors are only reported.
+    pub fn finish(&self, exit_code: i32) {
+        let run = CommandRun {
+            command: self.command.clone(),
+            started_at: self.started_at,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            exit_code,
+        };
+        if let Err(err) = self.log.record(&run) {
+            eprintln!("Warning: failed to record usage stats: {}", err);
+        }
+    }
+}
+
+/// Generations submitted in one week.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Week {
+    /// Monday the week starts on (UTC, `YYYY-MM-DD`).
+    pub week: String,
+    pub generations: usize,
+    pub complete: usize,
+    pub failed: usize,
+    /// Share of finished generations that completed.
+    pub success_rate: Option<f64>,
+    /// Mean submission-to-completion time of completed generations.
+    pub mean_render_seconds: Option<f64>,
+    /// Credits of completed generations (failed ones are refunded).
+    pub credits: u64,
+    /// Completed generations with no known price, not counted in `credits`.
+    pub unpriced: usize,
+}
+
+/// Runs of one command.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CommandStats {
+    pub command: String,
+    pub runs: usize,
+    pub succeeded: usize,
+    pub success_rate: f64,
+    pub mean_seconds: f64,
+}
+
+/// What `gametorch stats` reports.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Summary {
+    /// Most recent week first.
+    pub weeks: Vec<Week>,
+    /// Most used command first.
+    pub commands: Vec<CommandStats>,
+    /// Where `credits` prices came from (`backend` or `bundled`).
+    pub price_source: String,
+}
+
+/// Summarize the last `weeks` weeks of `jobs`, and all of `runs`.
+pub fn summarize(jobs: &[Job], runs: &[CommandRun], prices: &PriceTable, weeks: usize) -> Summary {
+    let mut by_week: BTreeMap<String, (Week, Vec<f64>)> = BTreeMap::new();
+    for job in jobs {
+        let week = week_of(job.submitted_at);
+        let (entry, render) = by_week.entry(week.clone()).or_insert_with(|| {
+            (
+                Week {
+                    week,
+                    ..Default::default()
+                },
+                Vec::new(),
+            )
+        });
+        entry.generations += 1;
+        match job.status.as_str() {
+            "complete" => {
+                entry.complete += 1;
+                if let Some(seconds) = job.render_seconds() {
+                    render.push(seconds as f64);
+                }
+                // History records the model by name, or by ID if no name was given.
+                let model_id = job.model.as_deref().and_then(|model| model.parse().ok());
+                let model_name = job.model.clone().filter(|_| model_id.is_none());
+                let priced = job
+                    .duration_seconds
+                    .and_then(|duration| prices.price(&pricing::Job::new(model_id, model_name, duration)).ok());
+                match priced {
+                    Some(price) => entry.credits += u64::from(price.credits),
+                    None => entry.unpriced += 1,
+                }
+            }
+            "failed" => entry.failed += 1,
+            _ => {}
+        }
+    }
+    let weeks = by_week
+        .into_values()
+        .rev()
+        .take(weeks)
+        .map(|(mut week, render)| {
+            let finished = week.complete + week.failed;
+            if finished > 0 {
+                week.success_rate = Some(week.complete as f64 / finished as f64);
+            }
+            if !render.is_empty() {
+                week.mean_render_seconds = Some(render.iter().sum::<f64>() / render.len() as f64);
+            }
+            week
+        })
+        .collect();
+
+    let mut by_command: BTreeMap<&str, (usize, usize, u64)> = BTreeMap::new();
+    for run in runs {
+        let (count, succeeded, elapsed) = by_command.entry(&run.command).or_default();
+        *count += 1;
+        *succeeded += usize::from(run.exit_code == 0);
+        *elapsed += run.elapsed_ms;
+    }
+    let mut commands: Vec<CommandStats> = by_command
+        .into_iter()
+        .map(|(command, (runs, succeeded, elapsed))| CommandStats {
+            command: command.to_string(),
+            runs,
+            succeeded,
+            success_rate: succeeded as f64 / runs as f64,
+            mean_seconds: elapsed as f64 / runs as f64 / 1000.0,
+        })
+        .collect();
+    commands.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.command.cmp(&b.command)));
+
+    Summary {
+        weeks,
+        commands,
+        price_source: prices.source.as_str().to_string(),
+    }
+}
+
+/// The Monday (UTC) of the week containing `unix`.
+fn week_of(unix: u64) -> String {
+    let days = unix / 86_400;
+    // 1970-01-01 was a Thursday.
+    let monday = days.saturating_sub((days + 3) % 7);
+    crate::clock::utc_date(monday * 86_400)
+}
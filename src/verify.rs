@@ -0,0 +1,102 @@
+//! Checking downloaded results against their SHA-256.
+//!
+//! When the backend sends a result ZIP's checksum (the
+//! `X-Checksum-Sha256` header), the download is [checked](check) before it
+//! is saved. The hash of every saved ZIP is kept: in the workspace's
+//! [asset manifest](crate::manifest), and in the [job history](crate::history)
+//! for downloads outside a workspace. `gametorch verify` re-checks files
+//! against those records later, by path or by animation or result ID.
+
+use crate::error::{Error, ErrorKind};
+use crate::history::History;
+use crate::manifest::{hash_bytes, hash_file, Manifest};
+use crate::workspace::Workspace;
+use serde::Serialize;
+use std::path::Path;
+
+/// Response header carrying the hex SHA-256 of a result ZIP.
+pub const CHECKSUM_HEADER: &str = "x-checksum-sha256";
+
+/// Fail unless `bytes` hash to `expected` (hex, any case).
+pub fn check(bytes: &[u8], expected: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let actual = hash_bytes(bytes);
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(Error::new(
+            ErrorKind::Server,
+            format!("checksum mismatch: expected SHA-256 {}, got {}", expected.trim(), actual),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The outcome of re-checking one file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Verification {
+    pub path: String,
+    pub expected: String,
+    /// `None` if the file is missing.
+    pub actual: Option<String>,
+    /// Where the expected hash was recorded: `manifest` or `history`.
+    pub source: &'static str,
+    pub ok: bool,
+}
+
+impl Verification {
+    fn new(path: &Path, expected: &str, source: &'static str) -> Verification {
+        let actual = hash_file(path).ok();
+        Verification {
+            path: path.display().to_string(),
+            expected: expected.to_string(),
+            ok: actual.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(expected)),
+            actual,
+            source,
+        }
+    }
+}
+
+/// Re-check the files recorded for `target`: a file path, or an animation
+/// or result ID. Files are looked up in the workspace manifest (if any)
+/// first and the job history second; it is an error if neither has a hash
+/// for the target.
+pub fn verify(target: &str, workspace: Option<&Workspace>) -> Result<Vec<Verification>, Box<dyn std::error::Error + Send + Sync>> {
+    let id = target.parse::<i64>().ok().filter(|_| !Path::new(target).exists());
+    let mut checks = Vec::new();
+
+    if let Some(workspace) = workspace {
+        let manifest = Manifest::load(workspace)?;
+        let wanted = match id {
+            None => manifest.relative(Path::new(target)).ok(),
+            Some(_) => None,
+        };
+        for asset in manifest.assets.values() {
+            let by_id = id.is_some_and(|id| asset.animation_id == id || asset.result_id == Some(id));
+            for file in asset.zip.iter().chain(&asset.files) {
+                if by_id || wanted.as_deref() == Some(file.path.as_str()) {
+                    checks.push(Verification::new(&manifest.resolve(file), &file.sha256, "manifest"));
+                }
+            }
+        }
+    }
+
+    if checks.is_empty() {
+        let absolute = std::path::absolute(target).ok();
+        for job in History::open_default()?.jobs()? {
+            let (Some(zip_path), Some(sha256)) = (&job.zip_path, &job.zip_sha256) else {
+                continue;
+            };
+            let matches = match id {
+                Some(id) => job.animation_id == id || job.result_id == Some(id),
+                None => absolute.as_deref() == std::path::absolute(zip_path).ok().as_deref(),
+            };
+            if matches {
+                checks.push(Verification::new(Path::new(zip_path), sha256, "history"));
+            }
+        }
+    }
+
+    if checks.is_empty() {
+        return Err(Error::new(ErrorKind::Validation, format!("no checksum recorded for {}", target)).into());
+    }
+    Ok(checks)
+}
@@ -0,0 +1,98 @@
+//! State for `gametorch watch`, which processes results as they complete.
+//!
+//! The watcher remembers which animations it has already handled in
+//! `watch.json` in the [data directory](crate::paths::data_dir), so it can be
+//! stopped and restarted (or run from cron with `--once`) without processing
+//! anything twice.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// File name of the watch state inside the data directory.
+pub const WATCH_STATE_FILE: &str = "watch.json";
+
+/// Default seconds between polls.
+pub const DEFAULT_INTERVAL_SECS: u64 = 30;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct StateFile {
+    /// Animation IDs already handled.
+    processed: BTreeSet<String>,
+}
+
+/// Which animations the watcher has handled.
+#[derive(Clone, Debug)]
+pub struct WatchState {
+    path: PathBuf,
+    processed: BTreeSet<String>,
+    /// Whether the state file existed when loaded.
+    existed: bool,
+}
+
+impl WatchState {
+    /// Load the state at `path`; a missing file is a fresh state.
+    pub fn load(path: impl Into<PathBuf>) -> Result<WatchState, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.into();
+        let (processed, existed) = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let file: StateFile = serde_json::from_slice(&bytes)
+                    .map_err(|e| format!("invalid watch state {}: {}", path.display(), e))?;
+                (file.processed, true)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (BTreeSet::new(), false),
+            Err(e) => return Err(format!("failed to read {}: {}", path.display(), e).into()),
+        };
+        Ok(WatchState { path, processed, existed })
+    }
+
+    /// The state in the default data directory.
+    pub fn load_default() -> Result<WatchState, Box<dyn std::error::Error + Send + Sync>> {
+        WatchState::load(crate::paths::data_dir()?.join(WATCH_STATE_FILE))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// True until the state has been saved once.
+    pub fn is_fresh(&self) -> bool {
+        !self.existed
+    }
+
+    pub fn is_processed(&self, animation_id: &str) -> bool {
+        self.processed.contains(animation_id)
+    }
+
+    pub fn mark_processed(&mut self, animation_id: &str) {
+        self.processed.insert(animation_id.to_string());
+    }
+
+    /// Write the state (via a temporary file and rename).
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = StateFile {
+            processed: self.processed.clone(),
+        };
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&file)?)?;
+        std::fs::rename(&tmp, &self.path)
+            .map_err(|e| format!("failed to write {}: {}", self.path.display(), e))?;
+        self.existed = true;
+        Ok(())
+    }
+}
+
+/// Whether a listed animation may have a completed result.
+///
+/// Listings that carry a numeric `status` are trusted; items without one
+/// have to be checked individually.
+pub fn maybe_complete(item: &Value) -> bool {
+    match item.get("status").and_then(|s| s.as_i64()) {
+        Some(status) => status == 2,
+        None => true,
+    }
+}
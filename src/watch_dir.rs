@@ -0,0 +1,137 @@
+//! Drop-folder automation for `gametorch watch-dir`.
+//!
+//! A [`DirWatcher`] follows a directory (through `notify`) and hands out every
+//! image that lands in it once the file has stopped changing for [`SETTLE`],
+//! so images still being copied are not uploaded half-written.
+//!
+//! The prompt for an image comes from a sidecar text file next to it
+//! (`knight.png` → `knight.txt`), else optionally from its file name
+//! (`knight_walking_left.png` → "knight walking left"), else from a fixed
+//! prompt; see [`prompt_for`].
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::time::Instant;
+
+/// How long an image must go unchanged before it is handed out.
+pub const SETTLE: Duration = Duration::from_millis(1500);
+
+/// File extensions treated as input images.
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+/// Whether `path` names an input image.
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.iter().any(|ext| e.eq_ignore_ascii_case(ext)))
+}
+
+/// The sidecar prompt file of an image.
+pub fn sidecar_path(image: &Path) -> PathBuf {
+    image.with_extension("txt")
+}
+
+/// A prompt made from an image's file name, with `_`, `-` and `.` read as
+/// spaces; `None` if nothing is left.
+pub fn filename_prompt(image: &Path) -> Option<String> {
+    let stem = image.file_stem()?.to_string_lossy();
+    let words: Vec<&str> = stem.split(['_', '-', '.', ' ']).filter(|w| !w.is_empty()).collect();
+    if words.is_empty() { None } else { Some(words.join(" ")) }
+}
+
+/// The prompt for `image`: its sidecar's text if there is a non-empty one,
+/// else its file name when `from_filename`, else `fallback`.
+pub fn prompt_for(
+    image: &Path,
+    from_filename: bool,
+    fallback: Option<&str>,
+) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let sidecar = sidecar_path(image);
+    match std::fs::read_to_string(&sidecar) {
+        Ok(text) if !text.trim().is_empty() => return Ok(Some(text.trim().to_string())),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("failed to read {}: {}", sidecar.display(), e).into()),
+    }
+    if from_filename && let Some(prompt) = filename_prompt(image) {
+        return Ok(Some(prompt));
+    }
+    Ok(fallback.map(str::to_string))
+}
+
+/// Images already in `dir`, in file name order.
+pub fn existing_images(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut images: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| format!("failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_image(path))
+        .collect();
+    images.sort();
+    Ok(images)
+}
+
+/// Images created or rewritten in one directory.
+pub struct DirWatcher {
+    // Dropping the watcher stops the events.
+    _watcher: RecommendedWatcher,
+    events: UnboundedReceiver<PathBuf>,
+    /// Images seen changing, with when they last changed.
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl DirWatcher {
+    /// Start watching `dir` (not its subdirectories).
+    pub fn new(dir: &Path) -> Result<DirWatcher, Box<dyn std::error::Error + Send + Sync>> {
+        let (tx, events) = unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else { return };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                return;
+            }
+            for path in event.paths.into_iter().filter(|path| is_image(path)) {
+                let _ = tx.send(path);
+            }
+        })?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("failed to watch {}: {}", dir.display(), e))?;
+        Ok(DirWatcher {
+            _watcher: watcher,
+            events,
+            pending: HashMap::new(),
+        })
+    }
+
+    /// The next image to have settled, or `None` once the watcher stops.
+    pub async fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            let settled = self
+                .pending
+                .iter()
+                .map(|(path, changed)| (path.clone(), *changed + SETTLE))
+                .min_by_key(|(_, due)| *due);
+            match settled {
+                Some((path, due)) if due <= Instant::now() => {
+                    self.pending.remove(&path);
+                    // Skip files moved away again, such as editors' temporaries.
+                    if path.is_file() {
+                        return Some(path);
+                    }
+                }
+                Some((_, due)) => {
+                    tokio::select! {
+                        event = self.events.recv() => self.pending.insert(event?, Instant::now()),
+                        () = tokio::time::sleep_until(due) => None,
+                    };
+                }
+                None => {
+                    let path = self.events.recv().await?;
+                    self.pending.insert(path, Instant::now());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Completion webhooks.
+//!
+//! `generate --webhook-url` asks the backend to `POST` to a URL when the
+//! animation finishes, so CI systems are notified without holding a
+//! connection open. The backend keeps the registered hooks; they can be
+//! listed and deleted here.
+
+use serde_json::Value;
+
+/// Check that `url` is an absolute `http(s)` URL the backend can call.
+pub fn validate_url(url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid webhook URL '{}': {}", url, e))?;
+    match parsed.scheme() {
+        "https" | "http" if parsed.host_str().is_some() => Ok(()),
+        _ => Err(format!("webhook URL must be an http(s) URL with a host, got '{}'", url).into()),
+    }
+}
+
+/// Registered webhooks.
+///
+/// Hits `GET /api/webhooks`.
+pub async fn list(api_key: &str, base_url: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let url = format!("{}/api/webhooks", base_url);
+
+    let json: Value = client
+        .send(client.get(&url).header("Authorization", format!("Bearer {}", api_key)))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(json)
+}
+
+/// Delete a registered webhook.
+///
+/// Hits `DELETE /api/webhooks/<webhook_id>`.
+pub async fn delete(
+    api_key: &str,
+    base_url: &str,
+    webhook_id: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let client = crate::client::shared();
+    let url = format!("{}/api/webhooks/{}", base_url, webhook_id);
+
+    client
+        .send(client.delete(&url).header("Authorization", format!("Bearer {}", api_key)))
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+//! Project workspaces: per-game defaults in `gametorch.toml`.
+//!
+//! `gametorch init` writes a `gametorch.toml` at the root of a game project.
+//! Commands run anywhere inside the project find it by walking up from the
+//! current directory and use its settings wherever no flag overrides them:
+//!
+//! ```toml
+//! name = "dungeon-crawler"
+//! model = "alpha/v2.1"     # model name or numeric ID for `generate`
+//! duration = 5
+//! palette = "art/palette.pal"
+//! output_dir = "assets/gametorch"
+//! target = "godot"
+//!
+//! [lint]
+//! grid = 4
+//! max_colors = 32
+//! ```
+//!
+//! Relative paths are relative to the project root. `generate --block`
+//! saves results into `output_dir`, `export` writes there for `target`,
+//! `process palettize` remaps to `palette` when given no colours, and `lint`
+//! checks results against the [`[lint]` rules](crate::lint). What was
+//! generated and exported is tracked in the [asset manifest](crate::manifest).
+
+use crate::export::Target;
+use crate::lint::LintRules;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the workspace configuration at a project root.
+pub const WORKSPACE_FILE: &str = "gametorch.toml";
+
+/// Where generated assets go when the workspace does not say.
+pub const DEFAULT_OUTPUT_DIR: &str = "assets/gametorch";
+
+/// Workspace settings as written in `gametorch.toml`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Animation model name, or a numeric model ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Default `generate` duration in seconds (5 or 10 for the default
+    /// model; checked against the model when generating).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<PathBuf>,
+    /// Export target for `export`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Rules for `lint`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lint: Option<LintRules>,
+}
+
+impl WorkspaceConfig {
+    /// Check every value is usable.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.target()?;
+        if let Some(rules) = &self.lint {
+            rules.validate()?;
+        }
+        if self.duration == Some(0) {
+            return Err("duration must be greater than zero".into());
+        }
+        Ok(())
+    }
+
+    pub fn target(&self) -> Result<Option<Target>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.target.as_deref().map(str::parse::<Target>).transpose()?)
+    }
+
+    /// The model as `(model_id, model_name)` for a
+    /// [`GenerateRequest`](crate::animations::GenerateRequest).
+    pub fn model(&self) -> (Option<u32>, Option<String>) {
+        match &self.model {
+            Some(model) => match model.parse::<u32>() {
+                Ok(id) => (Some(id), None),
+                Err(_) => (None, Some(model.clone())),
+            },
+            None => (None, None),
+        }
+    }
+}
+
+/// A project root and its settings.
+#[derive(Clone, Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub config: WorkspaceConfig,
+}
+
+impl Workspace {
+    /// Load the workspace rooted at `root`.
+    pub fn load(root: impl Into<PathBuf>) -> Result<Workspace, Box<dyn std::error::Error + Send + Sync>> {
+        let root = root.into();
+        let path = root.join(WORKSPACE_FILE);
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let config: WorkspaceConfig = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        config.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Workspace { root, config })
+    }
+
+    /// The workspace containing `dir`, searching its ancestors.
+    pub fn find(dir: &Path) -> Result<Option<Workspace>, Box<dyn std::error::Error + Send + Sync>> {
+        let dir = std::path::absolute(dir)?;
+        match dir.ancestors().find(|d| d.join(WORKSPACE_FILE).is_file()) {
+            Some(root) => Workspace::load(root).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The workspace containing the current directory.
+    pub fn current() -> Result<Option<Workspace>, Box<dyn std::error::Error + Send + Sync>> {
+        Workspace::find(&std::env::current_dir()?)
+    }
+
+    /// Create `gametorch.toml` in `root` (and the output directory).
+    /// Refuses to replace an existing file unless `force` is set.
+    pub fn init(
+        root: impl Into<PathBuf>,
+        config: WorkspaceConfig,
+        force: bool,
+    ) -> Result<Workspace, Box<dyn std::error::Error + Send + Sync>> {
+        config.validate()?;
+        let root = root.into();
+        let path = root.join(WORKSPACE_FILE);
+        if path.exists() && !force {
+            return Err(format!("{} already exists (use --force to replace it)", path.display()).into());
+        }
+        std::fs::create_dir_all(&root)?;
+        std::fs::write(&path, toml::to_string_pretty(&config)?)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+        let workspace = Workspace { root, config };
+        std::fs::create_dir_all(workspace.output_dir())?;
+        Ok(workspace)
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.root.join(WORKSPACE_FILE)
+    }
+
+    /// Where generated and exported assets go.
+    pub fn output_dir(&self) -> PathBuf {
+        self.resolve(self.config.output_dir.as_deref().unwrap_or(Path::new(DEFAULT_OUTPUT_DIR)))
+    }
+
+    pub fn palette(&self) -> Option<PathBuf> {
+        self.config.palette.as_deref().map(|p| self.resolve(p))
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.root.join(path)
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! `generate --block` against the mock API (`--features test-util`).
+
+use gametorch::animations::{self, GenerateRequest};
+use gametorch::error::ErrorKind;
+use gametorch::frames::Sequence;
+use gametorch::mock::{Lifecycle, MockGametorch, FIRST_ANIMATION_ID, MOCK_API_KEY};
+use std::path::PathBuf;
+
+/// A fresh path for a downloaded ZIP.
+fn zip_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("gametorch-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(format!("{}.zip", name))
+}
+
+#[tokio::test]
+async fn block_downloads_the_result_zip() {
+    let mock = MockGametorch::start().await;
+    let path = zip_path("downloads");
+    let request = GenerateRequest::new("a knight walking");
+
+    let json = animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, path.to_str(), true)
+        .await
+        .unwrap();
+
+    assert_eq!(json["animation_id"], FIRST_ANIMATION_ID);
+    assert_eq!(json["result_id"], Lifecycle::result_id(FIRST_ANIMATION_ID));
+    assert_eq!(json["seed"], 42);
+    assert_eq!(json["zip_path"], path.to_str().unwrap());
+    assert_eq!(Sequence::load(&path).unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn block_sends_the_request_parameters() {
+    let mock = MockGametorch::start().await;
+    let path = zip_path("parameters");
+    let request = GenerateRequest::new("a knight walking")
+        .duration(10)
+        .seed(7)
+        .negative_prompt("blurry");
+
+    animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, path.to_str(), true)
+        .await
+        .unwrap();
+
+    let submissions = mock.submissions().await;
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(submissions[0]["prompt"], "a knight walking");
+    assert_eq!(submissions[0]["duration_seconds"], 10);
+    assert_eq!(submissions[0]["seed"], 7);
+    assert_eq!(submissions[0]["negative_prompt"], "blurry");
+}
+
+#[tokio::test]
+async fn block_waits_while_generating_and_for_the_zip() {
+    let mock = MockGametorch::with_lifecycle(Lifecycle {
+        generating_polls: 1,
+        zip_pending_polls: 2,
+        ..Default::default()
+    })
+    .await;
+    let path = zip_path("waits");
+    let request = GenerateRequest::new("a knight walking");
+
+    let json = animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, path.to_str(), true)
+        .await
+        .unwrap();
+
+    assert_eq!(json["animation_id"], FIRST_ANIMATION_ID);
+    assert_eq!(mock.results_polls(FIRST_ANIMATION_ID), 2);
+    assert!(path.exists());
+}
+
+#[tokio::test]
+async fn block_fails_when_the_render_fails() {
+    let mock = MockGametorch::with_lifecycle(Lifecycle {
+        fails: true,
+        ..Default::default()
+    })
+    .await;
+    let path = zip_path("fails");
+    let request = GenerateRequest::new("a knight walking");
+
+    let err = animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, path.to_str(), true)
+        .await
+        .unwrap_err();
+
+    assert_eq!(ErrorKind::of(err.as_ref()), ErrorKind::FailedGeneration);
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn block_tolerates_a_few_zip_server_errors() {
+    let mock = MockGametorch::with_lifecycle(Lifecycle {
+        zip_server_errors: 2,
+        ..Default::default()
+    })
+    .await;
+    let path = zip_path("server-errors");
+    let request = GenerateRequest::new("a knight walking");
+
+    animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, path.to_str(), true)
+        .await
+        .unwrap();
+
+    assert_eq!(mock.zip_polls(Lifecycle::result_id(FIRST_ANIMATION_ID)), 3);
+    assert!(path.exists());
+}
+
+#[tokio::test]
+async fn block_reports_a_zip_server_that_keeps_failing() {
+    let mock = MockGametorch::with_lifecycle(Lifecycle {
+        zip_server_errors: 10,
+        ..Default::default()
+    })
+    .await;
+    let path = zip_path("server-failing");
+    let request = GenerateRequest::new("a knight walking");
+
+    let err = animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, path.to_str(), true)
+        .await
+        .unwrap_err();
+
+    assert_eq!(ErrorKind::of(err.as_ref()), ErrorKind::Server);
+    assert!(err.to_string().contains("zip storage unavailable"));
+    // One request per poll: the client does not retry them on top.
+    assert_eq!(mock.zip_polls(Lifecycle::result_id(FIRST_ANIMATION_ID)), 3);
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn without_block_only_submits() {
+    let mock = MockGametorch::start().await;
+    let request = GenerateRequest::new("a knight walking");
+
+    let json = animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, false, None, true)
+        .await
+        .unwrap();
+
+    assert_eq!(json["animation_id"], FIRST_ANIMATION_ID);
+    assert_eq!(mock.results_polls(FIRST_ANIMATION_ID), 0);
+}
+
+#[tokio::test]
+async fn a_wrong_api_key_is_an_auth_error() {
+    let mock = MockGametorch::start().await;
+    let request = GenerateRequest::new("a knight walking");
+
+    let err = animations::generate_from("not-the-key", &mock.uri(), &request, true, None, true)
+        .await
+        .unwrap_err();
+
+    assert_eq!(ErrorKind::of(err.as_ref()), ErrorKind::Auth);
+    assert_eq!(mock.submitted().await, 1);
+}
+
+#[tokio::test]
+async fn retry_on_failure_regenerates_failed_renders() {
+    let mock = MockGametorch::with_lifecycle(Lifecycle {
+        failed_attempts: 2,
+        ..Default::default()
+    })
+    .await;
+    let path = zip_path("retried");
+    let request = GenerateRequest::new("a knight walking").retry_on_failure(2);
+
+    let json = animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, path.to_str(), true)
+        .await
+        .unwrap();
+
+    assert_eq!(json["animation_id"], FIRST_ANIMATION_ID + 2);
+    assert_eq!(json["failed_animation_ids"].as_array().unwrap().len(), 2);
+    assert_eq!(mock.regenerated().await, 2);
+    assert!(path.exists());
+}
+
+#[tokio::test]
+async fn retry_on_failure_gives_up_after_n_retries() {
+    let mock = MockGametorch::with_lifecycle(Lifecycle {
+        fails: true,
+        ..Default::default()
+    })
+    .await;
+    let request = GenerateRequest::new("a knight walking").retry_on_failure(1);
+
+    let err = animations::generate_from(MOCK_API_KEY, &mock.uri(), &request, true, None, true)
+        .await
+        .unwrap_err();
+
+    assert_eq!(ErrorKind::of(err.as_ref()), ErrorKind::FailedGeneration);
+    assert_eq!(mock.regenerated().await, 1);
+}
@@ -0,0 +1,107 @@
+//! Client-side filtering, sorting and paging of listings, for servers that
+//! ignore the query parameters.
+
+use gametorch::animations::{ListOptions, ListSort};
+use serde_json::{Value, json};
+
+fn listing() -> Value {
+    json!([
+        { "id": 1, "status": 2, "created_at": 1_700_000_000, "prompt": "a knight walking" },
+        { "id": 2, "status": 3, "created_at": "2023-11-20T10:00:00Z", "prompt": "a slime bouncing" },
+        { "id": 3, "status": 2, "created_at": 1_701_000_000, "prompt": "a Knight attacking" },
+        { "id": 4, "status": 1, "created_at": 1_702_000_000, "prompt": "a bat flying" },
+        { "id": 5, "status": 2, "created_at": 1_703_000_000, "prompt": "a knight idle" },
+    ])
+}
+
+fn ids(list: &Value) -> Vec<i64> {
+    list.as_array().unwrap().iter().map(|item| item["id"].as_i64().unwrap()).collect()
+}
+
+#[test]
+fn status_keeps_matching_items() {
+    let options = ListOptions {
+        status: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(ids(&options.apply(listing())), [1, 3, 5]);
+}
+
+#[test]
+fn status_drops_items_without_one() {
+    let list = json!([{ "id": 1 }, { "id": 2, "status": 3 }, { "id": 3, "status": null }]);
+    let options = ListOptions {
+        status: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(ids(&options.apply(list)), [2]);
+}
+
+#[test]
+fn created_after_reads_timestamps_and_dates() {
+    let options = ListOptions {
+        created_after: Some(1_700_400_000),
+        ..Default::default()
+    };
+    assert_eq!(ids(&options.apply(listing())), [2, 3, 4, 5]);
+}
+
+#[test]
+fn search_is_case_insensitive() {
+    let options = ListOptions {
+        search: Some("KNIGHT".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(ids(&options.apply(listing())), [1, 3, 5]);
+}
+
+#[test]
+fn sort_orders_by_creation_time() {
+    let mut list = listing();
+    list.as_array_mut().unwrap().reverse();
+    let oldest = ListOptions {
+        sort: Some(ListSort::Oldest),
+        ..Default::default()
+    };
+    assert_eq!(ids(&oldest.apply(list.clone())), [1, 2, 3, 4, 5]);
+    let newest = ListOptions {
+        sort: Some(ListSort::Newest),
+        ..Default::default()
+    };
+    assert_eq!(ids(&newest.apply(list)), [5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn pages_a_listing_the_server_did_not_page() {
+    let options = |page| ListOptions {
+        page: Some(page),
+        per_page: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(ids(&options(1).apply(listing())), [1, 2]);
+    assert_eq!(ids(&options(3).apply(listing())), [5]);
+    assert!(ids(&options(4).apply(listing())).is_empty());
+}
+
+#[test]
+fn pages_by_the_unfiltered_length() {
+    // Five items for a page of three: the server ignored the parameters, so
+    // page 2 of the three matching items is empty.
+    let options = ListOptions {
+        page: Some(2),
+        per_page: Some(3),
+        status: Some(2),
+        ..Default::default()
+    };
+    assert!(ids(&options.apply(listing())).is_empty());
+}
+
+#[test]
+fn keeps_a_page_the_server_returned() {
+    let options = ListOptions {
+        page: Some(2),
+        per_page: Some(5),
+        ..Default::default()
+    };
+    assert_eq!(ids(&options.apply(listing())), [1, 2, 3, 4, 5]);
+}